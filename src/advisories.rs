@@ -0,0 +1,167 @@
+//! Known-vulnerability lookups against OSV.dev, so a downgrade never lands on
+//! a release with a published advisory.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::registry::RegistryClient;
+
+/// Crate names cannot start with `_`, so this never shadows a cached crate.
+const CACHE_PREFIX: &str = "_osv";
+
+#[derive(Debug, Serialize)]
+struct BatchQuery<'a> {
+    queries: Vec<Query<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Query<'a> {
+    package: Package<'a>,
+    version: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct Package<'a> {
+    name: &'a str,
+    ecosystem: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    results: Vec<QueryResult>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct QueryResult {
+    #[serde(default)]
+    vulns: Vec<Vuln>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vuln {
+    id: String,
+}
+
+/// Client for the OSV batch query endpoint with per-release cache entries.
+pub struct Advisories {
+    client: RegistryClient,
+    url: String,
+}
+
+impl Advisories {
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        if !config.osv {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            client: RegistryClient::new(config)?,
+            url: config.osv_url.clone(),
+        }))
+    }
+
+    /// Advisory IDs affecting each of `versions` of `name`; releases without
+    /// advisories are left out. Cached answers are reused until the cache TTL
+    /// expires so newly published advisories are still picked up.
+    pub async fn affected(
+        &self,
+        cache: &Cache,
+        name: &str,
+        versions: &[&str],
+    ) -> Result<BTreeMap<String, Vec<String>>> {
+        let mut affected = BTreeMap::new();
+        let mut missing = Vec::new();
+        for &version in versions {
+            match cache.get::<Vec<String>>(&cache_key(name, version)) {
+                Ok(Some(ids)) => {
+                    if !ids.is_empty() {
+                        affected.insert(version.to_string(), ids);
+                    }
+                }
+                _ => missing.push(version),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(affected);
+        }
+
+        let query = BatchQuery {
+            queries: missing
+                .iter()
+                .map(|&version| Query {
+                    package: Package {
+                        name,
+                        ecosystem: "crates.io",
+                    },
+                    version,
+                })
+                .collect(),
+        };
+        let response: BatchResponse = self.client.post_json(&self.url, &query).await?;
+        for (version, ids) in match_results(&missing, response)? {
+            debug!(crate = %name, %version, advisories = ?ids, "queried OSV");
+            cache.put(&cache_key(name, version), &ids)?;
+            if !ids.is_empty() {
+                affected.insert(version.to_string(), ids);
+            }
+        }
+        Ok(affected)
+    }
+}
+
+fn cache_key(name: &str, version: &str) -> String {
+    format!("{CACHE_PREFIX}/{name}/{version}")
+}
+
+/// Pair each queried version with the advisory IDs OSV returned for it; the
+/// batch endpoint answers in query order.
+fn match_results<'a>(
+    versions: &[&'a str],
+    response: BatchResponse,
+) -> Result<Vec<(&'a str, Vec<String>)>> {
+    if response.results.len() != versions.len() {
+        bail!(
+            "OSV answered {} results for {} queries",
+            response.results.len(),
+            versions.len()
+        );
+    }
+    Ok(versions
+        .iter()
+        .zip(response.results)
+        .map(|(&version, result)| {
+            (
+                version,
+                result.vulns.into_iter().map(|vuln| vuln.id).collect(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_results_follow_query_order() {
+        let response: BatchResponse = serde_json::from_str(
+            r#"{"results":[{},{"vulns":[{"id":"RUSTSEC-2024-0001","modified":"2024-01-01T00:00:00Z"}]}]}"#,
+        )
+        .unwrap();
+        let matched = match_results(&["1.0.0", "0.9.0"], response).unwrap();
+        assert_eq!(
+            matched,
+            vec![
+                ("1.0.0", vec![]),
+                ("0.9.0", vec!["RUSTSEC-2024-0001".to_string()])
+            ]
+        );
+
+        let short: BatchResponse = serde_json::from_str(r#"{"results":[]}"#).unwrap();
+        assert!(match_results(&["1.0.0"], short).is_err());
+    }
+}
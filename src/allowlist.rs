@@ -1,56 +1,153 @@
 use serde::Deserialize;
-use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use semver::{Version, VersionReq};
+use tracing::warn;
+
+use crate::config::{Mode, PinStrategy, field_names, manifest_metadata, unknown_keys};
+use crate::duration::deserialize_minutes;
 
 #[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Allowlist {
     #[serde(default)]
     pub allow: AllowSection,
+    /// Banned crates and versions, rejected regardless of age.
+    #[serde(default)]
+    pub deny: Vec<DenyEntry>,
+    /// The instant expiry dates are checked against; the current time when
+    /// unset.
+    #[serde(skip)]
+    pub evaluated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AllowSection {
     #[serde(default)]
     pub exact: Vec<AllowExact>,
     #[serde(default)]
     pub package: Vec<AllowPackage>,
+    #[serde(default)]
+    pub range: Vec<AllowRange>,
     pub global: Option<AllowGlobal>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct AllowExact {
+    /// Crate name, or a pattern where `*` matches any run of characters and
+    /// `?` a single one.
     #[serde(rename = "crate")]
     pub crate_name: String,
     pub version: String,
+    /// Why the exception exists; informational only.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// The exception lapses at this instant.
+    #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct AllowPackage {
+    /// Crate name or pattern, as in [`AllowExact::crate_name`].
     #[serde(rename = "crate")]
     pub crate_name: String,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_minutes")]
     pub minimum_release_age: Option<u64>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_minutes")]
     pub minutes: Option<u64>,
+    /// Overrides the run's pin strategy for matching crates.
+    #[serde(default)]
+    pub pin_strategy: Option<PinStrategy>,
+    /// Relaxes the run's mode for matching crates: `warn` reports their
+    /// fresh releases without blocking, `off` skips them.
+    #[serde(default)]
+    pub mode: Option<Mode>,
+    /// Why the exception exists; informational only.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// The exception lapses at this instant.
+    #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
 }
 
+/// Exempts every version of a crate matched by a reviewed semver requirement.
 #[derive(Debug, Deserialize, Clone)]
-pub struct AllowGlobal {
+#[serde(deny_unknown_fields)]
+pub struct AllowRange {
+    /// Crate name or pattern, as in [`AllowExact::crate_name`].
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub req: VersionReq,
+    /// Why the exception exists; informational only.
     #[serde(default)]
-    pub minimum_release_age: Option<u64>,
+    pub reason: Option<String>,
+    /// The exception lapses at this instant.
     #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DenyEntry {
+    /// Crate name or pattern, as in [`AllowExact::crate_name`].
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// Banned versions; every version of the crate when omitted.
+    #[serde(default)]
+    pub req: Option<VersionReq>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AllowGlobal {
+    #[serde(default, deserialize_with = "deserialize_minutes")]
+    pub minimum_release_age: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_minutes")]
     pub minutes: Option<u64>,
 }
 
+/// Unknown keys in an array of rule tables such as `allow.exact`.
+fn unknown_rule_keys(rules: Option<&toml::Value>, fields: &[&str], name: &str) -> Vec<String> {
+    let Some(toml::Value::Array(rules)) = rules else {
+        return Vec::new();
+    };
+    rules
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| match rule {
+            toml::Value::Table(rule) => Some(unknown_keys(
+                rule,
+                fields,
+                &format!("{name}[{index}]."),
+                &[],
+            )),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
 impl Allowlist {
-    pub fn load(path: Option<PathBuf>) -> Result<Self> {
-        let path = match path {
+    /// The configured allowlist path, or `cooldown-allowlist.toml` in the
+    /// current directory.
+    pub fn resolve_path(path: Option<PathBuf>) -> PathBuf {
+        match path {
             Some(p) => p,
             None => PathBuf::from("cooldown-allowlist.toml"),
-        };
+        }
+    }
+
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let path = Self::resolve_path(path);
 
         if !path.exists() {
             return Ok(Self::default());
@@ -58,24 +155,199 @@ impl Allowlist {
 
         let contents = fs::read_to_string(&path)
             .with_context(|| format!("failed to read allowlist at {}", path.display()))?;
-        let allowlist: Allowlist = toml::from_str(&contents)
-            .with_context(|| format!("failed to parse allowlist at {}", path.display()))?;
+        Self::parse(&contents, &path.display().to_string())
+    }
+
+    /// The `allow` and `deny` rules in the cooldown metadata of the
+    /// `Cargo.toml` in `root`, if there are any.
+    pub fn from_manifest(root: &Path) -> Result<Option<Self>> {
+        let path = root.join("Cargo.toml");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+        let origin = path.display().to_string();
+        let Some(mut metadata) = manifest_metadata(&contents)
+            .map_err(|err| anyhow!(err))
+            .with_context(|| format!("failed to parse {origin}"))?
+        else {
+            return Ok(None);
+        };
+        let mut rules = toml::Table::new();
+        for key in ["allow", "deny"] {
+            if let Some(value) = metadata.remove(key) {
+                rules.insert(key.to_string(), value);
+            }
+        }
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        let allowlist: Allowlist = rules
+            .try_into()
+            .with_context(|| format!("failed to parse the cooldown rules in {origin}"))?;
+        allowlist.warn_expired(&origin, Utc::now());
+        Ok(Some(allowlist))
+    }
+
+    /// Parse allowlist contents read from `origin`, a path or URL used in
+    /// messages.
+    pub fn parse(contents: &str, origin: &str) -> Result<Self> {
+        let allowlist: Allowlist = toml::from_str(contents)
+            .with_context(|| format!("failed to parse allowlist at {origin}"))?;
+        allowlist.warn_expired(origin, Utc::now());
         Ok(allowlist)
     }
 
+    /// Every problem in an allowlist: keys that are not part of any rule,
+    /// each with the closest one when it looks like a typo, and values of the
+    /// wrong type. Empty when the rules would be loaded as written.
+    pub fn validate(table: &toml::Table) -> Vec<String> {
+        let mut problems = unknown_keys(table, field_names::<Allowlist>(), "", &[]);
+        if let Some(toml::Value::Table(allow)) = table.get("allow") {
+            problems.extend(unknown_keys(
+                allow,
+                field_names::<AllowSection>(),
+                "allow.",
+                &[],
+            ));
+            let rules = [
+                ("exact", field_names::<AllowExact>()),
+                ("package", field_names::<AllowPackage>()),
+                ("range", field_names::<AllowRange>()),
+            ];
+            for (kind, fields) in rules {
+                problems.extend(unknown_rule_keys(
+                    allow.get(kind),
+                    fields,
+                    &format!("allow.{kind}"),
+                ));
+            }
+            if let Some(toml::Value::Table(global)) = allow.get("global") {
+                problems.extend(unknown_keys(
+                    global,
+                    field_names::<AllowGlobal>(),
+                    "allow.global.",
+                    &[],
+                ));
+            }
+        }
+        problems.extend(unknown_rule_keys(
+            table.get("deny"),
+            field_names::<DenyEntry>(),
+            "deny",
+        ));
+        // Unknown keys also fail the typed parse below; report them once.
+        if problems.is_empty()
+            && let Err(err) = table.clone().try_into::<Allowlist>()
+        {
+            problems.push(err.to_string());
+        }
+        problems
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.evaluated_at.unwrap_or_else(Utc::now)
+    }
+
+    /// Add every entry of `other`. The local `[allow.global]` wins over one
+    /// from `other`.
+    pub fn merge(&mut self, other: Allowlist) {
+        self.allow.exact.extend(other.allow.exact);
+        self.allow.package.extend(other.allow.package);
+        self.allow.range.extend(other.allow.range);
+        if self.allow.global.is_none() {
+            self.allow.global = other.allow.global;
+        }
+        self.deny.extend(other.deny);
+    }
+
+    fn warn_expired(&self, path: &str, now: DateTime<Utc>) {
+        for entry in &self.allow.exact {
+            if let Some(expires) = entry.expires.filter(|&expires| expires <= now) {
+                warn!(allowlist = %path, crate = %entry.crate_name, version = %entry.version, reason = ?entry.reason, %expires, "allowlist exception has expired and is ignored");
+            }
+        }
+        for entry in &self.allow.package {
+            if let Some(expires) = entry.expires.filter(|&expires| expires <= now) {
+                warn!(allowlist = %path, crate = %entry.crate_name, reason = ?entry.reason, %expires, "allowlist exception has expired and is ignored");
+            }
+        }
+        for entry in &self.allow.range {
+            if let Some(expires) = entry.expires.filter(|&expires| expires <= now) {
+                warn!(allowlist = %path, crate = %entry.crate_name, req = %entry.req, reason = ?entry.reason, %expires, "allowlist exception has expired and is ignored");
+            }
+        }
+    }
+
     pub fn is_exact_allowed(&self, name: &str, version: &str) -> bool {
+        let now = self.now();
+        self.allow.exact.iter().any(|entry| {
+            entry.version == version
+                && name_matches(&entry.crate_name, name)
+                && is_active(entry.expires, now)
+        })
+    }
+
+    /// Whether `version` falls inside a reviewed `[[allow.range]]`. Versions
+    /// that are not valid semver never match.
+    pub fn is_range_allowed(&self, name: &str, version: &str) -> bool {
+        let Ok(version) = Version::parse(version) else {
+            return false;
+        };
+        let now = self.now();
+        self.allow.range.iter().any(|entry| {
+            is_active(entry.expires, now)
+                && name_matches(&entry.crate_name, name)
+                && entry.req.matches(&version)
+        })
+    }
+
+    /// The first `[[deny]]` entry banning `name@version`, if any.
+    pub fn denial(&self, name: &str, version: &str) -> Option<&DenyEntry> {
+        self.deny.iter().find(|entry| {
+            name_matches(&entry.crate_name, name)
+                && entry.req.as_ref().is_none_or(|req| {
+                    Version::parse(version).is_ok_and(|version| req.matches(&version))
+                })
+        })
+    }
+
+    /// Per-crate window for `name`. An entry naming the crate literally wins
+    /// over patterns; among patterns the longest, most specific one applies.
+    pub fn package_minutes(&self, name: &str) -> Option<u64> {
+        let now = self.now();
         self.allow
-            .exact
+            .package
             .iter()
-            .any(|entry| entry.crate_name == name && entry.version == version)
+            .filter(|pkg| is_active(pkg.expires, now) && name_matches(&pkg.crate_name, name))
+            .filter_map(|pkg| pkg.effective_minutes().map(|minutes| (pkg, minutes)))
+            .max_by_key(|(pkg, _)| (pkg.crate_name == name, pkg.crate_name.len()))
+            .map(|(_, minutes)| minutes)
     }
 
-    pub fn per_crate_minutes(&self) -> HashMap<String, u64> {
+    /// Pin strategy of the most specific `[[allow.package]]` entry for
+    /// `name` that sets one.
+    pub fn package_pin_strategy(&self, name: &str) -> Option<PinStrategy> {
+        let now = self.now();
         self.allow
             .package
             .iter()
-            .filter_map(|pkg| pkg.effective_minutes().map(|m| (pkg.crate_name.clone(), m)))
-            .collect()
+            .filter(|pkg| is_active(pkg.expires, now) && name_matches(&pkg.crate_name, name))
+            .filter_map(|pkg| pkg.pin_strategy.map(|strategy| (pkg, strategy)))
+            .max_by_key(|(pkg, _)| (pkg.crate_name == name, pkg.crate_name.len()))
+            .map(|(_, strategy)| strategy)
+    }
+
+    /// Mode of the most specific `[[allow.package]]` entry for `name` that
+    /// sets one.
+    pub fn package_mode(&self, name: &str) -> Option<Mode> {
+        let now = self.now();
+        self.allow
+            .package
+            .iter()
+            .filter(|pkg| is_active(pkg.expires, now) && name_matches(&pkg.crate_name, name))
+            .filter_map(|pkg| pkg.mode.map(|mode| (pkg, mode)))
+            .max_by_key(|(pkg, _)| (pkg.crate_name == name, pkg.crate_name.len()))
+            .map(|(_, mode)| mode)
     }
 
     pub fn global_minutes(&self) -> Option<u64> {
@@ -90,15 +362,49 @@ impl Allowlist {
         if let Some(global) = self.global_minutes() {
             effective = effective.min(global);
         }
-        if let Some(rule) = self.allow.package.iter().find(|pkg| pkg.crate_name == name)
-            && let Some(minutes) = rule.effective_minutes()
-        {
+        if let Some(minutes) = self.package_minutes(name) {
             effective = effective.min(minutes);
         }
         effective
     }
 }
 
+/// Match a crate name against an allowlist pattern. `*` matches any run of
+/// characters (including none) and `?` exactly one; everything else is
+/// literal.
+pub fn name_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it is currently absorbing up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    p = star + 1;
+                    n = absorbed + 1;
+                    backtrack = Some((star, absorbed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn is_active(expires: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    expires.is_none_or(|expires| expires > now)
+}
+
 impl AllowPackage {
     pub fn effective_minutes(&self) -> Option<u64> {
         self.minimum_release_age.or(self.minutes)
@@ -130,10 +436,100 @@ mod tests {
         assert!(allowlist.is_exact_allowed("foo", "1.2.3"));
         assert!(!allowlist.is_exact_allowed("foo", "1.2.4"));
 
-        let per_crate = allowlist.per_crate_minutes();
-        assert_eq!(per_crate.get("bar"), Some(&3));
+        assert_eq!(allowlist.package_minutes("bar"), Some(3));
         assert_eq!(allowlist.global_minutes(), Some(5));
         assert_eq!(allowlist.effective_minutes_for("bar", 7), 3);
         assert_eq!(allowlist.effective_minutes_for("baz", 7), 5);
     }
+
+    #[test]
+    fn expired_entries_are_ignored() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "[[allow.exact]]\ncrate = \"foo\"\nversion = \"1.2.3\"\nexpires = \"2020-01-01T00:00:00Z\"\n[[allow.exact]]\ncrate = \"foo\"\nversion = \"1.3.0\"\nexpires = \"2999-01-01T00:00:00Z\"\n[[allow.package]]\ncrate = \"bar\"\nminutes = 3\nexpires = \"2020-01-01T00:00:00Z\"\n"
+        )
+        .unwrap();
+
+        let allowlist = Allowlist::load(Some(file.path().to_path_buf())).unwrap();
+        assert!(!allowlist.is_exact_allowed("foo", "1.2.3"));
+        assert!(allowlist.is_exact_allowed("foo", "1.3.0"));
+        assert_eq!(allowlist.effective_minutes_for("bar", 7), 7);
+    }
+
+    #[test]
+    fn expiry_follows_the_evaluation_instant() {
+        let mut allowlist: Allowlist = toml::from_str(
+            "[[allow.exact]]\ncrate = \"foo\"\nversion = \"1.2.3\"\nexpires = \"2020-01-01T00:00:00Z\"\n",
+        )
+        .unwrap();
+        assert!(!allowlist.is_exact_allowed("foo", "1.2.3"));
+        allowlist.evaluated_at = Some("2019-12-31T00:00:00Z".parse().unwrap());
+        assert!(allowlist.is_exact_allowed("foo", "1.2.3"));
+    }
+
+    #[test]
+    fn ranges_exempt_reviewed_versions() {
+        let allowlist: Allowlist = toml::from_str(
+            "[[allow.range]]\ncrate = \"serde\"\nreq = \">=1.0.200, <1.1\"\n[[allow.range]]\ncrate = \"tokio\"\nreq = \"1\"\nexpires = \"2020-01-01T00:00:00Z\"\n",
+        )
+        .unwrap();
+        assert!(allowlist.is_range_allowed("serde", "1.0.210"));
+        assert!(!allowlist.is_range_allowed("serde", "1.0.199"));
+        assert!(!allowlist.is_range_allowed("serde", "1.1.0"));
+        assert!(!allowlist.is_range_allowed("serde_json", "1.0.210"));
+        assert!(!allowlist.is_range_allowed("tokio", "1.40.0"));
+        assert!(
+            toml::from_str::<Allowlist>("[[allow.range]]\ncrate = \"x\"\nreq = \"not a req\"\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn deny_entries_ban_crates_and_ranges() {
+        let allowlist: Allowlist = toml::from_str(
+            "[[deny]]\ncrate = \"xz-backdoored\"\nreason = \"malware\"\n[[deny]]\ncrate = \"serde\"\nreq = \"=1.0.999\"\n",
+        )
+        .unwrap();
+        let denial = allowlist.denial("xz-backdoored", "0.1.0").unwrap();
+        assert_eq!(denial.reason.as_deref(), Some("malware"));
+        assert!(allowlist.denial("serde", "1.0.999").is_some());
+        assert!(allowlist.denial("serde", "1.0.200").is_none());
+        assert!(allowlist.denial("tokio", "1.0.0").is_none());
+    }
+
+    #[test]
+    fn patterns_match_crate_families() {
+        let allowlist: Allowlist = toml::from_str(
+            "[[allow.exact]]\ncrate = \"my-org-*\"\nversion = \"0.1.0\"\n[[allow.package]]\ncrate = \"tokio-*\"\nminutes = 60\n[[allow.package]]\ncrate = \"tokio-util*\"\nminutes = 30\n[[allow.package]]\ncrate = \"tokio-utils\"\nminutes = 90\n",
+        )
+        .unwrap();
+        assert!(allowlist.is_exact_allowed("my-org-core", "0.1.0"));
+        assert!(!allowlist.is_exact_allowed("other-core", "0.1.0"));
+        assert_eq!(allowlist.package_minutes("tokio-macros"), Some(60));
+        assert_eq!(allowlist.package_minutes("tokio-util"), Some(30));
+        assert_eq!(allowlist.package_minutes("tokio-utils"), Some(90));
+        assert_eq!(allowlist.package_minutes("tokio"), None);
+    }
+
+    #[test]
+    fn package_modes_follow_the_most_specific_entry() {
+        let allowlist: Allowlist = toml::from_str(
+            "[[allow.package]]\ncrate = \"my-org-*\"\nmode = \"warn\"\n[[allow.package]]\ncrate = \"my-org-vendored\"\nmode = \"off\"\n",
+        )
+        .unwrap();
+        assert_eq!(allowlist.package_mode("my-org-core"), Some(Mode::Warn));
+        assert_eq!(allowlist.package_mode("my-org-vendored"), Some(Mode::Off));
+        assert_eq!(allowlist.package_mode("serde"), None);
+        assert!(
+            toml::from_str::<Allowlist>("[[allow.package]]\ncrate = \"x\"\nmode = \"loud\"\n")
+                .is_err()
+        );
+
+        assert!(name_matches("*-sys", "openssl-sys"));
+        assert!(name_matches("a*b*c", "axxbyyc"));
+        assert!(name_matches("serde?", "serde1"));
+        assert!(!name_matches("serde?", "serde"));
+        assert!(!name_matches("a*b", "axxbc"));
+    }
 }
@@ -0,0 +1,192 @@
+//! Append-only audit trail of every decision a run makes, for compliance
+//! teams that need to show the cooldown policy was enforced.
+//!
+//! Each line is a JSON object carrying the hash of the line before it, so
+//! editing, dropping, or reordering entries breaks the chain and
+//! [`verify`] reports where.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::config::Config;
+use crate::events::Event;
+
+/// The `prev_hash` of the first entry of a log.
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+pub struct AuditLog {
+    path: PathBuf,
+    /// Fields every entry of this run repeats: who ran it, and under which
+    /// policy.
+    context: Map<String, Value>,
+    last_hash: Mutex<String>,
+}
+
+impl AuditLog {
+    /// Open the log at `path` and continue the chain its last entry ends.
+    pub fn open(path: &Path, config: &Config, policy_digest: &str) -> Result<Self> {
+        let mut policy = Map::new();
+        policy.insert(
+            "mode".into(),
+            format!("{:?}", config.mode).to_lowercase().into(),
+        );
+        policy.insert("cooldown_minutes".into(), config.cooldown_minutes.into());
+        policy.insert("policy_digest".into(), policy_digest.into());
+        Self::start(path, policy)
+    }
+
+    fn start(path: &Path, policy: Map<String, Value>) -> Result<Self> {
+        let last_hash = match fs::read_to_string(path) {
+            Ok(contents) => match contents.lines().rfind(|line| !line.trim().is_empty()) {
+                Some(line) => serde_json::from_str::<Value>(line)
+                    .ok()
+                    .and_then(|entry| entry.get("hash")?.as_str().map(str::to_string))
+                    .with_context(|| format!("the last entry of {} has no hash", path.display()))?,
+                None => GENESIS.to_string(),
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => GENESIS.to_string(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        let mut context = Map::new();
+        context.insert(
+            "run".into(),
+            format!("{}-{}", Utc::now().timestamp(), std::process::id()).into(),
+        );
+        context.insert("user".into(), current_user().into());
+        context.extend(policy);
+        Ok(Self {
+            path: path.to_path_buf(),
+            context,
+            last_hash: Mutex::new(last_hash),
+        })
+    }
+
+    /// Append `event` to the log. Like the event stream, a log that cannot be
+    /// written is reported and otherwise ignored.
+    pub fn record(&self, event: &Event<'_>) {
+        let mut entry = self.context.clone();
+        entry.insert("at".into(), Utc::now().to_rfc3339().into());
+        if let Ok(Value::Object(fields)) = serde_json::to_value(event) {
+            entry.extend(fields);
+        }
+        let mut last_hash = self
+            .last_hash
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let line = seal(entry, &last_hash);
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line.text));
+        match written {
+            Ok(()) => *last_hash = line.hash,
+            Err(err) => {
+                warn!(error = %err, log = %self.path.display(), "failed to write to the audit log")
+            }
+        }
+    }
+}
+
+struct Sealed {
+    text: String,
+    hash: String,
+}
+
+/// Chain `entry` to `prev_hash`: the hash covers the entry, `prev_hash`
+/// included, serialized without its own `hash` field.
+fn seal(mut entry: Map<String, Value>, prev_hash: &str) -> Sealed {
+    entry.insert("prev_hash".into(), prev_hash.into());
+    let hash = digest(&entry);
+    entry.insert("hash".into(), hash.clone().into());
+    Sealed {
+        text: Value::Object(entry).to_string(),
+        hash,
+    }
+}
+
+fn digest(entry: &Map<String, Value>) -> String {
+    hex::encode(Sha256::digest(
+        Value::Object(entry.clone()).to_string().as_bytes(),
+    ))
+}
+
+/// Check the hash chain of the log at `path`, returning how many entries it
+/// holds.
+pub fn verify(path: &Path) -> Result<usize> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut prev_hash = GENESIS.to_string();
+    let mut count = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let number = index + 1;
+        let Ok(Value::Object(mut entry)) = serde_json::from_str::<Value>(line) else {
+            bail!("line {number} is not a JSON object");
+        };
+        let Some(Value::String(hash)) = entry.remove("hash") else {
+            bail!("line {number} has no hash");
+        };
+        if entry.get("prev_hash").and_then(Value::as_str) != Some(prev_hash.as_str()) {
+            bail!("line {number} does not follow the entry before it");
+        }
+        if digest(&entry) != hash {
+            bail!("line {number} was modified after it was written");
+        }
+        prev_hash = hash;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tampering_breaks_the_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        for _ in 0..2 {
+            let mut policy = Map::new();
+            policy.insert("cooldown_minutes".into(), 1440.into());
+            let log = AuditLog::start(&path, policy).unwrap();
+            log.record(&Event::PinApplied {
+                name: "serde",
+                from: "1.0.210",
+                to: "1.0.200",
+            });
+            log.record(&Event::Blocked {
+                message: "nope",
+                code: None,
+            });
+        }
+        assert_eq!(verify(&path).unwrap(), 4);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::write(&path, contents.replacen("1.0.200", "1.0.199", 1)).unwrap();
+        assert!(verify(&path).unwrap_err().to_string().contains("line 1"));
+
+        let lines: Vec<&str> = contents.lines().collect();
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(verify(&path).unwrap_err().to_string().contains("line 2"));
+    }
+}
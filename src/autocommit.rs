@@ -0,0 +1,107 @@
+//! Committing the pins of a successful run to git.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::journal::PinRecord;
+
+/// Used when `commit_message_template` is not set. `{count}` is replaced
+/// with the number of pins and `{pins}` with one line per pin.
+pub const DEFAULT_TEMPLATE: &str = "Pin {count} crate(s) to cooled-down releases\n\n{pins}";
+
+pub fn render_message(template: &str, pins: &[PinRecord]) -> String {
+    let lines: Vec<String> = pins
+        .iter()
+        .map(|pin| {
+            let mut line = format!("- {} {} -> {}", pin.name, pin.from, pin.to);
+            if let Some(reason) = &pin.reason {
+                line.push_str(&format!(": {reason}"));
+            }
+            if let Some(published_at) = pin.published_at {
+                line.push_str(&format!(
+                    " (published {})",
+                    published_at.format("%Y-%m-%d %H:%M UTC")
+                ));
+            }
+            line
+        })
+        .collect();
+    template
+        .replace("{count}", &pins.len().to_string())
+        .replace("{pins}", &lines.join("\n"))
+}
+
+/// Commit the lockfiles of the workspaces in `pins`, and nothing else that
+/// happens to be staged, with a message from `template`.
+pub fn commit_pins(pins: &[PinRecord], template: &str) -> Result<()> {
+    let mut lockfiles: Vec<PathBuf> = pins
+        .iter()
+        .map(|pin| pin.workspace.join("Cargo.lock"))
+        .collect();
+    lockfiles.sort();
+    lockfiles.dedup();
+    let Some(first) = lockfiles.first() else {
+        return Ok(());
+    };
+    let dir = first.parent().unwrap_or(Path::new("."));
+    git(dir, &["add", "--"], &lockfiles)?;
+    git(
+        dir,
+        &["commit", "-m", &render_message(template, pins), "--"],
+        &lockfiles,
+    )
+}
+
+fn git(dir: &Path, args: &[&str], paths: &[PathBuf]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .args(paths)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn lists_every_pin_in_the_message() {
+        let published_at = Utc.with_ymd_and_hms(2025, 1, 2, 9, 30, 0).unwrap();
+        let pin = |name: &str, reason: Option<&str>, published_at| PinRecord {
+            pinned_at: Utc::now(),
+            workspace: PathBuf::from("/work/app"),
+            name: name.into(),
+            from: "1.0.2".into(),
+            to: "1.0.1".into(),
+            reason: reason.map(str::to_string),
+            published_at,
+        };
+        let pins = [
+            pin(
+                "serde",
+                Some("inside its 1440-minute cooldown window"),
+                Some(published_at),
+            ),
+            pin("itoa", None, None),
+        ];
+        assert_eq!(
+            render_message(DEFAULT_TEMPLATE, &pins),
+            "Pin 2 crate(s) to cooled-down releases\n\n\
+             - serde 1.0.2 -> 1.0.1: inside its 1440-minute cooldown window (published 2025-01-02 09:30 UTC)\n\
+             - itoa 1.0.2 -> 1.0.1"
+        );
+    }
+}
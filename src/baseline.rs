@@ -0,0 +1,124 @@
+//! Baseline lockfiles for diff-based enforcement: releases already present in
+//! the baseline count as reviewed, so only version changes get the cooldown.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use sha2::{Digest, Sha256};
+
+use crate::cache::Cache;
+use crate::config::Baseline;
+use crate::lockfile::{self, LockedPackage};
+
+/// Cache prefix for lockfiles that passed a run. Crate names cannot start
+/// with `_`, so this never shadows a cached crate.
+const STORE: &str = "_baselines";
+
+/// `(name, version)` of every registry release in a lockfile.
+pub type Releases = HashSet<(String, String)>;
+
+/// Releases recorded in `baseline`. Git references are looked up in the
+/// repository that holds `lockfile`.
+pub fn load(baseline: &Baseline, lockfile: &Path, cache: &Cache) -> Result<Releases> {
+    let contents = match baseline {
+        Baseline::File(path) => fs::read_to_string(path)
+            .with_context(|| format!("failed to read baseline lockfile {}", path.display()))?,
+        // A revision without a lockfile has nothing reviewed yet.
+        Baseline::GitRef(reference) => git_show(reference, lockfile)?.unwrap_or_default(),
+        Baseline::Digest(digest) => cache
+            .get_stale::<String>(&format!("{STORE}/{digest}"))?
+            .with_context(|| {
+                format!("no lockfile with sha256 {digest} passed an earlier run with this cache")
+            })?,
+    };
+    Ok(releases(&lockfile::parse(&contents)?))
+}
+
+pub fn releases(packages: &[LockedPackage]) -> Releases {
+    packages
+        .iter()
+        .filter(|pkg| pkg.source.is_some())
+        .map(|pkg| (pkg.name.clone(), pkg.version.clone()))
+        .collect()
+}
+
+/// Contents of `lockfile` as of the git revision `reference`, or `None` when
+/// the file did not exist in that revision.
+pub fn git_show(reference: &str, lockfile: &Path) -> Result<Option<String>> {
+    let dir = lockfile
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let name = lockfile
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Cargo.lock");
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("show")
+        .arg(format!("{reference}:./{name}"))
+        .output()
+        .context("failed to run `git show`")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("does not exist in") || stderr.contains("exists on disk, but not in") {
+            return Ok(None);
+        }
+        bail!("`git show {reference}:{name}` failed: {}", stderr.trim());
+    }
+    String::from_utf8(output.stdout)
+        .map(Some)
+        .context("baseline lockfile is not valid UTF-8")
+}
+
+/// Keep a copy of a lockfile that passed, so `sha256:<digest>` can name it
+/// as the baseline of a later run.
+pub fn remember(lockfile: &Path, cache: &Cache) -> Result<()> {
+    let contents = fs::read_to_string(lockfile)
+        .with_context(|| format!("failed to read {}", lockfile.display()))?;
+    let digest = hex::encode(Sha256::digest(contents.as_bytes()));
+    cache.put(&format!("{STORE}/{digest}"), &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn stored_baselines_are_found_by_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::with_root(dir.path().join("cache"), Duration::from_secs(60)).unwrap();
+        let lockfile = dir.path().join("Cargo.lock");
+        let contents = r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+        fs::write(&lockfile, contents).unwrap();
+        remember(&lockfile, &cache).unwrap();
+
+        let digest = hex::encode(Sha256::digest(contents.as_bytes()));
+        let releases = load(&Baseline::Digest(digest), &lockfile, &cache).unwrap();
+        assert_eq!(
+            releases,
+            Releases::from([("serde".to_string(), "1.0.210".to_string())])
+        );
+        assert!(load(&Baseline::Digest("00".into()), &lockfile, &cache).is_err());
+        assert_eq!(
+            Baseline::parse("git:origin/main"),
+            Some(Baseline::GitRef("origin/main".into()))
+        );
+    }
+}
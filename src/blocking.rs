@@ -0,0 +1,209 @@
+//! The part of the graph that kept a run from pinning, drawn as Graphviz DOT
+//! or Mermaid so users can see why no acceptable combination exists.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Fresh releases, the packages that require them, and the requirements
+/// themselves, keyed by `name@version`.
+#[derive(Debug, Clone, Default)]
+pub struct BlockingGraph {
+    nodes: BTreeMap<String, Node>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    /// Why the release is in the graph, when it is fresh.
+    fresh: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    from: String,
+    to: String,
+    requirement: String,
+}
+
+impl BlockingGraph {
+    /// A release inside its cooldown window; `note` says how long it needs.
+    pub fn add_fresh(&mut self, release: String, note: String) {
+        self.nodes.insert(release, Node { fresh: Some(note) });
+    }
+
+    /// `dependent` requires `release` through `requirement`.
+    pub fn add_requirement(&mut self, dependent: String, release: String, requirement: String) {
+        self.nodes
+            .entry(dependent.clone())
+            .or_insert(Node { fresh: None });
+        self.nodes
+            .entry(release.clone())
+            .or_insert(Node { fresh: None });
+        self.edges.push(Edge {
+            from: dependent,
+            to: release,
+            requirement,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Write the graph to `path`: Mermaid for `.mmd` and `.mermaid`, DOT
+    /// otherwise.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mermaid = path
+            .extension()
+            .is_some_and(|ext| ext == "mmd" || ext == "mermaid");
+        let rendered = if mermaid {
+            self.to_mermaid()
+        } else {
+            self.to_dot()
+        };
+        fs::write(path, rendered)
+            .with_context(|| format!("failed to write the blocking graph to {}", path.display()))
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph cooldown {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (release, node) in &self.nodes {
+            match &node.fresh {
+                Some(note) => {
+                    let _ = writeln!(
+                        out,
+                        "    \"{}\" [label=\"{}\\n{}\", color=red];",
+                        escape(release),
+                        escape(release),
+                        escape(note)
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "    \"{}\";", escape(release));
+                }
+            }
+        }
+        for edge in &self.edges {
+            let style = if is_exact(&edge.requirement) {
+                ", style=bold"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+                escape(&edge.from),
+                escape(&edge.to),
+                escape(&edge.requirement)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let ids: BTreeMap<&str, String> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(index, release)| (release.as_str(), format!("n{index}")))
+            .collect();
+        let mut out = String::from("graph LR\n");
+        for (release, node) in &self.nodes {
+            let label = match &node.fresh {
+                Some(note) => format!("{release}<br/>{note}"),
+                None => release.clone(),
+            };
+            let _ = writeln!(
+                out,
+                "    {}[\"{}\"]",
+                ids[release.as_str()],
+                label.replace('"', "#quot;")
+            );
+        }
+        for edge in &self.edges {
+            let arrow = if is_exact(&edge.requirement) {
+                "==>"
+            } else {
+                "-->"
+            };
+            let _ = writeln!(
+                out,
+                "    {} {arrow}|\"{}\"| {}",
+                ids[edge.from.as_str()],
+                edge.requirement.replace('"', "#quot;"),
+                ids[edge.to.as_str()]
+            );
+        }
+        let fresh: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.fresh.is_some())
+            .map(|(release, _)| ids[release.as_str()].as_str())
+            .collect();
+        if !fresh.is_empty() {
+            out.push_str("    classDef fresh stroke:#d00,stroke-width:2px\n");
+            let _ = writeln!(out, "    class {} fresh", fresh.join(","));
+        }
+        out
+    }
+}
+
+/// Exact requirements are the ones that force a parent to move along with
+/// the release, so they are drawn heavier.
+fn is_exact(requirement: &str) -> bool {
+    requirement.trim_start().starts_with('=')
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_dot_and_mermaid() {
+        let mut graph = BlockingGraph::default();
+        graph.add_fresh("tokio@1.40.0".into(), "fresh, needs 1d 0h".into());
+        graph.add_requirement("app@0.1.0".into(), "tokio@1.40.0".into(), "^1.40".into());
+        graph.add_requirement(
+            "tokio-util@0.7.12".into(),
+            "tokio@1.40.0".into(),
+            "=1.40.0".into(),
+        );
+
+        assert_eq!(
+            graph.to_dot().lines().collect::<Vec<_>>(),
+            [
+                "digraph cooldown {",
+                "    rankdir=LR;",
+                "    node [shape=box];",
+                r#"    "app@0.1.0";"#,
+                r#"    "tokio-util@0.7.12";"#,
+                r#"    "tokio@1.40.0" [label="tokio@1.40.0\nfresh, needs 1d 0h", color=red];"#,
+                r#"    "app@0.1.0" -> "tokio@1.40.0" [label="^1.40"];"#,
+                r#"    "tokio-util@0.7.12" -> "tokio@1.40.0" [label="=1.40.0", style=bold];"#,
+                "}",
+            ]
+        );
+        assert_eq!(
+            graph.to_mermaid().lines().collect::<Vec<_>>(),
+            [
+                "graph LR",
+                r#"    n0["app@0.1.0"]"#,
+                r#"    n1["tokio-util@0.7.12"]"#,
+                r#"    n2["tokio@1.40.0<br/>fresh, needs 1d 0h"]"#,
+                r#"    n0 -->|"^1.40"| n2"#,
+                r#"    n1 ==>|"=1.40.0"| n2"#,
+                "    classDef fresh stroke:#d00,stroke-width:2px",
+                "    class n2 fresh",
+            ]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Portable metadata bundles, exported on a connected machine and read back
+//! where there is no network: JSON bundles with the version lists needed to
+//! evaluate one lockfile, and `.tar.zst` archives of the whole cache.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::provider::{BoxFuture, VersionMetadataProvider};
+use crate::registry::VersionMeta;
+
+const BUNDLE_FORMAT: u32 = 1;
+
+/// Entries in the cache directory that describe this machine rather than the
+/// registry and therefore stay out of archives.
+const LOCAL_ONLY: [&str; 3] = ["pins.jsonl", "stamps", "_health"];
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetadataBundle {
+    pub format: u32,
+    pub generated_at: DateTime<Utc>,
+    /// Every known release per crate name.
+    pub versions: BTreeMap<String, Vec<VersionMeta>>,
+}
+
+impl MetadataBundle {
+    pub fn new(versions: BTreeMap<String, Vec<VersionMeta>>) -> Self {
+        Self {
+            format: BUNDLE_FORMAT,
+            generated_at: Utc::now(),
+            versions,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read metadata bundle {}", path.display()))?;
+        let bundle: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse metadata bundle {}", path.display()))?;
+        if bundle.format != BUNDLE_FORMAT {
+            bail!(
+                "metadata bundle {} has unsupported format {}",
+                path.display(),
+                bundle.format
+            );
+        }
+        Ok(bundle)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        fs::write(path, json)
+            .with_context(|| format!("failed to write metadata bundle {}", path.display()))
+    }
+
+    fn versions_of(&self, name: &str) -> Result<&[VersionMeta]> {
+        self.versions
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| anyhow!("crate {name} is not present in the metadata bundle"))
+    }
+}
+
+/// Whether `path` names a compressed cache archive rather than a JSON bundle.
+pub fn is_cache_archive(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    name.ends_with(".tar.zst") || name.ends_with(".tzst")
+}
+
+/// Pack every cache entry under `root` into a zstd-compressed tarball and
+/// return how many entries were written.
+pub fn export_cache(root: &Path, output: &Path) -> Result<usize> {
+    let files = cache_files(root)?;
+    let file =
+        File::create(output).with_context(|| format!("failed to create {}", output.display()))?;
+    let mut archive = tar::Builder::new(zstd::Encoder::new(file, 0)?);
+    let mut count = 0;
+    for path in files {
+        let relative = path.strip_prefix(root)?;
+        if path == output || LOCAL_ONLY.iter().any(|local| relative.starts_with(local)) {
+            continue;
+        }
+        archive
+            .append_path_with_name(&path, relative)
+            .with_context(|| format!("failed to archive {}", path.display()))?;
+        count += 1;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(count)
+}
+
+/// Unpack a cache archive into `root` and return how many entries were
+/// restored. Entries that would escape `root` are skipped.
+pub fn import_cache(bundle: &Path, root: &Path) -> Result<usize> {
+    let file =
+        File::open(bundle).with_context(|| format!("failed to open {}", bundle.display()))?;
+    let mut archive = tar::Archive::new(zstd::Decoder::new(file)?);
+    let mut count = 0;
+    for entry in archive
+        .entries()
+        .with_context(|| format!("failed to read {}", bundle.display()))?
+    {
+        let mut entry = entry?;
+        let is_file = entry.header().entry_type().is_file();
+        if entry.unpack_in(root)? && is_file {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn cache_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("failed to list {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+impl VersionMetadataProvider for MetadataBundle {
+    fn label(&self) -> &'static str {
+        "metadata bundle"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(async move {
+            self.versions_of(name)?
+                .iter()
+                .find(|meta| meta.num == version)
+                .cloned()
+                .ok_or_else(|| anyhow!("{name}@{version} is not present in the metadata bundle"))
+        })
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(async move { Ok(self.versions_of(name)?.to_vec()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    #[tokio::test]
+    async fn bundle_round_trips_and_answers_queries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bundle.json");
+        let meta = VersionMeta {
+            created_at: Utc::now(),
+            yanked: false,
+            num: "0.3.1".into(),
+            downloads: None,
+            license: None,
+        };
+        MetadataBundle::new(BTreeMap::from([("heapless".to_string(), vec![meta])]))
+            .write(&path)
+            .unwrap();
+
+        let bundle = MetadataBundle::load(&path).unwrap();
+        assert_eq!(
+            bundle.fetch_version("heapless", "0.3.1").await.unwrap().num,
+            "0.3.1"
+        );
+        assert!(bundle.fetch_version("heapless", "0.4.0").await.is_err());
+        assert!(bundle.list_versions("defmt").await.is_err());
+    }
+
+    #[test]
+    fn cache_archives_round_trip_without_local_files() {
+        let source = TempDir::new().unwrap();
+        fs::create_dir_all(source.path().join("serde")).unwrap();
+        fs::write(source.path().join("serde/_list"), "[]").unwrap();
+        fs::write(source.path().join("serde/1.0.0"), "{}").unwrap();
+        fs::write(source.path().join("pins.jsonl"), "").unwrap();
+
+        let archive = source.path().join("cache.tar.zst");
+        assert!(is_cache_archive(&archive));
+        assert_eq!(export_cache(source.path(), &archive).unwrap(), 2);
+
+        let target = TempDir::new().unwrap();
+        assert_eq!(import_cache(&archive, target.path()).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(target.path().join("serde/_list")).unwrap(),
+            "[]"
+        );
+        assert!(!target.path().join("pins.jsonl").exists());
+    }
+}
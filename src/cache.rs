@@ -1,10 +1,14 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 use dirs::cache_dir;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tracing::trace;
+
+use crate::config::Config;
+use crate::metrics;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct CacheEntry<T> {
@@ -38,6 +42,18 @@ impl Cache {
         Ok(Self { root, ttl })
     }
 
+    /// The cache selected by `COOLDOWN_CACHE_DIR`, or the user cache directory.
+    pub fn for_config(config: &Config) -> Result<Self> {
+        match &config.cache_dir {
+            Some(root) => Self::with_root(root.clone(), Duration::from_secs(config.ttl_seconds)),
+            None => Self::new(config.ttl_seconds),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
     fn path_for(&self, key: &str) -> PathBuf {
         let mut path = self.root.clone();
         for segment in key.split('/') {
@@ -54,19 +70,37 @@ impl Cache {
     }
 
     pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let Some(entry) = self.read_entry::<T>(key)? else {
+            metrics::cache_lookup(false);
+            return Ok(None);
+        };
+        let now = current_epoch();
+        if now.saturating_sub(entry.fetched_at) >= self.ttl.as_secs() {
+            trace!(key, "cache entry expired");
+            metrics::cache_lookup(false);
+            return Ok(None);
+        }
+        trace!(key, "cache hit");
+        metrics::cache_lookup(true);
+        Ok(Some(entry.value))
+    }
+
+    /// Like [`Cache::get`] but ignores the TTL, returning expired entries too.
+    pub fn get_stale<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        Ok(self.read_entry::<T>(key)?.map(|entry| entry.value))
+    }
+
+    fn read_entry<T: DeserializeOwned>(&self, key: &str) -> Result<Option<CacheEntry<T>>> {
         let path = self.path_for(key);
         if !path.exists() {
+            trace!(key, "cache miss");
             return Ok(None);
         }
         let contents = fs::read_to_string(&path)
             .with_context(|| format!("failed to read cache entry {}", path.display()))?;
         let entry: CacheEntry<T> = serde_json::from_str(&contents)
             .with_context(|| format!("failed to parse cache entry {}", path.display()))?;
-        let now = current_epoch();
-        if now.saturating_sub(entry.fetched_at) >= self.ttl.as_secs() {
-            return Ok(None);
-        }
-        Ok(Some(entry.value))
+        Ok(Some(entry))
     }
 
     pub fn put<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
@@ -109,5 +143,7 @@ mod tests {
         let expired = Cache::with_root(dir.path().to_path_buf(), Duration::from_secs(0)).unwrap();
         let value: Option<String> = expired.get("foo/bar").unwrap();
         assert!(value.is_none());
+        let stale: Option<String> = expired.get_stale("foo/bar").unwrap();
+        assert_eq!(stale.unwrap(), "hello");
     }
 }
@@ -0,0 +1,171 @@
+//! Which time counts toward a cooldown window. By default every minute
+//! does; with business days, weekends and holidays are skipped, so a release
+//! published on Friday evening is not cleared before anyone was at work to
+//! look at it.
+
+use std::collections::BTreeSet;
+use std::fs;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+
+use crate::config::Config;
+
+/// How far windows are followed through skipped days before giving up, so a
+/// huge window cannot loop for ever.
+const MAX_DAYS: usize = 100 * 366;
+
+/// Days are UTC days.
+#[derive(Debug, Clone, Default)]
+pub struct Calendar {
+    /// Saturdays and Sundays do not count.
+    skip_weekends: bool,
+    /// Dates that do not count.
+    holidays: BTreeSet<NaiveDate>,
+}
+
+impl Calendar {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let holidays = match &config.holidays_path {
+            Some(path) => {
+                let contents = fs::read_to_string(path).with_context(|| {
+                    format!("failed to read the holiday calendar at {}", path.display())
+                })?;
+                parse_holidays(&contents).with_context(|| {
+                    format!("failed to parse the holiday calendar at {}", path.display())
+                })?
+            }
+            None => BTreeSet::new(),
+        };
+        Ok(Self {
+            skip_weekends: config.business_days,
+            holidays,
+        })
+    }
+
+    fn counts(&self, date: NaiveDate) -> bool {
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        if self.skip_weekends && weekend {
+            return false;
+        }
+        !self.holidays.contains(&date)
+    }
+
+    fn is_wall_clock(&self) -> bool {
+        !self.skip_weekends && self.holidays.is_empty()
+    }
+
+    /// The latest publication instant at which a release has spent `minutes`
+    /// of counted time in its window by `now`.
+    pub fn window_start(&self, now: DateTime<Utc>, minutes: u64) -> DateTime<Utc> {
+        let mut remaining = Duration::minutes(minutes as i64);
+        if self.is_wall_clock() {
+            return now - remaining;
+        }
+        let mut end = now;
+        let mut date = now.date_naive();
+        for _ in 0..MAX_DAYS {
+            let start = date.and_time(NaiveTime::MIN).and_utc();
+            if self.counts(date) {
+                let available = end - start;
+                if available >= remaining {
+                    return end - remaining;
+                }
+                remaining -= available;
+            }
+            let Some(previous) = date.pred_opt() else {
+                break;
+            };
+            end = start;
+            date = previous;
+        }
+        DateTime::<Utc>::MIN_UTC
+    }
+
+    /// When a release published at `published_at` has spent `minutes` of
+    /// counted time in its window.
+    pub fn window_end(&self, published_at: DateTime<Utc>, minutes: u64) -> DateTime<Utc> {
+        let mut remaining = Duration::minutes(minutes as i64);
+        if self.is_wall_clock() {
+            return published_at + remaining;
+        }
+        let mut start = published_at;
+        let mut date = published_at.date_naive();
+        for _ in 0..MAX_DAYS {
+            let Some(next) = date.succ_opt() else {
+                break;
+            };
+            let end = next.and_time(NaiveTime::MIN).and_utc();
+            if self.counts(date) {
+                let available = end - start;
+                if available >= remaining {
+                    return start + remaining;
+                }
+                remaining -= available;
+            }
+            start = end;
+            date = next;
+        }
+        DateTime::<Utc>::MAX_UTC
+    }
+}
+
+/// One `YYYY-MM-DD` date per line; blank lines and `#` comments are
+/// ignored.
+fn parse_holidays(contents: &str) -> Result<BTreeSet<NaiveDate>> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            (!line.is_empty()).then_some((index + 1, line))
+        })
+        .map(|(number, line)| {
+            NaiveDate::parse_from_str(line, "%Y-%m-%d")
+                .with_context(|| format!("line {number}: `{line}` is not a YYYY-MM-DD date"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn business_days_skip_weekends_and_holidays() {
+        let calendar = Calendar {
+            skip_weekends: true,
+            holidays: parse_holidays("# office closed\n2025-01-06\n").unwrap(),
+        };
+        // Friday 2025-01-03 22:00, with three days (4320 minutes) to wait:
+        // two hours on Friday, nothing over the weekend or on the Monday
+        // holiday, then Tuesday through Thursday.
+        let published = at("2025-01-03T22:00:00Z");
+        let end = calendar.window_end(published, 4320);
+        assert_eq!(end, at("2025-01-09T22:00:00Z"));
+        assert_eq!(calendar.window_start(end, 4320), published);
+        assert!(calendar.window_start(end - Duration::minutes(1), 4320) < published);
+
+        // An hour into Monday, the window reaches back across the weekend:
+        // anything published before Monday has waited the hour.
+        let monday_morning = at("2025-01-13T01:00:00Z");
+        assert_eq!(
+            calendar.window_start(monday_morning, 60),
+            at("2025-01-13T00:00:00Z")
+        );
+        assert_eq!(
+            calendar.window_end(at("2025-01-11T10:00:00Z"), 60),
+            monday_morning
+        );
+
+        let wall = Calendar::default();
+        assert_eq!(wall.window_end(published, 4320), at("2025-01-06T22:00:00Z"));
+        assert!(parse_holidays("2025-13-01").is_err());
+    }
+}
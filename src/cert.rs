@@ -0,0 +1,277 @@
+//! Certificate format and verification shared by the CLI and build scripts.
+//!
+//! This module only depends on hashing, ed25519 and serde so it can be
+//! pulled in through the `cooldown-verify` feature without the rest of the
+//! tool.
+//!
+//! Certificates are signed with an ed25519 secret key that only the issuing
+//! run holds; verifiers get the public key, which cannot issue certificates.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const FORMAT: &str = "cargo-cooldown-cert/v2";
+
+/// Environment variable holding the hex-encoded ed25519 secret key that
+/// signs certificates.
+pub const KEY_ENV: &str = "COOLDOWN_CERT_KEY";
+
+/// Environment variable holding the hex-encoded ed25519 public key that
+/// verifies them.
+pub const PUBLIC_KEY_ENV: &str = "COOLDOWN_CERT_PUBLIC_KEY";
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("failed to read {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to parse certificate {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("unsupported certificate format {0}")]
+    UnsupportedFormat(String),
+    #[error("{PUBLIC_KEY_ENV} must be set to verify certificates")]
+    MissingKey,
+    #[error("invalid certificate key; expected 32 hex-encoded bytes")]
+    InvalidKey,
+    #[error("malformed certificate signature")]
+    MalformedSignature,
+    #[error(
+        "certificate signature does not match; it was tampered with or signed with a different key"
+    )]
+    BadSignature,
+    #[error(
+        "Cargo.lock changed since the certificate was issued (certified sha256 {certified}, current {current})"
+    )]
+    LockfileChanged { certified: String, current: String },
+    #[error("the cooldown policy changed since the certificate was issued")]
+    PolicyChanged,
+    #[error("no Cargo.lock found above {0}")]
+    LockfileNotFound(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Certificate {
+    pub format: String,
+    pub lockfile_sha256: String,
+    pub policy_digest: String,
+    pub issued_at: DateTime<Utc>,
+    pub tool_version: String,
+    /// Hex encoded ed25519 signature over every other field.
+    pub signature: String,
+}
+
+impl Certificate {
+    /// Sign with the hex-encoded ed25519 `secret_key`.
+    pub fn sign(
+        lockfile_sha256: String,
+        policy_digest: String,
+        issued_at: DateTime<Utc>,
+        secret_key: &str,
+    ) -> Result<Self, VerifyError> {
+        let key = SigningKey::from_bytes(&key_bytes(secret_key)?);
+        let mut certificate = Self {
+            format: FORMAT.to_string(),
+            lockfile_sha256,
+            policy_digest,
+            issued_at,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            signature: String::new(),
+        };
+        certificate.signature = hex::encode(key.sign(certificate.payload().as_bytes()).to_bytes());
+        Ok(certificate)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, VerifyError> {
+        let contents = fs::read_to_string(path).map_err(|source| VerifyError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        serde_json::from_str(contents.trim()).map_err(|source| VerifyError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn payload(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            self.format,
+            self.lockfile_sha256,
+            self.policy_digest,
+            self.issued_at.to_rfc3339(),
+            self.tool_version
+        )
+    }
+
+    /// Check the signature against the hex-encoded ed25519 `public_key`
+    /// first, then that the certificate still describes the given lockfile
+    /// and, when known, the given policy.
+    pub fn check(
+        &self,
+        lockfile_sha256: &str,
+        policy_digest: Option<&str>,
+        public_key: &str,
+    ) -> Result<(), VerifyError> {
+        if self.format != FORMAT {
+            return Err(VerifyError::UnsupportedFormat(self.format.clone()));
+        }
+        let key = VerifyingKey::from_bytes(&key_bytes(public_key)?)
+            .map_err(|_| VerifyError::InvalidKey)?;
+        let signature = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or(VerifyError::MalformedSignature)?;
+        if key
+            .verify_strict(self.payload().as_bytes(), &signature)
+            .is_err()
+        {
+            return Err(VerifyError::BadSignature);
+        }
+        if self.lockfile_sha256 != lockfile_sha256 {
+            return Err(VerifyError::LockfileChanged {
+                certified: self.lockfile_sha256.clone(),
+                current: lockfile_sha256.to_string(),
+            });
+        }
+        if policy_digest.is_some_and(|digest| digest != self.policy_digest) {
+            return Err(VerifyError::PolicyChanged);
+        }
+        Ok(())
+    }
+}
+
+/// The hex-encoded public key that verifies what `secret_key` signs.
+pub fn public_key(secret_key: &str) -> Result<String, VerifyError> {
+    let key = SigningKey::from_bytes(&key_bytes(secret_key)?);
+    Ok(hex::encode(key.verifying_key().to_bytes()))
+}
+
+fn key_bytes(key: &str) -> Result<[u8; 32], VerifyError> {
+    hex::decode(key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(VerifyError::InvalidKey)
+}
+
+pub fn file_sha256(path: &Path) -> Result<String, VerifyError> {
+    let contents = fs::read(path).map_err(|source| VerifyError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(hex::encode(Sha256::digest(&contents)))
+}
+
+/// Verify `certificate` against `lockfile` with the hex-encoded
+/// `public_key`.
+///
+/// The policy digest is not rechecked here: recomputing it needs the full CLI
+/// configuration, so the signature is what vouches for the policy in use.
+pub fn verify_file(
+    certificate: &Path,
+    lockfile: &Path,
+    public_key: &str,
+) -> Result<Certificate, VerifyError> {
+    let cert = Certificate::read(certificate)?;
+    cert.check(&file_sha256(lockfile)?, None, public_key)?;
+    Ok(cert)
+}
+
+/// Build-script entry point: verify `certificate` (relative to the package
+/// being built) against the nearest `Cargo.lock`, with the public key in
+/// `COOLDOWN_CERT_PUBLIC_KEY`.
+pub fn verify_workspace(certificate: impl AsRef<Path>) -> Result<Certificate, VerifyError> {
+    let key = env::var(PUBLIC_KEY_ENV)
+        .ok()
+        .filter(|key| !key.is_empty())
+        .ok_or(VerifyError::MissingKey)?;
+    let root = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let lockfile = find_lockfile(&root)?;
+    verify_file(&root.join(certificate), &lockfile, &key)
+}
+
+/// The workspace lockfile is either next to the package or in an ancestor.
+fn find_lockfile(start: &Path) -> Result<PathBuf, VerifyError> {
+    start
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+        .ok_or_else(|| VerifyError::LockfileNotFound(start.to_path_buf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const OTHER: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+
+    #[test]
+    fn detects_tampering_and_stale_lockfiles() {
+        let public = public_key(SECRET).unwrap();
+        let issued_at = Utc::now();
+        let certificate =
+            Certificate::sign("aaaa".into(), "pppp".into(), issued_at, SECRET).unwrap();
+        certificate.check("aaaa", Some("pppp"), &public).unwrap();
+
+        let other = public_key(OTHER).unwrap();
+        assert!(certificate.check("aaaa", Some("pppp"), &other).is_err());
+        assert!(certificate.check("bbbb", Some("pppp"), &public).is_err());
+        assert!(certificate.check("aaaa", Some("qqqq"), &public).is_err());
+
+        let mut forged = certificate.clone();
+        forged.lockfile_sha256 = "bbbb".into();
+        let err = forged.check("bbbb", Some("pppp"), &public).unwrap_err();
+        assert!(err.to_string().contains("signature"));
+    }
+
+    #[test]
+    fn the_public_key_cannot_issue_certificates() {
+        let public = public_key(SECRET).unwrap();
+        // Signing with the public key as if it were a secret yields a
+        // different key pair, which the verifier rejects.
+        let forged = Certificate::sign("aaaa".into(), "pppp".into(), Utc::now(), &public).unwrap();
+        assert!(matches!(
+            forged.check("aaaa", Some("pppp"), &public),
+            Err(VerifyError::BadSignature)
+        ));
+        assert!(matches!(
+            Certificate::sign("aaaa".into(), "pppp".into(), Utc::now(), "secret"),
+            Err(VerifyError::InvalidKey)
+        ));
+    }
+
+    #[test]
+    fn verifies_certificate_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile = dir.path().join("Cargo.lock");
+        fs::write(&lockfile, "version = 4\n").unwrap();
+        let certificate = Certificate::sign(
+            file_sha256(&lockfile).unwrap(),
+            "pppp".into(),
+            Utc::now(),
+            SECRET,
+        )
+        .unwrap();
+        let path = dir.path().join("cooldown.cert");
+        fs::write(&path, serde_json::to_string(&certificate).unwrap()).unwrap();
+
+        let public = public_key(SECRET).unwrap();
+        assert_eq!(verify_file(&path, &lockfile, &public).unwrap(), certificate);
+        fs::write(&lockfile, "version = 4\n# edited\n").unwrap();
+        assert!(matches!(
+            verify_file(&path, &lockfile, &public),
+            Err(VerifyError::LockfileChanged { .. })
+        ));
+    }
+}
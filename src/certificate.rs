@@ -0,0 +1,250 @@
+//! Signed "clean" certificates: proof that a lockfile passed the cooldown
+//! policy, so later pipeline stages can trust it without rerunning the check.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use clap_cargo::Manifest;
+use sha2::{Digest, Sha256};
+
+pub use cargo_cooldown::cert::{self as cert, Certificate};
+
+use crate::allowlist::Allowlist;
+use crate::config::Config;
+use crate::deny;
+use crate::metadata::lockfile_path;
+use crate::policy;
+use crate::vet;
+
+/// Issue a certificate for the lockfile of the workspace selected by `manifest`.
+pub fn issue(config: &Config, manifest: &Manifest) -> Result<Certificate> {
+    let key = signing_key(config)?;
+    let lockfile = lockfile_path(manifest)?;
+    Ok(Certificate::sign(
+        file_sha256(&lockfile)?,
+        policy_digest(config, lockfile.parent().unwrap_or(Path::new(".")))?,
+        Utc::now(),
+        key,
+    )?)
+}
+
+/// Write the certificate as a single JSON line to `path`, or stdout for `-`.
+pub fn write(certificate: &Certificate, path: &Path) -> Result<()> {
+    let json = serde_json::to_string(certificate)?;
+    if path.as_os_str() == "-" {
+        println!("{json}");
+    } else {
+        fs::write(path, format!("{json}\n"))
+            .with_context(|| format!("failed to write certificate to {}", path.display()))?;
+    }
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Certificate> {
+    Ok(Certificate::read(path)?)
+}
+
+/// Validate `certificate` against `lockfile` and the current policy.
+pub fn verify(certificate: &Certificate, config: &Config, lockfile: &Path) -> Result<()> {
+    let key = verifying_key(config)?;
+    let digest = policy_digest(config, lockfile.parent().unwrap_or(Path::new(".")))?;
+    Ok(certificate.check(&file_sha256(lockfile)?, Some(&digest), &key)?)
+}
+
+fn signing_key(config: &Config) -> Result<&str> {
+    match config.certificate_key.as_deref() {
+        Some(key) if !key.is_empty() => Ok(key),
+        _ => bail!("COOLDOWN_CERT_KEY must be set to sign certificates"),
+    }
+}
+
+/// The configured public key, or the one belonging to the signing key on
+/// the machine that issues certificates.
+fn verifying_key(config: &Config) -> Result<String> {
+    match (&config.certificate_public_key, &config.certificate_key) {
+        (Some(key), _) => Ok(key.clone()),
+        (None, Some(secret)) => Ok(cert::public_key(secret)?),
+        (None, None) => bail!("COOLDOWN_CERT_PUBLIC_KEY must be set to verify certificates"),
+    }
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    Ok(cargo_cooldown::cert::file_sha256(path)?)
+}
+
+/// Digest of every setting that decides whether a release is acceptable, and
+/// of the exemptions and bans the workspace at `workspace_root` adds, so a
+/// certificate issued under a looser policy is not accepted by a stricter one.
+pub fn policy_digest(config: &Config, workspace_root: &Path) -> Result<String> {
+    let allowlist = Allowlist::resolve_path(config.allowlist_path.clone());
+    let allowlist_digest = if allowlist.exists() {
+        file_sha256(&allowlist)?
+    } else {
+        "none".to_string()
+    };
+    let remote_digest = match policy::cached(config)? {
+        Some(contents) => hex::encode(Sha256::digest(contents.as_bytes())),
+        None => "none".to_string(),
+    };
+    let holidays_digest = match &config.holidays_path {
+        Some(path) => file_sha256(path)?,
+        None => "none".to_string(),
+    };
+    let workspace_digest = workspace_rules_digest(config, workspace_root)?;
+    let mut registries = config.allowed_registries.clone();
+    registries.sort();
+    let policy = format!(
+        "mode={:?}\nas_of={}\ncooldown_minutes={}\nwarn_minutes={:?}\nfreeze_after={}\nunknown_source_policy={:?}\nstrict_sources={}\ngit_minutes={:?}\noffline_ok={}\nallowed_registries={}\nmax_depth={:?}\nfilter_platform={}\nignore_dev_dependencies={}\nbuild_time={:?}\nbump_minutes={:?}\npatch_grace={}\nnew_crate={:?}/{}/{}\nsurvived_releases={:?}\nmin_downloads={:?}\nyanked={:?}\ntyposquat={:?}\nverify_checksums={}\nosv={}\nduplicate_majors={:?}\npin_strategy={:?}\nmax_downgrade={:?}\nrespect_msrv={:?}\nallow_license_change={}\nbaseline={:?}\ncargo_vet={}\ncargo_deny={}\nowner_change={:?}/{}\nbusiness_days={}\nholidays={holidays_digest}\nallowlist={allowlist_digest}\nremote_allowlist={remote_digest}\nworkspace={workspace_digest}",
+        config.mode,
+        config
+            .as_of
+            .map(|as_of| as_of.to_rfc3339())
+            .unwrap_or_default(),
+        config.cooldown_minutes,
+        config.warn_minutes,
+        config
+            .freeze_after
+            .map(|freeze| freeze.to_rfc3339())
+            .unwrap_or_default(),
+        config.unknown_source_policy,
+        config.strict_sources,
+        config.git_minutes,
+        config.offline_ok,
+        registries.join(","),
+        config.max_depth,
+        config.filter_platform,
+        config.ignore_dev_dependencies,
+        config.build_time,
+        config.bump_minutes,
+        config.patch_grace,
+        config.new_crate_minutes,
+        config.new_crate_min_versions,
+        config.new_crate_min_age_days,
+        config.survived_releases,
+        config.min_downloads,
+        config.yanked,
+        config.typosquat,
+        config.verify_checksums,
+        config.osv,
+        config.duplicate_majors,
+        config.pin_strategy,
+        config.max_downgrade,
+        config.respect_msrv,
+        config.allow_license_change,
+        config.baseline,
+        config.cargo_vet,
+        config.cargo_deny,
+        config.owner_change,
+        config.owner_change_minutes,
+        config.business_days,
+    );
+    Ok(hex::encode(Sha256::digest(policy.as_bytes())))
+}
+
+/// Digest of the per-crate rules in the workspace's `Cargo.toml` metadata,
+/// and of the cargo-vet audits and cargo-deny configuration the run takes
+/// exemptions and bans from when those are enabled.
+fn workspace_rules_digest(config: &Config, workspace_root: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    if let Some(rules) = Allowlist::from_manifest(workspace_root)? {
+        hasher.update(format!("manifest={rules:?}\n"));
+    }
+    if config.cargo_vet
+        && let Some(audits) = vet::load(workspace_root)?
+    {
+        hasher.update(format!("vet={audits:?}\n"));
+    }
+    if config.cargo_deny
+        && let Some(deny) = deny::load(workspace_root)?
+    {
+        hasher.update(format!(
+            "deny={:?}/{:?}\n",
+            deny.bans, deny.ignored_advisories
+        ));
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DuplicatePolicy, Mode};
+    use tempfile::tempdir;
+
+    #[test]
+    fn every_verdict_setting_changes_the_digest() {
+        let dir = tempdir().unwrap();
+        let mut base = Config::from_env();
+        base.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        base.allowlist_url = None;
+        base.holidays_path = None;
+        base.mode = Mode::Enforce;
+        base.as_of = None;
+        base.warn_minutes = None;
+        base.git_minutes = None;
+        base.strict_sources = false;
+        base.offline_ok = false;
+        base.filter_platform = false;
+        base.osv = false;
+        base.duplicate_majors = DuplicatePolicy::Report;
+        base.cargo_vet = true;
+        base.cargo_deny = true;
+        let digest = |config: &Config| policy_digest(config, dir.path()).unwrap();
+        let original = digest(&base);
+
+        type Change = fn(&mut Config);
+        let changes: [(&str, Change); 9] = [
+            ("mode", |config| config.mode = Mode::Warn),
+            ("as_of", |config| {
+                config.as_of = Some("2099-01-01T00:00:00Z".parse().unwrap())
+            }),
+            ("warn_minutes", |config| config.warn_minutes = Some(60)),
+            ("git_minutes", |config| config.git_minutes = Some(60)),
+            ("strict_sources", |config| config.strict_sources = true),
+            ("offline_ok", |config| config.offline_ok = true),
+            ("filter_platform", |config| config.filter_platform = true),
+            ("osv", |config| config.osv = true),
+            ("duplicate_majors", |config| {
+                config.duplicate_majors = DuplicatePolicy::Deny
+            }),
+        ];
+        for (setting, change) in changes {
+            let mut config = base.clone();
+            change(&mut config);
+            assert_ne!(digest(&config), original, "{setting}");
+        }
+
+        fs::write(
+            dir.path().join("deny.toml"),
+            "[bans]\ndeny = [{ name = \"left-pad\" }]\n",
+        )
+        .unwrap();
+        let banned = digest(&base);
+        assert_ne!(banned, original, "cargo-deny bans");
+
+        fs::create_dir(dir.path().join("supply-chain")).unwrap();
+        fs::write(
+            dir.path().join("supply-chain/audits.toml"),
+            "[[audits.serde]]\nwho = \"me\"\ncriteria = \"safe-to-deploy\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        let audited = digest(&base);
+        assert_ne!(audited, banned, "cargo-vet audits");
+
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[[package.metadata.cooldown.allow.package]]\ncrate = \"serde\"\nminimum_release_age = 60\n",
+        )
+        .unwrap();
+        let relaxed = digest(&base);
+        assert_ne!(relaxed, audited, "Cargo.toml metadata rules");
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[[package.metadata.cooldown.allow.package]]\ncrate = \"serde\"\nminimum_release_age = 1440\n",
+        )
+        .unwrap();
+        assert_ne!(digest(&base), relaxed, "Cargo.toml metadata rules");
+    }
+}
@@ -0,0 +1,137 @@
+//! Output for CI systems that understand more than log lines.
+
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::journal::PinRecord;
+use crate::report::{Violation, format_age};
+
+/// Report the violations `analyze` found: one annotation each, `error` when
+/// the run enforces and `warning` otherwise.
+pub fn report_violations(
+    violations: &[Violation],
+    enforce: bool,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let level = if enforce { "error" } else { "warning" };
+    let mut summary = String::from("## cargo-cooldown\n\n");
+    if violations.is_empty() {
+        summary.push_str("No release is inside its cooldown window.\n");
+    } else {
+        summary.push_str("| Crate | Version | Published | Age | Cooldown |\n");
+        summary.push_str("| --- | --- | --- | --- | --- |\n");
+    }
+    for violation in violations {
+        let age = format_age(now - violation.published_at);
+        println!(
+            "{}",
+            annotation(
+                level,
+                &format!(
+                    "{}@{} was published {age} ago, inside its {} minute cooldown",
+                    violation.name, violation.version, violation.minimum_minutes
+                )
+            )
+        );
+        summary.push_str(&format!(
+            "| `{}` | {} | {} | {age} | {} min |\n",
+            violation.name,
+            violation.version,
+            violation.published_at.format("%Y-%m-%d %H:%M UTC"),
+            violation.minimum_minutes
+        ));
+    }
+    append_env_file("GITHUB_STEP_SUMMARY", &summary)?;
+    append_env_file(
+        "GITHUB_OUTPUT",
+        &format!("blocked_count={}\npinned_count=0\n", violations.len()),
+    )
+}
+
+/// Report a pinning run: a warning for every pin applied, and an error with
+/// what blocked the run if it failed.
+pub fn report_pins(pins: &[PinRecord], blocked: Option<&str>) -> Result<()> {
+    let mut summary = String::from("## cargo-cooldown\n\n");
+    if pins.is_empty() && blocked.is_none() {
+        summary.push_str("No release is inside its cooldown window; nothing was pinned.\n");
+    }
+    if !pins.is_empty() {
+        summary.push_str("| Crate | Locked | Pinned to | Why |\n");
+        summary.push_str("| --- | --- | --- | --- |\n");
+    }
+    for pin in pins {
+        let reason = pin
+            .reason
+            .as_deref()
+            .unwrap_or("inside its cooldown window");
+        println!(
+            "{}",
+            annotation(
+                "warning",
+                &format!(
+                    "{} {} was pinned to {}: {reason}",
+                    pin.name, pin.from, pin.to
+                )
+            )
+        );
+        summary.push_str(&format!(
+            "| `{}` | {} | {} | {reason} |\n",
+            pin.name, pin.from, pin.to
+        ));
+    }
+    if let Some(blocked) = blocked {
+        println!("{}", annotation("error", blocked));
+        summary.push_str(&format!("\n**Blocked:** {}\n", blocked.replace('\n', " ")));
+    }
+    append_env_file("GITHUB_STEP_SUMMARY", &summary)?;
+    append_env_file(
+        "GITHUB_OUTPUT",
+        &format!(
+            "blocked_count={}\npinned_count={}\n",
+            usize::from(blocked.is_some()),
+            pins.len()
+        ),
+    )
+}
+
+/// A `::level title=cargo-cooldown::message` workflow command, with the
+/// message escaped so it stays on one line.
+fn annotation(level: &str, message: &str) -> String {
+    let message = message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    format!("::{level} title=cargo-cooldown::{message}")
+}
+
+/// Append to the file GitHub names in `var`; outside Actions the variable is
+/// unset and there is nothing to do.
+fn append_env_file(var: &str, contents: &str) -> Result<()> {
+    let Some(path) = env::var_os(var) else {
+        return Ok(());
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open ${var}"))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("failed to write ${var}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotations_stay_on_one_line() {
+        assert_eq!(
+            annotation("error", "100% blocked\nserde 1.0.210"),
+            "::error title=cargo-cooldown::100%25 blocked%0Aserde 1.0.210"
+        );
+    }
+}
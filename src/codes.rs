@@ -0,0 +1,376 @@
+//! Stable codes for each class of failure, printed with errors and in JSON
+//! output, so tools and runbooks can tell failures apart without matching
+//! on message text. `cargo cooldown explain <CODE>` prints the remediation
+//! guidance below.
+
+use std::fmt;
+
+use anyhow::anyhow;
+
+use crate::executor::CoolingDown;
+
+/// The discriminant is the code's number: `COOLDOWN002` is `NoCandidate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    FreshCrateBlocked = 1,
+    NoCandidate = 2,
+    RegistryUnreachable = 3,
+    PinRejected = 4,
+    ChecksumMismatch = 5,
+    YankedRelease = 6,
+    UnvettedSource = 7,
+    OwnershipChange = 8,
+    BannedDependency = 9,
+    SuspectedTyposquat = 10,
+    DirtyLockfile = 11,
+    BudgetExhausted = 12,
+    InvalidConfig = 13,
+    Aborted = 14,
+}
+
+impl ErrorCode {
+    /// Every code, in numeric order. Codes are never renumbered or reused.
+    pub const ALL: [ErrorCode; 14] = [
+        ErrorCode::FreshCrateBlocked,
+        ErrorCode::NoCandidate,
+        ErrorCode::RegistryUnreachable,
+        ErrorCode::PinRejected,
+        ErrorCode::ChecksumMismatch,
+        ErrorCode::YankedRelease,
+        ErrorCode::UnvettedSource,
+        ErrorCode::OwnershipChange,
+        ErrorCode::BannedDependency,
+        ErrorCode::SuspectedTyposquat,
+        ErrorCode::DirtyLockfile,
+        ErrorCode::BudgetExhausted,
+        ErrorCode::InvalidConfig,
+        ErrorCode::Aborted,
+    ];
+
+    fn number(self) -> usize {
+        self as usize
+    }
+
+    /// `COOLDOWN002`.
+    pub fn id(self) -> String {
+        format!("COOLDOWN{:03}", self.number())
+    }
+
+    /// `no-candidate`.
+    pub fn slug(self) -> &'static str {
+        match self {
+            ErrorCode::FreshCrateBlocked => "fresh-crate-blocked",
+            ErrorCode::NoCandidate => "no-candidate",
+            ErrorCode::RegistryUnreachable => "registry-unreachable",
+            ErrorCode::PinRejected => "pin-rejected",
+            ErrorCode::ChecksumMismatch => "checksum-mismatch",
+            ErrorCode::YankedRelease => "yanked-release",
+            ErrorCode::UnvettedSource => "unvetted-source",
+            ErrorCode::OwnershipChange => "ownership-change",
+            ErrorCode::BannedDependency => "banned-dependency",
+            ErrorCode::SuspectedTyposquat => "suspected-typosquat",
+            ErrorCode::DirtyLockfile => "dirty-lockfile",
+            ErrorCode::BudgetExhausted => "budget-exhausted",
+            ErrorCode::InvalidConfig => "invalid-config",
+            ErrorCode::Aborted => "aborted",
+        }
+    }
+
+    /// Accepts `COOLDOWN002`, `cooldown2`, `002`, `2`, or `no-candidate`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().to_ascii_lowercase();
+        let number = raw.strip_prefix("cooldown").unwrap_or(&raw);
+        if let Ok(number) = number.parse::<usize>() {
+            return Self::ALL.into_iter().find(|code| code.number() == number);
+        }
+        Self::ALL.into_iter().find(|code| code.slug() == raw)
+    }
+
+    /// What went wrong and how to get past it, for `cargo cooldown explain`.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ErrorCode::FreshCrateBlocked => {
+                "The graph locks releases that are still inside their cooldown window, and the run
+enforces the window without pinning them (`analyze` in enforce mode, or
+pin_strategy = \"fail\").
+
+Wait until the window passes (`--wait` does this for you), run a Cargo command
+through `cargo cooldown` to pin the releases back, or allowlist a release after
+reviewing it with `cargo cooldown allow`."
+            }
+            ErrorCode::NoCandidate => {
+                "A release inside its cooldown window has no older release to pin it to: every
+release that satisfies the dependency requirements is too young, yanked, or
+rejected by another policy, or moving back would exceed max_downgrade.
+
+Wait for the window to pass, relax the requirement in Cargo.toml so an older
+series qualifies, pin a reviewed version explicitly through [patch.crates-io],
+or allowlist the release after review."
+            }
+            ErrorCode::RegistryUnreachable => {
+                "No metadata source could say when a release was published, so its age could
+not be checked.
+
+Check the network and any proxy (COOLDOWN_HTTP_PROXY), retry later, or point
+metadata_sources at a source that works offline: a crates.io database dump, a
+clone of the registry index (index_repo_path), or a bundle made with
+`cargo cooldown export-metadata`."
+            }
+            ErrorCode::PinRejected => {
+                "Cargo refused every pin the run tried, usually because another dependency
+requires the fresh release, so the graph could not be cooled down.
+
+Run with `-v` to see Cargo's reasons. Update or pin the dependent that requires
+the fresh release, wait for the window to pass, or allowlist the release after
+review."
+            }
+            ErrorCode::ChecksumMismatch => {
+                "A checksum recorded in Cargo.lock differs from the one the registry index
+publishes for that release. The lockfile may have been tampered with, or it was
+generated against another registry.
+
+Do not build from it. Regenerate the affected entries with
+`cargo update -p <crate> --precise <version>` and review the diff."
+            }
+            ErrorCode::YankedRelease => {
+                "Cargo.lock locks releases their authors have since yanked, often because of a
+serious bug or a security issue.
+
+Move them with `cargo update -p <crate>`, or allowlist a release after
+reviewing why it was yanked."
+            }
+            ErrorCode::UnvettedSource => {
+                "The lockfile locks dependencies whose age cannot be checked (git, path, or
+unapproved registries) and the policy refuses them: strict_sources,
+unknown_source_policy = \"deny\", or a git commit inside the git cooldown window.
+
+Allowlist each dependency after review, move it to an approved registry, or
+wait for the git commit to cool down."
+            }
+            ErrorCode::OwnershipChange => {
+                "A release was published after the crate's owners changed, and
+owner_change = \"block\" holds such releases until someone reviews them.
+
+Check who the new owners are, then allowlist the release."
+            }
+            ErrorCode::BannedDependency => {
+                "The graph contains crates the allowlist's [[deny]] entries ban.
+
+Remove the dependency, or change the deny entry if the ban no longer applies."
+            }
+            ErrorCode::SuspectedTyposquat => {
+                "A dependency is named like a popular crate but is young or rarely downloaded,
+which is how typosquatting attacks look.
+
+Check the name in Cargo.toml. If the dependency is the one you meant,
+allowlist it after review."
+            }
+            ErrorCode::DirtyLockfile => {
+                "Pinning would rewrite a Cargo.lock that has uncommitted changes, mixing them
+with the pins, or the Cargo command was given --locked or --frozen and there is
+no Cargo.lock to check.
+
+Commit or stash the changes first, or pass --allow-dirty. Without a lockfile,
+run `cargo generate-lockfile` and commit it."
+            }
+            ErrorCode::BudgetExhausted => {
+                "Pinning stopped at max_iterations or max_duration_minutes with releases still
+to move, or reached a fixed point without resolving them.
+
+Raise the limits, run again to resume from the saved pins (resume_plans), or
+allowlist the remaining releases after review."
+            }
+            ErrorCode::InvalidConfig => {
+                "The configuration, the allowlist, or the cooldown metadata in Cargo.toml could
+not be read.
+
+Run `cargo cooldown config validate` to locate the problem."
+            }
+            ErrorCode::Aborted => {
+                "The run was stopped at an --interactive prompt. The lockfile was restored.
+
+Run again and choose another answer."
+            }
+        }
+    }
+
+    /// The class of outcome this failure maps to an exit code by.
+    pub fn outcome(self) -> Outcome {
+        match self {
+            ErrorCode::RegistryUnreachable => Outcome::Network,
+            ErrorCode::InvalidConfig => Outcome::Config,
+            ErrorCode::DirtyLockfile | ErrorCode::Aborted => Outcome::Error,
+            _ => Outcome::Violations,
+        }
+    }
+
+    /// An error carrying this code.
+    pub fn fail(self, message: impl fmt::Display) -> anyhow::Error {
+        self.wrap(anyhow!("{message}"))
+    }
+
+    /// `err` with this code, unless it already carries one.
+    pub fn wrap(self, err: anyhow::Error) -> anyhow::Error {
+        if classify(&err).is_some() {
+            return err;
+        }
+        anyhow::Error::new(Coded {
+            code: self,
+            inner: err,
+        })
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id())
+    }
+}
+
+/// Like `anyhow::bail!`, with the error carrying `code`.
+macro_rules! bail_with {
+    ($code:expr, $($arg:tt)+) => {
+        return Err($crate::codes::ErrorCode::fail($code, format!($($arg)+)))
+    };
+}
+pub(crate) use bail_with;
+
+/// How a run ended, which `exit_codes` maps to the process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    /// The policy blocked the build.
+    Violations,
+    /// Release ages could not be looked up.
+    Network,
+    /// The configuration could not be read.
+    Config,
+    /// Anything else that stopped the tool.
+    Error,
+    /// The forwarded Cargo command failed with this status.
+    Cargo(i32),
+}
+
+impl Outcome {
+    pub fn of(err: &anyhow::Error) -> Self {
+        classify(err).map_or(Outcome::Error, ErrorCode::outcome)
+    }
+
+    /// Its key in `exit_codes`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Violations => "violations",
+            Outcome::Network => "network",
+            Outcome::Config => "config",
+            Outcome::Error => "error",
+            Outcome::Cargo(_) => "cargo",
+        }
+    }
+}
+
+/// `cargo cooldown explain <CODE>`: the code's explanation.
+pub fn explain(raw: &str) -> anyhow::Result<String> {
+    let Some(code) = ErrorCode::parse(raw) else {
+        anyhow::bail!(
+            "unknown error code `{raw}`; codes run from {} to {}",
+            ErrorCode::ALL[0],
+            ErrorCode::ALL[ErrorCode::ALL.len() - 1]
+        );
+    };
+    Ok(format!(
+        "{code} ({}; exits as `{}`)\n\n{}\n",
+        code.slug(),
+        code.outcome().label(),
+        code.explanation()
+    ))
+}
+
+/// An error with its code; displays as the error it wraps.
+#[derive(Debug)]
+struct Coded {
+    code: ErrorCode,
+    inner: anyhow::Error,
+}
+
+impl fmt::Display for Coded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.inner)
+    }
+}
+
+impl std::error::Error for Coded {}
+
+/// The code `err` carries, if any layer of it has one.
+pub fn classify(err: &anyhow::Error) -> Option<ErrorCode> {
+    err.chain().find_map(|layer| {
+        if let Some(coded) = layer.downcast_ref::<Coded>() {
+            return Some(coded.code);
+        }
+        if let Some(cooling) = layer.downcast_ref::<CoolingDown>() {
+            return cooling.code;
+        }
+        layer
+            .downcast_ref::<reqwest::Error>()
+            .map(|_| ErrorCode::RegistryUnreachable)
+    })
+}
+
+/// `error[COOLDOWN002]: ...` plus where to read more, like rustc.
+pub fn render_error(err: &anyhow::Error) -> String {
+    match classify(err) {
+        Some(code) => format!(
+            "error[{code}]: {err:#}\n\nFor more information about this error, run `cargo cooldown explain {code}`."
+        ),
+        None => format!("error: {err:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn codes_keep_their_published_ids() {
+        assert_eq!(ErrorCode::FreshCrateBlocked.id(), "COOLDOWN001");
+        assert_eq!(ErrorCode::NoCandidate.id(), "COOLDOWN002");
+        assert_eq!(ErrorCode::RegistryUnreachable.id(), "COOLDOWN003");
+        assert_eq!(ErrorCode::PinRejected.id(), "COOLDOWN004");
+        assert_eq!(ErrorCode::ChecksumMismatch.id(), "COOLDOWN005");
+        assert_eq!(ErrorCode::YankedRelease.id(), "COOLDOWN006");
+        assert_eq!(ErrorCode::UnvettedSource.id(), "COOLDOWN007");
+        assert_eq!(ErrorCode::OwnershipChange.id(), "COOLDOWN008");
+        assert_eq!(ErrorCode::BannedDependency.id(), "COOLDOWN009");
+        assert_eq!(ErrorCode::SuspectedTyposquat.id(), "COOLDOWN010");
+        assert_eq!(ErrorCode::DirtyLockfile.id(), "COOLDOWN011");
+        assert_eq!(ErrorCode::BudgetExhausted.id(), "COOLDOWN012");
+        assert_eq!(ErrorCode::InvalidConfig.id(), "COOLDOWN013");
+        assert_eq!(ErrorCode::Aborted.id(), "COOLDOWN014");
+    }
+
+    #[test]
+    fn codes_survive_context_and_parse_back() {
+        for (index, code) in ErrorCode::ALL.into_iter().enumerate() {
+            assert_eq!(code.number(), index + 1);
+            assert_eq!(ErrorCode::parse(&code.id()), Some(code));
+            assert_eq!(ErrorCode::parse(code.slug()), Some(code));
+        }
+        assert_eq!(ErrorCode::parse("2"), Some(ErrorCode::NoCandidate));
+        assert_eq!(ErrorCode::parse("0"), None);
+        assert_eq!(ErrorCode::parse("COOLDOWN999"), None);
+
+        let err = Err::<(), _>(ErrorCode::NoCandidate.fail("no acceptable version found for foo"))
+            .context("while cooling down the workspace")
+            .unwrap_err();
+        assert_eq!(classify(&err), Some(ErrorCode::NoCandidate));
+        // Wrapping again keeps the first, more specific code.
+        let err = ErrorCode::BudgetExhausted.wrap(err);
+        assert_eq!(classify(&err), Some(ErrorCode::NoCandidate));
+        assert_eq!(
+            render_error(&err),
+            "error[COOLDOWN002]: while cooling down the workspace: no acceptable version found for foo\n\nFor more information about this error, run `cargo cooldown explain COOLDOWN002`."
+        );
+        assert_eq!(classify(&anyhow!("plain")), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::process::Command;
+
+use anyhow::{Context as _, Result, bail};
+use clap::Args;
+use semver::{Version, VersionReq};
+
+use super::Context;
+use crate::executor::{Session, newest_cooled};
+use crate::lockfile;
+use crate::metadata::{config_args, lockfile_path};
+use crate::resolver::{PinOutcome, try_pin_precise};
+
+#[derive(Debug, Args)]
+pub struct AddArgs {
+    /// Crates to add, as `NAME` or `NAME@REQ`.
+    #[arg(required = true, value_name = "CRATE")]
+    crates: Vec<String>,
+
+    /// Arguments passed on to `cargo add`, e.g. `-- --dev --features derive`.
+    #[arg(last = true, value_name = "CARGO_ADD_ARGS")]
+    cargo_args: Vec<String>,
+}
+
+pub async fn run(args: AddArgs, ctx: &Context<'_>) -> Result<i32> {
+    let session = Session::new(ctx.config).await?;
+    let now = ctx.config.now();
+    let mut chosen = Vec::new();
+    for spec in &args.crates {
+        let (name, req) = match spec.split_once('@') {
+            Some((name, req)) => (
+                name,
+                VersionReq::parse(req).with_context(|| format!("invalid version in `{spec}`"))?,
+            ),
+            None => (spec.as_str(), VersionReq::STAR),
+        };
+        let (version, minutes) = newest_cooled(&session, name, &req, now).await?;
+        let Some(version) = version else {
+            bail!(
+                "no release of {name} matching `{req}` is older than its {minutes}-minute cooldown window"
+            );
+        };
+        println!(
+            "adding {name} {version}, the newest release outside its {minutes}-minute cooldown window"
+        );
+        chosen.push((name.to_string(), version));
+    }
+
+    let manifest_path = ctx.manifest.manifest_path.as_deref();
+    let mut command = Command::new("cargo");
+    command.arg("add");
+    command.args(
+        chosen
+            .iter()
+            .map(|(name, version)| format!("{name}@{version}")),
+    );
+    if let Some(path) = manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+    command.args(config_args(&ctx.config.cargo_config));
+    command.args(&args.cargo_args);
+    let status = command.status().context("failed to run cargo add")?;
+    if !status.success() {
+        return Ok(status.code().unwrap_or(1));
+    }
+
+    // `cargo add` locks the newest release the new requirement accepts,
+    // which may be the fresh one; move it back to the chosen release.
+    let locked = lockfile::read(&lockfile_path(ctx.manifest)?)?;
+    for (name, version) in &chosen {
+        let series = VersionReq::parse(&format!("^{version}"))?;
+        let current = locked.iter().find(|pkg| {
+            &pkg.name == name
+                && Version::parse(&pkg.version).is_ok_and(|locked| series.matches(&locked))
+        });
+        let Some(current) = current.filter(|pkg| &pkg.version != version) else {
+            continue;
+        };
+        match try_pin_precise(
+            manifest_path,
+            &ctx.config.cargo_config,
+            name,
+            &current.version,
+            version,
+        )? {
+            PinOutcome::Applied => println!("locked {name} {} -> {version}", current.version),
+            PinOutcome::Rejected { stderr } => {
+                bail!(
+                    "cargo refused to lock {name} at {version}: {}",
+                    stderr.trim()
+                )
+            }
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::Duration;
+use clap::Args;
+
+use super::Context;
+use crate::duration::parse_duration;
+use crate::executor::{Session, release_age};
+use crate::lockfile;
+use crate::metadata::lockfile_path;
+use crate::report::render_ages;
+
+#[derive(Debug, Args)]
+pub struct AgesArgs {
+    /// Lockfile to list instead of the current workspace's `Cargo.lock`.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<PathBuf>,
+
+    /// Mark releases older than this (e.g. `365d`) as stale.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    threshold: Option<Duration>,
+}
+
+pub async fn run(args: AgesArgs, ctx: &Context<'_>) -> Result<i32> {
+    let lockfile = match args.lockfile {
+        Some(path) => path,
+        None => lockfile_path(ctx.manifest)?,
+    };
+    let packages = lockfile::read(&lockfile)?;
+    let session = Session::new(ctx.config).await?;
+
+    let now = ctx.config.now();
+    let mut ages = Vec::new();
+    for pkg in &packages {
+        let Some(source) = &pkg.source else {
+            continue;
+        };
+        if !ctx.config.is_registry_allowed(source) {
+            continue;
+        }
+        ages.push(release_age(&session, &pkg.name, &pkg.version, now).await);
+    }
+    print!("{}", render_ages(&ages, now, args.threshold));
+    Ok(0)
+}
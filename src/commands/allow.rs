@@ -0,0 +1,252 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result, bail};
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand};
+use semver::{Version, VersionReq};
+use toml::Value;
+
+use super::Context;
+use crate::allowlist::Allowlist;
+use crate::config::Config;
+use crate::duration::{parse_duration, parse_minutes};
+
+#[derive(Debug, Args)]
+pub struct AllowArgs {
+    #[command(subcommand)]
+    entry: AllowEntry,
+}
+
+#[derive(Debug, Subcommand)]
+enum AllowEntry {
+    /// Exempt a single release, e.g. `serde@1.0.210`.
+    Exact {
+        #[arg(value_name = "CRATE@VERSION")]
+        spec: String,
+        #[command(flatten)]
+        options: EntryOptions,
+    },
+    /// Give a crate (or a pattern such as `my-org-*`) its own cooldown window.
+    Package {
+        #[arg(value_name = "CRATE")]
+        crate_name: String,
+        /// Cooldown window for this crate, in minutes or as a duration
+        /// (`36h`, `7d`, `2w`).
+        #[arg(long, value_parser = parse_minutes)]
+        minutes: u64,
+        #[command(flatten)]
+        options: EntryOptions,
+    },
+    /// Exempt every release matched by a semver requirement.
+    Range {
+        #[arg(value_name = "CRATE")]
+        crate_name: String,
+        /// Requirement such as `">=1.0.200, <1.1"`.
+        #[arg(value_name = "REQ")]
+        req: VersionReq,
+        #[command(flatten)]
+        options: EntryOptions,
+    },
+}
+
+#[derive(Debug, Args)]
+pub(super) struct EntryOptions {
+    /// Why the exception exists, kept next to the entry for reviewers.
+    #[arg(long)]
+    reason: Option<String>,
+
+    /// When the exception lapses: a duration from now (`7d`, `36h`) or an
+    /// RFC 3339 timestamp.
+    #[arg(long, value_name = "DURATION|DATE")]
+    expires: Option<String>,
+}
+
+pub async fn run(args: AllowArgs, ctx: &Context<'_>) -> Result<i32> {
+    let (path, existing) = read_allowlist(ctx)?;
+    let entry = render_entry(&args.entry, Utc::now())?;
+    write_allowlist(&path, append_entry(&existing, &entry))?;
+    println!("added to {}:\n{entry}", path.display());
+    Ok(0)
+}
+
+/// Append an exact exemption for `name@version` on behalf of a run, e.g. one
+/// accepted at an `--interactive` prompt. Returns the allowlist path.
+pub(crate) fn append_exact(
+    config: &Config,
+    name: &str,
+    version: &str,
+    reason: &str,
+    expires: Option<DateTime<Utc>>,
+) -> Result<PathBuf> {
+    let path = Allowlist::resolve_path(config.allowlist_path.clone());
+    let existing = if path.exists() {
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let options = EntryOptions {
+        reason: Some(reason.to_string()),
+        expires: expires.map(|expires| expires.to_rfc3339()),
+    };
+    let entry = render_table(
+        "allow.exact",
+        vec![("crate", quoted(name)?), ("version", quoted(version)?)],
+        &options,
+        Utc::now(),
+    )?;
+    write_allowlist(&path, append_entry(&existing, &entry))?;
+    Ok(path)
+}
+
+/// Location and current contents of the allowlist file (empty when missing).
+pub(super) fn read_allowlist(ctx: &Context<'_>) -> Result<(PathBuf, String)> {
+    let path = Allowlist::resolve_path(ctx.config.allowlist_path.clone());
+    let existing = if path.exists() {
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    Ok((path, existing))
+}
+
+pub(super) fn write_allowlist(path: &Path, updated: String) -> Result<()> {
+    // Never write a file that would fail to load on the next run.
+    Allowlist::parse(&updated, &path.display().to_string())?;
+    fs::write(path, updated).with_context(|| format!("failed to write {}", path.display()))
+}
+
+pub(super) fn append_entry(existing: &str, entry: &str) -> String {
+    let mut updated = existing.to_string();
+    if !updated.is_empty() {
+        if !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push('\n');
+    }
+    updated.push_str(entry);
+    updated
+}
+
+fn render_entry(entry: &AllowEntry, now: DateTime<Utc>) -> Result<String> {
+    let (table, fields, options) = match entry {
+        AllowEntry::Exact { spec, options } => {
+            let Some((name, version)) = spec.split_once('@') else {
+                bail!("expected CRATE@VERSION, got `{spec}`");
+            };
+            Version::parse(version)
+                .with_context(|| format!("`{version}` is not a valid semver version"))?;
+            (
+                "allow.exact",
+                vec![("crate", quoted(name)?), ("version", quoted(version)?)],
+                options,
+            )
+        }
+        AllowEntry::Package {
+            crate_name,
+            minutes,
+            options,
+        } => (
+            "allow.package",
+            vec![
+                ("crate", quoted(crate_name)?),
+                ("minutes", minutes.to_string()),
+            ],
+            options,
+        ),
+        AllowEntry::Range {
+            crate_name,
+            req,
+            options,
+        } => (
+            "allow.range",
+            vec![
+                ("crate", quoted(crate_name)?),
+                ("req", quoted(&req.to_string())?),
+            ],
+            options,
+        ),
+    };
+
+    render_table(table, fields, options, now)
+}
+
+pub(super) fn render_table(
+    table: &str,
+    fields: Vec<(&str, String)>,
+    options: &EntryOptions,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let mut rendered = format!("[[{table}]]\n");
+    for (key, value) in fields {
+        rendered.push_str(&format!("{key} = {value}\n"));
+    }
+    if let Some(reason) = &options.reason {
+        rendered.push_str(&format!("reason = {}\n", quoted(reason)?));
+    }
+    if let Some(expires) = &options.expires {
+        let expires = parse_expiry(expires, now)?;
+        rendered.push_str(&format!(
+            "expires = {}\n",
+            quoted(&expires.format("%Y-%m-%dT%H:%M:%SZ").to_string())?
+        ));
+    }
+    Ok(rendered)
+}
+
+pub(super) fn quoted(value: &str) -> Result<String> {
+    if value.trim().is_empty() {
+        bail!("allowlist values must not be empty");
+    }
+    Ok(Value::String(value.to_string()).to_string())
+}
+
+fn parse_expiry(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return Ok(instant.with_timezone(&Utc));
+    }
+    let expires = now + parse_duration(value)?;
+    if expires <= now {
+        bail!("--expires must lie in the future");
+    }
+    Ok(expires)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn renders_entries_that_load_back() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let entry = AllowEntry::Exact {
+            spec: "serde@1.0.210".into(),
+            options: EntryOptions {
+                reason: Some("reviewed \"diff\"".into()),
+                expires: Some("7d".into()),
+            },
+        };
+        let rendered = render_entry(&entry, now).unwrap();
+        assert!(rendered.contains("expires = \"2025-03-08T12:00:00Z\""));
+
+        let existing = "[allow.global]\nminutes = 60";
+        let updated = append_entry(existing, &rendered);
+        let allowlist = Allowlist::parse(&updated, "test").unwrap();
+        assert_eq!(allowlist.global_minutes(), Some(60));
+        assert_eq!(allowlist.allow.exact[0].crate_name, "serde");
+        assert_eq!(
+            allowlist.allow.exact[0].reason.as_deref(),
+            Some("reviewed \"diff\"")
+        );
+
+        let bad = AllowEntry::Exact {
+            spec: "serde".into(),
+            options: EntryOptions {
+                reason: None,
+                expires: None,
+            },
+        };
+        assert!(render_entry(&bad, now).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use cargo_metadata::{Metadata, MetadataCommand};
+use clap::Args;
+use tracing::debug;
+
+use super::Context;
+use crate::bundle::{MetadataBundle, import_cache, is_cache_archive};
+use crate::ci;
+use crate::config::{CiProvider, Mode};
+use crate::executor::{Session, analyze_lockfile, analyze_metadata, check_lockfile, load_baseline};
+use crate::lockfile;
+use crate::metadata::read_metadata;
+use crate::notify;
+use crate::provider::{CacheOnly, ProviderChain, VersionMetadataProvider};
+use crate::report::{ReportFormat, render_deny_json, render_soft, render_violations, triage};
+
+#[derive(Debug, Args)]
+pub struct AnalyzeArgs {
+    /// Read `cargo metadata --format-version 1` output from this file (`-` for
+    /// stdin) instead of invoking Cargo.
+    #[arg(long, value_name = "PATH", conflicts_with = "lockfile")]
+    metadata_json: Option<PathBuf>,
+
+    /// Evaluate this `Cargo.lock` directly, without Cargo or a workspace.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<PathBuf>,
+
+    /// Answer every publication query from a bundle written by
+    /// `cargo cooldown export-metadata` instead of the network. A `.tar.zst`
+    /// cache archive is imported into the cache first.
+    #[arg(long, value_name = "PATH")]
+    metadata_bundle: Option<PathBuf>,
+
+    /// Output format for the violations.
+    #[arg(long, value_enum, default_value_t)]
+    format: ReportFormat,
+}
+
+pub async fn run(args: AnalyzeArgs, ctx: &Context<'_>) -> Result<i32> {
+    let mut session = Session::new(ctx.config).await?;
+    if let Some(path) = &args.metadata_bundle {
+        let provider: Box<dyn VersionMetadataProvider> = if is_cache_archive(path) {
+            import_cache(path, session.cache().root())?;
+            Box::new(CacheOnly::new(session.cache().clone()))
+        } else {
+            Box::new(MetadataBundle::load(path)?)
+        };
+        session = session.with_provider(ProviderChain::new(vec![provider]));
+    }
+
+    let (lockfile, violations) = match &args.lockfile {
+        Some(path) => {
+            let packages = lockfile::read(path)?;
+            session.set_baseline(load_baseline(&session, path, true)?);
+            session.load_workspace_policy(path)?;
+            (path.clone(), analyze_lockfile(&session, &packages).await?)
+        }
+        None => {
+            let metadata = match &args.metadata_json {
+                Some(path) => load_metadata_json(path)?,
+                None => read_metadata(ctx.manifest, ctx.features, &ctx.platforms, ctx.config)?,
+            };
+            let lockfile = metadata
+                .workspace_root
+                .join("Cargo.lock")
+                .into_std_path_buf();
+            session.set_baseline(load_baseline(&session, &lockfile, true)?);
+            session.load_workspace_policy(&lockfile)?;
+            (lockfile, analyze_metadata(&session, metadata).await?)
+        }
+    };
+
+    check_lockfile(&session, &lockfile).await?;
+
+    let enforce = ctx.config.mode == Mode::Enforce;
+    if ctx.config.ci == Some(CiProvider::Github) {
+        ci::report_violations(&violations, enforce, ctx.config.now())?;
+    }
+    if enforce && !violations.is_empty() {
+        notify::blocked(
+            ctx.config,
+            &notify::project_name(&lockfile),
+            &format!("{} release(s) inside the cooldown window", violations.len()),
+            &violations,
+            ctx.config.now(),
+        )
+        .await;
+    }
+    if args.format == ReportFormat::DenyJson {
+        let severity = if enforce { "error" } else { "warning" };
+        print!(
+            "{}",
+            render_deny_json(
+                &violations,
+                &session.soft_violations(),
+                ctx.config.now(),
+                severity
+            )
+        );
+        return Ok(i32::from(enforce && !violations.is_empty()));
+    }
+    let soft = session.soft_violations();
+    if !soft.is_empty() {
+        print!("{}", render_soft(&soft, ctx.config.now()));
+    }
+    if violations.is_empty() {
+        println!("dependency graph cooled down; no releases inside the cooldown window");
+        return Ok(0);
+    }
+
+    let wanted: Vec<(String, String)> = violations
+        .iter()
+        .map(|violation| (violation.name.clone(), violation.version.clone()))
+        .collect();
+    let introductions = lockfile::introductions(&lockfile, &wanted).unwrap_or_else(|err| {
+        debug!(error = %err, "lockfile history unavailable; skipping lockfile correlation");
+        Default::default()
+    });
+    let findings = triage(&violations, &introductions);
+    if !findings.is_empty() {
+        println!("Likely common cause:");
+        for finding in &findings {
+            println!("  - {finding}");
+        }
+        println!();
+    }
+    print!("{}", render_violations(&violations, ctx.config.now()));
+    Ok(i32::from(enforce))
+}
+
+fn load_metadata_json(path: &PathBuf) -> Result<Metadata> {
+    let json = if path.as_os_str() == "-" {
+        let mut buffer = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buffer)
+            .context("failed to read cargo metadata JSON from stdin")?;
+        buffer
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?
+    };
+    MetadataCommand::parse(json).context("failed to parse cargo metadata JSON")
+}
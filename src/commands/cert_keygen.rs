@@ -0,0 +1,19 @@
+use anyhow::{Context as _, Result};
+use clap::Args;
+
+use super::Context;
+use crate::certificate::cert;
+
+#[derive(Debug, Args)]
+pub struct CertKeygenArgs {}
+
+pub async fn run(_args: CertKeygenArgs, _ctx: &Context<'_>) -> Result<i32> {
+    let mut seed = [0u8; 32];
+    getrandom::fill(&mut seed).context("failed to generate a certificate key")?;
+    let secret = hex::encode(seed);
+    let public = cert::public_key(&secret)?;
+    println!("# Keep this one secret: it issues certificates.");
+    println!("{}={secret}", cert::KEY_ENV);
+    println!("{}={public}", cert::PUBLIC_KEY_ENV);
+    Ok(0)
+}
@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use clap::{Args, Command, CommandFactory};
+use clap_complete::Shell;
+
+use super::{BuiltinCli, Context};
+use crate::CargoCli;
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    /// Shell to write the completion script for.
+    #[arg(value_enum)]
+    shell: Shell,
+}
+
+pub async fn run(args: CompletionsArgs, _ctx: &Context<'_>) -> Result<i32> {
+    let mut script = Vec::new();
+    clap_complete::generate(args.shell, &mut command(), "cargo-cooldown", &mut script);
+    io::stdout().write_all(&script)?;
+    Ok(0)
+}
+
+/// `cargo-cooldown` with the wrapper's flags and every built-in command.
+/// Forwarded Cargo commands are left to Cargo's own completion.
+fn command() -> Command {
+    let cargo = CargoCli::command();
+    let cooldown = cargo
+        .find_subcommand("cooldown")
+        .expect("the cooldown command is defined");
+    Command::new("cargo-cooldown")
+        .about(cooldown.get_about().cloned().unwrap_or_default())
+        .args(
+            cooldown
+                .get_arguments()
+                .filter(|arg| !arg.is_positional())
+                .cloned(),
+        )
+        .subcommands(BuiltinCli::command().get_subcommands().cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_flags_and_builtin_commands() {
+        let command = command();
+        command.clone().debug_assert();
+        assert!(command.find_subcommand("analyze").is_some());
+        assert!(command.find_subcommand("install-alias").is_some());
+        assert!(
+            command
+                .get_arguments()
+                .any(|arg| arg.get_long() == Some("pin-strategy"))
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context as _, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::{Map, Value, json};
+
+use super::Context;
+use crate::allowlist::Allowlist;
+use crate::config::{Origin, config_files, manifest_metadata, validate_settings};
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Print every setting's effective value and where it came from: a flag,
+    /// an environment variable, a config file, or the default.
+    Show(ShowArgs),
+    /// Check the config files and the allowlist for unknown keys, suggesting
+    /// the closest setting for typos, and for values of the wrong type. Exits
+    /// with status 1 when a file has problems.
+    Validate,
+}
+
+#[derive(Debug, Args)]
+struct ShowArgs {
+    #[arg(long, value_enum, default_value_t)]
+    format: ShowFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum ShowFormat {
+    /// `key = value`, each followed by a comment naming its source.
+    #[default]
+    Toml,
+    /// `{"key": {"value": ..., "source": ...}}`.
+    Json,
+}
+
+impl ConfigArgs {
+    /// Whether this is `config validate`, which reports config files that
+    /// do not load rather than failing on them.
+    pub fn validates(&self) -> bool {
+        matches!(self.command, ConfigCommand::Validate)
+    }
+}
+
+pub async fn run(args: ConfigArgs, ctx: &Context<'_>) -> Result<i32> {
+    match args.command {
+        ConfigCommand::Show(args) => show(args, ctx),
+        ConfigCommand::Validate => validate(ctx),
+    }
+}
+
+fn show(args: ShowArgs, ctx: &Context<'_>) -> Result<i32> {
+    let Value::Object(values) =
+        serde_json::to_value(ctx.config).context("failed to serialize the configuration")?
+    else {
+        unreachable!("the configuration serializes to a map");
+    };
+    let origin = |key: &str| ctx.config.origin(key);
+    match args.format {
+        ShowFormat::Toml => print!("{}", render_toml(&values, origin)),
+        ShowFormat::Json => {
+            let annotated: Map<String, Value> = values
+                .into_iter()
+                .map(|(key, value)| {
+                    let source = origin(&key).to_string();
+                    (key, json!({ "value": value, "source": source }))
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&annotated)?);
+        }
+    }
+    Ok(0)
+}
+
+fn validate(ctx: &Context<'_>) -> Result<i32> {
+    let mut files = Vec::new();
+    for path in config_files() {
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let problems = if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+            match manifest_metadata(&contents) {
+                Ok(Some(mut settings)) => {
+                    let mut rules = toml::Table::new();
+                    for key in ["allow", "deny"] {
+                        if let Some(value) = settings.remove(key) {
+                            rules.insert(key.to_string(), value);
+                        }
+                    }
+                    let mut problems = validate_settings(&settings);
+                    problems.extend(Allowlist::validate(&rules));
+                    problems
+                }
+                Ok(None) => continue,
+                Err(err) => vec![err],
+            }
+        } else {
+            match toml::from_str(&contents) {
+                Ok(table) => validate_settings(&table),
+                Err(err) => vec![err.to_string()],
+            }
+        };
+        files.push((path, problems));
+    }
+
+    let allowlist = Allowlist::resolve_path(ctx.config.allowlist_path.clone());
+    if allowlist.exists() {
+        let contents = fs::read_to_string(&allowlist)
+            .with_context(|| format!("failed to read {}", allowlist.display()))?;
+        let problems = match toml::from_str(&contents) {
+            Ok(table) => Allowlist::validate(&table),
+            Err(err) => vec![err.to_string()],
+        };
+        files.push((allowlist, problems));
+    }
+
+    if files.is_empty() {
+        println!("no config file or allowlist found");
+        return Ok(0);
+    }
+    for (path, problems) in &files {
+        print!("{}", render_problems(path, problems));
+    }
+    Ok(i32::from(
+        files.iter().any(|(_, problems)| !problems.is_empty()),
+    ))
+}
+
+fn render_problems(path: &Path, problems: &[String]) -> String {
+    if problems.is_empty() {
+        return format!("{}: ok\n", path.display());
+    }
+    let mut out = format!("{}:\n", path.display());
+    for problem in problems {
+        // TOML syntax errors span several lines.
+        for (index, line) in problem.trim_end().lines().enumerate() {
+            let bullet = if index == 0 { "  - " } else { "    " };
+            out.push_str(&format!("{bullet}{line}\n"));
+        }
+    }
+    out
+}
+
+/// Settings without a value are listed as comments, so the output stays
+/// valid TOML.
+fn render_toml(values: &Map<String, Value>, origin: impl Fn(&str) -> Origin) -> String {
+    let mut out = String::new();
+    for (key, value) in values {
+        let source = origin(key);
+        match toml::Value::try_from(without_nulls(value)) {
+            Ok(value) => out.push_str(&format!("{key} = {value}  # {source}\n")),
+            Err(_) => out.push_str(&format!("# {key} is unset  # {source}\n")),
+        }
+    }
+    out
+}
+
+/// TOML has no null: nested settings that are unset are left out instead.
+fn without_nulls(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(without_nulls).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn annotates_each_value_with_its_source() {
+        let values = json!({
+            "cooldown_minutes": 10080,
+            "mode": "enforce",
+            "build_time": { "multiplier": 2.0, "minutes": null },
+            "allowlist_path": null,
+        });
+        let Value::Object(values) = values else {
+            unreachable!()
+        };
+        let rendered = render_toml(&values, |key| match key {
+            "cooldown_minutes" => Origin::File(PathBuf::from("/work/app/cooldown.toml")),
+            "mode" => Origin::Env("COOLDOWN_MODE".into()),
+            _ => Origin::Default,
+        });
+        assert_eq!(
+            rendered,
+            "# allowlist_path is unset  # default\n\
+             build_time = { multiplier = 2.0 }  # default\n\
+             cooldown_minutes = 10080  # file /work/app/cooldown.toml\n\
+             mode = \"enforce\"  # env COOLDOWN_MODE\n"
+        );
+    }
+}
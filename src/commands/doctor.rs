@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Instant;
+
+use anyhow::Result;
+use clap::Args;
+use reqwest::{Client, Url};
+
+use super::Context;
+use crate::cache::Cache;
+use crate::config::{Config, Mode, Origin, config_files, file_settings};
+use crate::executor::has_bans;
+use crate::metadata::lockfile_path;
+use crate::registry::http_client;
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one check, with what to do about it when it did not pass.
+#[derive(Debug)]
+struct Check {
+    status: Status,
+    name: String,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            status: Status::Ok,
+            name: name.to_string(),
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            status: Status::Warn,
+            fix: Some(fix.into()),
+            ..Self::ok(name, detail)
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            status: Status::Fail,
+            fix: Some(fix.into()),
+            ..Self::ok(name, detail)
+        }
+    }
+}
+
+pub async fn run(_args: DoctorArgs, ctx: &Context<'_>) -> Result<i32> {
+    let mut checks = vec![cargo(), lockfile(ctx), cache(ctx.config)];
+    checks.extend(config(ctx.config));
+    checks.push(policy(ctx));
+    match http_client(ctx.config) {
+        Ok(client) => {
+            checks.push(
+                endpoint(
+                    &client,
+                    "registry API",
+                    &ctx.config.registry_api,
+                    "crates/serde",
+                    "COOLDOWN_REGISTRY_API",
+                )
+                .await,
+            );
+            checks.push(
+                endpoint(
+                    &client,
+                    "sparse index",
+                    &ctx.config.sparse_index_url,
+                    "config.json",
+                    "COOLDOWN_SPARSE_INDEX_URL",
+                )
+                .await,
+            );
+        }
+        Err(err) => checks.push(Check::fail(
+            "network",
+            format!("{err:#}"),
+            "fix COOLDOWN_HTTP_PROXY or COOLDOWN_CA_BUNDLE_PATH",
+        )),
+    }
+    print!("{}", render(&checks));
+    Ok(i32::from(
+        checks.iter().any(|check| check.status == Status::Fail),
+    ))
+}
+
+fn cargo() -> Check {
+    match Command::new("cargo").arg("--version").output() {
+        Ok(output) if output.status.success() => Check::ok(
+            "cargo",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        Ok(output) => Check::fail(
+            "cargo",
+            format!(
+                "`cargo --version` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            "repair the toolchain with `rustup update`",
+        ),
+        Err(err) => Check::fail(
+            "cargo",
+            format!("cargo could not be run: {err}"),
+            "install Rust through rustup, or put cargo on PATH",
+        ),
+    }
+}
+
+fn lockfile(ctx: &Context<'_>) -> Check {
+    match lockfile_path(ctx.manifest) {
+        Ok(path) if path.exists() => Check::ok("lockfile", path.display().to_string()),
+        Ok(path) => Check::warn(
+            "lockfile",
+            format!("{} does not exist yet", path.display()),
+            "run `cargo generate-lockfile` and commit it, so every run checks the same graph",
+        ),
+        Err(err) => Check::fail(
+            "lockfile",
+            format!("{err:#}"),
+            "run inside a Cargo workspace, or pass --manifest-path",
+        ),
+    }
+}
+
+fn cache(config: &Config) -> Check {
+    let fix =
+        "fix the directory's permissions, or point COOLDOWN_CACHE_DIR at a writable directory";
+    let cache = match Cache::for_config(config) {
+        Ok(cache) => cache,
+        Err(err) => return Check::fail("cache", format!("{err:#}"), fix),
+    };
+    let root = cache.root();
+    let probe = root.join(".doctor-probe");
+    match fs::write(&probe, b"").and_then(|()| fs::remove_file(&probe)) {
+        Ok(()) => Check::ok("cache", root.display().to_string()),
+        Err(err) => Check::fail(
+            "cache",
+            format!("{} is not writable: {err}", root.display()),
+            fix,
+        ),
+    }
+}
+
+/// Files that are ignored because they do not load, and settings whose
+/// value in one place is shadowed by another.
+fn config(config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let mut files = Vec::new();
+    for path in config_files() {
+        match file_settings(&path) {
+            Ok(settings) => files.push((path, settings)),
+            Err(err) => checks.push(Check::fail(
+                "config",
+                format!("{} does not load: {err}", path.display()),
+                "`cargo cooldown config validate` lists every problem in the file",
+            )),
+        }
+    }
+    let shadowed = shadowed_settings(&files, |setting| config.origin(setting));
+    if shadowed.is_empty() {
+        let detail = match files.len() {
+            0 => "no config file; defaults and environment only".to_string(),
+            _ => files
+                .iter()
+                .map(|(path, _)| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        checks.push(Check::ok("config", detail));
+    }
+    for conflict in shadowed {
+        checks.push(Check::warn(
+            "config",
+            conflict,
+            "remove the value that does not apply, or check it with `cargo cooldown config show`",
+        ));
+    }
+    checks
+}
+
+/// Settings set in more than one file, or in a file and then overridden by
+/// the environment or a flag, each described with where the value that
+/// applies comes from.
+fn shadowed_settings(
+    files: &[(PathBuf, Vec<String>)],
+    origin: impl Fn(&str) -> Origin,
+) -> Vec<String> {
+    let mut set_in: BTreeMap<&str, Vec<&PathBuf>> = BTreeMap::new();
+    for (path, settings) in files {
+        for setting in settings {
+            set_in.entry(setting).or_default().push(path);
+        }
+    }
+    let mut shadowed = Vec::new();
+    for (setting, paths) in set_in {
+        let listed = paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" and ");
+        match origin(setting) {
+            source @ (Origin::Env(_) | Origin::Flag(_)) => {
+                shadowed.push(format!("`{setting}` in {listed} is overridden by {source}"));
+            }
+            Origin::File(winner) if paths.len() > 1 => {
+                shadowed.push(format!(
+                    "`{setting}` is set in {listed}; {} wins",
+                    winner.display()
+                ));
+            }
+            _ => {}
+        }
+    }
+    shadowed
+}
+
+fn policy(ctx: &Context<'_>) -> Check {
+    let config = ctx.config;
+    if config.mode == Mode::Off {
+        return Check::warn(
+            "policy",
+            "mode is off, so nothing is checked",
+            "unset COOLDOWN_MODE, or set it to `warn` or `enforce`",
+        );
+    }
+    if !config.has_policy() && !has_bans(config, ctx.manifest) {
+        return Check::warn(
+            "policy",
+            "no cooldown policy is configured, so commands go straight to Cargo",
+            "set COOLDOWN_MINUTES, or `cooldown_minutes` in cooldown.toml",
+        );
+    }
+    let mode = format!("{:?}", config.mode).to_lowercase();
+    Check::ok(
+        "policy",
+        format!("cooldown {} minutes, mode {mode}", config.cooldown_minutes),
+    )
+}
+
+async fn endpoint(client: &Client, name: &str, base: &str, path: &str, setting: &str) -> Check {
+    let url = match Url::parse(base).and_then(|base| base.join(path)) {
+        Ok(url) => url,
+        Err(err) => {
+            return Check::fail(
+                name,
+                format!("{base} is not a valid URL: {err}"),
+                format!("fix {setting}"),
+            );
+        }
+    };
+    let started = Instant::now();
+    let fix = format!(
+        "check the network and {setting}; behind a proxy set COOLDOWN_HTTP_PROXY, with a private CA set COOLDOWN_CA_BUNDLE_PATH, and without network access use `analyze --metadata-bundle`"
+    );
+    match client.get(url.clone()).send().await {
+        Ok(response) if response.status().is_success() => Check::ok(
+            name,
+            format!("{url} answered in {} ms", started.elapsed().as_millis()),
+        ),
+        Ok(response) => Check::fail(name, format!("{url} answered {}", response.status()), fix),
+        Err(err) => Check::fail(name, format!("{url} is unreachable: {err}"), fix),
+    }
+}
+
+fn render(checks: &[Check]) -> String {
+    let mut out = String::new();
+    for check in checks {
+        let status = match check.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        out.push_str(&format!("{status:<4}  {}: {}\n", check.name, check.detail));
+        if let Some(fix) = &check.fix {
+            out.push_str(&format!("      fix: {fix}\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_settings_shadowed_by_files_and_the_environment() {
+        let user = PathBuf::from("/home/me/.cargo/cooldown.toml");
+        let workspace = PathBuf::from("/work/app/cooldown.toml");
+        let files = vec![
+            (user, vec!["cooldown_minutes".into(), "mode".into()]),
+            (
+                workspace.clone(),
+                vec!["cooldown_minutes".into(), "ttl_seconds".into()],
+            ),
+        ];
+        let shadowed = shadowed_settings(&files, |setting| match setting {
+            "mode" => Origin::Env("COOLDOWN_MODE".into()),
+            _ => Origin::File(workspace.clone()),
+        });
+        assert_eq!(
+            shadowed,
+            vec![
+                "`cooldown_minutes` is set in /home/me/.cargo/cooldown.toml and /work/app/cooldown.toml; /work/app/cooldown.toml wins",
+                "`mode` in /home/me/.cargo/cooldown.toml is overridden by env COOLDOWN_MODE",
+            ]
+        );
+    }
+}
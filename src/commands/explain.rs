@@ -0,0 +1,25 @@
+use anyhow::Result;
+use clap::Args;
+
+use super::Context;
+use crate::codes::{self, ErrorCode};
+
+#[derive(Debug, Args)]
+pub struct ExplainArgs {
+    /// Code to explain, e.g. `COOLDOWN002`, `2`, or `no-candidate`. Lists
+    /// every code when omitted.
+    #[arg(value_name = "CODE")]
+    code: Option<String>,
+}
+
+pub async fn run(args: ExplainArgs, _ctx: &Context<'_>) -> Result<i32> {
+    match args.code {
+        Some(code) => print!("{}", codes::explain(&code)?),
+        None => {
+            for code in ErrorCode::ALL {
+                println!("{code}  {:<20}  {}", code.slug(), code.outcome().label());
+            }
+        }
+    }
+    Ok(0)
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use tracing::warn;
+
+use super::Context;
+use crate::bundle::{MetadataBundle, export_cache, is_cache_archive};
+use crate::executor::Session;
+use crate::lockfile;
+use crate::metadata::lockfile_path;
+use crate::registry::VersionMeta;
+
+#[derive(Debug, Args)]
+pub struct ExportMetadataArgs {
+    /// Where to write the bundle. A `.tar.zst` path writes an archive of the
+    /// whole cache instead of a JSON bundle for one lockfile.
+    #[arg(short, long, value_name = "PATH")]
+    output: PathBuf,
+
+    /// Lockfile whose registry packages are exported instead of the current
+    /// workspace's `Cargo.lock`. With a cache archive, its packages are
+    /// fetched into the cache before archiving.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<PathBuf>,
+}
+
+pub async fn run(args: ExportMetadataArgs, ctx: &Context<'_>) -> Result<i32> {
+    let session = Session::new(ctx.config).await?;
+
+    if is_cache_archive(&args.output) {
+        let mut missing = 0;
+        if let Some(lockfile) = &args.lockfile {
+            missing = collect_versions(&session, ctx, lockfile, &mut BTreeMap::new()).await?;
+        }
+        let exported = export_cache(session.cache().root(), &args.output)?;
+        println!(
+            "exported {exported} cache entries to {}",
+            args.output.display()
+        );
+        return Ok(if missing > 0 { 1 } else { 0 });
+    }
+
+    let lockfile = match args.lockfile {
+        Some(path) => path,
+        None => lockfile_path(ctx.manifest)?,
+    };
+    let mut versions = BTreeMap::new();
+    let missing = collect_versions(&session, ctx, &lockfile, &mut versions).await?;
+
+    let exported = versions.len();
+    MetadataBundle::new(versions).write(&args.output)?;
+    println!(
+        "exported version lists for {exported} crates to {}",
+        args.output.display()
+    );
+    Ok(if missing > 0 { 1 } else { 0 })
+}
+
+/// Fetch the version list of every registry package in `lockfile` through the
+/// cache and return how many could not be fetched.
+async fn collect_versions(
+    session: &Session<'_>,
+    ctx: &Context<'_>,
+    lockfile: &Path,
+    versions: &mut BTreeMap<String, Vec<VersionMeta>>,
+) -> Result<usize> {
+    let mut missing = 0;
+    for pkg in lockfile::read(lockfile)? {
+        let Some(source) = &pkg.source else {
+            continue;
+        };
+        if !ctx.config.is_registry_allowed(source) || versions.contains_key(&pkg.name) {
+            continue;
+        }
+        match session.version_list(&pkg.name).await {
+            Ok(list) => {
+                versions.insert(pkg.name, list);
+            }
+            Err(err) => {
+                warn!(crate = %pkg.name, error = %err, "could not export version list");
+                missing += 1;
+            }
+        }
+    }
+    Ok(missing)
+}
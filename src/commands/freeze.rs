@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::Utc;
+use clap::Args;
+
+use super::Context;
+use super::allow::{
+    EntryOptions, append_entry, quoted, read_allowlist, render_table, write_allowlist,
+};
+use crate::allowlist::Allowlist;
+use crate::config::Config;
+use crate::lockfile::{self, LockedPackage};
+use crate::metadata::lockfile_path;
+
+#[derive(Debug, Args)]
+pub struct FreezeArgs {
+    /// Lockfile to snapshot instead of the current workspace's `Cargo.lock`.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<PathBuf>,
+
+    /// Print the entries instead of appending them to the allowlist.
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    options: EntryOptions,
+}
+
+pub async fn run(args: FreezeArgs, ctx: &Context<'_>) -> Result<i32> {
+    let lockfile = match args.lockfile {
+        Some(path) => path,
+        None => lockfile_path(ctx.manifest)?,
+    };
+    let packages = lockfile::read(&lockfile)?;
+    let (path, existing) = read_allowlist(ctx)?;
+    let allowlist = Allowlist::parse(&existing, &path.display().to_string())?;
+
+    let now = Utc::now();
+    let releases = frozen_releases(&packages, &allowlist, ctx.config);
+    let mut entries = String::new();
+    for (name, version) in &releases {
+        let fields = vec![("crate", quoted(name)?), ("version", quoted(version)?)];
+        let entry = render_table("allow.exact", fields, &args.options, now)?;
+        entries = append_entry(&entries, &entry);
+    }
+
+    if args.dry_run {
+        print!("{entries}");
+        return Ok(0);
+    }
+    if !releases.is_empty() {
+        write_allowlist(&path, append_entry(&existing, &entries))?;
+    }
+    println!(
+        "froze {} release(s) from {} into {}",
+        releases.len(),
+        lockfile.display(),
+        path.display()
+    );
+    Ok(0)
+}
+
+/// Registry releases in the lockfile that are not already exempted, so running
+/// `freeze` again only adds what changed since the last snapshot.
+fn frozen_releases<'a>(
+    packages: &'a [LockedPackage],
+    allowlist: &Allowlist,
+    config: &Config,
+) -> BTreeSet<(&'a str, &'a str)> {
+    packages
+        .iter()
+        .filter(|pkg| {
+            pkg.source
+                .as_deref()
+                .is_some_and(|source| config.is_registry_allowed(source))
+        })
+        .filter(|pkg| !allowlist.is_exact_allowed(&pkg.name, &pkg.version))
+        .map(|pkg| (pkg.name.as_str(), pkg.version.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freezes_registry_releases_not_yet_exempted() {
+        let packages = lockfile::parse(
+            r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "tokio"
+version = "1.40.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "forked"
+version = "0.3.0"
+source = "git+https://example.com/forked#abc"
+"#,
+        )
+        .unwrap();
+        let allowlist = Allowlist::parse(
+            "[[allow.exact]]\ncrate = \"serde\"\nversion = \"1.0.210\"\n",
+            "test",
+        )
+        .unwrap();
+        let mut config = Config::from_env();
+        config.allowed_registries =
+            vec!["registry+https://github.com/rust-lang/crates.io-index".to_string()];
+
+        let releases = frozen_releases(&packages, &allowlist, &config);
+        assert_eq!(
+            releases.into_iter().collect::<Vec<_>>(),
+            [("tokio", "1.40.0")]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use super::Context;
+use crate::bundle::{MetadataBundle, import_cache, is_cache_archive};
+use crate::executor::Session;
+
+#[derive(Debug, Args)]
+pub struct ImportMetadataArgs {
+    /// Bundle written by `cargo cooldown export-metadata`, either a `.tar.zst`
+    /// cache archive or a JSON bundle.
+    #[arg(value_name = "BUNDLE")]
+    bundle: PathBuf,
+}
+
+pub async fn run(args: ImportMetadataArgs, ctx: &Context<'_>) -> Result<i32> {
+    let session = Session::new(ctx.config).await?;
+    let cache = session.cache();
+
+    if is_cache_archive(&args.bundle) {
+        let imported = import_cache(&args.bundle, cache.root())?;
+        println!(
+            "imported {imported} cache entries into {}",
+            cache.root().display()
+        );
+        return Ok(0);
+    }
+
+    let bundle = MetadataBundle::load(&args.bundle)?;
+    for (name, list) in &bundle.versions {
+        cache.put(&format!("{name}/_list"), list)?;
+        for meta in list {
+            cache.put(&format!("{name}/{}", meta.num), meta)?;
+        }
+    }
+    println!(
+        "imported version lists for {} crates into {}",
+        bundle.versions.len(),
+        cache.root().display()
+    );
+    Ok(0)
+}
@@ -0,0 +1,172 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context as _, Result, bail};
+use clap::Args;
+use clap_cargo::Manifest;
+use semver::VersionReq;
+use tracing::warn;
+
+use super::Context;
+use crate::config::Mode;
+use crate::executor::{self, Session, newest_cooled};
+use crate::metadata::{cargo_home, config_args};
+
+#[derive(Debug, Args)]
+pub struct InstallArgs {
+    /// The crate to install, as `NAME` or `NAME@REQ`.
+    #[arg(value_name = "CRATE")]
+    krate: String,
+
+    /// Arguments passed on to `cargo install`, e.g. `-- --features pcre2`.
+    #[arg(last = true, value_name = "CARGO_INSTALL_ARGS")]
+    cargo_args: Vec<String>,
+}
+
+pub async fn run(args: InstallArgs, ctx: &Context<'_>) -> Result<i32> {
+    let (name, req) = match args.krate.split_once('@') {
+        Some((name, req)) => (
+            name,
+            VersionReq::parse(req)
+                .with_context(|| format!("invalid version in `{}`", args.krate))?,
+        ),
+        None => (args.krate.as_str(), VersionReq::STAR),
+    };
+    if ctx.config.mode == Mode::Off || !ctx.config.has_policy() {
+        let mut command = Command::new("cargo");
+        command.arg("install").arg(name);
+        if req != VersionReq::STAR {
+            command.arg("--version").arg(req.to_string());
+        }
+        return install(command, ctx, &args.cargo_args);
+    }
+
+    let session = Session::new(ctx.config).await?;
+    let (version, minutes) = newest_cooled(&session, name, &req, ctx.config.now()).await?;
+    let Some(version) = version else {
+        bail!(
+            "no release of {name} matching `{req}` is older than its {minutes}-minute cooldown window"
+        );
+    };
+    println!(
+        "installing {name} {version}, the newest release outside its {minutes}-minute cooldown window"
+    );
+
+    // `cargo install` resolves the binary's dependencies on its own, so the
+    // graph is checked in a copy of the package source and installed from
+    // there with the lockfile the check left behind.
+    let scratch = Scratch::new()?;
+    let source = fetch_source(scratch.path(), name, &version, &ctx.config.cargo_config)?;
+    let package = scratch.path().join(format!("{name}-{version}"));
+    copy_dir(&source, &package)?;
+
+    let mut manifest = Manifest::default();
+    manifest.manifest_path = Some(package.join("Cargo.toml"));
+    let mut config = ctx.config.clone();
+    config.allow_dirty = true;
+    if let Err(err) =
+        executor::run_pinning_flow(&config, &manifest, ctx.features, &ctx.platforms).await
+    {
+        match config.mode {
+            Mode::Warn => {
+                warn!(error = %err, "cooldown guard failed; continuing due to warn mode")
+            }
+            _ => return Err(err.context(format!("{name} {version} was not installed"))),
+        }
+    }
+
+    let mut command = Command::new("cargo");
+    command
+        .arg("install")
+        .arg("--path")
+        .arg(&package)
+        .arg("--locked");
+    install(command, ctx, &args.cargo_args)
+}
+
+fn install(mut command: Command, ctx: &Context<'_>, cargo_args: &[String]) -> Result<i32> {
+    command.args(config_args(&ctx.config.cargo_config));
+    command.args(cargo_args);
+    let status = command.status().context("failed to run cargo install")?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Download `name` `version` through a throwaway package that depends on it
+/// and return where Cargo unpacked its source.
+fn fetch_source(dir: &Path, name: &str, version: &str, cargo_config: &[String]) -> Result<PathBuf> {
+    let probe = dir.join("probe");
+    fs::create_dir_all(probe.join("src"))?;
+    fs::write(
+        probe.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"cooldown-install-probe\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n[dependencies]\n{name} = \"={version}\"\n"
+        ),
+    )?;
+    fs::write(probe.join("src/lib.rs"), "")?;
+    let status = Command::new("cargo")
+        .arg("fetch")
+        .arg("--manifest-path")
+        .arg(probe.join("Cargo.toml"))
+        .args(config_args(cargo_config))
+        .status()
+        .context("failed to run cargo fetch")?;
+    if !status.success() {
+        bail!("failed to download {name} {version}");
+    }
+
+    let registry = cargo_home()
+        .context("could not locate the Cargo home directory")?
+        .join("registry")
+        .join("src");
+    let entries = fs::read_dir(&registry)
+        .with_context(|| format!("failed to read {}", registry.display()))?;
+    for entry in entries {
+        let source = entry?.path().join(format!("{name}-{version}"));
+        if source.join("Cargo.toml").is_file() {
+            return Ok(source);
+        }
+    }
+    bail!(
+        "{name} {version} was downloaded but its source is not under {}",
+        registry.display()
+    )
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// A temporary directory removed when the install finishes.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> Result<Self> {
+        let path = env::temp_dir().join(format!("cargo-cooldown-install-{}", std::process::id()));
+        fs::create_dir_all(&path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use clap::Args;
+use toml_edit::{DocumentMut, Item, Table, value};
+
+use super::Context;
+use crate::metadata::{cargo_home, lockfile_path};
+
+/// Aliases written when none are given: Cargo's own one-letter aliases,
+/// routed through the wrapper.
+const DEFAULT_ALIASES: [(&str, &str); 4] =
+    [("b", "build"), ("c", "check"), ("t", "test"), ("r", "run")];
+
+#[derive(Debug, Args)]
+pub struct InstallAliasArgs {
+    /// Aliases to define, as `NAME=COMMAND`, e.g. `b=build` for
+    /// `b = "cooldown build"`. Defaults to `b`, `c`, `t`, and `r` for
+    /// build, check, test, and run.
+    #[arg(value_name = "NAME=COMMAND", value_parser = parse_alias)]
+    aliases: Vec<(String, String)>,
+
+    /// Write to `$CARGO_HOME/config.toml` instead of the workspace's
+    /// `.cargo/config.toml`.
+    #[arg(long)]
+    global: bool,
+
+    /// Replace aliases that are already defined with another command.
+    #[arg(long)]
+    force: bool,
+}
+
+fn parse_alias(raw: &str) -> Result<(String, String)> {
+    let (name, command) = raw
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected NAME=COMMAND, got `{raw}`"))?;
+    let (name, command) = (name.trim(), command.trim());
+    if name.is_empty() || command.is_empty() {
+        bail!("expected NAME=COMMAND, got `{raw}`");
+    }
+    let command = if command == "cooldown" || command.starts_with("cooldown ") {
+        command.to_string()
+    } else {
+        format!("cooldown {command}")
+    };
+    Ok((name.to_string(), command))
+}
+
+pub async fn run(args: InstallAliasArgs, ctx: &Context<'_>) -> Result<i32> {
+    let aliases = if args.aliases.is_empty() {
+        DEFAULT_ALIASES
+            .iter()
+            .map(|(name, command)| (name.to_string(), format!("cooldown {command}")))
+            .collect()
+    } else {
+        args.aliases
+    };
+    let dir = if args.global {
+        cargo_home().ok_or_else(|| anyhow!("could not determine the Cargo home directory"))?
+    } else {
+        let lockfile = lockfile_path(ctx.manifest)?;
+        lockfile
+            .parent()
+            .map(|root| root.join(".cargo"))
+            .unwrap_or_else(|| PathBuf::from(".cargo"))
+    };
+    // Cargo still reads the extensionless `config` when there is no
+    // `config.toml`.
+    let path = [dir.join("config.toml"), dir.join("config")]
+        .into_iter()
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join("config.toml"));
+    let existing = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", path.display()));
+        }
+    };
+
+    let (updated, report) = add_aliases(&existing, &aliases, args.force)
+        .with_context(|| format!("failed to update {}", path.display()))?;
+    if updated != existing {
+        fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        fs::write(&path, updated).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    println!("{}:", path.display());
+    for line in &report {
+        println!("  {line}");
+    }
+    Ok(0)
+}
+
+/// `contents` with `aliases` in its `[alias]` table, and one line per alias
+/// saying what happened to it. An alias defined with another command is kept
+/// unless `force` is set.
+fn add_aliases(
+    contents: &str,
+    aliases: &[(String, String)],
+    force: bool,
+) -> Result<(String, Vec<String>)> {
+    let mut document: DocumentMut = contents.parse()?;
+    let table = document
+        .entry("alias")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .ok_or_else(|| anyhow!("`alias` is not a table"))?;
+    let mut report = Vec::new();
+    for (name, command) in aliases {
+        let current = table.get(name).map(|item| match item.as_str() {
+            Some(current) => current.to_string(),
+            None => item.to_string().trim().to_string(),
+        });
+        match current {
+            Some(current) if current == *command => {
+                report.push(format!("{name} = \"{command}\" (already set)"));
+            }
+            Some(current) if !force => {
+                report.push(format!(
+                    "{name} is already `{current}`; kept (use --force to replace it)"
+                ));
+            }
+            _ => {
+                table.insert(name, value(command.as_str()));
+                report.push(format!("{name} = \"{command}\""));
+            }
+        }
+    }
+    Ok((document.to_string(), report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_aliases_without_clobbering_existing_ones() {
+        let existing =
+            "[build]\njobs = 4\n\n[alias]\nb = \"build --release\"\nc = \"cooldown check\"\n";
+        let aliases = vec![
+            parse_alias("b=build").unwrap(),
+            parse_alias("c=check").unwrap(),
+            parse_alias("t=cooldown test").unwrap(),
+        ];
+        let (updated, report) = add_aliases(existing, &aliases, false).unwrap();
+        assert_eq!(
+            updated,
+            "[build]\njobs = 4\n\n[alias]\nb = \"build --release\"\nc = \"cooldown check\"\nt = \"cooldown test\"\n"
+        );
+        assert_eq!(
+            report,
+            vec![
+                "b is already `build --release`; kept (use --force to replace it)",
+                "c = \"cooldown check\" (already set)",
+                "t = \"cooldown test\"",
+            ]
+        );
+
+        let (updated, _) = add_aliases(existing, &aliases[..1], true).unwrap();
+        assert!(updated.contains("b = \"cooldown build\""));
+        assert!(parse_alias("b").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! Built-in `cargo cooldown <command>` subcommands that are handled by the
+//! tool itself instead of being forwarded to Cargo.
+
+mod add;
+mod ages;
+pub(crate) mod allow;
+mod analyze;
+mod cert_keygen;
+mod completions;
+mod config;
+mod doctor;
+mod explain;
+mod export_metadata;
+mod freeze;
+mod import_metadata;
+mod install;
+mod install_alias;
+mod tree;
+#[cfg(feature = "tui")]
+mod ui;
+mod update;
+mod vendor_verify;
+mod verify;
+mod verify_audit;
+mod verify_cert;
+mod watch;
+
+use std::ffi::OsString;
+
+use anyhow::Result;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_cargo::{Features, Manifest};
+
+use crate::codes::Outcome;
+use crate::config::Config;
+
+#[derive(Debug, Parser)]
+#[command(name = "cargo cooldown", no_binary_name = true)]
+struct BuiltinCli {
+    #[command(subcommand)]
+    command: Builtin,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Builtin {
+    /// Run `cargo add` with the newest release of each crate that is outside
+    /// its cooldown window, instead of the freshest one.
+    Add(add::AddArgs),
+    /// List every locked registry release with its publication time, age,
+    /// and cooldown status, youngest first.
+    Ages(ages::AgesArgs),
+    /// Append a validated entry to the allowlist file.
+    Allow(allow::AllowArgs),
+    /// Evaluate the resolved dependency graph and report releases that are still
+    /// inside the cooldown window, without pinning or running Cargo.
+    Analyze(analyze::AnalyzeArgs),
+    /// Generate an ed25519 key pair for signing certificates with
+    /// `COOLDOWN_CERT_KEY` and checking them with `COOLDOWN_CERT_PUBLIC_KEY`.
+    CertKeygen(cert_keygen::CertKeygenArgs),
+    /// Print a completion script for `cargo-cooldown` and its built-in
+    /// commands.
+    Completions(completions::CompletionsArgs),
+    /// Inspect the effective configuration.
+    Config(config::ConfigArgs),
+    /// Check the environment the tool runs in: Cargo, the lockfile, the
+    /// cache directory, config files, and the registry endpoints, with a fix
+    /// for each problem.
+    Doctor(doctor::DoctorArgs),
+    /// Explain an error code such as `COOLDOWN002` and how to get past it.
+    Explain(explain::ExplainArgs),
+    /// Write the version lists of every registry package in `Cargo.lock` to a
+    /// portable bundle for offline evaluation with `analyze --metadata-bundle`,
+    /// or archive the whole cache when the output ends in `.tar.zst`.
+    ExportMetadata(export_metadata::ExportMetadataArgs),
+    /// Append an exact exemption for every registry release currently in
+    /// `Cargo.lock`, so adopting the tool only cools down future bumps.
+    Freeze(freeze::FreezeArgs),
+    /// Seed the cache from a bundle written by `export-metadata`.
+    ImportMetadata(import_metadata::ImportMetadataArgs),
+    /// Run `cargo install` with the newest release of a crate that is outside
+    /// its cooldown window, and a lockfile whose releases all are.
+    Install(install::InstallArgs),
+    /// Define Cargo aliases such as `b = "cooldown build"` in
+    /// `.cargo/config.toml`, so the usual short commands go through the
+    /// cooldown.
+    InstallAlias(install_alias::InstallAliasArgs),
+    /// Draw the resolved dependency graph like `cargo tree`, with each
+    /// registry release's age and cooldown status.
+    Tree(tree::TreeArgs),
+    /// Browse the resolved dependency graph in a terminal dashboard with
+    /// each release's age, and allow, pin, or look up releases from there.
+    #[cfg(feature = "tui")]
+    Ui(ui::UiArgs),
+    /// Run `cargo update`, then pin every release it brought in that is still
+    /// inside its cooldown window back to the newest one outside it.
+    Update(update::UpdateArgs),
+    /// Map every crate in a `cargo vendor` directory back to its registry
+    /// release and check its checksum and publication age.
+    VendorVerify(vendor_verify::VendorVerifyArgs),
+    /// Check only the releases a change adds to `Cargo.lock` relative to a
+    /// git revision, for gating pull requests without re-resolving.
+    Verify(verify::VerifyArgs),
+    /// Check the hash chain of an audit log, reporting the first entry that
+    /// was edited, dropped, or reordered.
+    VerifyAudit(verify_audit::VerifyAuditArgs),
+    /// Validate a certificate issued by an earlier enforce run against the
+    /// current lockfile and policy.
+    VerifyCert(verify_cert::VerifyCertArgs),
+    /// Check the graph again, in warn mode, whenever a manifest or the
+    /// lockfile changes.
+    Watch(watch::WatchArgs),
+}
+
+/// Selectors and configuration shared by every built-in command.
+pub struct Context<'a> {
+    pub config: &'a Config,
+    pub manifest: &'a Manifest,
+    pub features: &'a Features,
+    /// Target triples used to filter platform-specific dependencies; empty
+    /// when platform filtering is disabled.
+    pub platforms: Vec<String>,
+}
+
+/// Recognize a built-in command at the front of the forwarded arguments.
+/// Returns `None` when the arguments should be passed through to Cargo.
+pub fn parse(cargo_args: &[OsString]) -> Option<Builtin> {
+    let name = cargo_args.first()?.to_str()?;
+    BuiltinCli::command().find_subcommand(name)?;
+    match BuiltinCli::try_parse_from(cargo_args) {
+        Ok(cli) => Some(cli.command),
+        Err(err) => err.exit(),
+    }
+}
+
+impl Builtin {
+    /// Whether the command diagnoses the config files, and so has to run
+    /// when one of them does not load.
+    pub fn diagnoses_config(&self) -> bool {
+        match self {
+            Builtin::Config(args) => args.validates(),
+            Builtin::Doctor(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Execute a built-in command and report how it ended.
+pub async fn run(command: Builtin, ctx: &Context<'_>) -> Result<Outcome> {
+    // What a non-zero status means: commands that wrap Cargo return its
+    // status, the rest fail for what they check.
+    let failure: fn(i32) -> Outcome = match &command {
+        Builtin::Add(_) | Builtin::Install(_) | Builtin::Update(_) => Outcome::Cargo,
+        Builtin::Config(_) => |_| Outcome::Config,
+        Builtin::Doctor(_) => |_| Outcome::Error,
+        Builtin::ExportMetadata(_) => |_| Outcome::Network,
+        _ => |_| Outcome::Violations,
+    };
+    let code = match command {
+        Builtin::Add(args) => add::run(args, ctx).await,
+        Builtin::Ages(args) => ages::run(args, ctx).await,
+        Builtin::Allow(args) => allow::run(args, ctx).await,
+        Builtin::Analyze(args) => analyze::run(args, ctx).await,
+        Builtin::CertKeygen(args) => cert_keygen::run(args, ctx).await,
+        Builtin::Completions(args) => completions::run(args, ctx).await,
+        Builtin::Config(args) => config::run(args, ctx).await,
+        Builtin::Doctor(args) => doctor::run(args, ctx).await,
+        Builtin::Explain(args) => explain::run(args, ctx).await,
+        Builtin::ExportMetadata(args) => export_metadata::run(args, ctx).await,
+        Builtin::Freeze(args) => freeze::run(args, ctx).await,
+        Builtin::ImportMetadata(args) => import_metadata::run(args, ctx).await,
+        Builtin::Install(args) => install::run(args, ctx).await,
+        Builtin::InstallAlias(args) => install_alias::run(args, ctx).await,
+        Builtin::Tree(args) => tree::run(args, ctx).await,
+        #[cfg(feature = "tui")]
+        Builtin::Ui(args) => ui::run(args, ctx).await,
+        Builtin::Update(args) => update::run(args, ctx).await,
+        Builtin::VendorVerify(args) => vendor_verify::run(args, ctx).await,
+        Builtin::Verify(args) => verify::run(args, ctx).await,
+        Builtin::VerifyAudit(args) => verify_audit::run(args, ctx).await,
+        Builtin::VerifyCert(args) => verify_cert::run(args, ctx).await,
+        Builtin::Watch(args) => watch::run(args, ctx).await,
+    }?;
+    Ok(match code {
+        0 => Outcome::Success,
+        code => failure(code),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_os_vec(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn forwards_unknown_commands_to_cargo() {
+        assert!(parse(&to_os_vec(&["build", "--release"])).is_none());
+        assert!(parse(&[]).is_none());
+    }
+
+    #[test]
+    fn recognizes_builtin_commands() {
+        let parsed = parse(&to_os_vec(&["analyze", "--metadata-json", "-"]));
+        assert!(matches!(parsed, Some(Builtin::Analyze(_))));
+        let parsed = parse(&to_os_vec(&["verify-cert", "cooldown.cert"]));
+        assert!(matches!(parsed, Some(Builtin::VerifyCert(_))));
+    }
+}
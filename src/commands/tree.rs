@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use anyhow::{Context as _, Result};
+use cargo_metadata::PackageId;
+use chrono::{Duration, Utc};
+use clap::Args;
+
+use super::Context;
+use crate::executor::{Session, release_age};
+use crate::graph::{adjacency, render_tree};
+use crate::metadata::read_metadata;
+use crate::report::{AgeStatus, ReleaseAge, format_age};
+
+#[derive(Debug, Args)]
+pub struct TreeArgs {
+    /// Only draw the paths that lead to a release inside its cooldown window.
+    #[arg(long)]
+    fresh_only: bool,
+}
+
+pub async fn run(args: TreeArgs, ctx: &Context<'_>) -> Result<i32> {
+    let metadata = read_metadata(ctx.manifest, ctx.features, &ctx.platforms, ctx.config)?;
+    let resolve = metadata
+        .resolve
+        .as_ref()
+        .context("cargo metadata did not resolve the dependency graph")?;
+    let session = Session::new(ctx.config).await?;
+
+    let now = ctx.config.now();
+    let mut labels = HashMap::new();
+    let mut ages: HashMap<PackageId, ReleaseAge> = HashMap::new();
+    for pkg in &metadata.packages {
+        let mut label = format!("{} v{}", pkg.name, pkg.version);
+        match &pkg.source {
+            Some(source) if ctx.config.is_registry_allowed(&source.repr) => {
+                let age = release_age(&session, &pkg.name, &pkg.version.to_string(), now).await;
+                label.push_str(&format!(" {}", annotation(&age, now)));
+                ages.insert(pkg.id.clone(), age);
+            }
+            Some(source) => label.push_str(&format!(" ({source})")),
+            None => label.push_str(&format!(
+                " ({})",
+                pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path)
+            )),
+        }
+        labels.insert(pkg.id.clone(), label);
+    }
+
+    let mut edges = adjacency(resolve);
+    for deps in edges.values_mut() {
+        deps.sort_by(|a, b| labels[a].cmp(&labels[b]));
+    }
+    let mut roots = metadata.workspace_members.clone();
+    roots.sort_by(|a, b| labels[a].cmp(&labels[b]));
+    let fresh = |id: &PackageId| {
+        ages.get(id)
+            .is_some_and(|age| age.status == AgeStatus::Fresh)
+    };
+    print!(
+        "{}",
+        render_tree(
+            &roots,
+            &edges,
+            |id| labels[id].clone(),
+            |id| !args.fresh_only || fresh(id)
+        )
+    );
+    Ok(0)
+}
+
+/// `✓ 3d 2h`, `✗ 42m (needs 1d 0h)`, `∅ exempt`, or `? unknown age`.
+pub(super) fn annotation(age: &ReleaseAge, now: chrono::DateTime<Utc>) -> String {
+    let elapsed = age.published_at.map(|at| format_age(now - at));
+    match (age.status, elapsed) {
+        (AgeStatus::Cooled, Some(elapsed)) => format!("✓ {elapsed}"),
+        (AgeStatus::Fresh, Some(elapsed)) => format!(
+            "✗ {elapsed} (needs {})",
+            format_age(Duration::minutes(age.minimum_minutes as i64))
+        ),
+        (AgeStatus::Exempt, _) => "∅ exempt".to_string(),
+        _ => "? unknown age".to_string(),
+    }
+}
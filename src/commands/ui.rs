@@ -0,0 +1,563 @@
+//! `cargo cooldown ui`: the resolved graph in a terminal dashboard, with each
+//! registry release's age and one-key actions, for periodic dependency
+//! reviews of large workspaces.
+
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context as _, Result};
+use cargo_metadata::PackageId;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+
+use super::Context;
+use super::allow::append_exact;
+use super::tree::annotation;
+use crate::executor::{Session, pin_release, release_age};
+use crate::graph::{TreeLine, adjacency, tree_lines};
+use crate::metadata::read_metadata;
+use crate::report::{AgeStatus, ReleaseAge};
+
+#[derive(Debug, Args)]
+pub struct UiArgs {
+    /// Start with only the paths that lead to a release inside its cooldown
+    /// window shown; `f` toggles it.
+    #[arg(long)]
+    fresh_only: bool,
+}
+
+const HELP: &str =
+    "↑↓ move  / search  f fresh only  a allow  p pin  o open on crates.io  r reload  q quit";
+
+pub async fn run(args: UiArgs, ctx: &Context<'_>) -> Result<i32> {
+    let session = Session::new(ctx.config).await?;
+    let graph = load(ctx, &session).await?;
+    let mut app = App::new(graph, args.fresh_only);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, ctx, session).await;
+    ratatui::restore();
+    result.map(|()| 0)
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    ctx: &Context<'_>,
+    mut session: Session<'_>,
+) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let Some(action) = app.key(key.code) else {
+            continue;
+        };
+        let reload = !matches!(action, Action::Open(_));
+        let outcome = match action {
+            Action::Quit => return Ok(()),
+            Action::Reload => Ok("reloaded".to_string()),
+            Action::Allow {
+                release,
+                cools_down_at,
+                reason,
+            } => append_exact(
+                ctx.config,
+                &release.name,
+                &release.version,
+                &reason,
+                cools_down_at,
+            )
+            .map(|path| {
+                format!(
+                    "allowed {}@{} in {}",
+                    release.name,
+                    release.version,
+                    path.display()
+                )
+            }),
+            Action::Pin(release) => pin(ctx, &session, &release).await,
+            Action::Open(release) => open(&release),
+        };
+        app.message = Some(match outcome {
+            Ok(message) => message,
+            Err(err) => format!("error: {err:#}"),
+        });
+        if !reload {
+            continue;
+        }
+        // The allowlist and the lockfile are read when a session starts.
+        session = Session::new(ctx.config).await?;
+        match load(ctx, &session).await {
+            Ok(graph) => app.replace(graph),
+            Err(err) => app.message = Some(format!("error: {err:#}")),
+        }
+    }
+}
+
+/// Move `release` off its cooldown window the way a run would, under the
+/// run lock and journaled.
+async fn pin(ctx: &Context<'_>, session: &Session<'_>, release: &Release) -> Result<String> {
+    let record = pin_release(
+        session,
+        ctx.manifest,
+        ctx.features,
+        &ctx.platforms,
+        &release.name,
+        &release.version,
+    )
+    .await?;
+    Ok(format!(
+        "pinned {} {} -> {} ({})",
+        record.name,
+        record.from,
+        record.to,
+        record.reason.unwrap_or_default()
+    ))
+}
+
+fn open(release: &Release) -> Result<String> {
+    let url = format!(
+        "https://crates.io/crates/{}/{}",
+        release.name, release.version
+    );
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(&url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to open {url}"))?;
+    Ok(format!("opened {url}"))
+}
+
+/// The resolved graph with the age of every registry release.
+struct Graph {
+    roots: Vec<PackageId>,
+    edges: HashMap<PackageId, Vec<PackageId>>,
+    nodes: HashMap<PackageId, Node>,
+}
+
+struct Node {
+    name: String,
+    /// `name vX.Y.Z`, with the source or path for non-registry packages.
+    label: String,
+    /// Set for registry releases only.
+    age: Option<ReleaseAge>,
+    cools_down_at: Option<DateTime<Utc>>,
+}
+
+async fn load(ctx: &Context<'_>, session: &Session<'_>) -> Result<Graph> {
+    let metadata = read_metadata(ctx.manifest, ctx.features, &ctx.platforms, ctx.config)?;
+    let resolve = metadata
+        .resolve
+        .as_ref()
+        .context("cargo metadata did not resolve the dependency graph")?;
+
+    let now = ctx.config.now();
+    let mut nodes = HashMap::new();
+    for pkg in &metadata.packages {
+        let mut label = format!("{} v{}", pkg.name, pkg.version);
+        let mut age = None;
+        match &pkg.source {
+            Some(source) if ctx.config.is_registry_allowed(&source.repr) => {
+                age = Some(release_age(session, &pkg.name, &pkg.version.to_string(), now).await);
+            }
+            Some(source) => label.push_str(&format!(" ({source})")),
+            None => label.push_str(&format!(
+                " ({})",
+                pkg.manifest_path.parent().unwrap_or(&pkg.manifest_path)
+            )),
+        }
+        let cools_down_at = age.as_ref().and_then(|age: &ReleaseAge| {
+            Some(
+                session
+                    .calendar()
+                    .window_end(age.published_at?, age.minimum_minutes),
+            )
+        });
+        nodes.insert(
+            pkg.id.clone(),
+            Node {
+                name: pkg.name.to_string(),
+                label,
+                age,
+                cools_down_at,
+            },
+        );
+    }
+
+    let mut edges = adjacency(resolve);
+    for deps in edges.values_mut() {
+        deps.sort_by(|a, b| nodes[a].label.cmp(&nodes[b].label));
+    }
+    let mut roots = metadata.workspace_members.clone();
+    roots.sort_by(|a, b| nodes[a].label.cmp(&nodes[b].label));
+    Ok(Graph {
+        roots,
+        edges,
+        nodes,
+    })
+}
+
+/// A registry release an action applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Release {
+    name: String,
+    version: String,
+}
+
+/// What a key asks the event loop to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Quit,
+    Reload,
+    Allow {
+        release: Release,
+        cools_down_at: Option<DateTime<Utc>>,
+        reason: String,
+    },
+    Pin(Release),
+    Open(Release),
+}
+
+/// Where typed keys go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Input {
+    Browse,
+    Search,
+    /// Typing the reason to record for an allowlist entry.
+    Reason(String),
+}
+
+struct App {
+    graph: Graph,
+    fresh_only: bool,
+    /// Only paths to crates whose name contains this are shown.
+    query: String,
+    lines: Vec<TreeLine<PackageId>>,
+    list: ListState,
+    input: Input,
+    message: Option<String>,
+}
+
+impl App {
+    fn new(graph: Graph, fresh_only: bool) -> Self {
+        let mut app = Self {
+            graph,
+            fresh_only,
+            query: String::new(),
+            lines: Vec::new(),
+            list: ListState::default(),
+            input: Input::Browse,
+            message: None,
+        };
+        app.refilter();
+        app
+    }
+
+    /// Swap in a reloaded graph, keeping the selection on the same crate
+    /// when it is still shown.
+    fn replace(&mut self, graph: Graph) {
+        let selected = self.selected().map(|node| node.name.clone());
+        self.graph = graph;
+        self.refilter();
+        if let Some(name) = selected
+            && let Some(index) = self
+                .lines
+                .iter()
+                .position(|line| self.graph.nodes[&line.node].name == name)
+        {
+            self.list.select(Some(index));
+        }
+    }
+
+    fn refilter(&mut self) {
+        let query = self.query.to_lowercase();
+        let nodes = &self.graph.nodes;
+        self.lines = tree_lines(&self.graph.roots, &self.graph.edges, |id| {
+            let node = &nodes[id];
+            let fresh = node
+                .age
+                .as_ref()
+                .is_some_and(|age| age.status == AgeStatus::Fresh);
+            (!self.fresh_only || fresh) && node.name.to_lowercase().contains(&query)
+        });
+        let selected = self.list.selected().unwrap_or(0);
+        self.list.select(match self.lines.len() {
+            0 => None,
+            len => Some(selected.min(len - 1)),
+        });
+    }
+
+    fn selected(&self) -> Option<&Node> {
+        let line = self.lines.get(self.list.selected()?)?;
+        Some(&self.graph.nodes[&line.node])
+    }
+
+    /// The selected registry release, or why there is none.
+    fn selected_release(&self) -> Result<Release, String> {
+        let node = self.selected().ok_or("nothing is selected")?;
+        let age = node
+            .age
+            .as_ref()
+            .ok_or_else(|| format!("{} is not a registry release", node.name))?;
+        Ok(Release {
+            name: age.name.clone(),
+            version: age.version.clone(),
+        })
+    }
+
+    fn key(&mut self, code: KeyCode) -> Option<Action> {
+        match &mut self.input {
+            Input::Search => {
+                match code {
+                    KeyCode::Enter => self.input = Input::Browse,
+                    KeyCode::Esc => {
+                        self.input = Input::Browse;
+                        self.query.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.query.pop();
+                    }
+                    KeyCode::Char(c) => self.query.push(c),
+                    _ => return None,
+                }
+                self.refilter();
+                return None;
+            }
+            Input::Reason(reason) => {
+                match code {
+                    KeyCode::Enter if !reason.trim().is_empty() => {
+                        let reason = reason.trim().to_string();
+                        self.input = Input::Browse;
+                        let release = self.selected_release().ok()?;
+                        let cools_down_at = self.selected()?.cools_down_at;
+                        return Some(Action::Allow {
+                            release,
+                            cools_down_at,
+                            reason,
+                        });
+                    }
+                    KeyCode::Esc => self.input = Input::Browse,
+                    KeyCode::Backspace => {
+                        reason.pop();
+                    }
+                    KeyCode::Char(c) => reason.push(c),
+                    _ => {}
+                }
+                return None;
+            }
+            Input::Browse => {}
+        }
+
+        self.message = None;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+            KeyCode::Char('r') => return Some(Action::Reload),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::PageDown => self.list.scroll_down_by(20),
+            KeyCode::PageUp => self.list.scroll_up_by(20),
+            KeyCode::Home => self.list.select_first(),
+            KeyCode::End => self.list.select_last(),
+            KeyCode::Char('/') => self.input = Input::Search,
+            KeyCode::Char('f') => {
+                self.fresh_only = !self.fresh_only;
+                self.refilter();
+            }
+            KeyCode::Char(key @ ('a' | 'p' | 'o')) => match self.selected_release() {
+                Ok(release) if key == 'p' => return Some(Action::Pin(release)),
+                Ok(release) if key == 'o' => return Some(Action::Open(release)),
+                Ok(_) => self.input = Input::Reason(String::new()),
+                Err(reason) => self.message = Some(reason),
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+fn draw(frame: &mut ratatui::Frame, app: &mut App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let fresh = app
+        .graph
+        .nodes
+        .values()
+        .filter(|node| {
+            node.age
+                .as_ref()
+                .is_some_and(|age| age.status == AgeStatus::Fresh)
+        })
+        .count();
+    let mut title = format!("cargo cooldown: {} releases inside their window", fresh);
+    if app.fresh_only {
+        title.push_str(" · fresh only");
+    }
+    if !app.query.is_empty() {
+        title.push_str(&format!(" · matching `{}`", app.query));
+    }
+    frame.render_widget(
+        Paragraph::new(title).style(Style::new().add_modifier(Modifier::BOLD)),
+        header,
+    );
+
+    let now = Utc::now();
+    let items: Vec<ListItem> = app
+        .lines
+        .iter()
+        .map(|line| {
+            let node = &app.graph.nodes[&line.node];
+            let mut spans = vec![
+                Span::raw(line.prefix.clone()),
+                Span::raw(node.label.clone()),
+            ];
+            if let Some(age) = &node.age {
+                let color = match age.status {
+                    AgeStatus::Fresh => Color::Red,
+                    AgeStatus::Cooled => Color::Green,
+                    AgeStatus::Exempt => Color::Blue,
+                    AgeStatus::Unknown => Color::Yellow,
+                };
+                spans.push(Span::styled(
+                    format!(" {}", annotation(age, now)),
+                    Style::new().fg(color),
+                ));
+            }
+            if line.repeated {
+                spans.push(Span::raw(" (*)"));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered())
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+        body,
+        &mut app.list,
+    );
+
+    let status = match &app.input {
+        Input::Search => format!("/{}", app.query),
+        Input::Reason(reason) => format!("reason to record in the allowlist: {reason}"),
+        Input::Browse => app.message.clone().unwrap_or_else(|| HELP.to_string()),
+    };
+    frame.render_widget(Paragraph::new(status), footer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> PackageId {
+        PackageId {
+            repr: name.to_string(),
+        }
+    }
+
+    fn node(name: &str, status: Option<AgeStatus>) -> Node {
+        Node {
+            name: name.to_string(),
+            label: format!("{name} v1.2.3"),
+            age: status.map(|status| ReleaseAge {
+                name: name.to_string(),
+                version: "1.2.3".to_string(),
+                published_at: None,
+                minimum_minutes: 10080,
+                status,
+            }),
+            cools_down_at: None,
+        }
+    }
+
+    #[test]
+    fn filters_and_turns_keys_into_actions() {
+        let graph = Graph {
+            roots: vec![id("app")],
+            edges: HashMap::from([
+                (id("app"), vec![id("serde"), id("tokio")]),
+                (id("tokio"), vec![id("mio")]),
+            ]),
+            nodes: HashMap::from([
+                (id("app"), node("app", None)),
+                (id("serde"), node("serde", Some(AgeStatus::Cooled))),
+                (id("tokio"), node("tokio", Some(AgeStatus::Cooled))),
+                (id("mio"), node("mio", Some(AgeStatus::Fresh))),
+            ]),
+        };
+        let mut app = App::new(graph, false);
+        assert_eq!(app.lines.len(), 4);
+
+        app.key(KeyCode::Char('f'));
+        let shown: Vec<&str> = app
+            .lines
+            .iter()
+            .map(|line| line.node.repr.as_str())
+            .collect();
+        assert_eq!(shown, ["app", "tokio", "mio"]);
+        app.key(KeyCode::Char('f'));
+
+        for c in "/ser".chars() {
+            app.key(KeyCode::Char(c));
+        }
+        app.key(KeyCode::Enter);
+        assert_eq!(app.lines.len(), 2);
+        app.key(KeyCode::Char('a'));
+        assert!(
+            app.message
+                .as_deref()
+                .unwrap()
+                .contains("not a registry release")
+        );
+
+        app.key(KeyCode::Down);
+        let serde = Release {
+            name: "serde".into(),
+            version: "1.2.3".into(),
+        };
+        assert_eq!(
+            app.key(KeyCode::Char('p')),
+            Some(Action::Pin(serde.clone()))
+        );
+        app.key(KeyCode::Char('a'));
+        assert_eq!(app.key(KeyCode::Enter), None);
+        for c in "reviewed".chars() {
+            app.key(KeyCode::Char(c));
+        }
+        assert_eq!(
+            app.key(KeyCode::Enter),
+            Some(Action::Allow {
+                release: serde,
+                cools_down_at: None,
+                reason: "reviewed".into(),
+            })
+        );
+        assert_eq!(app.key(KeyCode::Char('q')), Some(Action::Quit));
+    }
+}
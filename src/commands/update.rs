@@ -0,0 +1,66 @@
+use std::process::Command;
+
+use anyhow::{Context as _, Result, bail};
+use clap::Args;
+
+use super::Context;
+use crate::config::Mode;
+use crate::executor;
+use crate::lockfile;
+use crate::metadata::{config_args, lockfile_path};
+use crate::rollback::{self, LockfileGuard};
+
+#[derive(Debug, Args)]
+pub struct UpdateArgs {
+    /// Arguments passed on to `cargo update`, e.g. `-- -p serde`.
+    #[arg(last = true, value_name = "CARGO_UPDATE_ARGS")]
+    cargo_args: Vec<String>,
+}
+
+pub async fn run(args: UpdateArgs, ctx: &Context<'_>) -> Result<i32> {
+    let lockfile = lockfile_path(ctx.manifest)?;
+    if !ctx.config.allow_dirty && lockfile::has_uncommitted_changes(&lockfile) {
+        bail!(
+            "{} has uncommitted changes and updating would rewrite it; commit or stash them first, or pass --allow-dirty",
+            lockfile.display()
+        );
+    }
+    let before = lockfile::read(&lockfile).unwrap_or_default();
+    rollback::handle_interrupts();
+    // Covers the update too: if the fresh releases it pulled in cannot be
+    // pinned back, the lockfile is left as it was.
+    let guard = LockfileGuard::new(&lockfile, ctx.config.keep_partial)?;
+
+    let mut command = Command::new("cargo");
+    command.arg("update");
+    if let Some(path) = &ctx.manifest.manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+    command.args(config_args(&ctx.config.cargo_config));
+    command.args(&args.cargo_args);
+    let status = command.status().context("failed to run cargo update")?;
+    if !status.success() {
+        return Ok(status.code().unwrap_or(1));
+    }
+
+    if ctx.config.mode != Mode::Off
+        && (ctx.config.has_policy() || executor::has_bans(ctx.config, ctx.manifest))
+    {
+        // The lockfile is dirty now, but only with this update.
+        let mut config = ctx.config.clone();
+        config.allow_dirty = true;
+        executor::run_pinning_flow(&config, ctx.manifest, ctx.features, &ctx.platforms).await?;
+    }
+    guard.commit();
+
+    let after = lockfile::read(&lockfile)?;
+    let moved = lockfile::moved_releases(&before, &after);
+    println!(
+        "updated {} release(s) within the cooldown policy",
+        moved.len()
+    );
+    for (name, from, to) in moved {
+        println!("  {name} {from} -> {to}");
+    }
+    Ok(0)
+}
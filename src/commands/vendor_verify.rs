@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use super::Context;
+use crate::config::Mode;
+use crate::executor::{Session, analyze_lockfile, check_checksums};
+use crate::report::{ReportFormat, render_deny_json, render_soft, render_violations};
+use crate::vendor;
+
+#[derive(Debug, Args)]
+pub struct VendorVerifyArgs {
+    /// Directory written by `cargo vendor`.
+    #[arg(value_name = "DIR", default_value = "vendor")]
+    dir: PathBuf,
+
+    /// Output format for the violations.
+    #[arg(long, value_enum, default_value_t)]
+    format: ReportFormat,
+}
+
+pub async fn run(args: VendorVerifyArgs, ctx: &Context<'_>) -> Result<i32> {
+    // The vendor directory does not say which registry a crate came from;
+    // it is taken to be the first one the policy allows.
+    let source = ctx
+        .config
+        .allowed_registries
+        .first()
+        .map(String::as_str)
+        .unwrap_or("registry+https://github.com/rust-lang/crates.io-index");
+    let packages = vendor::read(&args.dir, source)?;
+
+    let mut config = ctx.config.clone();
+    config.verify_checksums = true;
+    let mut session = Session::new(&config).await?;
+    // Policy files are looked up next to the vendor directory, where the
+    // workspace that vendored it keeps them.
+    session.load_workspace_policy(&args.dir)?;
+    check_checksums(&session, &args.dir, &packages).await?;
+    let violations = analyze_lockfile(&session, &packages).await?;
+
+    let enforce = ctx.config.mode == Mode::Enforce;
+    if args.format == ReportFormat::DenyJson {
+        let severity = if enforce { "error" } else { "warning" };
+        print!(
+            "{}",
+            render_deny_json(
+                &violations,
+                &session.soft_violations(),
+                ctx.config.now(),
+                severity
+            )
+        );
+        return Ok(i32::from(enforce && !violations.is_empty()));
+    }
+    let registry = packages.iter().filter(|pkg| pkg.source.is_some()).count();
+    println!(
+        "{}: {} vendored crate(s), {registry} from a registry",
+        args.dir.display(),
+        packages.len()
+    );
+    let soft = session.soft_violations();
+    if !soft.is_empty() {
+        print!("{}", render_soft(&soft, ctx.config.now()));
+    }
+    if violations.is_empty() {
+        println!("every vendored release matches the index and is outside its cooldown window");
+        return Ok(0);
+    }
+    print!("{}", render_violations(&violations, ctx.config.now()));
+    Ok(i32::from(enforce))
+}
@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use super::Context;
+use crate::baseline::{self, Releases};
+use crate::config::Mode;
+use crate::executor::{Session, analyze_lockfile};
+use crate::lockfile::{self, LockedPackage};
+use crate::metadata::lockfile_path;
+use crate::report::{ReportFormat, render_deny_json, render_soft, render_violations};
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// Git revision the change is compared against, e.g. `origin/main`.
+    #[arg(long, value_name = "GIT_REF")]
+    base: String,
+
+    /// Lockfile to check instead of the current workspace's `Cargo.lock`.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<PathBuf>,
+
+    /// Output format for the violations.
+    #[arg(long, value_enum, default_value_t)]
+    format: ReportFormat,
+}
+
+/// A registry release that is new relative to the base revision.
+#[derive(Debug, PartialEq, Eq)]
+struct Change {
+    name: String,
+    version: String,
+    /// Versions of the same crate in the base lockfile; empty when added.
+    previous: Vec<String>,
+}
+
+pub async fn run(args: VerifyArgs, ctx: &Context<'_>) -> Result<i32> {
+    let lockfile = match args.lockfile {
+        Some(path) => path,
+        None => lockfile_path(ctx.manifest)?,
+    };
+    let packages = lockfile::read(&lockfile)?;
+    let base = match baseline::git_show(&args.base, &lockfile)? {
+        Some(contents) => lockfile::parse(&contents)?,
+        None => Vec::new(),
+    };
+
+    let mut session = Session::new(ctx.config).await?;
+    session.set_baseline(Some(baseline::releases(&base)));
+    session.load_workspace_policy(&lockfile)?;
+    let violations = analyze_lockfile(&session, &packages).await?;
+    let enforce = ctx.config.mode == Mode::Enforce;
+    if args.format == ReportFormat::DenyJson {
+        let severity = if enforce { "error" } else { "warning" };
+        print!(
+            "{}",
+            render_deny_json(
+                &violations,
+                &session.soft_violations(),
+                ctx.config.now(),
+                severity
+            )
+        );
+        return Ok(i32::from(enforce && !violations.is_empty()));
+    }
+
+    let changes = changed_releases(&base, &packages);
+    let added = changes.iter().filter(|c| c.previous.is_empty()).count();
+    println!(
+        "{} against {}: {added} added, {} updated",
+        lockfile.display(),
+        args.base,
+        changes.len() - added
+    );
+    for change in &changes {
+        if change.previous.is_empty() {
+            println!("  + {} {}", change.name, change.version);
+        } else {
+            println!(
+                "  ~ {} {} -> {}",
+                change.name,
+                change.previous.join(", "),
+                change.version
+            );
+        }
+    }
+
+    let soft = session.soft_violations();
+    if !soft.is_empty() {
+        print!("{}", render_soft(&soft, ctx.config.now()));
+    }
+    if violations.is_empty() {
+        println!("no added or updated release is inside the cooldown window");
+        return Ok(0);
+    }
+    println!();
+    print!("{}", render_violations(&violations, ctx.config.now()));
+    Ok(i32::from(enforce))
+}
+
+/// Registry releases in `current` that `base` does not have, with the
+/// versions of the same crate they replace.
+fn changed_releases(base: &[LockedPackage], current: &[LockedPackage]) -> Vec<Change> {
+    let reviewed: Releases = baseline::releases(base);
+    let mut previous: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for pkg in base.iter().filter(|pkg| pkg.source.is_some()) {
+        previous.entry(&pkg.name).or_default().insert(&pkg.version);
+    }
+    let mut changes: Vec<Change> = baseline::releases(current)
+        .into_iter()
+        .filter(|release| !reviewed.contains(release))
+        .map(|(name, version)| Change {
+            previous: previous
+                .get(name.as_str())
+                .map(|versions| versions.iter().map(|v| v.to_string()).collect())
+                .unwrap_or_default(),
+            name,
+            version,
+        })
+        .collect();
+    changes.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(name: &str, version: &str) -> LockedPackage {
+        lockfile::parse(&format!(
+            "version = 4\n\n[[package]]\nname = \"{name}\"\nversion = \"{version}\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n"
+        ))
+        .unwrap()
+        .remove(0)
+    }
+
+    #[test]
+    fn reports_added_and_updated_releases_only() {
+        let base = vec![registry("serde", "1.0.200"), registry("log", "0.4.22")];
+        let current = vec![
+            registry("serde", "1.0.210"),
+            registry("log", "0.4.22"),
+            registry("itoa", "1.0.11"),
+        ];
+        assert_eq!(
+            changed_releases(&base, &current),
+            vec![
+                Change {
+                    name: "itoa".into(),
+                    version: "1.0.11".into(),
+                    previous: vec![],
+                },
+                Change {
+                    name: "serde".into(),
+                    version: "1.0.210".into(),
+                    previous: vec!["1.0.200".into()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use clap::Args;
+
+use super::Context;
+use crate::audit;
+
+#[derive(Debug, Args)]
+pub struct VerifyAuditArgs {
+    /// Audit log to check instead of the configured `audit_log_path`.
+    #[arg(value_name = "FILE")]
+    log: Option<PathBuf>,
+}
+
+pub async fn run(args: VerifyAuditArgs, ctx: &Context<'_>) -> Result<i32> {
+    let path = args
+        .log
+        .or_else(|| ctx.config.audit_log_path.clone())
+        .context("no audit log given and COOLDOWN_AUDIT_LOG is not set")?;
+
+    match audit::verify(&path) {
+        Ok(entries) => {
+            println!("audit log intact: {} ({entries} entries)", path.display());
+            Ok(0)
+        }
+        Err(err) => {
+            eprintln!("audit log tampered with: {err:#}");
+            Ok(1)
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+
+use super::Context;
+use crate::certificate;
+use crate::metadata::lockfile_path;
+
+#[derive(Debug, Args)]
+pub struct VerifyCertArgs {
+    /// Certificate written by a previous enforce run.
+    #[arg(value_name = "FILE")]
+    certificate: PathBuf,
+
+    /// Lockfile to check instead of the current workspace's `Cargo.lock`.
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<PathBuf>,
+}
+
+pub async fn run(args: VerifyCertArgs, ctx: &Context<'_>) -> Result<i32> {
+    let certificate = certificate::read(&args.certificate)?;
+    let lockfile = match args.lockfile {
+        Some(path) => path,
+        None => lockfile_path(ctx.manifest)?,
+    };
+
+    match certificate::verify(&certificate, ctx.config, &lockfile) {
+        Ok(()) => {
+            println!(
+                "certificate valid: {} issued {} by cargo-cooldown {}",
+                lockfile.display(),
+                certificate.issued_at.format("%Y-%m-%d %H:%M UTC"),
+                certificate.tool_version
+            );
+            Ok(0)
+        }
+        Err(err) => {
+            eprintln!("certificate rejected: {err:#}");
+            Ok(1)
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as _, Result, bail};
+use clap::Args;
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+use tokio::sync::mpsc;
+
+use super::Context;
+use crate::executor::{Session, analyze_metadata};
+use crate::metadata::{lockfile_path, read_metadata};
+use crate::report::render_violations;
+use crate::stamp::{DEFAULT_PATTERNS, watched_files};
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    /// How long changes have to settle before checking again, in
+    /// milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+}
+
+/// Size and modification time of every watched file; any difference
+/// between two snapshots means something changed.
+type Snapshot = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+pub async fn run(args: WatchArgs, ctx: &Context<'_>) -> Result<i32> {
+    let lockfile = lockfile_path(ctx.manifest)?;
+    let root = lockfile
+        .parent()
+        .context("the lockfile has no parent directory")?
+        .to_path_buf();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(args.interval), move |events| {
+        let _ = tx.send(events);
+    })
+    .context("failed to start the file watcher")?;
+    debouncer
+        .watcher()
+        .watch(&root, RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {}", root.display()))?;
+    println!(
+        "watching the manifests and lockfile under {}; press Ctrl-C to stop",
+        root.display()
+    );
+
+    let mut last: Option<Snapshot> = None;
+    loop {
+        match snapshot(&root) {
+            Ok(current) if last.as_ref() != Some(&current) => {
+                if last.is_some() {
+                    println!("\nchange detected; checking again");
+                }
+                if let Err(err) = check(ctx).await {
+                    eprintln!("cooldown check failed: {err:#}");
+                }
+                // Reading metadata may have written the lockfile itself.
+                last = Some(snapshot(&root).unwrap_or(current));
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!("failed to list the watched files: {err:#}"),
+        }
+        // Builds write under `target/` all the time; only wake up for the
+        // files a check depends on.
+        loop {
+            match rx.recv().await {
+                Some(Ok(events)) if events.iter().any(|event| is_watched(&event.path)) => break,
+                Some(Ok(_)) => {}
+                Some(Err(err)) => eprintln!("file watcher error: {err}"),
+                None => bail!("the file watcher stopped"),
+            }
+        }
+    }
+}
+
+/// One warn-mode pass: report the fresh releases, never fail or pin.
+async fn check(ctx: &Context<'_>) -> Result<()> {
+    let session = Session::new(ctx.config).await?;
+    let metadata = read_metadata(ctx.manifest, ctx.features, &ctx.platforms, ctx.config)?;
+    let violations = analyze_metadata(&session, metadata).await?;
+    if violations.is_empty() {
+        println!("dependency graph cooled down; no releases inside the cooldown window");
+    } else {
+        print!("{}", render_violations(&violations, ctx.config.now()));
+    }
+    Ok(())
+}
+
+/// Whether a change to `path` can change the result of a check.
+fn is_watched(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        DEFAULT_PATTERNS
+            .iter()
+            .filter_map(|pattern| pattern.rsplit('/').next())
+            .any(|file| name == file)
+    })
+}
+
+fn snapshot(root: &Path) -> Result<Snapshot> {
+    let patterns: Vec<String> = DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+    Ok(watched_files(root, &patterns)?
+        .into_iter()
+        .map(|path| {
+            let meta = fs::metadata(&path).ok();
+            let len = meta.as_ref().map_or(0, |meta| meta.len());
+            let modified = meta.and_then(|meta| meta.modified().ok());
+            (path, len, modified)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_notice_manifest_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(dir.path().join("README.md"), "hello").unwrap();
+        let before = snapshot(dir.path()).unwrap();
+        assert_eq!(before.len(), 1);
+
+        fs::write(dir.path().join("README.md"), "hello again").unwrap();
+        assert_eq!(snapshot(dir.path()).unwrap(), before);
+
+        fs::write(dir.path().join("Cargo.lock"), "version = 4\n").unwrap();
+        assert_ne!(snapshot(dir.path()).unwrap(), before);
+    }
+
+    #[test]
+    fn wakes_up_for_manifests_and_lockfiles_only() {
+        assert!(is_watched(Path::new("/ws/crates/app/Cargo.toml")));
+        assert!(is_watched(Path::new("/ws/Cargo.lock")));
+        assert!(!is_watched(Path::new("/ws/target/debug/app")));
+        assert!(!is_watched(Path::new("/ws/src/main.rs")));
+    }
+
+    #[test]
+    fn rejects_a_zero_interval() {
+        use clap::Parser;
+
+        use crate::commands::BuiltinCli;
+
+        assert!(BuiltinCli::try_parse_from(["watch", "--interval", "0"]).is_err());
+        assert!(BuiltinCli::try_parse_from(["watch", "--interval", "250"]).is_ok());
+    }
+}
@@ -1,51 +1,893 @@
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use dirs::home_dir;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::codes::{ErrorCode, Outcome};
+use crate::duration::{deserialize_minutes, parse_minutes};
+use crate::heuristics::distance;
+use crate::rollout::RolloutDecision;
 
 const DEFAULT_REGISTRY_INDEX: &str = "registry+https://github.com/rust-lang/crates.io-index";
 const DEFAULT_SPARSE_REGISTRY_INDEX: &str = "registry+sparse+https://index.crates.io/";
+const DEFAULT_SPARSE_INDEX_URL: &str = "https://index.crates.io/";
+const DEFAULT_OSV_URL: &str = "https://api.osv.dev/v1/querybatch";
+/// Two weeks: long enough for a takeover to be noticed and reported.
+const DEFAULT_OWNER_CHANGE_MINUTES: u64 = 14 * 24 * 60;
+/// A day, so a waiting nightly job finishes before the next one starts.
+const DEFAULT_MAX_WAIT_MINUTES: u64 = 24 * 60;
+const DEFAULT_SUBPROJECT_DIRS: [&str; 3] = ["examples", "fuzz", "xtask"];
+
+/// A setting spelled as one of a fixed set of values in config files and
+/// the environment.
+pub trait Variants: Copy + 'static {
+    /// The setting's name in config files.
+    const SETTING: &'static str;
+    const VARIANTS: &'static [(&'static str, Self)];
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The variant spelled `value`, or an error naming the closest one: an
+/// unknown value must not quietly fall back to the default.
+fn variant<T: Variants>(value: &str) -> Result<T, String> {
+    T::VARIANTS
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, variant)| *variant)
+        .ok_or_else(|| unknown_value::<T>(value))
+}
+
+/// Checks of every [`Variants`] setting's value in a config file table.
+const VALUE_CHECKS: [fn(&toml::Table) -> Option<String>; 10] = [
+    invalid_value::<Mode>,
+    invalid_value::<SourcePolicy>,
+    invalid_value::<DuplicatePolicy>,
+    invalid_value::<CiProvider>,
+    invalid_value::<PinBackend>,
+    invalid_value::<PinStrategy>,
+    invalid_value::<MsrvPolicy>,
+    invalid_value::<TyposquatPolicy>,
+    invalid_value::<YankedPolicy>,
+    invalid_value::<OwnerChangePolicy>,
+];
+
+/// The problem with `T`'s value in `table`, if it is set to a string that
+/// is not one of its variants. Values of the wrong type are left to the
+/// typed parse.
+fn invalid_value<T: Variants>(table: &toml::Table) -> Option<String> {
+    let value = table.get(T::SETTING)?.as_str()?;
+    variant::<T>(&value.replace('-', "_")).err()
+}
+
+fn unknown_value<T: Variants>(value: &str) -> String {
+    let names: Vec<&str> = T::VARIANTS.iter().map(|(name, _)| *name).collect();
+    let closest = names
+        .iter()
+        .map(|name| (distance(&value.to_lowercase(), name), name))
+        .filter(|(distance, _)| *distance <= (value.len() / 4).max(2))
+        .min_by_key(|(distance, _)| *distance);
+    let hint = match closest {
+        Some((_, name)) => format!(" (did you mean `{name}`?)"),
+        None => String::new(),
+    };
+    format!(
+        "unknown value `{value}` for `{}`{hint}; expected one of {}",
+        T::SETTING,
+        names.join(", ")
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Mode {
     Enforce,
     Warn,
     Off,
 }
 
+impl Variants for Mode {
+    const SETTING: &'static str = "mode";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("enforce", Mode::Enforce),
+        ("warn", Mode::Warn),
+        ("off", Mode::Off),
+    ];
+}
+
 impl Mode {
-    pub fn from_env(value: Option<String>) -> Self {
-        match value.as_deref() {
-            Some("warn") => Mode::Warn,
-            Some("off") => Mode::Off,
-            _ => Mode::Enforce,
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(Mode::Enforce), |value| variant(&value))
+    }
+}
+
+/// How to treat packages whose source is neither an allowed registry, git,
+/// nor a local path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourcePolicy {
+    Skip,
+    Warn,
+    Deny,
+}
+
+impl Variants for SourcePolicy {
+    const SETTING: &'static str = "unknown_source_policy";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("skip", SourcePolicy::Skip),
+        ("warn", SourcePolicy::Warn),
+        ("deny", SourcePolicy::Deny),
+    ];
+}
+
+impl SourcePolicy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(SourcePolicy::Skip), |value| variant(&value))
+    }
+}
+
+/// What to do when a pin leaves a crate in the graph under several
+/// semver-incompatible versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Accept the pin and report the duplication.
+    Report,
+    /// Try the remaining candidates first and fall back to a duplicating pin.
+    Avoid,
+    /// Never accept a pin that duplicates a crate.
+    Deny,
+}
+
+impl Variants for DuplicatePolicy {
+    const SETTING: &'static str = "duplicate_majors";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("report", DuplicatePolicy::Report),
+        ("avoid", DuplicatePolicy::Avoid),
+        ("deny", DuplicatePolicy::Deny),
+    ];
+}
+
+impl DuplicatePolicy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(DuplicatePolicy::Report), |value| variant(&value))
+    }
+}
+
+/// CI systems with native reporting channels, selected with `--ci`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CiProvider {
+    /// Workflow annotations, a job summary, and step outputs for GitHub
+    /// Actions.
+    Github,
+}
+
+impl Variants for CiProvider {
+    const SETTING: &'static str = "ci";
+    const VARIANTS: &'static [(&'static str, Self)] = &[("github", CiProvider::Github)];
+}
+
+impl CiProvider {
+    pub fn from_env(value: Option<String>) -> Result<Option<Self>, String> {
+        value.map(|value| variant(&value)).transpose()
+    }
+}
+
+/// How a chosen candidate is written to `Cargo.lock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinBackend {
+    /// `cargo update --precise` for every candidate.
+    Cargo,
+    /// Rewrite the lockfile entry and have `cargo metadata --locked` confirm
+    /// it, falling back to `cargo update` when Cargo disagrees.
+    Lockfile,
+}
+
+impl Variants for PinBackend {
+    const SETTING: &'static str = "pin_backend";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("cargo", PinBackend::Cargo),
+        ("lockfile", PinBackend::Lockfile),
+    ];
+}
+
+impl PinBackend {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(PinBackend::Cargo), |value| variant(&value))
+    }
+}
+
+/// How fresh releases in the graph are dealt with, and which cooled-down
+/// release replaces them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum PinStrategy {
+    /// The most recently published cooled-down release.
+    NewestCooled,
+    /// The highest cooled-down version below the current one.
+    ClosestBelowCurrent,
+    /// Like `closest_below_current`, but never leaving the current
+    /// major.minor series.
+    SameMinorOnly,
+    /// The lowest cooled-down version that satisfies every requirement.
+    OldestCompatible,
+    /// Never touch the lockfile; report every fresh release and fail.
+    Fail,
+}
+
+impl Variants for PinStrategy {
+    const SETTING: &'static str = "pin_strategy";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("newest_cooled", PinStrategy::NewestCooled),
+        ("closest_below_current", PinStrategy::ClosestBelowCurrent),
+        ("same_minor_only", PinStrategy::SameMinorOnly),
+        ("oldest_compatible", PinStrategy::OldestCompatible),
+        ("fail", PinStrategy::Fail),
+    ];
+}
+
+impl PinStrategy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value
+            .map(|value| value.replace('-', "_"))
+            .map_or(Ok(PinStrategy::NewestCooled), |value| variant(&value))
+    }
+}
+
+/// Which `rust-version` declarations downgrade candidates must respect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MsrvPolicy {
+    Off,
+    /// Reject candidates that need a newer Rust than the workspace's
+    /// `rust-version`.
+    Workspace,
+    /// Additionally reject candidates declaring a lower `rust-version` than
+    /// the current release.
+    Exact,
+}
+
+impl Variants for MsrvPolicy {
+    const SETTING: &'static str = "respect_msrv";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("off", MsrvPolicy::Off),
+        ("workspace", MsrvPolicy::Workspace),
+        ("exact", MsrvPolicy::Exact),
+    ];
+}
+
+impl MsrvPolicy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(MsrvPolicy::Workspace), |value| variant(&value))
+    }
+}
+
+/// What to do with dependencies named like a popular crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TyposquatPolicy {
+    Off,
+    Warn,
+    Block,
+}
+
+impl Variants for TyposquatPolicy {
+    const SETTING: &'static str = "typosquat";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("off", TyposquatPolicy::Off),
+        ("warn", TyposquatPolicy::Warn),
+        ("block", TyposquatPolicy::Block),
+    ];
+}
+
+impl TyposquatPolicy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(TyposquatPolicy::Off), |value| variant(&value))
+    }
+}
+
+/// What to do with locked releases that have since been yanked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum YankedPolicy {
+    Off,
+    Warn,
+    Deny,
+}
+
+impl Variants for YankedPolicy {
+    const SETTING: &'static str = "yanked";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("off", YankedPolicy::Off),
+        ("warn", YankedPolicy::Warn),
+        ("deny", YankedPolicy::Deny),
+    ];
+}
+
+impl YankedPolicy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(YankedPolicy::Off), |value| variant(&value))
+    }
+}
+
+/// What to do with a recent release from a crate whose ownership changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnerChangePolicy {
+    Off,
+    /// Hold the release for the longer owner-change window.
+    Extend,
+    /// Refuse the release until it is reviewed and allowlisted.
+    Block,
+}
+
+impl Variants for OwnerChangePolicy {
+    const SETTING: &'static str = "owner_change";
+    const VARIANTS: &'static [(&'static str, Self)] = &[
+        ("off", OwnerChangePolicy::Off),
+        ("extend", OwnerChangePolicy::Extend),
+        ("block", OwnerChangePolicy::Block),
+    ];
+}
+
+impl OwnerChangePolicy {
+    pub fn from_env(value: Option<String>) -> Result<Self, String> {
+        value.map_or(Ok(OwnerChangePolicy::Off), |value| variant(&value))
+    }
+}
+
+/// A lockfile whose releases count as already reviewed, so only crates whose
+/// version changed since then go through the cooldown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Baseline {
+    /// A lockfile on disk, such as a committed copy.
+    File(PathBuf),
+    /// `Cargo.lock` as of a git revision (`git:<ref>`).
+    GitRef(String),
+    /// A lockfile that passed an earlier run, by the SHA-256 of its contents
+    /// (`sha256:<hex>`).
+    Digest(String),
+}
+
+/// Written back in the form [`Baseline::parse`] reads.
+impl Serialize for Baseline {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Baseline::File(path) => path.serialize(serializer),
+            Baseline::GitRef(reference) => serializer.serialize_str(&format!("git:{reference}")),
+            Baseline::Digest(digest) => serializer.serialize_str(&format!("sha256:{digest}")),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+impl Baseline {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        if let Some(reference) = value.strip_prefix("git:") {
+            return Some(Baseline::GitRef(reference.to_string()));
+        }
+        if let Some(digest) = value.strip_prefix("sha256:") {
+            return Some(Baseline::Digest(digest.to_ascii_lowercase()));
+        }
+        Some(Baseline::File(PathBuf::from(value)))
+    }
+}
+
+/// A provider that can answer publication metadata queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataSourceKind {
+    Sparse,
+    Api,
+    DbDump,
+    IndexHistory,
+    Cache,
+}
+
+impl MetadataSourceKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "sparse" | "index" => Some(MetadataSourceKind::Sparse),
+            "api" => Some(MetadataSourceKind::Api),
+            "db_dump" | "db-dump" => Some(MetadataSourceKind::DbDump),
+            "index_history" | "index-history" => Some(MetadataSourceKind::IndexHistory),
+            "cache" => Some(MetadataSourceKind::Cache),
+            _ => None,
+        }
+    }
+}
+
+/// Stricter cooldown for crates that run code at build time: proc macros,
+/// crates with a build script, and crates with a `links` key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildTimePolicy {
+    /// Factor applied to the regular cooldown.
+    pub multiplier: Option<f64>,
+    /// Cooldown floor in minutes.
+    #[serde(default, deserialize_with = "deserialize_minutes")]
+    pub minutes: Option<u64>,
+}
+
+impl BuildTimePolicy {
+    /// Cooldown for a build-time crate whose regular cooldown is `base`; the
+    /// larger of the scaled value and the floor.
+    pub fn apply(&self, base: u64) -> u64 {
+        let scaled = self
+            .multiplier
+            .map(|factor| (base as f64 * factor.max(0.0)).round() as u64)
+            .unwrap_or(base);
+        scaled.max(self.minutes.unwrap_or(0))
+    }
+}
+
+/// How far a release moves a crate from the version locked before the run,
+/// in Cargo's compatibility sense: a `0.y` minor bump is major.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// Cooldown per bump type, replacing `cooldown_minutes` for releases that
+/// bump a crate already in the lockfile. Unset types keep the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BumpWindows {
+    #[serde(default, deserialize_with = "deserialize_minutes")]
+    pub patch: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_minutes")]
+    pub minor: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_minutes")]
+    pub major: Option<u64>,
+}
+
+impl BumpWindows {
+    pub fn is_set(&self) -> bool {
+        self.patch.is_some() || self.minor.is_some() || self.major.is_some()
+    }
+
+    pub fn minutes(&self, bump: Bump) -> Option<u64> {
+        match bump {
+            Bump::Patch => self.patch,
+            Bump::Minor => self.minor,
+            Bump::Major => self.major,
+        }
+    }
+}
+
+/// Exit status per outcome of a run. Unset outcomes exit with 1, and a
+/// failed Cargo command with its own status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExitCodes {
+    /// The policy blocked the build.
+    pub violations: Option<u8>,
+    /// Release ages could not be looked up.
+    pub network: Option<u8>,
+    /// The configuration could not be read.
+    pub config: Option<u8>,
+    /// Any other failure of the tool itself.
+    pub error: Option<u8>,
+    /// The forwarded Cargo command failed; `"passthrough"`, the default,
+    /// keeps Cargo's status.
+    #[serde(default, deserialize_with = "deserialize_cargo_exit")]
+    pub cargo: Option<u8>,
+}
+
+impl ExitCodes {
+    /// `violations=3,network=4,cargo=passthrough`, as in `COOLDOWN_EXIT_CODES`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut codes = Self::default();
+        for pair in value
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (key, code) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected `outcome=code`, found `{pair}`"))?;
+            let (key, code) = (key.trim(), code.trim());
+            if key == "cargo" && code == "passthrough" {
+                codes.cargo = None;
+                continue;
+            }
+            let code = code
+                .parse::<u8>()
+                .map_err(|_| format!("invalid exit code `{code}` for {key}"))?;
+            let slot = match key {
+                "violations" => &mut codes.violations,
+                "network" => &mut codes.network,
+                "config" => &mut codes.config,
+                "error" => &mut codes.error,
+                "cargo" => &mut codes.cargo,
+                _ => return Err(format!("unknown outcome `{key}`")),
+            };
+            *slot = Some(code);
+        }
+        Ok(codes)
+    }
+
+    pub fn code(&self, outcome: Outcome) -> i32 {
+        let or_one = |code: Option<u8>| code.map_or(1, i32::from);
+        match outcome {
+            Outcome::Success => 0,
+            Outcome::Violations => or_one(self.violations),
+            Outcome::Network => or_one(self.network),
+            Outcome::Config => or_one(self.config),
+            Outcome::Error => or_one(self.error),
+            Outcome::Cargo(status) => self.cargo.map_or(status, i32::from),
+        }
+    }
+}
+
+fn deserialize_cargo_exit<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u8>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CargoExit {
+        Code(u8),
+        Keyword(String),
+    }
+    match CargoExit::deserialize(deserializer)? {
+        CargoExit::Code(code) => Ok(Some(code)),
+        CargoExit::Keyword(keyword) if keyword == "passthrough" => Ok(None),
+        CargoExit::Keyword(keyword) => Err(serde::de::Error::custom(format!(
+            "expected an exit code or \"passthrough\", found \"{keyword}\""
+        ))),
+    }
+}
+
+/// Every setting, with the environment variables that set it. A file key
+/// spelled like its variable is read as the setting.
+pub const SETTINGS: &[(&str, &[&str])] = &[
+    ("cooldown_minutes", &["COOLDOWN_MINUTES"]),
+    ("warn_minutes", &["COOLDOWN_WARN_MINUTES"]),
+    ("mode", &["COOLDOWN_MODE"]),
+    ("allowlist_path", &["COOLDOWN_ALLOWLIST_PATH"]),
+    ("allowlist_url", &["COOLDOWN_ALLOWLIST_URL"]),
+    ("allowlist_public_key", &["COOLDOWN_ALLOWLIST_PUBLIC_KEY"]),
+    ("ttl_seconds", &["COOLDOWN_TTL_SECONDS"]),
+    ("cache_dir", &["COOLDOWN_CACHE_DIR"]),
+    ("offline_ok", &["COOLDOWN_OFFLINE_OK"]),
+    ("http_retries", &["COOLDOWN_HTTP_RETRIES"]),
+    ("verbose", &["COOLDOWN_VERBOSE"]),
+    ("registry_api", &["COOLDOWN_REGISTRY_API"]),
+    ("registry_index", &["COOLDOWN_REGISTRY_INDEX"]),
+    ("freeze_after", &["COOLDOWN_FREEZE_AFTER"]),
+    ("as_of", &["COOLDOWN_AS_OF"]),
+    ("scan_subprojects", &["COOLDOWN_SCAN_SUBPROJECTS"]),
+    ("subproject_dirs", &["COOLDOWN_SUBPROJECT_DIRS"]),
+    ("http_proxy", &["COOLDOWN_HTTP_PROXY"]),
+    ("ca_bundle_path", &["COOLDOWN_CA_BUNDLE_PATH"]),
+    (
+        "danger_accept_invalid_certs",
+        &["COOLDOWN_DANGER_ACCEPT_INVALID_CERTS"],
+    ),
+    ("unknown_source_policy", &["COOLDOWN_UNKNOWN_SOURCE_POLICY"]),
+    ("duplicate_majors", &["COOLDOWN_DUPLICATE_MAJORS"]),
+    ("baseline", &["COOLDOWN_BASELINE"]),
+    ("db_dump_path", &["COOLDOWN_DB_DUMP_PATH"]),
+    ("index_repo_path", &["COOLDOWN_INDEX_REPO"]),
+    ("metadata_sources", &["COOLDOWN_METADATA_SOURCES"]),
+    ("sparse_index_url", &["COOLDOWN_SPARSE_INDEX_URL"]),
+    ("sparse_version_lists", &["COOLDOWN_SPARSE_VERSION_LISTS"]),
+    ("osv", &["COOLDOWN_OSV"]),
+    ("osv_url", &["COOLDOWN_OSV_URL"]),
+    ("cargo_vet", &["COOLDOWN_CARGO_VET"]),
+    ("cargo_deny", &["COOLDOWN_CARGO_DENY"]),
+    ("owner_change", &["COOLDOWN_OWNER_CHANGE"]),
+    ("owner_change_minutes", &["COOLDOWN_OWNER_CHANGE_MINUTES"]),
+    ("certificate_path", &["COOLDOWN_CERTIFICATE_PATH"]),
+    ("certificate_key", &["COOLDOWN_CERT_KEY"]),
+    ("certificate_public_key", &["COOLDOWN_CERT_PUBLIC_KEY"]),
+    ("max_depth", &["COOLDOWN_MAX_DEPTH"]),
+    ("filter_platform", &["COOLDOWN_FILTER_PLATFORM"]),
+    (
+        "ignore_dev_dependencies",
+        &["COOLDOWN_IGNORE_DEV_DEPENDENCIES"],
+    ),
+    (
+        "build_time",
+        &[
+            "COOLDOWN_BUILD_TIME_MULTIPLIER",
+            "COOLDOWN_BUILD_TIME_MINUTES",
+        ],
+    ),
+    (
+        "bump_minutes",
+        &[
+            "COOLDOWN_BUMP_PATCH_MINUTES",
+            "COOLDOWN_BUMP_MINOR_MINUTES",
+            "COOLDOWN_BUMP_MAJOR_MINUTES",
+        ],
+    ),
+    ("patch_grace", &["COOLDOWN_PATCH_GRACE"]),
+    ("new_crate_minutes", &["COOLDOWN_NEW_CRATE_MINUTES"]),
+    (
+        "new_crate_min_versions",
+        &["COOLDOWN_NEW_CRATE_MIN_VERSIONS"],
+    ),
+    (
+        "new_crate_min_age_days",
+        &["COOLDOWN_NEW_CRATE_MIN_AGE_DAYS"],
+    ),
+    ("pin_journal_path", &["COOLDOWN_PIN_JOURNAL_PATH"]),
+    (
+        "enforce_sample_percent",
+        &["COOLDOWN_ENFORCE_SAMPLE_PERCENT"],
+    ),
+    ("survived_releases", &["COOLDOWN_SURVIVED_RELEASES"]),
+    ("min_downloads", &["COOLDOWN_MIN_DOWNLOADS"]),
+    ("git_minutes", &["COOLDOWN_GIT_MINUTES"]),
+    ("strict_sources", &["COOLDOWN_STRICT_SOURCES"]),
+    ("business_days", &["COOLDOWN_BUSINESS_DAYS"]),
+    ("holidays_path", &["COOLDOWN_HOLIDAYS"]),
+    ("yanked", &["COOLDOWN_YANKED"]),
+    ("typosquat", &["COOLDOWN_TYPOSQUAT"]),
+    ("verify_checksums", &["COOLDOWN_VERIFY_CHECKSUMS"]),
+    ("pin_strategy", &["COOLDOWN_PIN_STRATEGY"]),
+    ("pin_backend", &["COOLDOWN_PIN_BACKEND"]),
+    ("plan_pins", &["COOLDOWN_PLAN_PINS"]),
+    ("keep_partial", &["COOLDOWN_KEEP_PARTIAL"]),
+    ("resume_plans", &["COOLDOWN_RESUME_PLANS"]),
+    ("wait", &["COOLDOWN_WAIT"]),
+    ("skip_unchanged", &["COOLDOWN_SKIP_UNCHANGED"]),
+    ("max_wait_minutes", &["COOLDOWN_MAX_WAIT_MINUTES"]),
+    ("max_duration_minutes", &["COOLDOWN_MAX_DURATION_MINUTES"]),
+    ("max_iterations", &["COOLDOWN_MAX_ITERATIONS"]),
+    ("allow_dirty", &["COOLDOWN_ALLOW_DIRTY"]),
+    ("commit", &["COOLDOWN_COMMIT"]),
+    (
+        "commit_message_template",
+        &["COOLDOWN_COMMIT_MESSAGE_TEMPLATE"],
+    ),
+    ("pr_comment", &["COOLDOWN_PR_COMMENT"]),
+    ("ci", &["COOLDOWN_CI"]),
+    ("exit_codes", &["COOLDOWN_EXIT_CODES"]),
+    ("events", &["COOLDOWN_EVENTS"]),
+    ("audit_log_path", &["COOLDOWN_AUDIT_LOG"]),
+    ("metrics_path", &["COOLDOWN_METRICS_PATH"]),
+    ("metrics_pushgateway", &["COOLDOWN_METRICS_PUSHGATEWAY"]),
+    ("webhook_url", &["COOLDOWN_WEBHOOK_URL"]),
+    ("webhook_template", &["COOLDOWN_WEBHOOK_TEMPLATE"]),
+    ("blocking_graph_path", &["COOLDOWN_BLOCKING_GRAPH"]),
+    ("max_downgrade", &["COOLDOWN_MAX_DOWNGRADE"]),
+    ("respect_msrv", &["COOLDOWN_RESPECT_MSRV"]),
+    ("allow_license_change", &["COOLDOWN_ALLOW_LICENSE_CHANGE"]),
+];
+
+/// Where the effective value of a setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Flag(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.write_str("default"),
+            Origin::File(path) => write!(f, "file {}", path.display()),
+            Origin::Env(var) => write!(f, "env {var}"),
+            Origin::Flag(flag) => write!(f, "flag {flag}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
     pub cooldown_minutes: u64,
+    /// Soft window: releases past their cooldown window but younger than
+    /// this are reported as warnings without blocking.
+    pub warn_minutes: Option<u64>,
     pub mode: Mode,
     pub ttl_seconds: u64,
     pub allowlist_path: Option<PathBuf>,
+    /// Organization allowlist fetched over HTTPS and merged into the local one.
+    pub allowlist_url: Option<String>,
+    /// Minisign public key that must have signed `allowlist_url`.
+    pub allowlist_public_key: Option<String>,
     pub cache_dir: Option<PathBuf>,
     pub offline_ok: bool,
     pub http_retries: u32,
     pub verbose: bool,
     pub registry_api: String,
+    #[serde(rename = "registry_index")]
     pub allowed_registries: Vec<String>,
+    pub freeze_after: Option<DateTime<Utc>>,
+    /// Evaluate the policy as of this instant instead of the current time,
+    /// to reproduce or replay a run.
+    pub as_of: Option<DateTime<Utc>>,
+    pub scan_subprojects: bool,
+    pub subproject_dirs: Vec<String>,
+    pub http_proxy: Option<String>,
+    pub ca_bundle_path: Option<PathBuf>,
+    pub danger_accept_invalid_certs: bool,
+    pub unknown_source_policy: SourcePolicy,
+    pub duplicate_majors: DuplicatePolicy,
+    pub baseline: Option<Baseline>,
+    /// Cargo `--config` overrides from the command line, passed to every
+    /// internal Cargo invocation. Not read from the environment.
+    #[serde(skip)]
+    pub cargo_config: Vec<String>,
+    /// Ask about each release instead of pinning on its own. Only set by
+    /// `--interactive`.
+    #[serde(skip)]
+    pub interactive: bool,
+    /// The forwarded Cargo command runs with `--offline` or `--frozen`:
+    /// registry metadata comes from the cache only.
+    #[serde(skip)]
+    pub offline: bool,
+    /// The forwarded Cargo command runs with `--locked` or `--frozen`: the
+    /// lockfile must not change, so nothing is pinned.
+    #[serde(skip)]
+    pub locked: bool,
+    pub db_dump_path: Option<PathBuf>,
+    /// Local git clone of the registry index, dating releases by the commit
+    /// that added them.
+    pub index_repo_path: Option<PathBuf>,
+    pub metadata_sources: Vec<MetadataSourceKind>,
+    pub sparse_index_url: String,
+    pub sparse_version_lists: bool,
+    pub osv: bool,
+    pub cargo_vet: bool,
+    pub cargo_deny: bool,
+    pub owner_change: OwnerChangePolicy,
+    pub owner_change_minutes: u64,
+    pub osv_url: String,
+    pub certificate_path: Option<PathBuf>,
+    /// Hex-encoded ed25519 secret key that signs certificates.
+    #[serde(serialize_with = "redacted")]
+    pub certificate_key: Option<String>,
+    /// Hex-encoded ed25519 public key that verifies them.
+    pub certificate_public_key: Option<String>,
+    pub max_depth: Option<usize>,
+    pub filter_platform: bool,
+    pub ignore_dev_dependencies: bool,
+    pub build_time: BuildTimePolicy,
+    /// Windows by how far a release bumps the previously locked version.
+    pub bump_minutes: BumpWindows,
+    /// Exempt patch releases of a major.minor series that was already
+    /// locked before the run.
+    pub patch_grace: bool,
+    pub new_crate_minutes: Option<u64>,
+    pub new_crate_min_versions: usize,
+    pub new_crate_min_age_days: u64,
+    pub pin_journal_path: Option<PathBuf>,
+    pub enforce_sample_percent: u8,
+    /// How the enforcement rollout sampled this run, when one is configured.
+    #[serde(skip)]
+    pub rollout: Option<RolloutDecision>,
+    /// Accept a release inside its window once this many newer releases
+    /// followed it without it being yanked.
+    pub survived_releases: Option<usize>,
+    /// Downloads a recent release must have accumulated to be accepted.
+    pub min_downloads: Option<u64>,
+    /// Cooldown for the commit a git dependency is locked to, counted from
+    /// its commit date. Git dependencies are not checked when unset.
+    pub git_minutes: Option<u64>,
+    /// Fail when the graph has path, git, or unapproved-registry packages
+    /// the cooldown cannot vet, instead of listing them.
+    pub strict_sources: bool,
+    /// Count cooldown windows over business days only: time on Saturdays and
+    /// Sundays (UTC) does not count toward them.
+    pub business_days: bool,
+    /// File of dates, one `YYYY-MM-DD` per line, whose time does not count
+    /// toward cooldown windows.
+    pub holidays_path: Option<PathBuf>,
+    pub yanked: YankedPolicy,
+    pub typosquat: TyposquatPolicy,
+    pub verify_checksums: bool,
+    pub pin_strategy: PinStrategy,
+    pub pin_backend: PinBackend,
+    /// Choose releases for every fresh crate at once before falling back to
+    /// pinning crate by crate.
+    pub plan_pins: bool,
+    /// Leave pins in place when the pinning flow fails instead of restoring
+    /// the lockfile.
+    pub keep_partial: bool,
+    /// Save pins to `.cooldown/plan.json` as they are applied and re-apply
+    /// them when a run that was cut short is started again.
+    pub resume_plans: bool,
+    /// Sleep until fresh releases cool down instead of failing.
+    pub wait: bool,
+    /// Skip the checks before Cargo commands when the manifests, lockfiles,
+    /// and policy match the last successful run.
+    pub skip_unchanged: bool,
+    /// Longest `wait` sleeps before giving up.
+    pub max_wait_minutes: u64,
+    /// Longest the pinning flow may run before giving up.
+    pub max_duration_minutes: Option<u64>,
+    /// Most times the pinning flow may analyze one workspace's graph.
+    pub max_iterations: Option<usize>,
+    /// Rewrite a `Cargo.lock` that has uncommitted changes.
+    pub allow_dirty: bool,
+    /// Commit the lockfiles to git after a run that pinned something.
+    pub commit: bool,
+    /// Message for those commits; `{count}` and `{pins}` are filled in.
+    pub commit_message_template: Option<String>,
+    /// Write `target/cooldown/pr-comment.md` after each pinning run.
+    pub pr_comment: bool,
+    /// Also report through this CI system's native channels.
+    pub ci: Option<CiProvider>,
+    /// Exit status for each way a run can end.
+    pub exit_codes: ExitCodes,
+    /// JSONL event stream: a file path, or `-` for stdout.
+    pub events: Option<String>,
+    /// Hash-chained JSONL log every decision of every run is appended to.
+    pub audit_log_path: Option<PathBuf>,
+    /// Prometheus textfile the run's metrics are written to.
+    pub metrics_path: Option<PathBuf>,
+    /// Pushgateway base URL the run's metrics are pushed to.
+    pub metrics_pushgateway: Option<String>,
+    /// URL a JSON payload is POSTed to when enforcement blocks a build.
+    #[serde(serialize_with = "redacted")]
+    pub webhook_url: Option<String>,
+    /// Text of the webhook message; see [`crate::notify::DEFAULT_TEMPLATE`].
+    pub webhook_template: Option<String>,
+    /// Where a failed run draws its fresh releases and what requires them;
+    /// Mermaid for `.mmd`, DOT otherwise.
+    pub blocking_graph_path: Option<PathBuf>,
+    /// How many minor series a pin may go back; `0` keeps the current
+    /// major.minor. Never crosses a major version.
+    pub max_downgrade: Option<u64>,
+    pub respect_msrv: MsrvPolicy,
+    /// Pin to releases under a different license than the current one
+    /// without an allowlist entry.
+    pub allow_license_change: bool,
+    /// The config file each file-provided setting was read from.
+    #[serde(skip)]
+    file_origins: BTreeMap<String, PathBuf>,
+    /// Settings a command-line flag overrode, and the flag.
+    #[serde(skip)]
+    flag_origins: BTreeMap<String, String>,
 }
 
 impl Config {
+    #[cfg(test)]
     pub fn from_env() -> Self {
-        let file_config = load_file_config();
+        Self::with_profile(None).expect("the config files load")
+    }
+
+    /// Like [`Config::from_env`], with the keys of the `[profile.<name>]`
+    /// table of the config file laid over its top-level ones. `None` uses
+    /// `COOLDOWN_PROFILE`, if set. A config file that does not load fails
+    /// with [`ErrorCode::InvalidConfig`].
+    pub fn with_profile(profile: Option<&str>) -> anyhow::Result<Self> {
+        Self::load(profile, true)
+    }
+
+    /// Like [`Config::with_profile`], skipping config files that do not load
+    /// instead of failing, for the commands that diagnose them.
+    pub fn skipping_broken_files(profile: Option<&str>) -> anyhow::Result<Self> {
+        Self::load(profile, false)
+    }
+
+    fn load(profile: Option<&str>, strict: bool) -> anyhow::Result<Self> {
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| env::var("COOLDOWN_PROFILE").ok());
+        let file_config = load_file_config(config_files(), profile.as_deref(), strict)?;
+        let invalid = |err: String| ErrorCode::InvalidConfig.fail(err);
 
         let cooldown_minutes = env::var("COOLDOWN_MINUTES")
             .ok()
-            .and_then(|v| v.parse().ok())
+            .and_then(|v| parse_minutes(&v).ok())
             .or_else(|| {
                 file_config
                     .as_ref()
@@ -53,11 +895,18 @@ impl Config {
             })
             .unwrap_or(0); // Default to 0 (no cooldown)
 
+        let warn_minutes = env::var("COOLDOWN_WARN_MINUTES")
+            .ok()
+            .and_then(|v| parse_minutes(&v).ok())
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.warn_minutes))
+            .filter(|&minutes| minutes > 0);
+
         let mode = Mode::from_env(
             env::var("COOLDOWN_MODE")
                 .ok()
                 .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.mode.clone())),
-        );
+        )
+        .map_err(invalid)?;
 
         let ttl_seconds = env::var("COOLDOWN_TTL_SECONDS")
             .ok()
@@ -70,6 +919,24 @@ impl Config {
             .or_else(|| file_config.as_ref().and_then(|cfg| cfg.allowlist_path()))
             .filter(|path| !path.as_os_str().is_empty());
 
+        let allowlist_url = env::var("COOLDOWN_ALLOWLIST_URL")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.allowlist_url.clone())
+            })
+            .filter(|url| !url.is_empty());
+
+        let allowlist_public_key = env::var("COOLDOWN_ALLOWLIST_PUBLIC_KEY")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.allowlist_public_key.clone())
+            })
+            .filter(|key| !key.is_empty());
+
         let cache_dir = env::var_os("COOLDOWN_CACHE_DIR")
             .map(PathBuf::from)
             .or_else(|| file_config.as_ref().and_then(|cfg| cfg.cache_dir()))
@@ -122,25 +989,748 @@ impl Config {
             .map(|value| parse_registry_list(&value))
             .unwrap_or_else(default_allowed_registries);
 
-        Self {
+        let freeze_after = env::var("COOLDOWN_FREEZE_AFTER")
+            .ok()
+            .and_then(|v| DateTime::parse_from_rfc3339(v.trim()).ok())
+            .map(|v| v.with_timezone(&Utc))
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.freeze_after));
+
+        let as_of = env::var("COOLDOWN_AS_OF")
+            .ok()
+            .and_then(|v| DateTime::parse_from_rfc3339(v.trim()).ok())
+            .map(|v| v.with_timezone(&Utc))
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.as_of));
+
+        let scan_subprojects = match env::var("COOLDOWN_SCAN_SUBPROJECTS") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.scan_subprojects)
+                .unwrap_or(true),
+        };
+
+        let subproject_dirs = env::var("COOLDOWN_SUBPROJECT_DIRS")
+            .ok()
+            .map(|value| parse_list(&value))
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.subproject_dirs.clone())
+            })
+            .unwrap_or_else(|| {
+                DEFAULT_SUBPROJECT_DIRS
+                    .iter()
+                    .map(|dir| dir.to_string())
+                    .collect()
+            });
+
+        let http_proxy = env::var("COOLDOWN_HTTP_PROXY")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.http_proxy.clone())
+            })
+            .filter(|value| !value.trim().is_empty());
+
+        let ca_bundle_path = env::var_os("COOLDOWN_CA_BUNDLE_PATH")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.ca_bundle_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let danger_accept_invalid_certs = match env::var("COOLDOWN_DANGER_ACCEPT_INVALID_CERTS") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.danger_accept_invalid_certs)
+                .unwrap_or(false),
+        };
+
+        let unknown_source_policy =
+            SourcePolicy::from_env(env::var("COOLDOWN_UNKNOWN_SOURCE_POLICY").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.unknown_source_policy.clone())
+            }))
+            .map_err(invalid)?;
+
+        let duplicate_majors =
+            DuplicatePolicy::from_env(env::var("COOLDOWN_DUPLICATE_MAJORS").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.duplicate_majors.clone())
+            }))
+            .map_err(invalid)?;
+
+        let baseline = env::var("COOLDOWN_BASELINE")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.baseline.clone())
+            })
+            .and_then(|value| Baseline::parse(&value));
+
+        let db_dump_path = env::var_os("COOLDOWN_DB_DUMP_PATH")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.db_dump_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let index_repo_path = env::var_os("COOLDOWN_INDEX_REPO")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.index_repo_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let metadata_sources = env::var("COOLDOWN_METADATA_SOURCES")
+            .ok()
+            .map(|value| parse_list(&value))
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.metadata_sources.clone())
+            })
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| MetadataSourceKind::parse(value))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|kinds| !kinds.is_empty())
+            .unwrap_or_else(|| {
+                if db_dump_path.is_some() {
+                    vec![MetadataSourceKind::DbDump]
+                } else if index_repo_path.is_some() {
+                    vec![MetadataSourceKind::IndexHistory]
+                } else {
+                    vec![MetadataSourceKind::Api]
+                }
+            });
+
+        let sparse_index_url = env::var("COOLDOWN_SPARSE_INDEX_URL")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.sparse_index_url.clone())
+            })
+            .unwrap_or_else(|| DEFAULT_SPARSE_INDEX_URL.to_string());
+
+        let sparse_version_lists = match env::var("COOLDOWN_SPARSE_VERSION_LISTS") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.sparse_version_lists)
+                .unwrap_or(db_dump_path.is_none()),
+        };
+
+        let osv = match env::var("COOLDOWN_OSV") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.osv)
+                .unwrap_or(false),
+        };
+
+        let cargo_vet = match env::var("COOLDOWN_CARGO_VET") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.cargo_vet)
+                .unwrap_or(true),
+        };
+
+        let cargo_deny = match env::var("COOLDOWN_CARGO_DENY") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.cargo_deny)
+                .unwrap_or(true),
+        };
+
+        let verify_checksums = match env::var("COOLDOWN_VERIFY_CHECKSUMS") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.verify_checksums)
+                .unwrap_or(false),
+        };
+
+        let owner_change =
+            OwnerChangePolicy::from_env(env::var("COOLDOWN_OWNER_CHANGE").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.owner_change.clone())
+            }))
+            .map_err(invalid)?;
+
+        let owner_change_minutes = env::var("COOLDOWN_OWNER_CHANGE_MINUTES")
+            .ok()
+            .and_then(|v| parse_minutes(&v).ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.owner_change_minutes)
+            })
+            .unwrap_or(DEFAULT_OWNER_CHANGE_MINUTES);
+
+        let osv_url = env::var("COOLDOWN_OSV_URL")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.osv_url.clone())
+            })
+            .unwrap_or_else(|| DEFAULT_OSV_URL.to_string());
+
+        let certificate_path = env::var_os("COOLDOWN_CERTIFICATE_PATH")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.certificate_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        // The signing key is a secret and is only read from the environment.
+        let certificate_key = env::var("COOLDOWN_CERT_KEY")
+            .ok()
+            .filter(|key| !key.is_empty());
+        let certificate_public_key = env::var("COOLDOWN_CERT_PUBLIC_KEY")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.certificate_public_key.clone())
+            })
+            .filter(|key| !key.is_empty());
+
+        let max_depth = env::var("COOLDOWN_MAX_DEPTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.max_depth));
+
+        let filter_platform = match env::var("COOLDOWN_FILTER_PLATFORM") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.filter_platform)
+                .unwrap_or(false),
+        };
+
+        let ignore_dev_dependencies = match env::var("COOLDOWN_IGNORE_DEV_DEPENDENCIES") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.ignore_dev_dependencies)
+                .unwrap_or(false),
+        };
+
+        let file_build_time = file_config
+            .as_ref()
+            .and_then(|cfg| cfg.data.build_time)
+            .unwrap_or_default();
+        let build_time = BuildTimePolicy {
+            multiplier: env::var("COOLDOWN_BUILD_TIME_MULTIPLIER")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .or(file_build_time.multiplier),
+            minutes: env::var("COOLDOWN_BUILD_TIME_MINUTES")
+                .ok()
+                .and_then(|v| parse_minutes(&v).ok())
+                .or(file_build_time.minutes),
+        };
+
+        let file_bump_minutes = file_config
+            .as_ref()
+            .and_then(|cfg| cfg.data.bump_minutes)
+            .unwrap_or_default();
+        let bump_window = |var: &str, file: Option<u64>| {
+            env::var(var)
+                .ok()
+                .and_then(|v| parse_minutes(&v).ok())
+                .or(file)
+        };
+        let bump_minutes = BumpWindows {
+            patch: bump_window("COOLDOWN_BUMP_PATCH_MINUTES", file_bump_minutes.patch),
+            minor: bump_window("COOLDOWN_BUMP_MINOR_MINUTES", file_bump_minutes.minor),
+            major: bump_window("COOLDOWN_BUMP_MAJOR_MINUTES", file_bump_minutes.major),
+        };
+
+        let patch_grace = match env::var("COOLDOWN_PATCH_GRACE") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.patch_grace)
+                .unwrap_or(false),
+        };
+
+        let new_crate_minutes = env::var("COOLDOWN_NEW_CRATE_MINUTES")
+            .ok()
+            .and_then(|v| parse_minutes(&v).ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.new_crate_minutes)
+            });
+
+        let new_crate_min_versions = env::var("COOLDOWN_NEW_CRATE_MIN_VERSIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.new_crate_min_versions)
+            })
+            .unwrap_or(5);
+
+        let new_crate_min_age_days = env::var("COOLDOWN_NEW_CRATE_MIN_AGE_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.new_crate_min_age_days)
+            })
+            .unwrap_or(30);
+
+        let pin_journal_path = env::var_os("COOLDOWN_PIN_JOURNAL_PATH")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.pin_journal_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let enforce_sample_percent = env::var("COOLDOWN_ENFORCE_SAMPLE_PERCENT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.enforce_sample_percent)
+            })
+            .unwrap_or(100);
+
+        let survived_releases = env::var("COOLDOWN_SURVIVED_RELEASES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.survived_releases)
+            })
+            .filter(|&releases| releases > 0);
+
+        let min_downloads = env::var("COOLDOWN_MIN_DOWNLOADS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.min_downloads))
+            .filter(|&downloads| downloads > 0);
+
+        let yanked = YankedPolicy::from_env(
+            env::var("COOLDOWN_YANKED")
+                .ok()
+                .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.yanked.clone())),
+        )
+        .map_err(invalid)?;
+
+        let typosquat =
+            TyposquatPolicy::from_env(env::var("COOLDOWN_TYPOSQUAT").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.typosquat.clone())
+            }))
+            .map_err(invalid)?;
+
+        let pin_strategy =
+            PinStrategy::from_env(env::var("COOLDOWN_PIN_STRATEGY").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.pin_strategy.clone())
+            }))
+            .map_err(invalid)?;
+
+        let pin_backend =
+            PinBackend::from_env(env::var("COOLDOWN_PIN_BACKEND").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.pin_backend.clone())
+            }))
+            .map_err(invalid)?;
+
+        let plan_pins = match env::var("COOLDOWN_PLAN_PINS") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.plan_pins)
+                .unwrap_or(true),
+        };
+
+        let keep_partial = match env::var("COOLDOWN_KEEP_PARTIAL") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.keep_partial)
+                .unwrap_or(false),
+        };
+
+        let allow_dirty = match env::var("COOLDOWN_ALLOW_DIRTY") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.allow_dirty)
+                .unwrap_or(false),
+        };
+
+        let commit = match env::var("COOLDOWN_COMMIT") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.commit)
+                .unwrap_or(false),
+        };
+
+        let commit_message_template = env::var("COOLDOWN_COMMIT_MESSAGE_TEMPLATE")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.commit_message_template.clone())
+            })
+            .filter(|template| !template.trim().is_empty());
+
+        let pr_comment = match env::var("COOLDOWN_PR_COMMENT") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.pr_comment)
+                .unwrap_or(false),
+        };
+
+        let ci = CiProvider::from_env(
+            env::var("COOLDOWN_CI")
+                .ok()
+                .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.ci.clone())),
+        )
+        .map_err(invalid)?;
+
+        let exit_codes = env::var("COOLDOWN_EXIT_CODES")
+            .ok()
+            .and_then(|v| ExitCodes::parse(&v).ok())
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.exit_codes))
+            .unwrap_or_default();
+
+        let events = env::var("COOLDOWN_EVENTS")
+            .ok()
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.events.clone()))
+            .filter(|target| !target.trim().is_empty());
+
+        let audit_log_path = env::var_os("COOLDOWN_AUDIT_LOG")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.audit_log_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let metrics_path = env::var_os("COOLDOWN_METRICS_PATH")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.metrics_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let metrics_pushgateway = env::var("COOLDOWN_METRICS_PUSHGATEWAY")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.metrics_pushgateway.clone())
+            })
+            .filter(|url| !url.trim().is_empty());
+
+        let webhook_url = env::var("COOLDOWN_WEBHOOK_URL")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.webhook_url.clone())
+            })
+            .filter(|url| !url.trim().is_empty());
+
+        let webhook_template = env::var("COOLDOWN_WEBHOOK_TEMPLATE").ok().or_else(|| {
+            file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.webhook_template.clone())
+        });
+
+        let blocking_graph_path = env::var_os("COOLDOWN_BLOCKING_GRAPH")
+            .map(PathBuf::from)
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.blocking_graph_path())
+            })
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let wait = match env::var("COOLDOWN_WAIT") {
+            Ok(v) => parse_bool(&v),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.wait)
+                .unwrap_or(false),
+        };
+
+        let resume_plans = match env::var("COOLDOWN_RESUME_PLANS") {
+            Ok(v) => parse_bool(&v),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.resume_plans)
+                .unwrap_or(false),
+        };
+
+        let skip_unchanged = match env::var("COOLDOWN_SKIP_UNCHANGED") {
+            Ok(v) => parse_bool(&v),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.skip_unchanged)
+                .unwrap_or(false),
+        };
+
+        let max_wait_minutes = env::var("COOLDOWN_MAX_WAIT_MINUTES")
+            .ok()
+            .and_then(|v| parse_minutes(&v).ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.max_wait_minutes)
+            })
+            .unwrap_or(DEFAULT_MAX_WAIT_MINUTES);
+
+        let max_duration_minutes = env::var("COOLDOWN_MAX_DURATION_MINUTES")
+            .ok()
+            .and_then(|v| parse_minutes(&v).ok())
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.max_duration_minutes)
+            })
+            .filter(|&minutes| minutes > 0);
+
+        let max_iterations = env::var("COOLDOWN_MAX_ITERATIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.max_iterations))
+            .filter(|&iterations| iterations > 0);
+
+        let git_minutes = env::var("COOLDOWN_GIT_MINUTES")
+            .ok()
+            .and_then(|v| parse_minutes(&v).ok())
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.data.git_minutes));
+
+        let strict_sources = match env::var("COOLDOWN_STRICT_SOURCES") {
+            Ok(v) => parse_bool(&v),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.strict_sources)
+                .unwrap_or(false),
+        };
+
+        let business_days = match env::var("COOLDOWN_BUSINESS_DAYS") {
+            Ok(v) => parse_bool(&v),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.business_days)
+                .unwrap_or(false),
+        };
+
+        let holidays_path = env::var_os("COOLDOWN_HOLIDAYS")
+            .map(PathBuf::from)
+            .or_else(|| file_config.as_ref().and_then(|cfg| cfg.holidays_path()))
+            .filter(|path| !path.as_os_str().is_empty());
+
+        let max_downgrade = env::var("COOLDOWN_MAX_DOWNGRADE")
+            .ok()
+            .or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.max_downgrade.clone())
+                    .map(|setting| match setting {
+                        MaxDowngrade::Steps(steps) => steps.to_string(),
+                        MaxDowngrade::Level(level) => level,
+                    })
+            })
+            .and_then(|value| parse_max_downgrade(&value));
+
+        let respect_msrv =
+            MsrvPolicy::from_env(env::var("COOLDOWN_RESPECT_MSRV").ok().or_else(|| {
+                file_config
+                    .as_ref()
+                    .and_then(|cfg| cfg.data.respect_msrv.clone())
+            }))
+            .map_err(invalid)?;
+
+        let allow_license_change = match env::var("COOLDOWN_ALLOW_LICENSE_CHANGE") {
+            Ok(value) => parse_bool(&value),
+            Err(_) => file_config
+                .as_ref()
+                .and_then(|cfg| cfg.data.allow_license_change)
+                .unwrap_or(false),
+        };
+
+        Ok(Self {
             cooldown_minutes,
+            warn_minutes,
             mode,
             ttl_seconds,
             allowlist_path,
+            allowlist_url,
+            allowlist_public_key,
             cache_dir,
             offline_ok,
             http_retries,
             verbose,
             registry_api,
             allowed_registries,
+            freeze_after,
+            as_of,
+            scan_subprojects,
+            subproject_dirs,
+            http_proxy,
+            ca_bundle_path,
+            danger_accept_invalid_certs,
+            unknown_source_policy,
+            duplicate_majors,
+            baseline,
+            cargo_config: Vec::new(),
+            interactive: false,
+            offline: false,
+            locked: false,
+            db_dump_path,
+            index_repo_path,
+            metadata_sources,
+            sparse_index_url,
+            sparse_version_lists,
+            osv,
+            osv_url,
+            cargo_vet,
+            cargo_deny,
+            owner_change,
+            owner_change_minutes,
+            certificate_path,
+            certificate_key,
+            certificate_public_key,
+            max_depth,
+            filter_platform,
+            ignore_dev_dependencies,
+            build_time,
+            bump_minutes,
+            patch_grace,
+            new_crate_minutes,
+            new_crate_min_versions,
+            new_crate_min_age_days,
+            pin_journal_path,
+            enforce_sample_percent,
+            rollout: None,
+            survived_releases,
+            min_downloads,
+            git_minutes,
+            strict_sources,
+            business_days,
+            holidays_path,
+            yanked,
+            typosquat,
+            verify_checksums,
+            pin_strategy,
+            pin_backend,
+            plan_pins,
+            keep_partial,
+            resume_plans,
+            wait,
+            skip_unchanged,
+            max_wait_minutes,
+            max_duration_minutes,
+            max_iterations,
+            allow_dirty,
+            commit,
+            commit_message_template,
+            pr_comment,
+            ci,
+            exit_codes,
+            events,
+            audit_log_path,
+            metrics_path,
+            metrics_pushgateway,
+            webhook_url,
+            webhook_template,
+            blocking_graph_path,
+            max_downgrade,
+            respect_msrv,
+            allow_license_change,
+            file_origins: file_config.map(|cfg| cfg.origins).unwrap_or_default(),
+            flag_origins: BTreeMap::new(),
+        })
+    }
+
+    /// Record that `flag` overrode `setting` for this run.
+    pub fn set_flag(&mut self, setting: &str, flag: &str) {
+        self.flag_origins
+            .insert(setting.to_string(), flag.to_string());
+    }
+
+    /// Where `setting` got its value: a flag, then the environment, then
+    /// the config files, and the default otherwise.
+    pub fn origin(&self, setting: &str) -> Origin {
+        if let Some(flag) = self.flag_origins.get(setting) {
+            return Origin::Flag(flag.clone());
+        }
+        let vars = SETTINGS
+            .iter()
+            .find(|(name, _)| *name == setting)
+            .map_or(&[][..], |(_, vars)| *vars);
+        if let Some(var) = vars.iter().find(|var| env::var_os(var).is_some()) {
+            return Origin::Env(var.to_string());
+        }
+        match self.file_origins.get(setting) {
+            Some(path) => Origin::File(path.clone()),
+            None => Origin::Default,
         }
     }
 
+    /// The instant the policy is evaluated at: `as_of` when set, otherwise
+    /// the current time.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.as_of.unwrap_or_else(Utc::now)
+    }
+
+    /// Whether any policy is configured that requires inspecting the graph.
+    pub fn has_policy(&self) -> bool {
+        self.cooldown_minutes > 0
+            || self.warn_minutes.is_some()
+            || self.freeze_after.is_some()
+            || self.build_time.minutes.is_some_and(|minutes| minutes > 0)
+            || self.bump_minutes.is_set()
+            || self.new_crate_minutes.is_some_and(|minutes| minutes > 0)
+            || self.owner_change != OwnerChangePolicy::Off
+            || self.min_downloads.is_some()
+            || self.git_minutes.is_some()
+            || self.strict_sources
+            || self.unknown_source_policy != SourcePolicy::Skip
+            || self.yanked != YankedPolicy::Off
+            || self.typosquat != TyposquatPolicy::Off
+            || self.verify_checksums
+    }
+
     pub fn is_registry_allowed(&self, source: &str) -> bool {
         self.allowed_registries
             .iter()
             .any(|allowed| allowed == source)
     }
+
+    /// Whether the lockfile `source` is the registry behind
+    /// `sparse_index_url`. Cargo records crates.io under its git index URL
+    /// whichever protocol fetched it.
+    pub fn is_sparse_indexed(&self, source: &str) -> bool {
+        let url = self.sparse_index_url.trim_end_matches('/');
+        if url == DEFAULT_SPARSE_INDEX_URL.trim_end_matches('/')
+            && (source == DEFAULT_REGISTRY_INDEX || source == DEFAULT_SPARSE_REGISTRY_INDEX)
+        {
+            return true;
+        }
+        source
+            .strip_prefix("registry+")
+            .unwrap_or(source)
+            .strip_prefix("sparse+")
+            .is_some_and(|indexed| indexed.trim_end_matches('/') == url)
+    }
 }
 
 fn normalize_registry_index(raw: &str) -> String {
@@ -163,6 +1753,14 @@ fn parse_registry_list(raw: &str) -> Vec<String> {
         .collect()
 }
 
+fn parse_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 fn default_allowed_registries() -> Vec<String> {
     vec![
         DEFAULT_REGISTRY_INDEX.to_string(),
@@ -170,19 +1768,41 @@ fn default_allowed_registries() -> Vec<String> {
     ]
 }
 
+/// Secrets are shown as set or unset, never with their value.
+fn redacted<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_str("<redacted>"),
+        None => serializer.serialize_none(),
+    }
+}
+
 fn parse_bool(value: &str) -> bool {
     value == "1" || value.eq_ignore_ascii_case("true")
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
 struct RawFileConfig {
-    #[serde(alias = "COOLDOWN_MINUTES")]
+    #[serde(
+        alias = "COOLDOWN_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
     cooldown_minutes: Option<u64>,
+    #[serde(
+        alias = "COOLDOWN_WARN_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
+    warn_minutes: Option<u64>,
     #[serde(alias = "COOLDOWN_MODE")]
     mode: Option<String>,
     #[serde(alias = "COOLDOWN_ALLOWLIST_PATH")]
     allowlist_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_ALLOWLIST_URL")]
+    allowlist_url: Option<String>,
+    #[serde(alias = "COOLDOWN_ALLOWLIST_PUBLIC_KEY")]
+    allowlist_public_key: Option<String>,
     #[serde(alias = "COOLDOWN_TTL_SECONDS")]
     ttl_seconds: Option<u64>,
     #[serde(alias = "COOLDOWN_CACHE_DIR")]
@@ -197,55 +1817,629 @@ struct RawFileConfig {
     registry_api: Option<String>,
     #[serde(alias = "COOLDOWN_REGISTRY_INDEX")]
     registry_index: Option<String>,
+    #[serde(alias = "COOLDOWN_FREEZE_AFTER")]
+    freeze_after: Option<DateTime<Utc>>,
+    #[serde(alias = "COOLDOWN_AS_OF")]
+    as_of: Option<DateTime<Utc>>,
+    #[serde(alias = "COOLDOWN_SCAN_SUBPROJECTS")]
+    scan_subprojects: Option<bool>,
+    #[serde(alias = "COOLDOWN_SUBPROJECT_DIRS")]
+    subproject_dirs: Option<Vec<String>>,
+    #[serde(alias = "COOLDOWN_HTTP_PROXY")]
+    http_proxy: Option<String>,
+    #[serde(alias = "COOLDOWN_CA_BUNDLE_PATH")]
+    ca_bundle_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_DANGER_ACCEPT_INVALID_CERTS")]
+    danger_accept_invalid_certs: Option<bool>,
+    #[serde(alias = "COOLDOWN_UNKNOWN_SOURCE_POLICY")]
+    unknown_source_policy: Option<String>,
+    #[serde(alias = "COOLDOWN_DUPLICATE_MAJORS")]
+    duplicate_majors: Option<String>,
+    #[serde(alias = "COOLDOWN_BASELINE")]
+    baseline: Option<String>,
+    #[serde(alias = "COOLDOWN_DB_DUMP_PATH")]
+    db_dump_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_INDEX_REPO")]
+    index_repo_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_METADATA_SOURCES")]
+    metadata_sources: Option<Vec<String>>,
+    #[serde(alias = "COOLDOWN_SPARSE_INDEX_URL")]
+    sparse_index_url: Option<String>,
+    #[serde(alias = "COOLDOWN_SPARSE_VERSION_LISTS")]
+    sparse_version_lists: Option<bool>,
+    #[serde(alias = "COOLDOWN_OSV")]
+    osv: Option<bool>,
+    #[serde(alias = "COOLDOWN_OSV_URL")]
+    osv_url: Option<String>,
+    #[serde(alias = "COOLDOWN_CARGO_VET")]
+    cargo_vet: Option<bool>,
+    #[serde(alias = "COOLDOWN_CARGO_DENY")]
+    cargo_deny: Option<bool>,
+    #[serde(alias = "COOLDOWN_OWNER_CHANGE")]
+    owner_change: Option<String>,
+    #[serde(
+        alias = "COOLDOWN_OWNER_CHANGE_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
+    owner_change_minutes: Option<u64>,
+    #[serde(alias = "COOLDOWN_CERTIFICATE_PATH")]
+    certificate_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_CERT_PUBLIC_KEY")]
+    certificate_public_key: Option<String>,
+    #[serde(alias = "COOLDOWN_MAX_DEPTH")]
+    max_depth: Option<usize>,
+    #[serde(alias = "COOLDOWN_FILTER_PLATFORM")]
+    filter_platform: Option<bool>,
+    #[serde(alias = "COOLDOWN_IGNORE_DEV_DEPENDENCIES")]
+    ignore_dev_dependencies: Option<bool>,
+    build_time: Option<BuildTimePolicy>,
+    bump_minutes: Option<BumpWindows>,
+    #[serde(alias = "COOLDOWN_PATCH_GRACE")]
+    patch_grace: Option<bool>,
+    #[serde(
+        alias = "COOLDOWN_NEW_CRATE_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
+    new_crate_minutes: Option<u64>,
+    #[serde(alias = "COOLDOWN_NEW_CRATE_MIN_VERSIONS")]
+    new_crate_min_versions: Option<usize>,
+    #[serde(alias = "COOLDOWN_NEW_CRATE_MIN_AGE_DAYS")]
+    new_crate_min_age_days: Option<u64>,
+    #[serde(alias = "COOLDOWN_PIN_JOURNAL_PATH")]
+    pin_journal_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_ENFORCE_SAMPLE_PERCENT")]
+    enforce_sample_percent: Option<u8>,
+    #[serde(alias = "COOLDOWN_SURVIVED_RELEASES")]
+    survived_releases: Option<usize>,
+    #[serde(alias = "COOLDOWN_MIN_DOWNLOADS")]
+    min_downloads: Option<u64>,
+    #[serde(
+        alias = "COOLDOWN_GIT_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
+    git_minutes: Option<u64>,
+    #[serde(alias = "COOLDOWN_STRICT_SOURCES")]
+    strict_sources: Option<bool>,
+    #[serde(alias = "COOLDOWN_BUSINESS_DAYS")]
+    business_days: Option<bool>,
+    #[serde(alias = "COOLDOWN_HOLIDAYS")]
+    holidays_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_YANKED")]
+    yanked: Option<String>,
+    #[serde(alias = "COOLDOWN_TYPOSQUAT")]
+    typosquat: Option<String>,
+    #[serde(alias = "COOLDOWN_VERIFY_CHECKSUMS")]
+    verify_checksums: Option<bool>,
+    #[serde(alias = "COOLDOWN_PIN_STRATEGY")]
+    pin_strategy: Option<String>,
+    #[serde(alias = "COOLDOWN_PIN_BACKEND")]
+    pin_backend: Option<String>,
+    #[serde(alias = "COOLDOWN_PLAN_PINS")]
+    plan_pins: Option<bool>,
+    #[serde(alias = "COOLDOWN_KEEP_PARTIAL")]
+    keep_partial: Option<bool>,
+    #[serde(alias = "COOLDOWN_RESUME_PLANS")]
+    resume_plans: Option<bool>,
+    #[serde(alias = "COOLDOWN_WAIT")]
+    wait: Option<bool>,
+    #[serde(alias = "COOLDOWN_SKIP_UNCHANGED")]
+    skip_unchanged: Option<bool>,
+    #[serde(
+        alias = "COOLDOWN_MAX_WAIT_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
+    max_wait_minutes: Option<u64>,
+    #[serde(
+        alias = "COOLDOWN_MAX_DURATION_MINUTES",
+        default,
+        deserialize_with = "deserialize_minutes"
+    )]
+    max_duration_minutes: Option<u64>,
+    #[serde(alias = "COOLDOWN_MAX_ITERATIONS")]
+    max_iterations: Option<usize>,
+    #[serde(alias = "COOLDOWN_ALLOW_DIRTY")]
+    allow_dirty: Option<bool>,
+    #[serde(alias = "COOLDOWN_COMMIT")]
+    commit: Option<bool>,
+    #[serde(alias = "COOLDOWN_COMMIT_MESSAGE_TEMPLATE")]
+    commit_message_template: Option<String>,
+    #[serde(alias = "COOLDOWN_PR_COMMENT")]
+    pr_comment: Option<bool>,
+    #[serde(alias = "COOLDOWN_CI")]
+    ci: Option<String>,
+    exit_codes: Option<ExitCodes>,
+    #[serde(alias = "COOLDOWN_EVENTS")]
+    events: Option<String>,
+    #[serde(alias = "COOLDOWN_AUDIT_LOG")]
+    audit_log_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_METRICS_PATH")]
+    metrics_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_METRICS_PUSHGATEWAY")]
+    metrics_pushgateway: Option<String>,
+    #[serde(alias = "COOLDOWN_WEBHOOK_URL")]
+    webhook_url: Option<String>,
+    #[serde(alias = "COOLDOWN_WEBHOOK_TEMPLATE")]
+    webhook_template: Option<String>,
+    #[serde(alias = "COOLDOWN_BLOCKING_GRAPH")]
+    blocking_graph_path: Option<PathBuf>,
+    #[serde(alias = "COOLDOWN_MAX_DOWNGRADE")]
+    max_downgrade: Option<MaxDowngrade>,
+    #[serde(alias = "COOLDOWN_RESPECT_MSRV")]
+    respect_msrv: Option<String>,
+    #[serde(alias = "COOLDOWN_ALLOW_LICENSE_CHANGE")]
+    allow_license_change: Option<bool>,
+}
+
+/// `max_downgrade` as written in the file: `"patch"`, `"minor"`, or a
+/// number of minor series.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum MaxDowngrade {
+    Steps(u64),
+    Level(String),
+}
+
+fn parse_max_downgrade(value: &str) -> Option<u64> {
+    match value.trim() {
+        "patch" => Some(0),
+        "minor" => Some(1),
+        other => other.parse().ok(),
+    }
 }
 
 #[derive(Debug, Clone)]
 struct FileConfig {
-    path: PathBuf,
+    /// The file each top-level key was last set by.
+    origins: BTreeMap<String, PathBuf>,
     data: RawFileConfig,
 }
 
 impl FileConfig {
-    fn base_dir(&self) -> PathBuf {
-        self.path
-            .parent()
-            .map(Path::to_path_buf)
-            .unwrap_or_else(|| PathBuf::from("."))
-    }
-
-    fn resolve_path(&self, candidate: &PathBuf) -> PathBuf {
+    /// Resolve a path setting against the directory of the file that set
+    /// it.
+    fn resolve_path(&self, key: &str, candidate: &PathBuf) -> PathBuf {
         if candidate.is_absolute() {
-            candidate.clone()
-        } else {
-            self.base_dir().join(candidate)
+            return candidate.clone();
         }
+        let base_dir = self
+            .origins
+            .get(key)
+            .and_then(|path| path.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        base_dir.join(candidate)
     }
 
     fn allowlist_path(&self) -> Option<PathBuf> {
         self.data
             .allowlist_path
             .as_ref()
-            .map(|path| self.resolve_path(path))
+            .map(|path| self.resolve_path("allowlist_path", path))
     }
 
     fn cache_dir(&self) -> Option<PathBuf> {
         self.data
             .cache_dir
             .as_ref()
-            .map(|path| self.resolve_path(path))
+            .map(|path| self.resolve_path("cache_dir", path))
+    }
+
+    fn holidays_path(&self) -> Option<PathBuf> {
+        self.data
+            .holidays_path
+            .as_ref()
+            .map(|path| self.resolve_path("holidays_path", path))
+    }
+
+    fn index_repo_path(&self) -> Option<PathBuf> {
+        self.data
+            .index_repo_path
+            .as_ref()
+            .map(|path| self.resolve_path("index_repo_path", path))
+    }
+
+    fn db_dump_path(&self) -> Option<PathBuf> {
+        self.data
+            .db_dump_path
+            .as_ref()
+            .map(|path| self.resolve_path("db_dump_path", path))
+    }
+
+    fn certificate_path(&self) -> Option<PathBuf> {
+        // `-` means stdout and must not be resolved against the file location.
+        self.data.certificate_path.as_ref().map(|path| {
+            if path.as_os_str() == "-" {
+                path.clone()
+            } else {
+                self.resolve_path("certificate_path", path)
+            }
+        })
+    }
+
+    fn pin_journal_path(&self) -> Option<PathBuf> {
+        self.data
+            .pin_journal_path
+            .as_ref()
+            .map(|path| self.resolve_path("pin_journal_path", path))
+    }
+
+    fn audit_log_path(&self) -> Option<PathBuf> {
+        self.data
+            .audit_log_path
+            .as_ref()
+            .map(|path| self.resolve_path("audit_log_path", path))
+    }
+
+    fn metrics_path(&self) -> Option<PathBuf> {
+        self.data
+            .metrics_path
+            .as_ref()
+            .map(|path| self.resolve_path("metrics_path", path))
+    }
+
+    fn blocking_graph_path(&self) -> Option<PathBuf> {
+        self.data
+            .blocking_graph_path
+            .as_ref()
+            .map(|path| self.resolve_path("blocking_graph_path", path))
+    }
+
+    fn ca_bundle_path(&self) -> Option<PathBuf> {
+        self.data
+            .ca_bundle_path
+            .as_ref()
+            .map(|path| self.resolve_path("ca_bundle_path", path))
+    }
+}
+
+/// The files settings are read from, lowest precedence first: the
+/// user-level `~/.cargo/cooldown.toml`, the workspace `Cargo.toml` (for its
+/// cooldown metadata), and the workspace `cooldown.toml`.
+pub fn config_files() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = [
+        user_config_path(),
+        workspace_manifest_path(),
+        workspace_config_path(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    paths.dedup();
+    paths
+}
+
+/// The settings a config file sets, by their canonical names, after the
+/// same checks [`load_file_config`] applies. An error means runs fail until
+/// the file is fixed.
+pub fn file_settings(path: &Path) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let table = if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+        let Some(mut table) = manifest_metadata(&contents)? else {
+            return Ok(Vec::new());
+        };
+        table.remove("allow");
+        table.remove("deny");
+        table
+    } else {
+        toml::from_str(&contents).map_err(|err| err.to_string())?
+    };
+    Ok(layer(table, None)?
+        .table
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect())
+}
+
+/// Merge `paths`, the [`config_files`], key by key, later ones winning. A file that
+/// does not load is an error when `strict`, and skipped otherwise.
+fn load_file_config(
+    paths: Vec<PathBuf>,
+    profile: Option<&str>,
+    strict: bool,
+) -> anyhow::Result<Option<FileConfig>> {
+    let mut layers = Vec::new();
+    for path in paths {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("Failed to read {}: {err}", path.display());
+                continue;
+            }
+        };
+        let parsed = if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+            manifest_metadata(&contents).and_then(|table| match table {
+                Some(mut table) => {
+                    // Per-crate rules are read with the allowlist.
+                    table.remove("allow");
+                    table.remove("deny");
+                    layer(table, profile).map(Some)
+                }
+                None => Ok(None),
+            })
+        } else {
+            parse_layer(&contents, profile).map(Some)
+        };
+        match parsed {
+            Ok(Some(layer)) => layers.push((path, layer)),
+            Ok(None) => {}
+            // Dropping the file would fall back to the defaults, which
+            // enforce nothing, so a typo must not let the run go on.
+            Err(err) if strict => {
+                return Err(ErrorCode::InvalidConfig
+                    .fail(format!("failed to load {}: {err}", path.display())));
+            }
+            Err(err) => eprintln!("Failed to parse {}: {err}", path.display()),
+        }
+    }
+    // Running without the profile would quietly apply the wrong windows, so
+    // a profile no file defines is fatal.
+    if let Some(name) = profile
+        && !layers.iter().any(|(_, layer)| layer.has_profile)
+    {
+        let mut defined: Vec<&str> = layers
+            .iter()
+            .flat_map(|(_, layer)| layer.profiles.iter().map(String::as_str))
+            .collect();
+        defined.sort_unstable();
+        defined.dedup();
+        return Err(ErrorCode::InvalidConfig.fail(format!(
+            "profile `{name}` is not defined in any config file (defined: {})",
+            if defined.is_empty() {
+                "none".to_string()
+            } else {
+                defined.join(", ")
+            }
+        )));
+    }
+    if layers.is_empty() {
+        return Ok(None);
+    }
+    merge_layers(layers).map(Some).map_err(|err| {
+        ErrorCode::InvalidConfig.fail(format!("failed to merge the config files: {err}"))
+    })
+}
+
+/// One config file, with its selected profile applied.
+#[derive(Debug)]
+struct Layer {
+    table: toml::Table,
+    /// The profiles the file defines.
+    profiles: Vec<String>,
+    /// Whether it defines the selected profile.
+    has_profile: bool,
+}
+
+/// Parse a config file, applying `profile` when the file defines it. Keys
+/// spelled like their environment variable are renamed to the setting, so
+/// files written either way merge.
+fn parse_layer(contents: &str, profile: Option<&str>) -> Result<Layer, String> {
+    layer(
+        toml::from_str(contents).map_err(|err| err.to_string())?,
+        profile,
+    )
+}
+
+fn layer(table: toml::Table, profile: Option<&str>) -> Result<Layer, String> {
+    let mut table = canonical_keys(table);
+    let profiles = match table.remove("profile") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => return Err("`profile` must be a table of profiles".to_string()),
+        None => toml::Table::new(),
+    };
+    let mut has_profile = false;
+    if let Some(name) = profile
+        && let Some(overrides) = profiles.get(name)
+    {
+        let toml::Value::Table(overrides) = overrides else {
+            return Err(format!("`profile.{name}` must be a table"));
+        };
+        merge_table(&mut table, canonical_keys(overrides.clone()));
+        has_profile = true;
+    }
+    // A file that does not parse on its own fails the run.
+    let unknown = unknown_settings(&table, "", &[]);
+    if !unknown.is_empty() {
+        return Err(unknown.join(", "));
+    }
+    toml::Value::Table(table.clone())
+        .try_into::<RawFileConfig>()
+        .map_err(|err| err.to_string())?;
+    let invalid: Vec<String> = VALUE_CHECKS
+        .iter()
+        .filter_map(|check| check(&table))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(invalid.join(", "));
+    }
+    Ok(Layer {
+        table,
+        profiles: profiles.keys().cloned().collect(),
+        has_profile,
+    })
+}
+
+fn canonical_keys(table: toml::Table) -> toml::Table {
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let setting = SETTINGS
+                .iter()
+                .find(|(_, vars)| *vars == [key.as_str()])
+                .map_or(key, |(setting, _)| setting.to_string());
+            (setting, value)
+        })
+        .collect()
+}
+
+/// Lay `layers`, lowest precedence first, over each other.
+fn merge_layers(layers: Vec<(PathBuf, Layer)>) -> Result<FileConfig, String> {
+    let mut merged = toml::Table::new();
+    let mut origins = BTreeMap::new();
+    for (path, layer) in layers {
+        for key in layer.table.keys() {
+            origins.insert(key.clone(), path.clone());
+        }
+        merge_table(&mut merged, layer.table);
+    }
+    let data = toml::Value::Table(merged)
+        .try_into()
+        .map_err(|err: toml::de::Error| err.to_string())?;
+    Ok(FileConfig { origins, data })
+}
+
+/// Overlay `top` on `base`, merging nested tables such as `build_time`
+/// field by field.
+fn merge_table(base: &mut toml::Table, top: toml::Table) {
+    for (key, value) in top {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(top)) => merge_table(base, top),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The `[workspace.metadata.cooldown]` and `[package.metadata.cooldown]`
+/// tables of a `Cargo.toml`, the package one laid over the workspace one, or
+/// `None` when it has neither.
+pub fn manifest_metadata(contents: &str) -> Result<Option<toml::Table>, String> {
+    let manifest: toml::Table = toml::from_str(contents).map_err(|err| err.to_string())?;
+    let mut merged: Option<toml::Table> = None;
+    for section in ["workspace", "package"] {
+        let table = manifest
+            .get(section)
+            .and_then(|section| section.get("metadata"))
+            .and_then(|metadata| metadata.get("cooldown"));
+        match table {
+            Some(toml::Value::Table(table)) => {
+                merge_table(merged.get_or_insert_default(), table.clone())
+            }
+            Some(_) => return Err(format!("`{section}.metadata.cooldown` must be a table")),
+            None => {}
+        }
+    }
+    Ok(merged)
+}
+
+/// Every problem in the settings of one config file: keys that are not
+/// settings, each with the closest one when it looks like a typo, and values
+/// of the wrong type. Empty when the file would be loaded as written.
+pub fn validate_settings(table: &toml::Table) -> Vec<String> {
+    let mut problems = unknown_settings(table, "", &["profile"]);
+    let profiles = match table.get("profile") {
+        Some(toml::Value::Table(profiles)) => profiles.clone(),
+        _ => toml::Table::new(),
+    };
+    for (name, overrides) in &profiles {
+        if let toml::Value::Table(overrides) = overrides {
+            problems.extend(unknown_settings(
+                overrides,
+                &format!("profile.{name}."),
+                &[],
+            ));
+        }
+    }
+    // Unknown keys also fail the typed parse below; report them once.
+    if !problems.is_empty() {
+        return problems;
+    }
+    let selected = std::iter::once(None).chain(profiles.keys().map(|name| Some(name.as_str())));
+    for profile in selected {
+        if let Err(err) = layer(table.clone(), profile) {
+            problems.push(match profile {
+                Some(name) => format!("profile `{name}`: {err}"),
+                None => err,
+            });
+        }
+    }
+    problems
+}
+
+fn unknown_settings(table: &toml::Table, prefix: &str, extra: &[&str]) -> Vec<String> {
+    let mut problems = unknown_keys(table, field_names::<RawFileConfig>(), prefix, extra);
+    let nested = [
+        ("build_time", field_names::<BuildTimePolicy>()),
+        ("bump_minutes", field_names::<BumpWindows>()),
+        ("exit_codes", field_names::<ExitCodes>()),
+    ];
+    for (key, known) in nested {
+        if let Some(toml::Value::Table(inner)) = table.get(key) {
+            problems.extend(unknown_keys(inner, known, &format!("{prefix}{key}."), &[]));
+        }
     }
+    problems
+}
+
+/// The keys of `table` that are neither in `known` nor `extra`, with
+/// `prefix` naming the table they are in.
+pub fn unknown_keys(
+    table: &toml::Table,
+    known: &[&str],
+    prefix: &str,
+    extra: &[&str],
+) -> Vec<String> {
+    table
+        .keys()
+        .filter(|key| !known.contains(&key.as_str()) && !extra.contains(&key.as_str()))
+        .map(|key| {
+            let closest = known
+                .iter()
+                .chain(extra)
+                .map(|candidate| (distance(key, candidate), candidate))
+                .filter(|(distance, _)| *distance <= (key.len() / 4).max(2))
+                .min_by_key(|(distance, _)| *distance);
+            match closest {
+                Some((_, candidate)) => {
+                    format!("unknown key `{prefix}{key}` (did you mean `{prefix}{candidate}`?)")
+                }
+                None => format!("unknown key `{prefix}{key}`"),
+            }
+        })
+        .collect()
+}
+
+/// The field names, aliases included, that `T` deserializes from.
+pub fn field_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut names = FieldNames(&[]);
+    let _ = T::deserialize(&mut names);
+    names.0
 }
 
-fn load_file_config() -> Option<FileConfig> {
-    if let Some(path) = workspace_config_path() {
-        return read_file_config(&path);
+/// Collects the field names serde asks a struct deserializer for.
+struct FieldNames(&'static [&'static str]);
+
+impl<'de> serde::Deserializer<'de> for &mut FieldNames {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("only structs are inspected"))
+    }
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0 = fields;
+        Err(serde::de::Error::custom("only the field names are needed"))
     }
 
-    if let Some(path) = user_config_path() {
-        return read_file_config(&path);
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
     }
+}
 
-    None
+fn workspace_manifest_path() -> Option<PathBuf> {
+    let path = env::current_dir().ok()?.join("Cargo.toml");
+    if path.exists() { Some(path) } else { None }
 }
 
 fn workspace_config_path() -> Option<PathBuf> {
@@ -262,27 +2456,6 @@ fn user_config_path() -> Option<PathBuf> {
     if path.exists() { Some(path) } else { None }
 }
 
-fn read_file_config(path: &Path) -> Option<FileConfig> {
-    let contents = match fs::read_to_string(path) {
-        Ok(contents) => contents,
-        Err(err) => {
-            eprintln!("Failed to read {}: {err}", path.display());
-            return None;
-        }
-    };
-
-    match toml::from_str::<RawFileConfig>(&contents) {
-        Ok(data) => Some(FileConfig {
-            path: path.to_path_buf(),
-            data,
-        }),
-        Err(err) => {
-            eprintln!("Failed to parse {}: {err}", path.display());
-            None
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,6 +2482,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn build_time_policy_scales_and_floors() {
+        let policy = BuildTimePolicy {
+            multiplier: Some(2.0),
+            minutes: Some(10_080),
+        };
+        assert_eq!(policy.apply(1_440), 10_080);
+        assert_eq!(policy.apply(7_200), 14_400);
+        assert_eq!(BuildTimePolicy::default().apply(60), 60);
+    }
+
     #[test]
     fn default_allowed_registries_include_sparse_and_git() {
         with_env_var("COOLDOWN_REGISTRY_INDEX", None, || {
@@ -419,6 +2603,303 @@ registry_index = "https://mirror.example/index"
         fake_home.close().unwrap();
     }
 
+    #[test]
+    fn a_typo_fails_the_run_instead_of_dropping_the_policy() {
+        let workspace = TempDir::new().unwrap();
+        let file = workspace.child("cooldown.toml");
+        file.write_str("cooldown_minutes = 10080\ncooldwon_mode = \"warn\"\n")
+            .unwrap();
+
+        let err = load_file_config(vec![file.to_path_buf()], None, true).unwrap_err();
+        assert_eq!(crate::codes::classify(&err), Some(ErrorCode::InvalidConfig));
+        let message = format!("{err:#}");
+        assert!(message.contains(&file.path().display().to_string()));
+        assert!(message.contains("unknown key `cooldwon_mode`"));
+    }
+
+    #[test]
+    fn an_unknown_profile_is_a_config_error() {
+        let workspace = TempDir::new().unwrap();
+        let file = workspace.child("cooldown.toml");
+        file.write_str("cooldown_minutes = 60\n\n[profile.release]\ncooldown_minutes = 10080\n")
+            .unwrap();
+
+        let err = load_file_config(vec![file.to_path_buf()], Some("relase"), true).unwrap_err();
+        assert_eq!(crate::codes::classify(&err), Some(ErrorCode::InvalidConfig));
+        assert!(format!("{err:#}").contains("(defined: release)"));
+        let release = load_file_config(vec![file.to_path_buf()], Some("release"), true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(release.data.cooldown_minutes, Some(10080));
+    }
+
+    #[test]
+    fn profiles_override_top_level_keys() {
+        let contents = r#"cooldown_minutes = 1440
+mode = "warn"
+allowlist_path = "allow.toml"
+
+[profile.release]
+COOLDOWN_MINUTES = 10080
+mode = "enforce"
+"#;
+        let base = parse_layer(contents, None).unwrap();
+        assert_eq!(base.table["cooldown_minutes"].as_integer(), Some(1440));
+        assert_eq!(base.profiles, ["release"]);
+        assert!(!base.has_profile);
+
+        let release = parse_layer(contents, Some("release")).unwrap();
+        assert!(release.has_profile);
+        assert_eq!(release.table["cooldown_minutes"].as_integer(), Some(10080));
+        assert_eq!(release.table["mode"].as_str(), Some("enforce"));
+        assert_eq!(release.table["allowlist_path"].as_str(), Some("allow.toml"));
+
+        assert!(!parse_layer(contents, Some("relase")).unwrap().has_profile);
+    }
+
+    #[test]
+    fn later_layers_win_key_by_key() {
+        let user = parse_layer(
+            r#"cooldown_minutes = 5
+http_retries = 3
+allowlist_path = "allow.toml"
+
+[build_time]
+multiplier = 2.0
+"#,
+            None,
+        )
+        .unwrap();
+        let workspace = parse_layer(
+            r#"COOLDOWN_MINUTES = 60
+
+[build_time]
+minutes = 10080
+"#,
+            None,
+        )
+        .unwrap();
+        let config = merge_layers(vec![
+            (PathBuf::from("/home/me/.cargo/cooldown.toml"), user),
+            (PathBuf::from("/work/app/cooldown.toml"), workspace),
+        ])
+        .unwrap();
+
+        assert_eq!(config.data.cooldown_minutes, Some(60));
+        assert_eq!(config.data.http_retries, Some(3));
+        let build_time = config.data.build_time.unwrap();
+        assert_eq!(build_time.multiplier, Some(2.0));
+        assert_eq!(build_time.minutes, Some(10080));
+        assert_eq!(
+            config.allowlist_path(),
+            Some(PathBuf::from("/home/me/.cargo/allow.toml"))
+        );
+        assert_eq!(
+            config.origins["cooldown_minutes"],
+            PathBuf::from("/work/app/cooldown.toml")
+        );
+        assert_eq!(
+            config.origins["http_retries"],
+            PathBuf::from("/home/me/.cargo/cooldown.toml")
+        );
+    }
+
+    #[test]
+    fn reads_cooldown_metadata_from_the_manifest() {
+        let manifest = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[workspace.metadata.cooldown]
+cooldown_minutes = 1440
+mode = "warn"
+
+[package.metadata.cooldown]
+mode = "enforce"
+
+[[package.metadata.cooldown.allow.package]]
+crate = "tokio"
+minutes = 60
+"#;
+        let table = manifest_metadata(manifest).unwrap().unwrap();
+        assert_eq!(table["cooldown_minutes"].as_integer(), Some(1440));
+        assert_eq!(table["mode"].as_str(), Some("enforce"));
+        assert!(table.contains_key("allow"));
+
+        assert_eq!(
+            manifest_metadata("[package]\nname = \"app\"\n").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn validation_suggests_settings_for_typos() {
+        let table: toml::Table = toml::from_str(
+            r#"
+cooldwon_minutes = 60
+COOLDOWN_MODE = "warn"
+nonsense = true
+
+[build_time]
+multiplyer = 2.0
+
+[profile.ci]
+strict_source = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            validate_settings(&table),
+            [
+                "unknown key `cooldwon_minutes` (did you mean `cooldown_minutes`?)",
+                "unknown key `nonsense`",
+                "unknown key `build_time.multiplyer` (did you mean `build_time.multiplier`?)",
+                "unknown key `profile.ci.strict_source` (did you mean `profile.ci.strict_sources`?)",
+            ]
+        );
+
+        let table: toml::Table = toml::from_str("cooldown_minutes = \"soon\"").unwrap();
+        assert_eq!(validate_settings(&table).len(), 1);
+        let table: toml::Table = toml::from_str("cooldown_minutes = 60").unwrap();
+        assert!(validate_settings(&table).is_empty());
+    }
+
+    #[test]
+    fn every_file_setting_is_listed() {
+        let listed: Vec<&str> = SETTINGS.iter().map(|(setting, _)| *setting).collect();
+        // serde lists the aliases, which are the variables, alongside the
+        // fields.
+        for field in field_names::<RawFileConfig>() {
+            assert!(
+                listed.contains(field) || SETTINGS.iter().any(|(_, vars)| vars.contains(field)),
+                "{field} is missing from SETTINGS"
+            );
+        }
+    }
+
+    #[test]
+    fn freeze_after_parses_rfc3339() {
+        with_env_var(
+            "COOLDOWN_FREEZE_AFTER",
+            Some("2025-01-15T00:00:00Z"),
+            || {
+                let config = Config::from_env();
+                assert_eq!(
+                    config.freeze_after.map(|v| v.to_rfc3339()),
+                    Some("2025-01-15T00:00:00+00:00".to_string())
+                );
+                assert!(config.has_policy());
+            },
+        );
+    }
+
+    #[test]
+    fn subproject_dirs_accept_comma_separated_list() {
+        with_env_var(
+            "COOLDOWN_SUBPROJECT_DIRS",
+            Some("fuzz, tools/xtask"),
+            || {
+                let config = Config::from_env();
+                assert_eq!(
+                    config.subproject_dirs,
+                    vec!["fuzz".to_string(), "tools/xtask".to_string()]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn unknown_source_policy_defaults_to_skip() {
+        assert_eq!(SourcePolicy::from_env(None), Ok(SourcePolicy::Skip));
+        assert_eq!(
+            SourcePolicy::from_env(Some("deny".into())),
+            Ok(SourcePolicy::Deny)
+        );
+        with_env_var("COOLDOWN_UNKNOWN_SOURCE_POLICY", Some("warn"), || {
+            assert_eq!(Config::from_env().unknown_source_policy, SourcePolicy::Warn);
+
+            // Checking sources needs the graph even without a cooldown window.
+            let mut config = Config::from_env();
+            config.cooldown_minutes = 0;
+            config.unknown_source_policy = SourcePolicy::Deny;
+            assert!(config.has_policy());
+        });
+    }
+
+    #[test]
+    fn validation_reports_unknown_values() {
+        let table: toml::Table = toml::from_str(
+            r#"unknown_source_policy = "Deny"
+pin_strategy = "oldest-compatible"
+
+[profile.ci]
+yanked = "block"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            validate_settings(&table),
+            vec![
+                "unknown value `Deny` for `unknown_source_policy` (did you mean `deny`?); expected one of skip, warn, deny",
+                "profile `ci`: unknown value `Deny` for `unknown_source_policy` (did you mean `deny`?); expected one of skip, warn, deny, unknown value `block` for `yanked`; expected one of off, warn, deny",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_setting_values_are_config_errors() {
+        assert_eq!(
+            SourcePolicy::from_env(Some("Deny".into())).unwrap_err(),
+            "unknown value `Deny` for `unknown_source_policy` (did you mean `deny`?); expected one of skip, warn, deny"
+        );
+        assert!(DuplicatePolicy::from_env(Some("block".into())).is_err());
+        assert_eq!(
+            PinStrategy::from_env(Some("oldest-compatible".into())),
+            Ok(PinStrategy::OldestCompatible)
+        );
+        assert_eq!(CiProvider::from_env(None), Ok(None));
+    }
+
+    #[test]
+    fn metadata_sources_parse_fallback_chain() {
+        with_env_var(
+            "COOLDOWN_METADATA_SOURCES",
+            Some("sparse, api, bogus, db-dump, index-history, cache"),
+            || {
+                let config = Config::from_env();
+                assert_eq!(
+                    config.metadata_sources,
+                    vec![
+                        MetadataSourceKind::Sparse,
+                        MetadataSourceKind::Api,
+                        MetadataSourceKind::DbDump,
+                        MetadataSourceKind::IndexHistory,
+                        MetadataSourceKind::Cache,
+                    ]
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn exit_codes_map_outcomes() {
+        let codes = ExitCodes::parse("violations=3, network=4,config=5").unwrap();
+        assert_eq!(codes.code(Outcome::Success), 0);
+        assert_eq!(codes.code(Outcome::Violations), 3);
+        assert_eq!(codes.code(Outcome::Network), 4);
+        assert_eq!(codes.code(Outcome::Error), 1);
+        assert_eq!(codes.code(Outcome::Cargo(101)), 101);
+        assert!(ExitCodes::parse("policy=3").is_err());
+        assert!(ExitCodes::parse("violations=300").is_err());
+
+        let file: ExitCodes = toml::from_str("cargo = 6\nerror = 2\n").unwrap();
+        assert_eq!(file.code(Outcome::Cargo(101)), 6);
+        assert_eq!(file.code(Outcome::Error), 2);
+        let file: ExitCodes = toml::from_str("cargo = \"passthrough\"\n").unwrap();
+        assert_eq!(file.code(Outcome::Cargo(101)), 101);
+        assert!(toml::from_str::<ExitCodes>("cargo = \"never\"\n").is_err());
+    }
+
     #[test]
     fn loads_user_cargo_cooldown_file_when_workspace_missing() {
         let _guard = env_lock().lock().unwrap();
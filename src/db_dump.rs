@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::provider::{BoxFuture, VersionMetadataProvider};
+use crate::registry::VersionMeta;
+
+/// Publication metadata loaded from an extracted crates.io database dump
+/// (<https://static.crates.io/db-dump.tar.gz>).
+pub struct DbDump {
+    versions: HashMap<String, Vec<VersionMeta>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateRow {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionRow {
+    crate_id: u64,
+    num: String,
+    created_at: String,
+    yanked: String,
+    #[serde(default)]
+    license: Option<String>,
+}
+
+impl DbDump {
+    /// Load `crates.csv` and `versions.csv` from `path`, which may point at
+    /// the extracted dump root or directly at its `data/` directory.
+    pub fn open(path: &Path) -> Result<Self> {
+        let data_dir = locate_data_dir(path)?;
+
+        let mut names: HashMap<u64, String> = HashMap::new();
+        let crates_path = data_dir.join("crates.csv");
+        let mut reader = csv::Reader::from_path(&crates_path)
+            .with_context(|| format!("failed to open {}", crates_path.display()))?;
+        for row in reader.deserialize::<CrateRow>() {
+            let row = row.with_context(|| format!("failed to parse {}", crates_path.display()))?;
+            names.insert(row.id, row.name);
+        }
+
+        let mut versions: HashMap<String, Vec<VersionMeta>> = HashMap::new();
+        let versions_path = data_dir.join("versions.csv");
+        let mut reader = csv::Reader::from_path(&versions_path)
+            .with_context(|| format!("failed to open {}", versions_path.display()))?;
+        for row in reader.deserialize::<VersionRow>() {
+            let row =
+                row.with_context(|| format!("failed to parse {}", versions_path.display()))?;
+            let Some(name) = names.get(&row.crate_id) else {
+                continue;
+            };
+            let created_at = parse_timestamp(&row.created_at).with_context(|| {
+                format!(
+                    "invalid created_at for {name}@{}: {}",
+                    row.num, row.created_at
+                )
+            })?;
+            versions.entry(name.clone()).or_default().push(VersionMeta {
+                created_at,
+                yanked: matches!(row.yanked.as_str(), "t" | "true"),
+                num: row.num,
+                downloads: None,
+                license: row.license.filter(|license| !license.is_empty()),
+            });
+        }
+
+        debug!(crates = versions.len(), path = %data_dir.display(), "loaded crates.io database dump");
+        Ok(Self { versions })
+    }
+
+    fn versions_of(&self, name: &str) -> Result<&[VersionMeta]> {
+        self.versions
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| anyhow!("crate {name} is not present in the database dump"))
+    }
+}
+
+impl VersionMetadataProvider for DbDump {
+    fn label(&self) -> &'static str {
+        "database dump"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(async move {
+            self.versions_of(name)?
+                .iter()
+                .find(|meta| meta.num == version)
+                .cloned()
+                .ok_or_else(|| anyhow!("{name}@{version} is not present in the database dump"))
+        })
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(async move { Ok(self.versions_of(name)?.to_vec()) })
+    }
+}
+
+pub fn locate_data_dir(path: &Path) -> Result<PathBuf> {
+    for candidate in [path.join("data"), path.to_path_buf()] {
+        if candidate.join("versions.csv").is_file() {
+            return Ok(candidate);
+        }
+    }
+    bail!(
+        "{} does not look like an extracted crates.io database dump (missing data/versions.csv)",
+        path.display()
+    )
+}
+
+fn parse_timestamp(raw: &str) -> Result<DateTime<Utc>> {
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+    let trimmed = raw.trim_end_matches("+00");
+    let naive = NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%.f")?;
+    Ok(naive.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    #[tokio::test]
+    async fn reads_versions_from_extracted_dump() {
+        let dump = TempDir::new().unwrap();
+        dump.child("data/crates.csv")
+            .write_str("created_at,downloads,id,name\n2020-01-01 00:00:00,10,1,foo\n")
+            .unwrap();
+        dump.child("data/versions.csv")
+            .write_str(
+                "crate_id,created_at,id,num,yanked\n\
+                 1,2024-09-30 22:00:00.123456,10,1.0.0,f\n\
+                 1,2024-10-01 08:00:00+00,11,1.1.0,t\n",
+            )
+            .unwrap();
+
+        let source = DbDump::open(dump.path()).unwrap();
+        let versions = source.list_versions("foo").await.unwrap();
+        assert_eq!(versions.len(), 2);
+
+        let meta = source.fetch_version("foo", "1.1.0").await.unwrap();
+        assert!(meta.yanked);
+        assert_eq!(meta.created_at.to_rfc3339(), "2024-10-01T08:00:00+00:00");
+        assert!(source.fetch_version("bar", "1.0.0").await.is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! cargo-deny interop: bans from the workspace's `deny.toml` become
+//! `[[deny]]` entries and ignored advisories are not held against downgrade
+//! candidates, so teams keep one exception file instead of two.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use semver::VersionReq;
+use serde::Deserialize;
+
+use crate::allowlist::{Allowlist, DenyEntry};
+
+/// Where cargo-deny looks for its configuration, in order.
+const LOCATIONS: [&str; 3] = ["deny.toml", ".deny.toml", ".cargo/deny.toml"];
+
+#[derive(Debug, Default, Deserialize)]
+struct DenyToml {
+    #[serde(default)]
+    bans: Bans,
+    #[serde(default)]
+    advisories: AdvisoriesSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Bans {
+    #[serde(default)]
+    deny: Vec<PackageSpec>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AdvisoriesSection {
+    #[serde(default)]
+    ignore: Vec<IgnoreSpec>,
+}
+
+/// cargo-deny accepts a bare `"name[@req]"` string or a table with either
+/// `crate = "name[@req]"` or the older `name`/`version` pair.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PackageSpec {
+    Spec(String),
+    Table {
+        #[serde(default, rename = "crate")]
+        crate_spec: Option<String>,
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        version: Option<String>,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum IgnoreSpec {
+    Id(String),
+    Table { id: String },
+}
+
+/// What cooldown takes over from a `deny.toml`.
+#[derive(Debug, Default)]
+pub struct DenyConfig {
+    pub path: PathBuf,
+    /// `[bans] deny` as `[[deny]]` entries.
+    pub bans: Allowlist,
+    /// `[advisories] ignore` IDs.
+    pub ignored_advisories: Vec<String>,
+}
+
+/// Read the first `deny.toml` cargo-deny would use for `workspace_root`.
+pub fn load(workspace_root: &Path) -> Result<Option<DenyConfig>> {
+    let Some(path) = LOCATIONS
+        .iter()
+        .map(|location| workspace_root.join(location))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let parsed: DenyToml =
+        toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let mut bans = Allowlist::default();
+    for spec in parsed.bans.deny {
+        let entry = spec
+            .into_entry()
+            .with_context(|| format!("invalid [bans] entry in {}", path.display()))?;
+        bans.deny.push(entry);
+    }
+    let ignored_advisories = parsed
+        .advisories
+        .ignore
+        .into_iter()
+        .map(|spec| match spec {
+            IgnoreSpec::Id(id) | IgnoreSpec::Table { id } => id,
+        })
+        .collect();
+    Ok(Some(DenyConfig {
+        path,
+        bans,
+        ignored_advisories,
+    }))
+}
+
+impl PackageSpec {
+    fn into_entry(self) -> Result<DenyEntry> {
+        let (spec, version, reason) = match self {
+            PackageSpec::Spec(spec) => (spec, None, None),
+            PackageSpec::Table {
+                crate_spec,
+                name,
+                version,
+                reason,
+            } => (
+                crate_spec.or(name).context("entry names no crate")?,
+                version,
+                reason,
+            ),
+        };
+        let (name, req) = match spec.split_once(['@', ':']) {
+            Some((name, req)) => (name.to_string(), Some(req.to_string())),
+            None => (spec, version),
+        };
+        let req = req
+            .map(|req| {
+                VersionReq::parse(&req)
+                    .with_context(|| format!("invalid version `{req}` for {name}"))
+            })
+            .transpose()?;
+        Ok(DenyEntry {
+            crate_name: name,
+            req,
+            reason: Some(reason.unwrap_or_else(|| "banned in deny.toml".to_string())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bans_and_ignored_advisories() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("deny.toml"),
+            r#"
+[advisories]
+ignore = ["RUSTSEC-2020-0071", { id = "RUSTSEC-2023-0001", reason = "not reachable" }]
+
+[bans]
+multiple-versions = "warn"
+deny = [
+    "openssl",
+    { crate = "time@<0.2", reason = "CVE" },
+    { name = "chrono", version = "=0.4.19" },
+]
+"#,
+        )
+        .unwrap();
+
+        let config = load(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            config.ignored_advisories,
+            ["RUSTSEC-2020-0071", "RUSTSEC-2023-0001"]
+        );
+        assert!(config.bans.denial("openssl", "0.10.66").is_some());
+        assert!(config.bans.denial("time", "0.1.45").is_some());
+        assert!(config.bans.denial("time", "0.3.36").is_none());
+        assert!(config.bans.denial("chrono", "0.4.19").is_some());
+        assert!(config.bans.denial("chrono", "0.4.38").is_none());
+        assert!(load(&dir.path().join("missing")).unwrap().is_none());
+    }
+}
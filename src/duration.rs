@@ -0,0 +1,113 @@
+//! Human-friendly durations such as `90m`, `36h`, `7d`, or `2w`.
+
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use chrono::Duration;
+use serde::de::{self, Deserializer, Visitor};
+
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("invalid duration `{value}`; expected e.g. `90m`, `36h`, `7d`"))?;
+    let duration = match unit.trim() {
+        "m" | "min" | "mins" | "minutes" => Duration::try_minutes(amount),
+        "h" | "hours" => Duration::try_hours(amount),
+        "d" | "days" => Duration::try_days(amount),
+        "w" | "weeks" => Duration::try_weeks(amount),
+        other => bail!("unknown duration unit `{other}` in `{value}`; use m, h, d, or w"),
+    };
+    duration.with_context(|| format!("duration `{value}` is too large"))
+}
+
+/// A number of minutes, written as a plain count (`10080`) or as a duration
+/// (`7d`).
+pub fn parse_minutes(value: &str) -> Result<u64> {
+    let value = value.trim();
+    if let Ok(minutes) = value.parse() {
+        return Ok(minutes);
+    }
+    let minutes = parse_duration(value)?.num_minutes();
+    // The parser only reads unsigned amounts.
+    Ok(u64::try_from(minutes).unwrap_or_default())
+}
+
+/// For `#[serde(default, deserialize_with = "...")]` on `Option<u64>` minute
+/// fields: accepts an integer or a string such as `"36h"`.
+pub fn deserialize_minutes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    struct MinutesVisitor;
+
+    impl Visitor<'_> for MinutesVisitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number of minutes or a duration such as \"7d\"")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+            parse_minutes(value).map_err(|err| E::custom(format!("{err:#}")))
+        }
+    }
+
+    struct Minutes(u64);
+
+    impl<'de> serde::Deserialize<'de> for Minutes {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(MinutesVisitor).map(Minutes)
+        }
+    }
+
+    let minutes: Option<Minutes> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(minutes.map(|Minutes(minutes)| minutes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_common_units() {
+        assert_eq!(parse_duration("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("36h").unwrap(), Duration::hours(36));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn minutes_accept_counts_and_durations() {
+        assert_eq!(parse_minutes("10080").unwrap(), 10_080);
+        assert_eq!(parse_minutes("7d").unwrap(), 10_080);
+        assert_eq!(parse_minutes(" 36h ").unwrap(), 2_160);
+        assert!(parse_minutes("soon").is_err());
+
+        #[derive(serde::Deserialize)]
+        struct Window {
+            #[serde(default, deserialize_with = "deserialize_minutes")]
+            minutes: Option<u64>,
+        }
+        let parse = |contents: &str| toml::from_str::<Window>(contents).map(|w| w.minutes);
+        assert_eq!(parse("minutes = 90").unwrap(), Some(90));
+        assert_eq!(parse("minutes = \"2w\"").unwrap(), Some(20_160));
+        assert_eq!(parse("").unwrap(), None);
+        assert!(parse("minutes = -5").is_err());
+        assert!(parse("minutes = \"7 fortnights\"").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Machine-readable stream of the decisions a run makes, one JSON object
+//! per line, for orchestrators that follow long runs as they happen.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::warn;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A registry release whose age was looked up.
+    CrateInspected {
+        name: &'a str,
+        version: &'a str,
+        published_at: DateTime<Utc>,
+        minimum_minutes: u64,
+    },
+    /// A release inside its cooldown window that needs pinning.
+    CrateFresh {
+        name: &'a str,
+        version: &'a str,
+        published_at: DateTime<Utc>,
+        minimum_minutes: u64,
+    },
+    /// A release past its cooldown window but inside `warn_minutes`,
+    /// reported without blocking.
+    CrateWarned {
+        name: &'a str,
+        version: &'a str,
+        published_at: DateTime<Utc>,
+        minimum_minutes: u64,
+    },
+    PinAttempted {
+        name: &'a str,
+        from: &'a str,
+        to: &'a str,
+    },
+    PinApplied {
+        name: &'a str,
+        from: &'a str,
+        to: &'a str,
+    },
+    PinRejected {
+        name: &'a str,
+        from: &'a str,
+        to: &'a str,
+    },
+    /// The enforcement rollout sampled this run: runs in a bucket below
+    /// `percent` enforce, the others only warn.
+    Rollout {
+        percent: u8,
+        bucket: u8,
+        enforced: bool,
+    },
+    /// The run gave up; `message` says why, and `code` is its stable
+    /// `COOLDOWNnnn` identifier when it has one.
+    Blocked {
+        message: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        code: Option<&'a str>,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    at: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Where events go: a file, or stdout for `-`.
+pub struct EventSink {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventSink {
+    pub fn open(target: &str) -> Result<Self> {
+        let out: Box<dyn Write + Send> = if target == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(
+                File::create(target)
+                    .with_context(|| format!("failed to create event stream {target}"))?,
+            )
+        };
+        Ok(Self {
+            out: Mutex::new(out),
+        })
+    }
+
+    /// Write one event and flush it, so readers see it right away. A broken
+    /// stream is logged and otherwise ignored; it never fails the run.
+    pub fn emit(&self, event: &Event<'_>) {
+        let line = render(event, Utc::now());
+        let mut out = self
+            .out
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(err) = writeln!(out, "{line}").and_then(|()| out.flush()) {
+            warn!(error = %err, "failed to write to the event stream");
+        }
+    }
+}
+
+fn render(event: &Event<'_>, at: DateTime<Utc>) -> String {
+    serde_json::to_string(&Line { at, event }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn events_are_flat_json_objects() {
+        let at = Utc.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let event = Event::PinApplied {
+            name: "serde",
+            from: "1.0.210",
+            to: "1.0.200",
+        };
+        assert_eq!(
+            render(&event, at),
+            r#"{"at":"2025-01-02T12:00:00Z","event":"pin_applied","name":"serde","from":"1.0.210","to":"1.0.200"}"#
+        );
+    }
+}
@@ -1,178 +1,1930 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::Path;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::time::Duration;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
-use cargo_metadata::PackageId;
-use chrono::Utc;
+use cargo_metadata::{Metadata, MetadataCommand, PackageId};
+use chrono::{DateTime, Utc};
 use semver::{Op, Version, VersionReq};
-use tracing::{debug, info, warn};
+use tracing::{debug, info, instrument, warn};
 
+use crate::advisories::Advisories;
 use crate::allowlist::Allowlist;
+use crate::audit::AuditLog;
+use crate::autocommit;
+use crate::baseline::{self, Releases};
+use crate::blocking::BlockingGraph;
 use crate::cache::Cache;
-use crate::config::Config;
-use crate::metadata::read_metadata;
-use crate::registry::{RegistryClient, VersionMeta};
-use crate::resolver::{PinOutcome, filter_candidates, try_pin_precise};
+use crate::calendar::Calendar;
+use crate::certificate::policy_digest;
+use crate::ci;
+use crate::codes::{self, ErrorCode, bail_with};
+use crate::commands::allow::append_exact;
+use crate::config::{
+    Baseline, Bump, CiProvider, Config, DuplicatePolicy, Mode, MsrvPolicy, OwnerChangePolicy,
+    PinBackend, PinStrategy, SourcePolicy, TyposquatPolicy, YankedPolicy,
+};
+use crate::deny;
+use crate::events::{Event, EventSink};
+use crate::git_source;
+use crate::graph::{
+    DuplicateMajor, adjacency, blockers, build_adjacency, duplicate_majors, reached_through,
+    shortest_depths,
+};
+use crate::heuristics::{LOW_DOWNLOADS, PopularCrates};
+use crate::integrity;
+use crate::interactive::{self, Decision, Prompt};
+use crate::journal::{Journal, PinRecord};
+use crate::lockfile::{self, LockedPackage};
+use crate::metadata::{config_args, discover_subprojects, lockfile_path, read_metadata};
+use crate::metrics;
+use crate::notify;
+use crate::owners::OwnerWatch;
+use crate::plan::{self, PlanCandidate, PlanCrate};
+use crate::policy;
+use crate::provider::{ProviderChain, VersionMetadataProvider};
+use crate::registry::{IndexEntry, SparseIndexClient, VersionMeta};
+use crate::report::{
+    AgeStatus, ReleaseAge, SkipReason, Skipped, Violation, format_age, graph_cools_down_at,
+    graph_eta, render_pr_comment, render_skipped, render_violations,
+};
+use crate::resolver::{
+    Candidate, LockEdit, MIN_DOWNLOADS_HORIZON_DAYS, PinOutcome, bump_from, cooldown_cutoff,
+    filter_candidates, index_candidates, is_new_crate, lacks_downloads, license_changed,
+    msrv_rejection, order_candidates, parse_rust_version, pin_spec, prerelease_allowed,
+    prerelease_rejection, satisfies_requirements, survived_releases, try_pin_in_lockfile,
+    try_pin_precise, within_downgrade, without_build_metadata,
+};
+use crate::resume::PlanFile;
+use crate::rollback::LockfileGuard;
+use crate::runlock::RunLock;
+use crate::vet;
 use clap_cargo::{Features, Manifest};
 
+/// Shared state for one cooldown run, reused across the main workspace and
+/// any discovered subprojects.
+pub struct Session<'a> {
+    config: &'a Config,
+    allowlist: Allowlist,
+    cache: Cache,
+    provider: ProviderChain,
+    /// Answers version lists and candidates when `sparse_version_lists` is on.
+    index: Option<SparseIndexClient>,
+    /// Release details only the index has, such as checksums and
+    /// `rust-version`; absent when every answer comes from a bundle.
+    sparse: Option<SparseIndexClient>,
+    journal: Journal,
+    /// Pins applied by this run, in order.
+    applied: Mutex<Vec<PinRecord>>,
+    events: Option<EventSink>,
+    /// Every release found inside its window, for the blocked-build
+    /// notification.
+    fresh: Mutex<Vec<Violation>>,
+    /// Releases past their cooldown window but inside `warn_minutes`.
+    soft: Mutex<Vec<Violation>>,
+    /// The fresh releases of the latest analysis and what requires them,
+    /// kept when `blocking_graph_path` asks for them on failure.
+    blocking: Mutex<Option<BlockingGraph>>,
+    audit: Option<AuditLog>,
+    advisories: Option<Advisories>,
+    /// Advisory IDs that do not disqualify a candidate.
+    ignored_advisories: Vec<String>,
+    /// Releases of the baseline lockfile, taken as already reviewed.
+    baseline: Option<Releases>,
+    /// Releases locked before the run, which bump windows are measured from.
+    previous: Option<Releases>,
+    owners: Option<OwnerWatch>,
+    popular: Option<PopularCrates>,
+    /// Which time counts toward cooldown windows.
+    calendar: Calendar,
+    /// When the run started, for `max_duration_minutes`.
+    started: Instant,
+    /// Releases allowed at an `--interactive` prompt for the rest of the run.
+    accepted: Mutex<HashSet<(String, String)>>,
+}
+
+impl<'a> Session<'a> {
+    pub async fn new(config: &'a Config) -> Result<Self> {
+        let mut allowlist = Allowlist::load(config.allowlist_path.clone())
+            .map_err(|err| ErrorCode::InvalidConfig.wrap(err))?;
+        let cache = Cache::for_config(config)?;
+        if let Some(contents) = policy::fetch(config, &cache).await? {
+            let url = config.allowlist_url.as_deref().unwrap_or_default();
+            allowlist.merge(Allowlist::parse(&contents, url)?);
+        }
+        allowlist.evaluated_at = config.as_of;
+        let provider = ProviderChain::from_config(config, &cache)?;
+        let journal = Journal::new(
+            config
+                .pin_journal_path
+                .clone()
+                .unwrap_or_else(|| cache.root().join("pins.jsonl")),
+        );
+        let sparse = SparseIndexClient::new(config)?;
+        let index = config.sparse_version_lists.then(|| sparse.clone());
+        Ok(Self {
+            config,
+            allowlist,
+            cache,
+            provider,
+            index,
+            // Offline runs answer from the cache alone.
+            sparse: (!config.offline).then_some(sparse),
+            journal,
+            applied: Mutex::new(Vec::new()),
+            events: config.events.as_deref().map(EventSink::open).transpose()?,
+            fresh: Mutex::new(Vec::new()),
+            soft: Mutex::new(Vec::new()),
+            blocking: Mutex::new(None),
+            audit: match &config.audit_log_path {
+                // The workspace is not selected yet; the one the config
+                // files were read from stands in.
+                Some(path) => Some(AuditLog::open(
+                    path,
+                    config,
+                    &policy_digest(config, Path::new("."))?,
+                )?),
+                None => None,
+            },
+            advisories: Advisories::from_config(config)?,
+            ignored_advisories: Vec::new(),
+            baseline: None,
+            previous: None,
+            owners: OwnerWatch::from_config(config)?,
+            popular: match config.typosquat {
+                TyposquatPolicy::Off => None,
+                _ => Some(PopularCrates::load(config.db_dump_path.as_deref())?),
+            },
+            calendar: Calendar::from_config(config)?,
+            started: Instant::now(),
+            accepted: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn emit(&self, event: Event<'_>) {
+        if let Some(events) = &self.events {
+            events.emit(&event);
+        }
+        if let Some(audit) = &self.audit {
+            audit.record(&event);
+        }
+        metrics::observe(&event);
+        if let Event::CrateFresh {
+            name,
+            version,
+            published_at,
+            minimum_minutes,
+        } = event
+        {
+            self.fresh
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(Violation {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    published_at,
+                    minimum_minutes,
+                    cools_down_at: self.calendar.window_end(published_at, minimum_minutes),
+                    via: Vec::new(),
+                });
+        }
+    }
+
+    /// Journal a pin and remember it for the end of the run.
+    fn record_pin(&self, record: PinRecord) {
+        self.emit(Event::PinApplied {
+            name: &record.name,
+            from: &record.from,
+            to: &record.to,
+        });
+        if let Err(err) = self.journal.append(&record) {
+            warn!(error = %err, "failed to record pin in the journal");
+        }
+        self.applied
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(record);
+    }
+
+    /// Releases found inside their window that no pin of this run replaced.
+    fn unpinned(&self) -> Vec<Violation> {
+        let applied = self.applied_pins();
+        self.fresh
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter(|fresh| {
+                !applied
+                    .iter()
+                    .any(|pin| pin.name == fresh.name && pin.from == fresh.version)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn applied_pins(&self) -> Vec<PinRecord> {
+        self.applied
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Answer every metadata query from `provider` alone, e.g. a bundle on
+    /// an air-gapped machine.
+    pub fn with_provider(mut self, provider: ProviderChain) -> Self {
+        self.provider = provider;
+        self.index = None;
+        self.sparse = None;
+        self
+    }
+
+    /// Take over the exceptions kept for other tools in the workspace that
+    /// owns `lockfile`: cargo-vet audits and cargo-deny bans.
+    pub fn load_workspace_policy(&mut self, lockfile: &Path) -> Result<()> {
+        let root = lockfile.parent().unwrap_or(Path::new("."));
+        if let Some(rules) =
+            Allowlist::from_manifest(root).map_err(|err| ErrorCode::InvalidConfig.wrap(err))?
+        {
+            debug!(root = %root.display(), "applying cooldown rules from Cargo.toml metadata");
+            self.allowlist.merge(rules);
+        }
+        if self.config.cargo_vet
+            && let Some(audits) = vet::load(root)?
+        {
+            debug!(root = %root.display(), releases = audits.allow.exact.len(), "trusting cargo-vet audits");
+            self.allowlist.merge(audits);
+        }
+        if self.config.cargo_deny
+            && let Some(deny) = deny::load(root)?
+        {
+            debug!(path = %deny.path.display(), bans = deny.bans.deny.len(), ignored = deny.ignored_advisories.len(), "applying cargo-deny configuration");
+            self.allowlist.merge(deny.bans);
+            self.ignored_advisories.extend(deny.ignored_advisories);
+        }
+        Ok(())
+    }
+
+    /// Only check releases that are not part of `baseline`.
+    /// Bump windows are then measured from the baseline too.
+    pub fn set_baseline(&mut self, baseline: Option<Releases>) {
+        self.previous = baseline.clone();
+        self.baseline = baseline;
+    }
+
+    fn in_baseline(&self, name: &str, version: &str) -> bool {
+        self.baseline
+            .as_ref()
+            .is_some_and(|baseline| baseline.contains(&(name.to_string(), version.to_string())))
+    }
+
+    /// Whether a release inside its window may be accepted early because
+    /// enough later releases followed it without it being yanked.
+    async fn has_survived(&self, name: &str, version: &str) -> bool {
+        let Some(required) = self.config.survived_releases else {
+            return false;
+        };
+        match self.version_list(name).await {
+            Ok(list) => survived_releases(&list, version, required),
+            Err(err) => {
+                debug!(crate = %name, error = %err, "version list unavailable; not applying survived_releases");
+                false
+            }
+        }
+    }
+
+    /// The pin strategy for `name`: its allowlist entry's, else the run's.
+    fn pin_strategy(&self, name: &str) -> PinStrategy {
+        self.allowlist
+            .package_pin_strategy(name)
+            .unwrap_or(self.config.pin_strategy)
+    }
+
+    /// Whether fresh releases of `name` are only warned about, because its
+    /// allowlist entry sets `mode = "warn"`. A crate's mode never makes the
+    /// run stricter.
+    fn warns_only(&self, name: &str) -> bool {
+        self.allowlist.package_mode(name) == Some(Mode::Warn)
+    }
+
+    /// Whether a release that is old enough still has too few downloads.
+    fn lacks_downloads(&self, name: &str, meta: &VersionMeta, now: DateTime<Utc>) -> bool {
+        let lacking = lacks_downloads(meta, self.config.min_downloads, now);
+        if lacking {
+            debug!(crate = %name, version = %meta.num, downloads = ?meta.downloads, "release has too few downloads");
+        }
+        lacking
+    }
+
+    /// Record a release that cleared its cooldown window but is still
+    /// inside the `warn_minutes` soft window; it is reported, not blocked.
+    fn note_soft(
+        &self,
+        name: &str,
+        version: &str,
+        published_at: DateTime<Utc>,
+        minimum_minutes: u64,
+        now: DateTime<Utc>,
+    ) {
+        let Some(warn_minutes) = self
+            .config
+            .warn_minutes
+            .filter(|&warn_minutes| warn_minutes > minimum_minutes)
+        else {
+            return;
+        };
+        let cutoff = cooldown_cutoff(now, warn_minutes, self.config.freeze_after, &self.calendar);
+        if published_at > cutoff {
+            self.note_warning(name, version, published_at, warn_minutes);
+        }
+    }
+
+    /// Record a release that is reported as a warning instead of blocking.
+    fn note_warning(
+        &self,
+        name: &str,
+        version: &str,
+        published_at: DateTime<Utc>,
+        warn_minutes: u64,
+    ) {
+        {
+            let mut soft = self
+                .soft
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if soft
+                .iter()
+                .any(|seen| seen.name == name && seen.version == version)
+            {
+                return;
+            }
+            soft.push(Violation {
+                name: name.to_string(),
+                version: version.to_string(),
+                published_at,
+                minimum_minutes: warn_minutes,
+                cools_down_at: self.calendar.window_end(published_at, warn_minutes),
+                via: Vec::new(),
+            });
+        }
+        warn!(crate = %name, %version, "fresh release is only warned about; not blocking");
+        self.emit(Event::CrateWarned {
+            name,
+            version,
+            published_at,
+            minimum_minutes: warn_minutes,
+        });
+    }
+
+    /// Releases warned about so far: those inside only the soft window and
+    /// fresh releases of warn-only crates.
+    pub fn soft_violations(&self) -> Vec<Violation> {
+        self.soft
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    #[cfg(feature = "tui")]
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    /// Every release of `name` published by the time the run evaluates
+    /// at, through the cache.
+    pub async fn version_list(&self, name: &str) -> Result<Vec<VersionMeta>> {
+        let mut list = fetch_version_list(&self.provider, &self.cache, name).await?;
+        // A replay sees the registry as it was at that instant.
+        if let Some(as_of) = self.config.as_of {
+            list.retain(|meta| meta.created_at <= as_of);
+        }
+        Ok(list)
+    }
+
+    /// The window configured for how far `version` moves `name` from the
+    /// release locked before the run, if any.
+    fn bump_minutes(&self, name: &str, version: &str) -> Option<u64> {
+        if !self.config.bump_minutes.is_set() {
+            return None;
+        }
+        let bump = self.bump(name, version)?;
+        let minutes = self.config.bump_minutes.minutes(bump)?;
+        debug!(crate = %name, version = %version, bump = ?bump, minutes, "applying the bump window");
+        Some(minutes)
+    }
+
+    /// How far `version` moves `name` from the releases locked before the
+    /// run.
+    fn bump(&self, name: &str, version: &str) -> Option<Bump> {
+        let previous = self
+            .previous
+            .iter()
+            .flatten()
+            .filter(|(locked, _)| locked == name)
+            .map(|(_, version)| version.as_str());
+        bump_from(previous, version)
+    }
+
+    /// Whether `patch_grace` exempts `version`: a stable patch release of a
+    /// major.minor series of `name` that was already locked.
+    fn has_grace(&self, name: &str, version: &str) -> bool {
+        let graced = self.config.patch_grace
+            && Version::parse(version).is_ok_and(|version| version.pre.is_empty())
+            && self.bump(name, version) == Some(Bump::Patch);
+        if graced {
+            debug!(crate = %name, version = %version, "patch release of a locked series; exempt by patch_grace");
+        }
+        graced
+    }
+
+    /// Cooldown for `name` before allowlist exemptions: the configured
+    /// minutes, or the bump window of `version` when it has one, raised for
+    /// build-time crates and for crates that are still new on the registry.
+    async fn base_minutes(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        build_time: bool,
+        now: DateTime<Utc>,
+    ) -> Result<u64> {
+        let config = self.config;
+        let mut minutes = version
+            .and_then(|version| self.bump_minutes(name, version))
+            .unwrap_or(config.cooldown_minutes);
+        if build_time {
+            minutes = config.build_time.apply(minutes);
+        }
+        if let Some(quarantine) = config
+            .new_crate_minutes
+            .filter(|&quarantine| quarantine > minutes)
+        {
+            match self.version_list(name).await {
+                Ok(list)
+                    if is_new_crate(
+                        &list,
+                        now,
+                        config.new_crate_min_versions,
+                        config.new_crate_min_age_days,
+                    ) =>
+                {
+                    debug!(crate = %name, releases = list.len(), "crate is new; applying the quarantine window");
+                    minutes = quarantine;
+                }
+                Ok(_) => {}
+                Err(err) if config.offline_ok => {
+                    warn!(crate = %name, error = %err, "skipping new crate check due to offline mode");
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(minutes)
+    }
+
+    /// Raise `base_minutes` to the owner-change window, or refuse the
+    /// release, when it comes from a publisher or owner not seen before.
+    /// Allowlisted releases have been reviewed and are not looked up.
+    async fn owner_check(
+        &self,
+        name: &str,
+        version: &str,
+        base_minutes: u64,
+        now: DateTime<Utc>,
+    ) -> Result<u64> {
+        let Some(owners) = &self.owners else {
+            return Ok(base_minutes);
+        };
+        if self.allowlist.is_exact_allowed(name, version)
+            || self.allowlist.is_range_allowed(name, version)
+        {
+            return Ok(base_minutes);
+        }
+        let change = match owners.check(&self.cache, name, version, now).await {
+            Ok(Some(change)) => change,
+            Ok(None) => return Ok(base_minutes),
+            Err(err) if self.config.offline_ok => {
+                warn!(crate = %name, error = %err, "skipping owner-change check due to offline mode");
+                return Ok(base_minutes);
+            }
+            Err(err) => {
+                return Err(err.context(format!("failed to check the owners of {name}")));
+            }
+        };
+        match self.config.owner_change {
+            OwnerChangePolicy::Block => bail_with!(
+                ErrorCode::OwnershipChange,
+                "{name}@{version} was {change}; review it and add it to the allowlist to proceed"
+            ),
+            _ => {
+                warn!(crate = %name, version = %version, change = %change, "ownership changed; applying the owner-change window");
+                Ok(base_minutes.max(owners.minutes()))
+            }
+        }
+    }
+
+    fn is_accepted(&self, name: &str, version: &str) -> bool {
+        self.accepted
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .contains(&(name.to_string(), version.to_string()))
+    }
+
+    /// Cooldown window for one release: the effective minutes, the cutoff,
+    /// and whether the release is exempt altogether.
+    fn window(
+        &self,
+        name: &str,
+        version: &str,
+        base_minutes: u64,
+        now: DateTime<Utc>,
+    ) -> (u64, DateTime<Utc>, bool) {
+        let mut minimum_minutes = base_minutes;
+        if let Some(global) = self.allowlist.global_minutes() {
+            minimum_minutes = minimum_minutes.min(global);
+        }
+        if let Some(minutes) = self.allowlist.package_minutes(name) {
+            minimum_minutes = minimum_minutes.min(minutes);
+        }
+
+        let cutoff = cooldown_cutoff(
+            now,
+            minimum_minutes,
+            self.config.freeze_after,
+            &self.calendar,
+        );
+        let exempt = self.allowlist.package_mode(name) == Some(Mode::Off)
+            || self.allowlist.is_exact_allowed(name, version)
+            || self.allowlist.is_range_allowed(name, version)
+            || self.is_accepted(name, version)
+            || self.has_grace(name, version)
+            || (cutoff >= now
+                && self
+                    .config
+                    .warn_minutes
+                    .is_none_or(|warn_minutes| warn_minutes <= minimum_minutes));
+        (minimum_minutes, cutoff, exempt)
+    }
+}
+
+/// A run that failed while releases it could not pin are still inside their
+/// windows; `until` is when the last of them cools down.
+#[derive(Debug)]
+pub struct CoolingDown {
+    pub until: DateTime<Utc>,
+    /// The code of the failure it wraps.
+    pub code: Option<ErrorCode>,
+    message: String,
+}
+
+impl fmt::Display for CoolingDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CoolingDown {}
+
+/// Whether `[[deny]]` entries in the allowlist or the workspace's
+/// Cargo.toml metadata, or the `[bans]` of its cargo-deny configuration, ban
+/// releases, which fails the run regardless of age
+/// and so needs the graph checked even when no window is configured. A
+/// remote allowlist may carry bans, and files that do not load count, so
+/// the run reports them.
+pub fn has_bans(config: &Config, manifest: &Manifest) -> bool {
+    if config.allowlist_url.is_some() {
+        return true;
+    }
+    let denies = |rules: Result<Option<Allowlist>>| {
+        rules.map_or(true, |rules| {
+            rules.is_some_and(|rules| !rules.deny.is_empty())
+        })
+    };
+    if denies(Allowlist::load(config.allowlist_path.clone()).map(Some)) {
+        return true;
+    }
+    // Outside a workspace there is no manifest to read rules from.
+    let Ok(lockfile) = lockfile_path(manifest) else {
+        return false;
+    };
+    let root = lockfile.parent().unwrap_or(Path::new("."));
+    denies(Allowlist::from_manifest(root))
+        || (config.cargo_deny && denies(deny::load(root).map(|deny| deny.map(|deny| deny.bans))))
+}
+
 pub async fn run_pinning_flow(
     config: &Config,
     manifest: &Manifest,
     features: &Features,
+    platforms: &[String],
 ) -> Result<()> {
-    ensure_lockfile()?;
+    let mut session = Session::new(config).await?;
+    if let Some(rollout) = config.rollout {
+        session.emit(Event::Rollout {
+            percent: rollout.percent,
+            bucket: rollout.bucket,
+            enforced: rollout.enforced,
+        });
+    }
+    let lockfile = lockfile_path(manifest)?;
+    session.set_baseline(load_baseline(&session, &lockfile, true)?);
+    load_previous(&mut session, &lockfile);
+    session.load_workspace_policy(&lockfile)?;
 
-    let allowlist = Allowlist::load(config.allowlist_path.clone())?;
-    let per_crate_minutes = allowlist.per_crate_minutes();
-    let global_minutes = allowlist.global_minutes();
-    let cache = if let Some(ref root) = config.cache_dir {
-        Cache::with_root(root.clone(), Duration::from_secs(config.ttl_seconds))?
-    } else {
-        Cache::new(config.ttl_seconds)?
+    let outcome = cool_down_all(&mut session, manifest, features, platforms, &lockfile)
+        .await
+        .map_err(|err| {
+            let unpinned = session.unpinned();
+            let now = config.now();
+            match (
+                graph_cools_down_at(&unpinned, now),
+                graph_eta(&unpinned, now),
+            ) {
+                (Some(until), Some(eta)) => anyhow::Error::new(CoolingDown {
+                    until,
+                    code: codes::classify(&err),
+                    message: format!("{err:#}\n{eta}"),
+                }),
+                _ => err,
+            }
+        });
+    if let Err(err) = &outcome {
+        let message = format!("{err:#}");
+        let code = codes::classify(err).map(|code| code.id());
+        session.emit(Event::Blocked {
+            message: &message,
+            code: code.as_deref(),
+        });
+        if let Some(path) = &config.blocking_graph_path {
+            write_blocking_graph(&session, path);
+        }
+        if config.mode == Mode::Enforce {
+            notify::blocked(
+                config,
+                &notify::project_name(&lockfile),
+                &message,
+                &session.unpinned(),
+                config.now(),
+            )
+            .await;
+        }
+    }
+    if config.pr_comment
+        && let Err(err) = write_pr_comment(&session, &lockfile, outcome.as_ref().err())
+    {
+        warn!(error = %err, "failed to write the pull request comment");
+    }
+    if config.ci == Some(CiProvider::Github) {
+        let blocked = outcome.as_ref().err().map(|err| format!("{err:#}"));
+        if let Err(err) = ci::report_pins(&session.applied_pins(), blocked.as_deref()) {
+            warn!(error = %err, "failed to report to GitHub Actions");
+        }
+    }
+    outcome
+}
+
+fn write_blocking_graph(session: &Session<'_>, path: &Path) {
+    let graph = session
+        .blocking
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .take();
+    let Some(graph) = graph.filter(|graph| !graph.is_empty()) else {
+        return;
     };
-    let client = RegistryClient::new(config)?;
+    match graph.write(path) {
+        Ok(()) => info!(path = %path.display(), "wrote the graph of what blocked the run"),
+        Err(err) => warn!(error = %err, "failed to write the blocking graph"),
+    }
+}
 
-    let mut visited_failures: HashSet<String> = HashSet::new();
+/// The main workspace and every subproject, restoring all their lockfiles
+/// if any of them fails.
+async fn cool_down_all(
+    session: &mut Session<'_>,
+    manifest: &Manifest,
+    features: &Features,
+    platforms: &[String],
+    lockfile: &Path,
+) -> Result<()> {
+    let config = session.config;
+    let _run_lock = RunLock::acquire(lockfile)?;
+    // Dropped without a commit when the flow errors out, which puts every
+    // lockfile back the way it was; also what an interrupt restores.
+    let mut guards = vec![LockfileGuard::new(lockfile, config.keep_partial)?];
+    let workspace_root = cool_down_workspace(session, manifest, features, platforms).await?;
+    check_lockfile(session, lockfile).await?;
+    check_journal_pins(session, &workspace_root).await;
+    remember_passed(session, lockfile);
 
-    'outer: loop {
-        let metadata = read_metadata(manifest, features)?;
-        let resolve = metadata
-            .resolve
-            .clone()
-            .context("cargo metadata output did not include a resolved dependency graph")?;
-        let packages: HashMap<PackageId, cargo_metadata::Package> = metadata
-            .packages
-            .into_iter()
-            .map(|pkg| (pkg.id.clone(), pkg))
-            .collect();
+    if config.scan_subprojects {
+        for subproject in discover_subprojects(&workspace_root, &config.subproject_dirs) {
+            info!(manifest = %subproject.display(), "checking subproject with its own lockfile");
+            let mut sub_manifest = Manifest::default();
+            sub_manifest.manifest_path = Some(subproject.clone());
+            let sub_lockfile = subproject.with_file_name("Cargo.lock");
+            let _sub_run_lock = RunLock::acquire(&sub_lockfile)?;
+            guards.push(LockfileGuard::new(&sub_lockfile, config.keep_partial)?);
+            session.set_baseline(load_baseline(session, &sub_lockfile, false)?);
+            load_previous(session, &sub_lockfile);
+            let sub_root =
+                cool_down_workspace(session, &sub_manifest, &Features::default(), platforms)
+                    .await
+                    .with_context(|| {
+                        format!("cooldown failed for subproject {}", subproject.display())
+                    })?;
+            check_lockfile(session, &sub_lockfile).await?;
+            check_journal_pins(session, &sub_root).await;
+            remember_passed(session, &sub_lockfile);
+        }
+    }
 
-        let mut name_version_to_id: HashMap<(String, String), PackageId> = HashMap::new();
-        for (id, pkg) in &packages {
-            name_version_to_id.insert((pkg.name.to_string(), pkg.version.to_string()), id.clone());
+    guards.into_iter().for_each(LockfileGuard::commit);
+    let pins = session.applied_pins();
+    if config.commit && !pins.is_empty() {
+        let template = config
+            .commit_message_template
+            .as_deref()
+            .unwrap_or(autocommit::DEFAULT_TEMPLATE);
+        autocommit::commit_pins(&pins, template)
+            .context("pins were applied but could not be committed")?;
+        info!(pins = pins.len(), "committed the pinned lockfiles");
+    }
+    Ok(())
+}
+
+/// Pin one release inside its cooldown window outside a run, e.g. from
+/// `cargo cooldown ui`, the way a run would: under the run lock, refusing a
+/// dirty lockfile, through the same candidate screens and pin backend, and
+/// journaled. The lockfile is restored when no candidate is accepted.
+#[cfg(feature = "tui")]
+pub async fn pin_release(
+    session: &Session<'_>,
+    manifest: &Manifest,
+    features: &Features,
+    platforms: &[String],
+    name: &str,
+    version: &str,
+) -> Result<PinRecord> {
+    let config = session.config;
+    let lockfile = lockfile_path(manifest)?;
+    let _run_lock = RunLock::acquire(&lockfile)?;
+    if !config.allow_dirty {
+        ensure_clean(&lockfile)?;
+    }
+    let guard = LockfileGuard::new(&lockfile, config.keep_partial)?;
+    let metadata = read_metadata(manifest, features, platforms, config)?;
+    let workspace_msrv = workspace_msrv(&metadata);
+    let license = metadata
+        .packages
+        .iter()
+        .find(|pkg| pkg.name.as_str() == name && pkg.version.to_string() == version)
+        .and_then(|pkg| pkg.license.clone());
+    let analysis = analyze_graph(session, metadata, config.now()).await?;
+    let Some(fresh) = analysis
+        .fresh
+        .into_iter()
+        .find(|fresh| fresh.name == name && fresh.current_version == version)
+    else {
+        bail!("{name}@{version} is not inside its cooldown window");
+    };
+    if session.pin_strategy(name) == PinStrategy::Fail {
+        bail_with!(
+            ErrorCode::FreshCrateBlocked,
+            "the pin_strategy of {name} is \"fail\"; wait for the window to pass or allowlist the release after review"
+        );
+    }
+
+    let requirements = analysis
+        .version_requirements
+        .get(&fresh.package_id)
+        .cloned()
+        .unwrap_or_default();
+    let mut candidates = discover_candidates(session, &fresh, &requirements).await?;
+    if let Some(max_steps) = config.max_downgrade {
+        candidates.retain(|candidate| {
+            within_downgrade(&fresh.current_version, &candidate.version, max_steps)
+        });
+    }
+    let mut refusal = None;
+    let candidates = screen_candidates(
+        session,
+        &fresh,
+        workspace_msrv.as_ref(),
+        license.as_deref(),
+        candidates,
+        &mut refusal,
+    )
+    .await;
+    let locked = lockfile::read(&lockfile).ok();
+    for candidate in candidates {
+        if candidate.version == fresh.current_version {
+            continue;
+        }
+        session.emit(Event::PinAttempted {
+            name,
+            from: version,
+            to: &candidate.version,
+        });
+        let outcome = pin_candidate(
+            session,
+            manifest.manifest_path.as_deref(),
+            &lockfile,
+            locked.as_deref(),
+            &fresh,
+            &candidate.version,
+        )
+        .await?;
+        if let PinOutcome::Rejected { .. } = outcome {
+            session.emit(Event::PinRejected {
+                name,
+                from: version,
+                to: &candidate.version,
+            });
+            debug!(crate = %name, candidate = %candidate.version, "cargo update rejected candidate");
+            continue;
         }
+        let record = PinRecord {
+            pinned_at: Utc::now(),
+            workspace: analysis.workspace_root.clone(),
+            name: fresh.name.clone(),
+            from: fresh.current_version.clone(),
+            to: candidate.version.clone(),
+            reason: Some(fresh.pin_reason()),
+            published_at: fresh.published_at,
+        };
+        session.record_pin(record.clone());
+        guard.commit();
+        return Ok(record);
+    }
+    bail_with!(
+        ErrorCode::NoCandidate,
+        "no cooled-down release of {name} below {version} is accepted{}",
+        refusal
+            .map(|refusal| format!(": {refusal}"))
+            .unwrap_or_default()
+    );
+}
 
-        let now = Utc::now();
-        let mut crate_states: HashMap<PackageId, CrateState> = HashMap::new();
-        let mut fresh_entries: Vec<FreshCrate> = Vec::new();
-        let mut equality_dependents: HashMap<PackageId, Vec<PackageId>> = HashMap::new();
-        let mut requirement_origins: HashMap<PackageId, Vec<RequirementOrigin>> = HashMap::new();
-        let mut version_requirements: HashMap<PackageId, Vec<VersionReq>> = HashMap::new();
-        let mut seen: HashSet<PackageId> = HashSet::new();
-
-        for node in &resolve.nodes {
-            if !seen.insert(node.id.clone()) {
-                continue;
+/// Write `target/cooldown/pr-comment.md` for the run that pinned `lockfile`,
+/// failed with `error` or not.
+fn write_pr_comment(
+    session: &Session<'_>,
+    lockfile: &Path,
+    error: Option<&anyhow::Error>,
+) -> Result<()> {
+    let target_dir = env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| lockfile.with_file_name("target"));
+    let path = target_dir.join("cooldown").join("pr-comment.md");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let blocked = error.map(|err| format!("{err:#}"));
+    let rolled_back = error.is_some() && !session.config.keep_partial;
+    let comment = render_pr_comment(&session.applied_pins(), blocked.as_deref(), rolled_back);
+    fs::write(&path, comment).with_context(|| format!("failed to write {}", path.display()))?;
+    info!(path = %path.display(), "wrote the pull request comment");
+    Ok(())
+}
+
+/// Baseline releases for `lockfile`. A file or digest names one specific
+/// lockfile, so it only applies to the main workspace; subprojects fall back
+/// to a full check unless the baseline is a git reference.
+pub fn load_baseline(
+    session: &Session<'_>,
+    lockfile: &Path,
+    primary: bool,
+) -> Result<Option<Releases>> {
+    let Some(source) = &session.config.baseline else {
+        return Ok(None);
+    };
+    if !primary && !matches!(source, Baseline::GitRef(_)) {
+        return Ok(None);
+    }
+    let releases = baseline::load(source, lockfile, &session.cache)
+        .with_context(|| format!("failed to load the baseline for {}", lockfile.display()))?;
+    debug!(lockfile = %lockfile.display(), releases = releases.len(), "loaded baseline");
+    Ok(Some(releases))
+}
+
+/// Without a baseline, bump windows and `patch_grace` are measured from
+/// `lockfile` as it is before the run resolves or pins anything.
+fn load_previous(session: &mut Session<'_>, lockfile: &Path) {
+    let measures_bumps = session.config.bump_minutes.is_set() || session.config.patch_grace;
+    if !measures_bumps || session.previous.is_some() || !lockfile.exists() {
+        return;
+    }
+    match lockfile::read(lockfile) {
+        Ok(packages) => session.previous = Some(baseline::releases(&packages)),
+        Err(err) => {
+            warn!(lockfile = %lockfile.display(), error = %err, "could not read the lockfile; bump windows do not apply")
+        }
+    }
+}
+
+/// Store a lockfile that passed so a later run can use it as `sha256:` baseline.
+fn remember_passed(session: &Session<'_>, lockfile: &Path) {
+    if let Err(err) = baseline::remember(lockfile, &session.cache) {
+        debug!(lockfile = %lockfile.display(), error = %err, "could not store the lockfile as a baseline");
+    }
+}
+
+/// Evaluate an already resolved dependency graph without invoking Cargo and
+/// return every release that is still inside its cooldown window.
+pub async fn analyze_metadata(session: &Session<'_>, metadata: Metadata) -> Result<Vec<Violation>> {
+    let names: HashMap<PackageId, String> = metadata
+        .packages
+        .iter()
+        .map(|pkg| (pkg.id.clone(), pkg.name.to_string()))
+        .collect();
+    let through = metadata
+        .resolve
+        .as_ref()
+        .map(|resolve| reached_through(&metadata.workspace_members, &adjacency(resolve)))
+        .unwrap_or_default();
+
+    let analysis = analyze_graph(session, metadata, session.config.now()).await?;
+    Ok(analysis
+        .fresh
+        .into_iter()
+        .filter_map(|fresh| {
+            let via = through
+                .get(&fresh.package_id)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| names.get(id).cloned())
+                .collect();
+            let published_at = fresh.published_at?;
+            Some(Violation {
+                published_at,
+                name: fresh.name,
+                version: fresh.current_version,
+                minimum_minutes: fresh.minimum_minutes,
+                cools_down_at: session
+                    .calendar
+                    .window_end(published_at, fresh.minimum_minutes),
+                via,
+            })
+        })
+        .collect())
+}
+
+/// Where one locked release stands against its cooldown window, for the
+/// read-only listings. Nothing here fails: a release whose publication time
+/// cannot be looked up is reported as unknown, and ownership changes are
+/// left to the checks.
+pub async fn release_age(
+    session: &Session<'_>,
+    name: &str,
+    version: &str,
+    now: DateTime<Utc>,
+) -> ReleaseAge {
+    let base_minutes = session
+        .base_minutes(name, Some(version), false, now)
+        .await
+        .unwrap_or(session.config.cooldown_minutes);
+    let (minimum_minutes, cutoff, exempt) = session.window(name, version, base_minutes, now);
+    let published_at = fetch_version_meta(&session.provider, &session.cache, name, version)
+        .await
+        .map(|meta| meta.created_at)
+        .ok();
+    let status = match published_at {
+        _ if exempt || session.in_baseline(name, version) => AgeStatus::Exempt,
+        None => AgeStatus::Unknown,
+        Some(at) if at > cutoff && !session.has_survived(name, version).await => AgeStatus::Fresh,
+        Some(_) => AgeStatus::Cooled,
+    };
+    ReleaseAge {
+        name: name.to_string(),
+        version: version.to_string(),
+        published_at,
+        minimum_minutes,
+        status,
+    }
+}
+
+/// The newest release of `name` that is outside its cooldown window, or
+/// exempted from it, and matches `req`. Yanked releases and releases with
+/// too few downloads never qualify; pre-releases only when `req` names one.
+/// Returns the version with the window that applied.
+pub async fn newest_cooled(
+    session: &Session<'_>,
+    name: &str,
+    req: &VersionReq,
+    now: DateTime<Utc>,
+) -> Result<(Option<String>, u64)> {
+    let mut releases: Vec<(Version, VersionMeta)> = session
+        .version_list(name)
+        .await?
+        .into_iter()
+        .filter(|meta| !meta.yanked && !session.lacks_downloads(name, meta, now))
+        .filter_map(|meta| Some((Version::parse(&meta.num).ok()?, meta)))
+        .filter(|(version, _)| req.matches(version))
+        .collect();
+    releases.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut minimum = session.base_minutes(name, None, false, now).await?;
+    for (version, meta) in releases {
+        let base_minutes = session
+            .base_minutes(name, Some(&meta.num), false, now)
+            .await?;
+        let (minimum_minutes, cutoff, exempt) = session.window(name, &meta.num, base_minutes, now);
+        minimum = minimum_minutes;
+        if exempt || meta.created_at <= cutoff {
+            return Ok((Some(version.to_string()), minimum_minutes));
+        }
+    }
+    Ok((None, minimum))
+}
+
+/// Evaluate a `Cargo.lock` on its own, without Cargo or a workspace, and
+/// return every release that is still inside its cooldown window.
+pub async fn analyze_lockfile(
+    session: &Session<'_>,
+    packages: &[LockedPackage],
+) -> Result<Vec<Violation>> {
+    let now = session.config.now();
+    let mut violations = Vec::new();
+    deny_banned(
+        &session.allowlist,
+        packages
+            .iter()
+            .filter(|pkg| pkg.source.is_some())
+            .map(|pkg| (pkg.name.as_str(), pkg.version.clone())),
+    )?;
+    let mut denied_sources = Vec::new();
+    for pkg in packages {
+        let Some(source) = &pkg.source else {
+            continue;
+        };
+        if !is_checked_source(
+            session.config,
+            &pkg.name,
+            &pkg.version,
+            source,
+            &mut denied_sources,
+        ) {
+            continue;
+        }
+
+        if session.in_baseline(&pkg.name, &pkg.version) {
+            continue;
+        }
+
+        // The lockfile does not record target kinds, so the build-time
+        // policy cannot be applied here.
+        let base_minutes = session
+            .base_minutes(&pkg.name, Some(&pkg.version), false, now)
+            .await?;
+        let base_minutes = session
+            .owner_check(&pkg.name, &pkg.version, base_minutes, now)
+            .await?;
+        let (minimum_minutes, cutoff, exempt) =
+            session.window(&pkg.name, &pkg.version, base_minutes, now);
+        if exempt {
+            continue;
+        }
+        let meta = fetch_version_meta(&session.provider, &session.cache, &pkg.name, &pkg.version)
+            .await
+            .inspect(|meta| {
+                session.emit(Event::CrateInspected {
+                    name: &pkg.name,
+                    version: &pkg.version,
+                    published_at: meta.created_at,
+                    minimum_minutes,
+                })
+            });
+        match meta {
+            Ok(meta)
+                if meta.created_at > cutoff || session.lacks_downloads(&pkg.name, &meta, now) =>
+            {
+                if meta.created_at > cutoff && session.has_survived(&pkg.name, &pkg.version).await {
+                    debug!(crate = %pkg.name, version = %pkg.version, "release survived later releases; accepting early");
+                    continue;
+                }
+                if session.warns_only(&pkg.name) {
+                    session.note_warning(&pkg.name, &pkg.version, meta.created_at, minimum_minutes);
+                    continue;
+                }
+                session.emit(Event::CrateFresh {
+                    name: &pkg.name,
+                    version: &pkg.version,
+                    published_at: meta.created_at,
+                    minimum_minutes,
+                });
+                violations.push(Violation {
+                    name: pkg.name.clone(),
+                    version: pkg.version.clone(),
+                    published_at: meta.created_at,
+                    minimum_minutes,
+                    cools_down_at: session
+                        .calendar
+                        .window_end(meta.created_at, minimum_minutes),
+                    via: Vec::new(),
+                });
             }
-            let Some(pkg) = packages.get(&node.id) else {
-                continue;
-            };
-            let Some(source) = pkg.source.as_ref() else {
-                continue;
-            };
-            if !config.is_registry_allowed(&source.repr) {
-                debug!(crate = %pkg.name, source = %source.repr, "skipping non-crates.io registry dependency");
-                continue;
+            Ok(meta) => session.note_soft(
+                &pkg.name,
+                &pkg.version,
+                meta.created_at,
+                minimum_minutes,
+                now,
+            ),
+            Err(err) if session.config.offline_ok => {
+                warn!(crate = %pkg.name, error = %err, "skipping metadata fetch due to offline mode");
             }
+            Err(err) => return Err(err),
+        }
+    }
+    deny_unknown_sources(&denied_sources)?;
+    Ok(violations)
+}
+
+/// Whether packages from `source` are subject to the cooldown. Git
+/// dependencies are skipped; other sources outside the allowed registries
+/// follow `unknown_source_policy`, and denied packages are collected.
+fn is_checked_source(
+    config: &Config,
+    name: &str,
+    version: &str,
+    source: &str,
+    denied: &mut Vec<String>,
+) -> bool {
+    if config.is_registry_allowed(source) {
+        return true;
+    }
+    if source.starts_with("git+") {
+        debug!(crate = %name, source = %source, "skipping git dependency");
+        return false;
+    }
+    match config.unknown_source_policy {
+        SourcePolicy::Skip => {
+            debug!(crate = %name, source = %source, "skipping non-crates.io registry dependency");
+        }
+        SourcePolicy::Warn => {
+            warn!(crate = %name, source = %source, "dependency comes from an unapproved source");
+        }
+        SourcePolicy::Deny => denied.push(format!("{name}@{version} ({source})")),
+    }
+    false
+}
+
+/// Fail on any `(name, version)` banned by the denylist, before any cooldown
+/// logic runs, so a banned crate is reported even when it is old.
+fn deny_banned<'p>(
+    allowlist: &Allowlist,
+    packages: impl IntoIterator<Item = (&'p str, String)>,
+) -> Result<()> {
+    let banned: Vec<String> = packages
+        .into_iter()
+        .filter_map(|(name, version)| {
+            let entry = allowlist.denial(name, &version)?;
+            Some(match &entry.reason {
+                Some(reason) => format!("{name}@{version} ({reason})"),
+                None => format!("{name}@{version}"),
+            })
+        })
+        .collect();
+    if !banned.is_empty() {
+        bail_with!(
+            ErrorCode::BannedDependency,
+            "banned dependencies found by the allowlist's [[deny]] entries: {}",
+            banned.join(", ")
+        );
+    }
+    Ok(())
+}
 
-            let current_version = pkg.version.to_string();
-            let mut minimum_minutes = config.cooldown_minutes;
-            if let Some(global) = global_minutes {
-                minimum_minutes = minimum_minutes.min(global);
+/// Checks on the locked releases that do not depend on their age: checksums,
+/// yanked releases, and lookalikes of popular crates.
+pub async fn check_lockfile(session: &Session<'_>, lockfile: &Path) -> Result<()> {
+    let packages = match lockfile::read(lockfile) {
+        Ok(packages) => packages,
+        Err(err) => {
+            debug!(error = %err, "skipping lockfile checks");
+            return Ok(());
+        }
+    };
+    check_sources(session, lockfile, &packages)?;
+    check_checksums(session, lockfile, &packages).await?;
+    check_typosquats(session, &packages).await?;
+    check_yanked(session, lockfile, &packages).await?;
+    check_git_commits(session, lockfile, &packages)
+}
+
+/// Refuse a lockfile whose recorded checksums differ from the ones the
+/// sparse index publishes, which means it was tampered with.
+pub async fn check_checksums(
+    session: &Session<'_>,
+    lockfile: &Path,
+    packages: &[LockedPackage],
+) -> Result<()> {
+    let config = session.config;
+    if !config.verify_checksums {
+        return Ok(());
+    }
+    let Some(index) = &session.sparse else {
+        warn!("checksums cannot be verified without the registry index; skipping");
+        return Ok(());
+    };
+    let mut mismatches = Vec::new();
+    let mut checked = 0;
+    for pkg in packages {
+        if pkg.checksum.is_none()
+            || !pkg
+                .source
+                .as_deref()
+                .is_some_and(|source| config.is_sparse_indexed(source))
+        {
+            continue;
+        }
+        let mut entries = fetch_index_entries(index, &session.cache, &pkg.name).await;
+        // Only fresh index data can convict a lockfile: entries cached before
+        // checksums were kept, or before the locked release was published,
+        // are refetched rather than reported.
+        if entries.as_ref().is_ok_and(|entries| {
+            entries.iter().any(|entry| entry.cksum.is_none())
+                || integrity::compare(pkg, entries).is_some()
+        }) {
+            entries = index.entries(&pkg.name).await;
+            if let Ok(entries) = &entries {
+                session
+                    .cache
+                    .put(&format!("{}/_index", pkg.name), entries)?;
+            }
+        }
+        match entries {
+            Ok(entries) => {
+                checked += 1;
+                mismatches.extend(integrity::compare(pkg, &entries));
             }
-            if let Some(&minutes) = per_crate_minutes.get(pkg.name.as_str()) {
-                minimum_minutes = minimum_minutes.min(minutes);
+            Err(err) if config.offline_ok => {
+                warn!(crate = %pkg.name, error = %err, "skipping checksum verification due to offline mode");
             }
+            Err(err) => {
+                return Err(err.context(format!("failed to verify the checksum of {}", pkg.name)));
+            }
+        }
+    }
+    if !mismatches.is_empty() {
+        let described: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+        bail_with!(
+            ErrorCode::ChecksumMismatch,
+            "{} does not match the registry index:\n  {}",
+            lockfile.display(),
+            described.join("\n  ")
+        );
+    }
+    debug!(lockfile = %lockfile.display(), checked, "locked checksums match the registry index");
+    Ok(())
+}
 
-            let exact_allowed = allowlist.is_exact_allowed(pkg.name.as_str(), &current_version);
-            crate_states.insert(
-                node.id.clone(),
-                CrateState {
-                    name: pkg.name.to_string(),
-                    current_version: current_version.clone(),
-                    minimum_minutes,
-                    exact_allowed,
-                },
-            );
+/// Warn about, or refuse, dependencies named like a popular crate that are
+/// themselves young or rarely downloaded.
+async fn check_typosquats(session: &Session<'_>, packages: &[LockedPackage]) -> Result<()> {
+    let Some(popular) = &session.popular else {
+        return Ok(());
+    };
+    let config = session.config;
+    let now = config.now();
+    let mut suspects = Vec::new();
+    for pkg in packages {
+        let Some(source) = &pkg.source else {
+            continue;
+        };
+        if !config.is_registry_allowed(source)
+            || session.allowlist.is_exact_allowed(&pkg.name, &pkg.version)
+            || session.allowlist.is_range_allowed(&pkg.name, &pkg.version)
+        {
+            continue;
+        }
+        let Some(original) = popular.lookalike(&pkg.name) else {
+            continue;
+        };
+        let list = match session.version_list(&pkg.name).await {
+            Ok(list) => list,
+            Err(err) => {
+                debug!(crate = %pkg.name, error = %err, "could not look up a crate named like {original}");
+                continue;
+            }
+        };
+        let downloads: Option<u64> = list.iter().map(|meta| meta.downloads).sum();
+        let young = is_new_crate(
+            &list,
+            now,
+            config.new_crate_min_versions,
+            config.new_crate_min_age_days,
+        );
+        if young || downloads.is_some_and(|downloads| downloads < LOW_DOWNLOADS) {
+            suspects.push(format!(
+                "{}@{} (looks like {original})",
+                pkg.name, pkg.version
+            ));
+        }
+    }
+    if suspects.is_empty() {
+        return Ok(());
+    }
+    if config.typosquat == TyposquatPolicy::Block {
+        bail_with!(
+            ErrorCode::SuspectedTyposquat,
+            "dependencies named like popular crates, but young or rarely downloaded: {}. Check the names, or allowlist them after review.",
+            suspects.join(", ")
+        );
+    }
+    for suspect in &suspects {
+        warn!(dependency = %suspect, "dependency is named like a popular crate but is young or rarely downloaded; check for a typosquat");
+    }
+    Ok(())
+}
 
-            for dep in &node.deps {
-                let Some(dep_pkg) = packages.get(&dep.pkg) else {
-                    continue;
-                };
-                if !dep_pkg
-                    .source
-                    .as_ref()
-                    .map(|src| config.is_registry_allowed(&src.repr))
-                    .unwrap_or(false)
-                {
+/// Warn about, or refuse, locked registry releases that have been yanked
+/// since they were locked. Allowlisted releases have been reviewed and are
+/// accepted as they are.
+async fn check_yanked(
+    session: &Session<'_>,
+    lockfile: &Path,
+    packages: &[LockedPackage],
+) -> Result<()> {
+    if session.config.yanked == YankedPolicy::Off {
+        return Ok(());
+    }
+    let mut yanked = Vec::new();
+    for pkg in packages {
+        let Some(source) = &pkg.source else {
+            continue;
+        };
+        if !session.config.is_registry_allowed(source)
+            || session.allowlist.is_exact_allowed(&pkg.name, &pkg.version)
+        {
+            continue;
+        }
+        match session.version_list(&pkg.name).await {
+            Ok(list)
+                if list
+                    .iter()
+                    .any(|meta| meta.num == pkg.version && meta.yanked) =>
+            {
+                yanked.push(format!("{}@{}", pkg.name, pkg.version));
+            }
+            Ok(_) => {}
+            Err(err) if session.config.offline_ok => {
+                warn!(crate = %pkg.name, error = %err, "skipping yanked check due to offline mode");
+            }
+            Err(err) => {
+                return Err(err.context(format!(
+                    "failed to check whether {}@{} was yanked",
+                    pkg.name, pkg.version
+                )));
+            }
+        }
+    }
+    if yanked.is_empty() {
+        return Ok(());
+    }
+    if session.config.yanked == YankedPolicy::Deny {
+        bail_with!(
+            ErrorCode::YankedRelease,
+            "{} locks releases that have been yanked upstream: {}. Update them with `cargo update -p <crate>` or allowlist them after review.",
+            lockfile.display(),
+            yanked.join(", ")
+        );
+    }
+    for release in &yanked {
+        warn!(release = %release, lockfile = %lockfile.display(), "locked release has been yanked upstream");
+    }
+    Ok(())
+}
+
+/// Say which locked packages enforcement left out and why, and refuse them
+/// under `strict_sources`.
+fn check_sources(session: &Session<'_>, lockfile: &Path, packages: &[LockedPackage]) -> Result<()> {
+    let config = session.config;
+    // Path packages are only told apart from the workspace's own crates
+    // when the workspace manifest can be read.
+    let members = workspace_members(lockfile);
+    let mut skipped = Vec::new();
+    for pkg in packages {
+        if session.allowlist.is_exact_allowed(&pkg.name, &pkg.version) {
+            continue;
+        }
+        let reason = match &pkg.source {
+            None => match &members {
+                Some(members) if !members.contains(&(pkg.name.clone(), pkg.version.clone())) => {
+                    SkipReason::Path
+                }
+                _ => continue,
+            },
+            Some(source) if source.starts_with("git+") => {
+                if config.git_minutes.is_some() {
                     continue;
                 }
+                SkipReason::Git
+            }
+            Some(source) if !config.is_registry_allowed(source) => {
+                SkipReason::Registry(source.clone())
+            }
+            Some(_) => continue,
+        };
+        skipped.push(Skipped {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            reason,
+        });
+    }
+    if skipped.is_empty() {
+        return Ok(());
+    }
+    if config.strict_sources {
+        bail_with!(
+            ErrorCode::UnvettedSource,
+            "{} locks dependencies the cooldown cannot vet and strict_sources = true refuses them; allowlist each after review:\n{}",
+            lockfile.display(),
+            render_skipped(&skipped).trim_end()
+        );
+    }
+    info!("{}", render_skipped(&skipped).trim_end());
+    Ok(())
+}
 
-                if let Some(manifest_dep) =
-                    find_manifest_dependency(&pkg.dependencies, &dep.name, &dep_pkg.name)
-                {
-                    let requirements = version_requirements.entry(dep.pkg.clone()).or_default();
-                    if !requirements.iter().any(|req| req == &manifest_dep.req) {
-                        requirements.push(manifest_dep.req.clone());
-                    }
-
-                    let origins = requirement_origins.entry(dep.pkg.clone()).or_default();
-                    if !origins.iter().any(|origin| {
-                        origin.parent_id == node.id && origin.requirement == manifest_dep.req
-                    }) {
-                        origins.push(RequirementOrigin {
-                            parent_id: node.id.clone(),
-                            parent_name: pkg.name.to_string(),
-                            requirement: manifest_dep.req.clone(),
-                        });
-                    }
+/// The `(name, version)` of every member of the workspace `lockfile`
+/// belongs to, or `None` when its manifest cannot be read.
+fn workspace_members(lockfile: &Path) -> Option<HashSet<(String, String)>> {
+    let metadata = MetadataCommand::new()
+        .manifest_path(lockfile.with_file_name("Cargo.toml"))
+        .no_deps()
+        .exec()
+        .inspect_err(|err| debug!(error = %err, "workspace members unknown; not reporting path dependencies"))
+        .ok()?;
+    Some(
+        metadata
+            .workspace_packages()
+            .into_iter()
+            .map(|pkg| (pkg.name.to_string(), pkg.version.to_string()))
+            .collect(),
+    )
+}
 
-                    if is_exact_requirement(&manifest_dep.req) {
-                        equality_dependents
-                            .entry(dep.pkg.clone())
-                            .or_default()
-                            .push(node.id.clone());
-                    }
-                }
+/// Refuse git dependencies locked to a commit made inside `git_minutes`.
+/// Pinning cannot pick an older commit, so these are reported, not pinned.
+fn check_git_commits(
+    session: &Session<'_>,
+    lockfile: &Path,
+    packages: &[LockedPackage],
+) -> Result<()> {
+    let Some(minutes) = session.config.git_minutes else {
+        return Ok(());
+    };
+    let now = session.config.now();
+    let cutoff = cooldown_cutoff(now, minutes, session.config.freeze_after, &session.calendar);
+    let mut fresh = Vec::new();
+    for pkg in packages {
+        let Some((repository, commit)) = pkg.source.as_deref().and_then(git_source::locked_commit)
+        else {
+            continue;
+        };
+        if session.allowlist.is_exact_allowed(&pkg.name, &pkg.version) {
+            continue;
+        }
+        // Commits never change, so a cached date never goes stale.
+        let key = format!("{}/_git-{commit}", pkg.name);
+        let committed_at = match session.cache.get_stale::<DateTime<Utc>>(&key)? {
+            Some(date) => date,
+            None => {
+                let date = git_source::commit_date(repository, commit)?;
+                session.cache.put(&key, &date)?;
+                date
             }
+        };
+        if committed_at > cutoff {
+            fresh.push(Violation {
+                name: pkg.name.clone(),
+                version: format!("{} (git {})", pkg.version, &commit[..commit.len().min(8)]),
+                published_at: committed_at,
+                minimum_minutes: minutes,
+                cools_down_at: session.calendar.window_end(committed_at, minutes),
+                via: Vec::new(),
+            });
+        }
+    }
+    if fresh.is_empty() {
+        return Ok(());
+    }
+    bail_with!(
+        ErrorCode::UnvettedSource,
+        "{} locks git dependencies committed inside the {minutes}-minute git cooldown window:\n{}",
+        lockfile.display(),
+        render_violations(&fresh, now).trim_end()
+    )
+}
+
+fn deny_unknown_sources(denied: &[String]) -> Result<()> {
+    if !denied.is_empty() {
+        bail_with!(
+            ErrorCode::UnvettedSource,
+            "dependencies from unapproved sources are not allowed by unknown_source_policy = \"deny\": {}",
+            denied.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Everything learned about one resolved graph in a single analysis pass.
+struct GraphAnalysis {
+    workspace_root: PathBuf,
+    crate_states: HashMap<PackageId, CrateState>,
+    fresh: Vec<FreshCrate>,
+    equality_dependents: HashMap<PackageId, Vec<PackageId>>,
+    requirement_origins: HashMap<PackageId, Vec<RequirementOrigin>>,
+    version_requirements: HashMap<PackageId, Vec<VersionReq>>,
+}
+
+async fn analyze_graph(
+    session: &Session<'_>,
+    metadata: Metadata,
+    now: DateTime<Utc>,
+) -> Result<GraphAnalysis> {
+    let Session {
+        config,
+        cache,
+        provider,
+        ..
+    } = session;
+
+    let resolve = metadata
+        .resolve
+        .clone()
+        .context("cargo metadata output did not include a resolved dependency graph")?;
+    let workspace_root = metadata.workspace_root.clone().into_std_path_buf();
+    let depths = config
+        .max_depth
+        .map(|_| shortest_depths(&metadata.workspace_members, &adjacency(&resolve)));
+    let compiled = config
+        .ignore_dev_dependencies
+        .then(|| shortest_depths(&metadata.workspace_members, &build_adjacency(&resolve)));
+    let packages: HashMap<PackageId, cargo_metadata::Package> = metadata
+        .packages
+        .into_iter()
+        .map(|pkg| (pkg.id.clone(), pkg))
+        .collect();
+    deny_banned(
+        &session.allowlist,
+        resolve
+            .nodes
+            .iter()
+            .filter_map(|node| packages.get(&node.id))
+            .filter(|pkg| pkg.source.is_some())
+            .map(|pkg| (pkg.name.as_str(), pkg.version.to_string())),
+    )?;
+
+    let mut crate_states: HashMap<PackageId, CrateState> = HashMap::new();
+    let mut fresh_entries: Vec<FreshCrate> = Vec::new();
+    let mut equality_dependents: HashMap<PackageId, Vec<PackageId>> = HashMap::new();
+    let mut requirement_origins: HashMap<PackageId, Vec<RequirementOrigin>> = HashMap::new();
+    let mut version_requirements: HashMap<PackageId, Vec<VersionReq>> = HashMap::new();
+    let mut seen: HashSet<PackageId> = HashSet::new();
+    let mut denied_sources: Vec<String> = Vec::new();
 
-            if exact_allowed || minimum_minutes == 0 {
+    for node in &resolve.nodes {
+        if !seen.insert(node.id.clone()) {
+            continue;
+        }
+        let Some(pkg) = packages.get(&node.id) else {
+            continue;
+        };
+        let Some(source) = pkg.source.as_ref() else {
+            continue;
+        };
+        if compiled
+            .as_ref()
+            .is_some_and(|compiled| !compiled.contains_key(&node.id))
+        {
+            debug!(crate = %pkg.name, "skipping dev-only dependency");
+            continue;
+        }
+        let current_version = pkg.version.to_string();
+        if !is_checked_source(
+            config,
+            &pkg.name,
+            &current_version,
+            &source.repr,
+            &mut denied_sources,
+        ) {
+            continue;
+        }
+
+        // Unchanged since the baseline: keep the crate in the graph for its
+        // requirements, but do not look at its age.
+        let unchanged = session.in_baseline(&pkg.name, &current_version);
+        let base_minutes = if unchanged {
+            config.cooldown_minutes
+        } else {
+            let base_minutes = session
+                .base_minutes(
+                    &pkg.name,
+                    Some(&current_version),
+                    runs_at_build_time(pkg),
+                    now,
+                )
+                .await?;
+            session
+                .owner_check(&pkg.name, &current_version, base_minutes, now)
+                .await?
+        };
+        let (minimum_minutes, cutoff, exempt) =
+            session.window(&pkg.name, &current_version, base_minutes, now);
+        let exempt = exempt || unchanged;
+        crate_states.insert(
+            node.id.clone(),
+            CrateState {
+                name: pkg.name.to_string(),
+                current_version: current_version.clone(),
+                minimum_minutes,
+                cutoff,
+                exempt,
+            },
+        );
+
+        for dep in &node.deps {
+            let Some(dep_pkg) = packages.get(&dep.pkg) else {
+                continue;
+            };
+            if !dep_pkg
+                .source
+                .as_ref()
+                .map(|src| config.is_registry_allowed(&src.repr))
+                .unwrap_or(false)
+            {
                 continue;
             }
 
-            match fetch_version_meta(&client, &cache, pkg.name.as_str(), &current_version).await {
-                Ok(meta) => {
-                    let age_minutes = (now - meta.created_at).num_minutes();
-                    debug!(
+            if let Some(manifest_dep) =
+                find_manifest_dependency(&pkg.dependencies, &dep.name, &dep_pkg.name)
+            {
+                let requirements = version_requirements.entry(dep.pkg.clone()).or_default();
+                if !requirements.iter().any(|req| req == &manifest_dep.req) {
+                    requirements.push(manifest_dep.req.clone());
+                }
+
+                let origins = requirement_origins.entry(dep.pkg.clone()).or_default();
+                if !origins.iter().any(|origin| {
+                    origin.parent_id == node.id && origin.requirement == manifest_dep.req
+                }) {
+                    origins.push(RequirementOrigin {
+                        parent_id: node.id.clone(),
+                        parent_name: pkg.name.to_string(),
+                        requirement: manifest_dep.req.clone(),
+                    });
+                }
+
+                if is_exact_requirement(&manifest_dep.req) {
+                    equality_dependents
+                        .entry(dep.pkg.clone())
+                        .or_default()
+                        .push(node.id.clone());
+                }
+            }
+        }
+
+        if exempt {
+            continue;
+        }
+
+        match fetch_version_meta(provider, cache, pkg.name.as_str(), &current_version).await {
+            Ok(meta) => {
+                session.emit(Event::CrateInspected {
+                    name: &pkg.name,
+                    version: &current_version,
+                    published_at: meta.created_at,
+                    minimum_minutes,
+                });
+                let age_minutes = (now - meta.created_at).num_minutes();
+                debug!(
+                    crate = %pkg.name,
+                    %age_minutes,
+                    %minimum_minutes,
+                    created_at = %meta.created_at,
+                    "crate age inspected"
+                );
+                let held =
+                    meta.created_at > cutoff || session.lacks_downloads(&pkg.name, &meta, now);
+                let too_deep = depths
+                    .as_ref()
+                    .and_then(|depths| depths.get(&node.id).copied())
+                    .filter(|&depth| config.max_depth.is_some_and(|max| depth > max));
+                if let Some(depth) = too_deep.filter(|_| held) {
+                    warn!(
                         crate = %pkg.name,
-                        %age_minutes,
-                        %minimum_minutes,
-                        created_at = %meta.created_at,
-                        "crate age inspected"
+                        version = %current_version,
+                        depth,
+                        "release is inside the cooldown window but deeper than max_depth; not enforcing"
+                    );
+                } else if meta.created_at > cutoff
+                    && session.has_survived(&pkg.name, &current_version).await
+                {
+                    debug!(crate = %pkg.name, version = %current_version, "release survived later releases; accepting early");
+                } else if held && session.warns_only(&pkg.name) {
+                    session.note_warning(
+                        &pkg.name,
+                        &current_version,
+                        meta.created_at,
+                        minimum_minutes,
+                    );
+                } else if held {
+                    session.emit(Event::CrateFresh {
+                        name: &pkg.name,
+                        version: &current_version,
+                        published_at: meta.created_at,
+                        minimum_minutes,
+                    });
+                    fresh_entries.push(FreshCrate {
+                        package_id: node.id.clone(),
+                        name: pkg.name.to_string(),
+                        current_version: current_version.clone(),
+                        minimum_minutes,
+                        cutoff,
+                        published_at: Some(meta.created_at),
+                    });
+                } else {
+                    session.note_soft(
+                        &pkg.name,
+                        &current_version,
+                        meta.created_at,
+                        minimum_minutes,
+                        now,
                     );
-                    if age_minutes < minimum_minutes as i64 {
-                        fresh_entries.push(FreshCrate {
-                            package_id: node.id.clone(),
-                            name: pkg.name.to_string(),
-                            current_version: current_version.clone(),
-                            minimum_minutes,
-                        });
-                    }
                 }
-                Err(err) => {
-                    if config.offline_ok {
-                        warn!(crate = %pkg.name, error = %err, "skipping metadata fetch due to offline mode");
-                    } else {
-                        return Err(err);
-                    }
+            }
+            Err(err) => {
+                if config.offline_ok {
+                    warn!(crate = %pkg.name, error = %err, "skipping metadata fetch due to offline mode");
+                } else {
+                    return Err(err);
                 }
             }
         }
+    }
+
+    deny_unknown_sources(&denied_sources)?;
+
+    Ok(GraphAnalysis {
+        workspace_root,
+        crate_states,
+        fresh: fresh_entries,
+        equality_dependents,
+        requirement_origins,
+        version_requirements,
+    })
+}
+
+/// Run the pinning loop for a single Cargo workspace and return its root.
+#[instrument(level = "debug", skip_all, fields(manifest = ?manifest.manifest_path))]
+async fn cool_down_workspace(
+    session: &Session<'_>,
+    manifest: &Manifest,
+    features: &Features,
+    platforms: &[String],
+) -> Result<PathBuf> {
+    let config = session.config;
+    let manifest_path = manifest.manifest_path.as_deref();
+    if config.locked && !lockfile_path(manifest)?.exists() {
+        bail_with!(
+            ErrorCode::DirtyLockfile,
+            "Cargo.lock does not exist and the Cargo command was given --locked or --frozen; run `cargo generate-lockfile` first"
+        );
+    }
+    ensure_lockfile(manifest, &config.cargo_config)?;
+
+    if config.pin_strategy == PinStrategy::Fail {
+        let metadata = read_metadata(manifest, features, platforms, config)?;
+        let workspace_root = metadata.workspace_root.clone().into_std_path_buf();
+        let violations = analyze_metadata(session, metadata).await?;
+        if !violations.is_empty() {
+            let untouched = if config.locked {
+                "the Cargo command was given --locked or --frozen"
+            } else {
+                "pin_strategy = \"fail\" leaves the lockfile untouched"
+            };
+            bail_with!(
+                ErrorCode::FreshCrateBlocked,
+                "{} releases are inside the cooldown window and {untouched}:\n{}",
+                violations.len(),
+                render_violations(&violations, session.config.now()).trim_end()
+            );
+        }
+        info!("dependency graph cooled down; continuing with Cargo command");
+        return Ok(workspace_root);
+    }
+
+    let mut visited_failures: HashSet<String> = HashSet::new();
+    let mut checked_dirty = config.allow_dirty;
+    // An interactive run asks about each crate instead of planning them all.
+    let mut planned = !config.plan_pins
+        || config.duplicate_majors != DuplicatePolicy::Report
+        || config.interactive;
+    let mut initial_duplicates: Option<Vec<DuplicateMajor>> = None;
+
+    let mut saved = match config.resume_plans {
+        true => {
+            let lockfile = lockfile_path(manifest)?;
+            let root = lockfile.parent().unwrap_or(Path::new("."));
+            Some(PlanFile::open(&lockfile, &policy_digest(config, root)?)?)
+        }
+        false => None,
+    };
+    if let Some(saved) = saved.as_mut().filter(|saved| !saved.pins().is_empty()) {
+        let lockfile = lockfile_path(manifest)?;
+        if !checked_dirty {
+            checked_dirty = true;
+            ensure_clean(&lockfile)?;
+        }
+        match try_pin_in_lockfile(
+            manifest_path,
+            &config.cargo_config,
+            &lockfile,
+            &saved.edits(),
+        ) {
+            Ok(PinOutcome::Applied) => {
+                info!(
+                    pins = saved.pins().len(),
+                    "re-applied the pins of an interrupted run"
+                );
+                for pin in saved.pins() {
+                    session.record_pin(PinRecord {
+                        pinned_at: Utc::now(),
+                        ..pin.record.clone()
+                    });
+                }
+            }
+            Ok(PinOutcome::Rejected { .. }) => {
+                warn!(
+                    "Cargo no longer accepts the pins saved by an interrupted run; starting over"
+                );
+                saved.clear()?;
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to re-apply the pins saved by an interrupted run; starting over");
+                saved.clear()?;
+            }
+        }
+    }
+
+    let mut iterations = 0;
+    let workspace_root = 'outer: loop {
+        let metadata = read_metadata(manifest, features, platforms, config)?;
+        let workspace_msrv = workspace_msrv(&metadata);
+        let licenses: HashMap<PackageId, String> = metadata
+            .packages
+            .iter()
+            .filter_map(|pkg| Some((pkg.id.clone(), pkg.license.clone()?)))
+            .collect();
+        let duplicates = duplicate_majors(&metadata);
+        let initial_duplicates = initial_duplicates.get_or_insert_with(|| duplicates.clone());
+        let GraphAnalysis {
+            workspace_root,
+            crate_states,
+            fresh: mut fresh_entries,
+            equality_dependents,
+            requirement_origins,
+            version_requirements,
+        } = analyze_graph(session, metadata, session.config.now()).await?;
 
         if fresh_entries.is_empty() {
+            for duplicate in introduced_duplicates(initial_duplicates, &duplicates) {
+                warn!(
+                    crate = %duplicate.name,
+                    versions = %duplicate.versions.join(", "),
+                    extra_crates = duplicate.extra_crates,
+                    "pinning left several incompatible versions of this crate in the graph, adding build time and binary size"
+                );
+            }
+            if let Some(saved) = saved.take()
+                && let Err(err) = saved.finish()
+            {
+                warn!(error = %err, "failed to remove the saved plan");
+            }
             info!("dependency graph cooled down; continuing with Cargo command");
-            break;
+            break 'outer workspace_root;
+        }
+        iterations += 1;
+        check_budget(session, iterations, &fresh_entries)?;
+
+        if config.blocking_graph_path.is_some() {
+            *session
+                .blocking
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(blocking_graph(
+                &fresh_entries,
+                &crate_states,
+                &requirement_origins,
+            ));
         }
 
         let fresh_ids: HashSet<PackageId> =
@@ -189,20 +1941,103 @@ pub async fn run_pinning_flow(
                 .unwrap_or(0)
         });
 
+        if !checked_dirty {
+            checked_dirty = true;
+            ensure_clean(&workspace_root.join("Cargo.lock"))?;
+        }
+
+        if !planned {
+            planned = true;
+            let lockfile = workspace_root.join("Cargo.lock");
+            let plan = plan_pins(
+                session,
+                &fresh_entries,
+                &crate_states,
+                &requirement_origins,
+                &version_requirements,
+                &lockfile,
+                workspace_msrv.as_ref(),
+                &licenses,
+            )
+            .await;
+            if let Some(edits) = plan {
+                match try_pin_in_lockfile(manifest_path, &config.cargo_config, &lockfile, &edits) {
+                    Ok(PinOutcome::Applied) => {
+                        for (edit, fresh) in edits.into_iter().zip(&fresh_entries) {
+                            info!(crate = %edit.name, pinned = %edit.to, "pin applied from plan");
+                            record_pin(
+                                session,
+                                saved.as_mut(),
+                                &lockfile,
+                                PinRecord {
+                                    pinned_at: Utc::now(),
+                                    workspace: workspace_root.clone(),
+                                    name: edit.name,
+                                    from: edit.from,
+                                    to: edit.to,
+                                    reason: Some(fresh.pin_reason()),
+                                    published_at: fresh.published_at,
+                                },
+                            );
+                        }
+                        continue 'outer;
+                    }
+                    Ok(PinOutcome::Rejected { .. }) => {
+                        debug!(
+                            "Cargo does not accept the planned lockfile; pinning crate by crate"
+                        );
+                    }
+                    Err(err) => {
+                        debug!(error = %err, "failed to apply the plan; pinning crate by crate");
+                    }
+                }
+            }
+        }
+
         let mut queue: VecDeque<FreshCrate> = fresh_entries.into();
+        // Pins applied in this pass that left the rest of the graph alone;
+        // the analysis stays valid for every other crate until one does not.
+        let mut pinned: HashSet<PackageId> = HashSet::new();
 
         'queue_loop: while let Some(fresh) = queue.pop_front() {
+            if pinned.contains(&fresh.package_id) {
+                continue;
+            }
+            check_budget(
+                session,
+                iterations,
+                std::iter::once(&fresh).chain(
+                    queue
+                        .iter()
+                        .filter(|queued| !pinned.contains(&queued.package_id)),
+                ),
+            )?;
             let key = format!("{}@{}", fresh.name, fresh.current_version);
             if visited_failures.contains(&key) {
-                bail!(
+                bail_with!(
+                    ErrorCode::NoCandidate,
                     "no acceptable version found for {} (cooldown {} minutes). Consider waiting for the cooldown window, temporarily downgrading, or applying a [patch.crates-io] override.",
                     fresh.name,
                     fresh.minimum_minutes
                 );
             }
 
-            let candidate_list = match fetch_version_list(&client, &cache, &fresh.name).await {
-                Ok(list) => list,
+            if session.pin_strategy(&fresh.name) == PinStrategy::Fail {
+                bail_with!(
+                    ErrorCode::FreshCrateBlocked,
+                    "{}@{} is inside its {}-minute cooldown window and its pin_strategy is \"fail\"; wait for the window to pass or allowlist the release after review.",
+                    fresh.name,
+                    fresh.current_version,
+                    fresh.minimum_minutes
+                );
+            }
+
+            let requirements = version_requirements
+                .get(&fresh.package_id)
+                .cloned()
+                .unwrap_or_default();
+            let mut candidates = match discover_candidates(session, &fresh, &requirements).await {
+                Ok(candidates) => candidates,
                 Err(err) => {
                     if config.offline_ok {
                         warn!(crate = %fresh.name, error = %err, "skipping candidate discovery due to offline mode");
@@ -214,22 +2049,27 @@ pub async fn run_pinning_flow(
                 }
             };
 
-            let mut candidates = filter_candidates(candidate_list, fresh.minimum_minutes, now);
-            let requirements = version_requirements
-                .get(&fresh.package_id)
-                .cloned()
-                .unwrap_or_default();
-            if !requirements.is_empty() {
-                candidates
-                    .retain(|candidate| satisfies_requirements(&candidate.version, &requirements));
-            }
-
-            if let Ok(current_semver) = Version::parse(&fresh.current_version) {
-                candidates.retain(|candidate| {
-                    Version::parse(&candidate.version)
-                        .map(|version| version < current_semver)
-                        .unwrap_or(true)
-                });
+            if let Some(max_steps) = config.max_downgrade {
+                let (within, beyond): (Vec<Candidate>, Vec<Candidate>) =
+                    candidates.into_iter().partition(|candidate| {
+                        within_downgrade(&fresh.current_version, &candidate.version, max_steps)
+                    });
+                if within.is_empty()
+                    && let Some(closest) = beyond
+                        .iter()
+                        .max_by_key(|candidate| Version::parse(&candidate.version).ok())
+                {
+                    bail_with!(
+                        ErrorCode::NoCandidate,
+                        "{}@{} is inside its {}-minute cooldown window, and its closest cooled-down release {} is further back than max_downgrade allows ({} minor series). Pinning it could drop bug fixes; wait for the window to pass or allowlist the release after review.",
+                        fresh.name,
+                        fresh.current_version,
+                        fresh.minimum_minutes,
+                        closest.version,
+                        max_steps
+                    );
+                }
+                candidates = within;
             }
 
             if candidates.is_empty() {
@@ -239,7 +2079,7 @@ pub async fn run_pinning_flow(
                     debug!(crate = %fresh.name, parents = ?origins, "enqueuing parents due to unsatisfied requirements");
                     for origin in origins {
                         if let Some(state) = crate_states.get(&origin.parent_id) {
-                            if state.exact_allowed || state.minimum_minutes == 0 {
+                            if state.exempt {
                                 continue;
                             }
                             queue.push_front(FreshCrate {
@@ -247,6 +2087,8 @@ pub async fn run_pinning_flow(
                                 name: origin.parent_name.clone(),
                                 current_version: state.current_version.clone(),
                                 minimum_minutes: state.minimum_minutes,
+                                cutoff: state.cutoff,
+                                published_at: None,
                             });
                             queued_parent = true;
                         }
@@ -257,49 +2099,165 @@ pub async fn run_pinning_flow(
                     continue 'queue_loop;
                 }
 
+                if config.interactive {
+                    match ask_remedy(session, &fresh, Vec::new()).await? {
+                        Remedy::Accepted => {
+                            pinned.insert(fresh.package_id.clone());
+                            continue 'queue_loop;
+                        }
+                        Remedy::Waited => continue 'outer,
+                        Remedy::Pin(_) => {}
+                    }
+                }
                 visited_failures.insert(key.clone());
-                bail!(
+                bail_with!(
+                    ErrorCode::NoCandidate,
                     "crate {} lacks versions older than {} minutes that satisfy the semver constraint. Options: wait for the cooldown to elapse, relax the dependency requirement, or pin explicitly via [patch.crates-io].",
                     fresh.name,
                     fresh.minimum_minutes
                 );
             }
 
-            for candidate in candidates {
+            let mut refusal = None;
+            let candidates = screen_candidates(
+                session,
+                &fresh,
+                workspace_msrv.as_ref(),
+                licenses.get(&fresh.package_id).map(String::as_str),
+                candidates,
+                &mut refusal,
+            )
+            .await;
+            let candidates = match config.interactive {
+                false => candidates,
+                true => match ask_remedy(session, &fresh, candidates).await? {
+                    Remedy::Pin(candidates) => candidates,
+                    Remedy::Accepted => {
+                        pinned.insert(fresh.package_id.clone());
+                        continue 'queue_loop;
+                    }
+                    Remedy::Waited => continue 'outer,
+                },
+            };
+            let lockfile = workspace_root.join("Cargo.lock");
+            let remaining_candidates = candidates.len();
+            for (index, candidate) in candidates.into_iter().enumerate() {
                 if candidate.version == fresh.current_version {
                     continue;
                 }
+                let snapshot = match config.duplicate_majors {
+                    DuplicatePolicy::Report => None,
+                    _ => Some(
+                        fs::read(&lockfile)
+                            .with_context(|| format!("failed to read {}", lockfile.display()))?,
+                    ),
+                };
+                let before = lockfile::read(&lockfile).ok();
                 info!(crate = %fresh.name, current = %fresh.current_version, candidate = %candidate.version, "attempting pin");
-                match try_pin_precise(&fresh.name, &fresh.current_version, &candidate.version) {
+                session.emit(Event::PinAttempted {
+                    name: &fresh.name,
+                    from: &fresh.current_version,
+                    to: &candidate.version,
+                });
+                let outcome = pin_candidate(
+                    session,
+                    manifest_path,
+                    &lockfile,
+                    before.as_deref(),
+                    &fresh,
+                    &candidate.version,
+                )
+                .await;
+                if let Ok(PinOutcome::Rejected { .. }) = &outcome {
+                    session.emit(Event::PinRejected {
+                        name: &fresh.name,
+                        from: &fresh.current_version,
+                        to: &candidate.version,
+                    });
+                }
+                match outcome {
                     Ok(PinOutcome::Applied) => {
+                        if let Some(snapshot) = snapshot {
+                            let after = duplicate_majors(&read_metadata(
+                                manifest, features, platforms, config,
+                            )?);
+                            let introduced = introduced_duplicates(&duplicates, &after);
+                            if !introduced.is_empty() {
+                                let described = describe_duplicates(&introduced);
+                                let last = index + 1 == remaining_candidates;
+                                if config.duplicate_majors == DuplicatePolicy::Deny || !last {
+                                    fs::write(&lockfile, snapshot).with_context(|| {
+                                        format!("failed to restore {}", lockfile.display())
+                                    })?;
+                                    warn!(crate = %fresh.name, candidate = %candidate.version, duplicates = %described, "pin would duplicate crates in the graph; trying other candidates");
+                                    refusal = Some(format!(
+                                        "the remaining candidates would duplicate {described}"
+                                    ));
+                                    continue;
+                                }
+                                warn!(crate = %fresh.name, candidate = %candidate.version, duplicates = %described, "no candidate avoids duplicating crates; accepting the pin");
+                            }
+                        }
                         info!(crate = %fresh.name, pinned = %candidate.version, "pin applied");
+                        record_pin(
+                            session,
+                            saved.as_mut(),
+                            &lockfile,
+                            PinRecord {
+                                pinned_at: Utc::now(),
+                                workspace: workspace_root.clone(),
+                                name: fresh.name.clone(),
+                                from: fresh.current_version.clone(),
+                                to: candidate.version.clone(),
+                                reason: Some(fresh.pin_reason()),
+                                published_at: fresh.published_at,
+                            },
+                        );
+                        let graph_unchanged = before
+                            .zip(lockfile::read(&lockfile).ok())
+                            .is_some_and(|(before, after)| {
+                                lockfile::only_replaced(
+                                    &before,
+                                    &after,
+                                    &fresh.name,
+                                    &fresh.current_version,
+                                    &candidate.version,
+                                )
+                            });
+                        if !graph_unchanged {
+                            debug!(crate = %fresh.name, "pin changed other locked releases; analyzing the graph again");
+                            continue 'outer;
+                        }
+                        pinned.insert(fresh.package_id.clone());
+                        continue 'queue_loop;
+                    }
+                    Ok(PinOutcome::Rejected { .. }) if !pinned.is_empty() => {
+                        debug!(crate = %fresh.name, candidate = %candidate.version, "pin rejected after earlier pins in this pass; analyzing the graph again");
                         continue 'outer;
                     }
-                    Ok(PinOutcome::Rejected { stdout, stderr }) => {
-                        let blockers = parse_blockers(&stdout, &stderr);
+                    Ok(PinOutcome::Rejected { stderr }) => {
+                        let blockers = pin_blockers(
+                            session,
+                            &fresh,
+                            &candidate.version,
+                            &crate_states,
+                            requirement_origins.get(&fresh.package_id),
+                        )
+                        .await;
                         if blockers.is_empty() {
-                            debug!(crate = %fresh.name, candidate = %candidate.version, "cargo update rejected candidate");
+                            if let Some(reason) =
+                                prerelease_rejection(&fresh.name, &candidate.version, &stderr)
+                            {
+                                warn!("{reason}");
+                                refusal = Some(reason);
+                            } else {
+                                debug!(crate = %fresh.name, candidate = %candidate.version, "cargo update rejected candidate");
+                            }
                             continue;
                         }
-                        for blocker in blockers {
-                            let blocker_id = blocker
-                                .version
-                                .as_ref()
-                                .and_then(|ver| {
-                                    name_version_to_id.get(&(blocker.name.clone(), ver.clone()))
-                                })
-                                .cloned()
-                                .or_else(|| {
-                                    crate_states
-                                        .iter()
-                                        .find(|(_, state)| state.name == blocker.name)
-                                        .map(|(id, _)| id.clone())
-                                });
-
-                            if let Some(id) = blocker_id
-                                && let Some(state) = crate_states.get(&id)
-                            {
-                                if state.exact_allowed || state.minimum_minutes == 0 {
+                        for id in blockers {
+                            if let Some(state) = crate_states.get(&id) {
+                                if state.exempt {
                                     debug!(crate = %state.name, "blocking crate is exempt from cooldown; skipping downgrade");
                                     continue;
                                 }
@@ -308,6 +2266,8 @@ pub async fn run_pinning_flow(
                                     name: state.name.clone(),
                                     current_version: state.current_version.clone(),
                                     minimum_minutes: state.minimum_minutes,
+                                    cutoff: state.cutoff,
+                                    published_at: None,
                                 });
                             }
                         }
@@ -327,26 +2287,429 @@ pub async fn run_pinning_flow(
             }
 
             visited_failures.insert(key.clone());
-            bail!(
+            if let Some(reason) = refusal {
+                bail_with!(
+                    ErrorCode::PinRejected,
+                    "unable to pin crate {} to an older compatible release within the cooldown window ({} minutes): {reason}",
+                    fresh.name,
+                    fresh.minimum_minutes
+                );
+            }
+            bail_with!(
+                ErrorCode::PinRejected,
                 "unable to pin crate {} to an older compatible release within the cooldown window ({} minutes). Try waiting or adding a manual override.",
                 fresh.name,
                 fresh.minimum_minutes
             );
         }
 
-        bail!(
+        if !pinned.is_empty() {
+            debug!(
+                pins = pinned.len(),
+                "applied a batch of pins; analyzing the graph again"
+            );
+            continue 'outer;
+        }
+        bail_with!(
+            ErrorCode::BudgetExhausted,
             "reached a fixed point without resolving all fresh dependencies; aborting to avoid endless loop"
         );
+    };
+
+    Ok(workspace_root)
+}
+
+/// Drop candidates whose `rust-version` conflicts with `respect_msrv`. A
+/// failed index lookup leaves the candidates unscreened.
+async fn screen_msrv(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    workspace_msrv: Option<&Version>,
+    mut candidates: Vec<Candidate>,
+    refusal: &mut Option<String>,
+) -> Vec<Candidate> {
+    let policy = session.config.respect_msrv;
+    if policy == MsrvPolicy::Off || (workspace_msrv.is_none() && policy == MsrvPolicy::Workspace) {
+        return candidates;
+    }
+    let Some(index) = &session.sparse else {
+        return candidates;
+    };
+    let entries = match fetch_index_entries(index, &session.cache, &fresh.name).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            debug!(crate = %fresh.name, error = %err, "index lookup failed; candidates are not screened for rust-version");
+            return candidates;
+        }
+    };
+    let msrv_of = |version: &str| {
+        entries
+            .iter()
+            .find(|entry| entry.vers == version)
+            .and_then(|entry| entry.rust_version.as_deref())
+            .and_then(parse_rust_version)
+    };
+    let current = msrv_of(&fresh.current_version);
+    candidates.retain(|candidate| {
+        let Some(reason) = msrv_rejection(
+            policy,
+            msrv_of(&candidate.version).as_ref(),
+            workspace_msrv,
+            current.as_ref(),
+        ) else {
+            return true;
+        };
+        debug!(crate = %fresh.name, candidate = %candidate.version, reason = %reason, "candidate conflicts with respect_msrv; skipping");
+        *refusal = Some(format!("{}@{} {reason}", fresh.name, candidate.version));
+        false
+    });
+    candidates
+}
+
+/// Drop candidates published under a different license than `current`,
+/// unless license changes are allowed or the candidate is allowlisted.
+/// Candidates whose license cannot be looked up are kept.
+async fn screen_licenses(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    current: Option<&str>,
+    candidates: Vec<Candidate>,
+    refusal: &mut Option<String>,
+) -> Vec<Candidate> {
+    let Some(current) = current.filter(|_| !session.config.allow_license_change) else {
+        return candidates;
+    };
+    let mut kept = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if session
+            .allowlist
+            .is_exact_allowed(&fresh.name, &candidate.version)
+        {
+            kept.push(candidate);
+            continue;
+        }
+        let license = match fetch_version_meta(
+            &session.provider,
+            &session.cache,
+            &fresh.name,
+            &candidate.version,
+        )
+        .await
+        {
+            Ok(meta) => meta.license,
+            Err(err) => {
+                debug!(crate = %fresh.name, candidate = %candidate.version, error = %err, "license lookup failed; not comparing licenses");
+                None
+            }
+        };
+        match license.filter(|license| license_changed(current, license)) {
+            Some(license) => {
+                warn!(crate = %fresh.name, candidate = %candidate.version, current = %current, license = %license, "candidate is published under a different license; skipping");
+                *refusal = Some(format!(
+                    "{}@{} is licensed {license} instead of {current}; set COOLDOWN_ALLOW_LICENSE_CHANGE or allowlist the release to accept it",
+                    fresh.name, candidate.version
+                ));
+            }
+            None => kept.push(candidate),
+        }
+    }
+    kept
+}
+
+/// Run the candidate screens that do not depend on the rest of the graph.
+async fn screen_candidates(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    workspace_msrv: Option<&Version>,
+    license: Option<&str>,
+    candidates: Vec<Candidate>,
+    refusal: &mut Option<String>,
+) -> Vec<Candidate> {
+    let candidates = screen_msrv(session, fresh, workspace_msrv, candidates, refusal).await;
+    let candidates = screen_licenses(session, fresh, license, candidates, refusal).await;
+    screen_advisories(session, &fresh.name, candidates, refusal).await
+}
+
+/// Drop candidates with a known vulnerability. A failed lookup leaves the
+/// candidates unscreened rather than blocking the pin.
+async fn screen_advisories(
+    session: &Session<'_>,
+    name: &str,
+    mut candidates: Vec<Candidate>,
+    refusal: &mut Option<String>,
+) -> Vec<Candidate> {
+    let Some(advisories) = &session.advisories else {
+        return candidates;
+    };
+    let versions: Vec<&str> = candidates.iter().map(|c| c.version.as_str()).collect();
+    let affected = match advisories.affected(&session.cache, name, &versions).await {
+        Ok(mut affected) => {
+            for ids in affected.values_mut() {
+                ids.retain(|id| !session.ignored_advisories.contains(id));
+            }
+            affected.retain(|_, ids| !ids.is_empty());
+            affected
+        }
+        Err(err) => {
+            warn!(crate = %name, error = %err, "OSV lookup failed; candidates are not screened for advisories");
+            return candidates;
+        }
+    };
+    candidates.retain(|candidate| match affected.get(&candidate.version) {
+        Some(ids) => {
+            let ids = ids.join(", ");
+            warn!(crate = %name, candidate = %candidate.version, advisories = %ids, "candidate has known vulnerabilities; skipping");
+            *refusal = Some(format!("{name}@{} is affected by {ids}", candidate.version));
+            false
+        }
+        None => true,
+    });
+    candidates
+}
+
+/// Crates whose number of incompatible series grew between `before` and `after`.
+fn introduced_duplicates<'a>(
+    before: &[DuplicateMajor],
+    after: &'a [DuplicateMajor],
+) -> Vec<&'a DuplicateMajor> {
+    after
+        .iter()
+        .filter(|duplicate| {
+            before
+                .iter()
+                .find(|previous| previous.name == duplicate.name)
+                .is_none_or(|previous| previous.versions.len() < duplicate.versions.len())
+        })
+        .collect()
+}
+
+fn describe_duplicates(duplicates: &[&DuplicateMajor]) -> String {
+    duplicates
+        .iter()
+        .map(|duplicate| {
+            format!(
+                "{} ({}; ~{} extra crates to build)",
+                duplicate.name,
+                duplicate.versions.join(", "),
+                duplicate.extra_crates
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Re-check versions this tool pinned earlier. A pin that has since been
+/// yanked upstream is escalated with a suggested replacement. Never fails the
+/// run; problems reading the journal or the registry are only logged.
+async fn check_journal_pins(session: &Session<'_>, workspace_root: &Path) {
+    let locked = match lockfile::read(&workspace_root.join("Cargo.lock")) {
+        Ok(packages) => packages
+            .into_iter()
+            .map(|pkg| (pkg.name, pkg.version))
+            .collect(),
+        Err(err) => {
+            debug!(error = %err, "skipping pin journal check");
+            return;
+        }
+    };
+    let pins = match session.journal.active_pins(workspace_root, &locked) {
+        Ok(pins) => pins,
+        Err(err) => {
+            warn!(error = %err, "failed to read the pin journal");
+            return;
+        }
+    };
+
+    let now = session.config.now();
+    for pin in pins {
+        // Skip the cache: a yank is exactly what a cached entry would hide.
+        let list = match session.provider.list_versions(&pin.name).await {
+            Ok(list) => list,
+            Err(err) => {
+                debug!(crate = %pin.name, error = %err, "could not re-check pinned version");
+                continue;
+            }
+        };
+        if let Err(err) = session.cache.put(&format!("{}/_list", pin.name), &list) {
+            debug!(error = %err, "failed to refresh cached version list");
+        }
+        if !list.iter().any(|meta| meta.num == pin.to && meta.yanked) {
+            continue;
+        }
+
+        let (_, cutoff, _) =
+            session.window(&pin.name, &pin.to, session.config.cooldown_minutes, now);
+        let compatible = VersionReq::parse(&format!("^{}", pin.to)).ok();
+        let replacement = filter_candidates(list, cutoff)
+            .into_iter()
+            .find(|candidate| {
+                candidate.version != pin.to
+                    && compatible.as_ref().is_none_or(|req| {
+                        satisfies_requirements(&candidate.version, std::slice::from_ref(req))
+                    })
+            });
+
+        warn!(crate = %pin.name, version = %pin.to, pinned_at = %pin.pinned_at, "a version pinned by cargo-cooldown has been yanked upstream");
+        eprintln!(
+            "\nWARNING: {}@{} was pinned by cargo-cooldown on {} and has since been yanked upstream.",
+            pin.name,
+            pin.to,
+            pin.pinned_at.format("%Y-%m-%d")
+        );
+        match replacement {
+            Some(candidate) => eprintln!(
+                "         Re-pin with: cargo update -p {} --precise {}\n",
+                pin_spec(&pin.name, &pin.to),
+                without_build_metadata(&candidate.version)
+            ),
+            None => eprintln!(
+                "         No compatible release outside the cooldown window is available yet.\n"
+            ),
+        }
+    }
+}
+
+/// Stop the pinning flow once it has made `max_iterations` passes over the
+/// graph or run for `max_duration_minutes`, naming the releases it leaves
+/// inside their windows.
+fn check_budget<'f>(
+    session: &Session<'_>,
+    iterations: usize,
+    remaining: impl IntoIterator<Item = &'f FreshCrate>,
+) -> Result<()> {
+    let config = session.config;
+    let reason = if let Some(max) = config.max_iterations.filter(|&max| iterations > max) {
+        format!("{max} passes over the graph (max_iterations)")
+    } else if let Some(minutes) = config.max_duration_minutes.filter(|&minutes| {
+        session.started.elapsed() > Duration::from_secs(minutes.saturating_mul(60))
+    }) {
+        format!("{minutes} minutes (max_duration_minutes)")
+    } else {
+        return Ok(());
+    };
+    let mut remaining: Vec<String> = remaining
+        .into_iter()
+        .map(|fresh| format!("{}@{}", fresh.name, fresh.current_version))
+        .collect();
+    remaining.sort();
+    remaining.dedup();
+    bail_with!(
+        ErrorCode::BudgetExhausted,
+        "pinning stopped after {reason} with {} releases still to move: {}",
+        remaining.len(),
+        remaining.join(", ")
+    );
+}
+
+/// What an `--interactive` prompt settled for one release.
+enum Remedy {
+    /// Try these candidates, the chosen one alone.
+    Pin(Vec<Candidate>),
+    /// Keep the release for the rest of the run.
+    Accepted,
+    /// Slept until the release cooled down.
+    Waited,
+}
+
+async fn ask_remedy(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    candidates: Vec<Candidate>,
+) -> Result<Remedy> {
+    let config = session.config;
+    // Waiting cannot help when the run is evaluated at a fixed `--as-of`.
+    let cools_down_at = fresh
+        .published_at
+        .filter(|_| config.as_of.is_none())
+        .map(|published_at| {
+            session
+                .calendar
+                .window_end(published_at, fresh.minimum_minutes)
+        });
+    let prompt = Prompt {
+        name: &fresh.name,
+        version: &fresh.current_version,
+        minimum_minutes: fresh.minimum_minutes,
+        published_at: fresh.published_at,
+        cools_down_at,
+        candidates: &candidates,
+    };
+    match interactive::ask(&prompt, config.now())? {
+        Decision::Downgrade(index) => Ok(Remedy::Pin(vec![candidates[index].clone()])),
+        Decision::Allow { reason } => {
+            session
+                .accepted
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert((fresh.name.clone(), fresh.current_version.clone()));
+            if let Some(reason) = reason {
+                let path = append_exact(
+                    config,
+                    &fresh.name,
+                    &fresh.current_version,
+                    &reason,
+                    cools_down_at,
+                )?;
+                info!(crate = %fresh.name, version = %fresh.current_version, path = %path.display(), "recorded the release in the allowlist");
+            }
+            Ok(Remedy::Accepted)
+        }
+        Decision::Wait => {
+            if let Some(until) = cools_down_at {
+                info!(crate = %fresh.name, until = %until, "waiting for the release to cool down");
+                tokio::time::sleep((until - Utc::now()).to_std().unwrap_or_default()).await;
+            }
+            Ok(Remedy::Waited)
+        }
+        Decision::Abort => bail_with!(
+            ErrorCode::Aborted,
+            "aborted at {}@{}; the lockfile is restored",
+            fresh.name,
+            fresh.current_version
+        ),
     }
+}
 
+/// Refuse to rewrite a lockfile with changes that are not committed yet.
+fn ensure_clean(lockfile: &Path) -> Result<()> {
+    if lockfile::has_uncommitted_changes(lockfile) {
+        bail_with!(
+            ErrorCode::DirtyLockfile,
+            "{} has uncommitted changes and pinning would rewrite it; commit or stash them first, or pass --allow-dirty",
+            lockfile.display()
+        );
+    }
     Ok(())
 }
 
-fn ensure_lockfile() -> Result<()> {
-    if Path::new("Cargo.lock").exists() {
+/// Record a pin just applied to `lockfile`, saving it for a resumed run when
+/// plans are kept.
+fn record_pin(
+    session: &Session<'_>,
+    saved: Option<&mut PlanFile>,
+    lockfile: &Path,
+    record: PinRecord,
+) {
+    if let Some(saved) = saved
+        && let Err(err) = saved.record(record.clone(), lockfile)
+    {
+        warn!(error = %err, "failed to save the pin for resuming");
+    }
+    session.record_pin(record);
+}
+
+fn ensure_lockfile(manifest: &Manifest, cargo_config: &[String]) -> Result<()> {
+    if lockfile_path(manifest)?.exists() {
         return Ok(());
     }
-    let status = Command::new("cargo").args(["generate-lockfile"]).status()?;
+    let mut command = Command::new("cargo");
+    command
+        .arg("generate-lockfile")
+        .args(config_args(cargo_config));
+    if let Some(path) = &manifest.manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+    let status = command.status()?;
     if !status.success() {
         bail!("failed to generate Cargo.lock via `cargo generate-lockfile`");
     }
@@ -359,13 +2722,64 @@ struct FreshCrate {
     name: String,
     current_version: String,
     minimum_minutes: u64,
+    cutoff: DateTime<Utc>,
+    /// Publication time, when known; crates queued only because they block
+    /// another downgrade are enqueued without it.
+    published_at: Option<DateTime<Utc>>,
+}
+
+impl FreshCrate {
+    /// Why pinning moved this crate, for the journal and commit messages.
+    fn pin_reason(&self) -> String {
+        match self.published_at {
+            Some(_) => format!("inside its {}-minute cooldown window", self.minimum_minutes),
+            None => "moved so that fresher crates could be pinned".to_string(),
+        }
+    }
 }
 
 struct CrateState {
     name: String,
     current_version: String,
     minimum_minutes: u64,
-    exact_allowed: bool,
+    cutoff: DateTime<Utc>,
+    exempt: bool,
+}
+
+/// The fresh releases of an analysis and every requirement on them, for
+/// drawing why the run could not pin them.
+fn blocking_graph(
+    fresh_entries: &[FreshCrate],
+    crate_states: &HashMap<PackageId, CrateState>,
+    requirement_origins: &HashMap<PackageId, Vec<RequirementOrigin>>,
+) -> BlockingGraph {
+    let release = |id: &PackageId, name: &str| match crate_states.get(id) {
+        Some(state) => format!("{}@{}", state.name, state.current_version),
+        None => name.to_string(),
+    };
+    let mut graph = BlockingGraph::default();
+    for fresh in fresh_entries {
+        let node = format!("{}@{}", fresh.name, fresh.current_version);
+        graph.add_fresh(
+            node.clone(),
+            format!(
+                "fresh, needs {}",
+                format_age(chrono::Duration::minutes(fresh.minimum_minutes as i64))
+            ),
+        );
+        for origin in requirement_origins
+            .get(&fresh.package_id)
+            .into_iter()
+            .flatten()
+        {
+            graph.add_requirement(
+                release(&origin.parent_id, &origin.parent_name),
+                node.clone(),
+                origin.requirement.to_string(),
+            );
+        }
+    }
+    graph
 }
 
 #[derive(Clone, Debug)]
@@ -376,7 +2790,7 @@ struct RequirementOrigin {
 }
 
 async fn fetch_version_meta(
-    client: &RegistryClient,
+    source: &dyn VersionMetadataProvider,
     cache: &Cache,
     name: &str,
     version: &str,
@@ -385,13 +2799,13 @@ async fn fetch_version_meta(
     if let Some(meta) = cache.get::<VersionMeta>(&key)? {
         return Ok(meta);
     }
-    let meta = client.fetch_version(name, version).await?;
+    let meta = source.fetch_version(name, version).await?;
     cache.put(&key, &meta)?;
     Ok(meta)
 }
 
 async fn fetch_version_list(
-    client: &RegistryClient,
+    source: &dyn VersionMetadataProvider,
     cache: &Cache,
     name: &str,
 ) -> Result<Vec<VersionMeta>> {
@@ -399,11 +2813,388 @@ async fn fetch_version_list(
     if let Some(list) = cache.get::<Vec<VersionMeta>>(&key)? {
         return Ok(list);
     }
-    let list = client.list_versions(name).await?;
+    let list = source.list_versions(name).await?;
     cache.put(&key, &list)?;
     Ok(list)
 }
 
+/// Maximum number of cooled-down candidates gathered from the sparse index
+/// before the walk towards older releases stops.
+const INDEX_CANDIDATE_WINDOW: usize = 5;
+
+/// Cooled-down releases older than the current one that satisfy every
+/// observed requirement, in the order the crate's pin strategy prefers.
+async fn discover_candidates(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    requirements: &[VersionReq],
+) -> Result<Vec<Candidate>> {
+    let strategy = session.pin_strategy(&fresh.name);
+    let candidates = gather_candidates(session, fresh, requirements, strategy).await?;
+    Ok(order_candidates(
+        strategy,
+        &fresh.current_version,
+        candidates,
+    ))
+}
+
+async fn gather_candidates(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    requirements: &[VersionReq],
+    strategy: PinStrategy,
+) -> Result<Vec<Candidate>> {
+    // The index walk stops after the highest few candidates, which never
+    // include the oldest compatible one.
+    if let Some(index) = session
+        .index
+        .as_ref()
+        .filter(|_| strategy != PinStrategy::OldestCompatible)
+    {
+        match candidates_from_index(session, index, fresh, requirements).await {
+            Ok(candidates) => return Ok(candidates),
+            Err(err) => {
+                debug!(crate = %fresh.name, error = %err, "sparse index lookup failed; falling back to the full version list");
+            }
+        }
+    }
+
+    let mut list = session.version_list(&fresh.name).await?;
+    let now = session.config.now();
+    list.retain(|meta| !session.lacks_downloads(&fresh.name, meta, now));
+    let mut candidates = filter_candidates(list, fresh.cutoff);
+    candidates.retain(|candidate| satisfies_requirements(&candidate.version, requirements));
+    if let Ok(current_semver) = Version::parse(&fresh.current_version) {
+        candidates.retain(|candidate| {
+            Version::parse(&candidate.version)
+                .map(|version| {
+                    version < current_semver && prerelease_allowed(&current_semver, &version)
+                })
+                .unwrap_or(true)
+        });
+    }
+    Ok(candidates)
+}
+
+/// Walk the sparse index from the current release downwards and only look up
+/// publication times (when the index lacks `pubtime`) for the releases just
+/// below it, which is where the cooldown cutoff usually falls.
+async fn candidates_from_index(
+    session: &Session<'_>,
+    index: &SparseIndexClient,
+    fresh: &FreshCrate,
+    requirements: &[VersionReq],
+) -> Result<Vec<Candidate>> {
+    let entries = fetch_index_entries(index, &session.cache, &fresh.name).await?;
+    let mut candidates = Vec::new();
+    let now = session.config.now();
+    for entry in index_candidates(entries, &fresh.current_version, requirements) {
+        let created_at = match entry.pubtime {
+            Some(pubtime) => pubtime,
+            None => {
+                fetch_version_meta(&session.provider, &session.cache, &fresh.name, &entry.vers)
+                    .await?
+                    .created_at
+            }
+        };
+        if created_at > fresh.cutoff {
+            continue;
+        }
+        // The index carries no download counts; ask the provider for recent
+        // releases when a minimum is configured.
+        if session.config.min_downloads.is_some()
+            && created_at > now - chrono::Duration::days(MIN_DOWNLOADS_HORIZON_DAYS)
+        {
+            let meta =
+                fetch_version_meta(&session.provider, &session.cache, &fresh.name, &entry.vers)
+                    .await?;
+            if session.lacks_downloads(&fresh.name, &meta, now) {
+                continue;
+            }
+        }
+        candidates.push(Candidate {
+            version: entry.vers,
+            created_at,
+        });
+        if candidates.len() >= INDEX_CANDIDATE_WINDOW {
+            break;
+        }
+    }
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.created_at));
+    Ok(candidates)
+}
+
+/// Choose a cooled-down release for every fresh crate at once, from the
+/// candidates the pinning loop would try and the dependencies the sparse
+/// index lists for them. `None` when some crate cannot be planned this way
+/// or no consistent choice exists; the loop then pins crate by crate.
+#[allow(clippy::too_many_arguments)]
+async fn plan_pins(
+    session: &Session<'_>,
+    fresh_entries: &[FreshCrate],
+    crate_states: &HashMap<PackageId, CrateState>,
+    requirement_origins: &HashMap<PackageId, Vec<RequirementOrigin>>,
+    version_requirements: &HashMap<PackageId, Vec<VersionReq>>,
+    lockfile: &Path,
+    workspace_msrv: Option<&Version>,
+    licenses: &HashMap<PackageId, String>,
+) -> Option<Vec<LockEdit>> {
+    let config = session.config;
+    let index = session.sparse.as_ref()?;
+    let locked = lockfile::read(lockfile).ok()?;
+    let mut crates = Vec::new();
+    let mut checksums = Vec::new();
+    for fresh in fresh_entries {
+        if session.pin_strategy(&fresh.name) == PinStrategy::Fail {
+            return None;
+        }
+        locked.iter().find(|pkg| {
+            pkg.name == fresh.name
+                && pkg.version == fresh.current_version
+                && pkg
+                    .source
+                    .as_deref()
+                    .is_some_and(|source| config.is_sparse_indexed(source))
+        })?;
+        let requirements = version_requirements
+            .get(&fresh.package_id)
+            .cloned()
+            .unwrap_or_default();
+        let mut candidates = discover_candidates(session, fresh, &requirements)
+            .await
+            .ok()?;
+        if let Some(max_steps) = config.max_downgrade {
+            candidates.retain(|candidate| {
+                within_downgrade(&fresh.current_version, &candidate.version, max_steps)
+            });
+        }
+        let candidates = screen_candidates(
+            session,
+            fresh,
+            workspace_msrv,
+            licenses.get(&fresh.package_id).map(String::as_str),
+            candidates,
+            &mut None,
+        )
+        .await;
+        let entries = fetch_index_entries(index, &session.cache, &fresh.name)
+            .await
+            .ok()?;
+        let mut releases = Vec::new();
+        let mut release_checksums = Vec::new();
+        for candidate in candidates {
+            if candidate.version == fresh.current_version {
+                continue;
+            }
+            let Some(entry) = entries.iter().find(|entry| entry.vers == candidate.version) else {
+                continue;
+            };
+            let Ok(version) = Version::parse(&candidate.version) else {
+                continue;
+            };
+            let deps = entry
+                .deps
+                .iter()
+                .filter(|dep| dep.kind.as_deref() != Some("dev") && !dep.optional)
+                .filter_map(|dep| {
+                    Some((
+                        dep.crate_name().to_string(),
+                        VersionReq::parse(&dep.req).ok()?,
+                    ))
+                })
+                .collect();
+            releases.push(PlanCandidate { version, deps });
+            release_checksums.push(entry.cksum.clone());
+        }
+        crates.push(PlanCrate {
+            id: fresh.package_id.clone(),
+            name: fresh.name.clone(),
+            dependents: requirement_origins
+                .get(&fresh.package_id)
+                .into_iter()
+                .flatten()
+                .map(|origin| (origin.parent_id.clone(), origin.requirement.clone()))
+                .collect(),
+            candidates: releases,
+        });
+        checksums.push(release_checksums);
+    }
+    let locked_versions: Vec<(PackageId, String, Version)> = crate_states
+        .iter()
+        .filter_map(|(id, state)| {
+            let version = Version::parse(&state.current_version).ok()?;
+            Some((id.clone(), state.name.clone(), version))
+        })
+        .collect();
+    let Some(chosen) = plan::solve(&crates, &locked_versions) else {
+        debug!(
+            crates = crates.len(),
+            "no consistent plan for the fresh crates"
+        );
+        return None;
+    };
+    info!(crates = crates.len(), "planned pins for every fresh crate");
+    Some(
+        fresh_entries
+            .iter()
+            .zip(&crates)
+            .zip(&checksums)
+            .zip(chosen)
+            .map(|(((fresh, planned), checksums), index)| LockEdit {
+                name: fresh.name.clone(),
+                from: fresh.current_version.clone(),
+                to: planned.candidates[index].version.to_string(),
+                checksum: checksums[index].clone(),
+            })
+            .collect(),
+    )
+}
+
+/// Pin `fresh` to `candidate` with the configured backend. The lockfile
+/// backend needs the release's checksum from the sparse index; anything it
+/// cannot edit, or that Cargo does not accept as edited, goes through
+/// `cargo update` instead.
+async fn pin_candidate(
+    session: &Session<'_>,
+    manifest_path: Option<&Path>,
+    lockfile: &Path,
+    locked: Option<&[LockedPackage]>,
+    fresh: &FreshCrate,
+    candidate: &str,
+) -> Result<PinOutcome> {
+    let config = session.config;
+    if config.pin_backend == PinBackend::Lockfile
+        && let Some(index) = &session.sparse
+        && let Some(entry) = locked.into_iter().flatten().find(|pkg| {
+            pkg.name == fresh.name
+                && pkg.version == fresh.current_version
+                && pkg
+                    .source
+                    .as_deref()
+                    .is_some_and(|source| config.is_sparse_indexed(source))
+        })
+    {
+        let checksum = match fetch_index_entries(index, &session.cache, &fresh.name).await {
+            Ok(entries) => entries
+                .into_iter()
+                .find(|release| release.vers == candidate)
+                .and_then(|release| release.cksum),
+            Err(err) => {
+                debug!(crate = %fresh.name, error = %err, "index lookup failed; pinning through cargo update");
+                None
+            }
+        };
+        if entry.checksum.is_none() || checksum.is_some() {
+            let edit = LockEdit {
+                name: fresh.name.clone(),
+                from: fresh.current_version.clone(),
+                to: candidate.to_string(),
+                checksum,
+            };
+            match try_pin_in_lockfile(manifest_path, &config.cargo_config, lockfile, &[edit]) {
+                Ok(PinOutcome::Applied) => return Ok(PinOutcome::Applied),
+                Ok(PinOutcome::Rejected { .. }) => {
+                    debug!(crate = %fresh.name, candidate = %candidate, "edited lockfile does not resolve as is; pinning through cargo update");
+                }
+                Err(err) => {
+                    debug!(crate = %fresh.name, candidate = %candidate, error = %err, "lockfile edit failed; pinning through cargo update");
+                }
+            }
+        }
+    }
+    try_pin_precise(
+        manifest_path,
+        &config.cargo_config,
+        &fresh.name,
+        &fresh.current_version,
+        candidate,
+    )
+}
+
+/// Locked packages standing in the way of pinning `fresh` to `candidate`:
+/// dependents whose requirement excludes it, and releases its own
+/// dependencies would have to move off. Worked out from the graph and the
+/// sparse index, so it does not depend on the wording of Cargo's errors.
+async fn pin_blockers(
+    session: &Session<'_>,
+    fresh: &FreshCrate,
+    candidate: &str,
+    crate_states: &HashMap<PackageId, CrateState>,
+    origins: Option<&Vec<RequirementOrigin>>,
+) -> Vec<PackageId> {
+    let Ok(candidate) = Version::parse(candidate) else {
+        return Vec::new();
+    };
+    let dependents: Vec<(PackageId, VersionReq)> = origins
+        .into_iter()
+        .flatten()
+        .map(|origin| (origin.parent_id.clone(), origin.requirement.clone()))
+        .collect();
+    let mut candidate_deps = Vec::new();
+    if let Some(index) = &session.sparse {
+        match fetch_index_entries(index, &session.cache, &fresh.name).await {
+            Ok(entries) => {
+                let declared = entries
+                    .iter()
+                    .find(|entry| Version::parse(&entry.vers).ok().as_ref() == Some(&candidate));
+                for dep in declared.into_iter().flat_map(|entry| &entry.deps) {
+                    if dep.kind.as_deref() == Some("dev") {
+                        continue;
+                    }
+                    if let Ok(req) = VersionReq::parse(&dep.req) {
+                        candidate_deps.push((dep.crate_name().to_string(), req));
+                    }
+                }
+            }
+            Err(err) => {
+                debug!(crate = %fresh.name, error = %err, "index lookup failed; only dependents are checked for blockers");
+            }
+        }
+    }
+    let locked: Vec<(PackageId, String, Version)> = crate_states
+        .iter()
+        .filter_map(|(id, state)| {
+            let version = Version::parse(&state.current_version).ok()?;
+            Some((id.clone(), state.name.clone(), version))
+        })
+        .collect();
+    blockers(&candidate, &dependents, &candidate_deps, &locked)
+}
+
+async fn fetch_index_entries(
+    index: &SparseIndexClient,
+    cache: &Cache,
+    name: &str,
+) -> Result<Vec<IndexEntry>> {
+    let key = format!("{name}/_index");
+    if let Some(entries) = cache.get::<Vec<IndexEntry>>(&key)? {
+        return Ok(entries);
+    }
+    let entries = index.entries(name).await?;
+    cache.put(&key, &entries)?;
+    Ok(entries)
+}
+
+/// The lowest `rust-version` declared by a workspace member: the oldest
+/// toolchain the workspace promises to build with.
+fn workspace_msrv(metadata: &Metadata) -> Option<Version> {
+    metadata
+        .workspace_packages()
+        .into_iter()
+        .filter_map(|pkg| pkg.rust_version.clone())
+        .min()
+}
+
+/// Proc macros, build scripts, and native `links` crates execute code on the
+/// machine that compiles them.
+fn runs_at_build_time(pkg: &cargo_metadata::Package) -> bool {
+    pkg.links.is_some()
+        || pkg
+            .targets
+            .iter()
+            .any(|target| target.is_proc_macro() || target.is_custom_build())
+}
+
 fn is_exact_requirement(req: &semver::VersionReq) -> bool {
     if req.comparators.len() != 1 {
         return false;
@@ -427,50 +3218,286 @@ fn find_manifest_dependency<'a>(
     })
 }
 
-fn parse_blockers(stdout: &str, stderr: &str) -> Vec<Blocker> {
-    let mut blockers = Vec::new();
-    for line in stdout.lines().chain(stderr.lines()) {
-        let trimmed = line.trim();
-        if let Some(rest) = trimmed.strip_prefix("required by package `")
-            && let Some(end) = rest.find('`')
-        {
-            let inner = &rest[..end];
-            if let Some((name, version)) = inner.rsplit_once(' ') {
-                let version = version.trim_start_matches('v').to_string();
-                if !blockers.iter().any(|existing: &Blocker| {
-                    existing.name == name && existing.version.as_deref() == Some(&version)
-                }) {
-                    blockers.push(Blocker {
-                        name: name.to_string(),
-                        version: Some(version),
-                    });
-                }
-            } else if !blockers
-                .iter()
-                .any(|existing: &Blocker| existing.name == inner)
-            {
-                blockers.push(Blocker {
-                    name: inner.to_string(),
-                    version: None,
-                });
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MetadataSourceKind;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn bans_fail_the_run_without_a_cooldown_window() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[[package.metadata.cooldown.deny]]
+crate = "left-pad"
+reason = "unmaintained"
+"#,
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        fs::write(
+            dir.path().join("Cargo.lock"),
+            r#"version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["left-pad"]
+
+[[package]]
+name = "left-pad"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+        )
+        .unwrap();
+        let mut config = Config::from_env();
+        config.cooldown_minutes = 0;
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        config.cache_dir = Some(dir.path().join("cache"));
+        let mut manifest = Manifest::default();
+        manifest.manifest_path = Some(dir.path().join("Cargo.toml"));
+
+        assert!(!config.has_policy());
+        assert!(has_bans(&config, &manifest));
+
+        let lockfile = lockfile_path(&manifest).unwrap();
+        let mut session = Session::new(&config).await.unwrap();
+        session.load_workspace_policy(&lockfile).unwrap();
+        let packages = lockfile::read(&lockfile).unwrap();
+        let err = analyze_lockfile(&session, &packages).await.unwrap_err();
+        assert_eq!(codes::classify(&err), Some(ErrorCode::BannedDependency));
+        assert!(format!("{err:#}").contains("left-pad@1.0.0 (unmaintained)"));
+    }
+
+    #[tokio::test]
+    async fn stale_index_entries_are_refetched_before_reporting_a_mismatch() {
+        let dir = tempdir().unwrap();
+        let mut server = mockito::Server::new_async().await;
+        let index = server
+            .mock("GET", "/le/ft/left-pad")
+            .with_body(concat!(
+                r#"{"name":"left-pad","vers":"1.0.0","deps":[],"cksum":"aa11","features":{}}"#,
+                "\n",
+                r#"{"name":"left-pad","vers":"1.1.0","deps":[],"cksum":"bb22","features":{}}"#,
+            ))
+            .expect(1)
+            .create_async()
+            .await;
+        let mut config = Config::from_env();
+        config.offline = false;
+        config.verify_checksums = true;
+        config.sparse_index_url = format!("{}/", server.url());
+        config.cache_dir = Some(dir.path().join("cache"));
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        let session = Session::new(&config).await.unwrap();
+        // Cached before 1.1.0 was published.
+        let cached: Vec<IndexEntry> =
+            serde_json::from_str(r#"[{"vers":"1.0.0","cksum":"aa11"}]"#).unwrap();
+        session.cache.put("left-pad/_index", &cached).unwrap();
+        let locked = |checksum: &str| LockedPackage {
+            name: "left-pad".into(),
+            version: "1.1.0".into(),
+            source: Some(format!("sparse+{}/", server.url())),
+            checksum: Some(checksum.into()),
+        };
+        let lockfile = dir.path().join("Cargo.lock");
+
+        check_checksums(&session, &lockfile, &[locked("bb22")])
+            .await
+            .unwrap();
+        index.assert_async().await;
+
+        // The refreshed cache still convicts a tampered checksum.
+        let err = check_checksums(&session, &lockfile, &[locked("ff00")])
+            .await
+            .unwrap_err();
+        assert_eq!(codes::classify(&err), Some(ErrorCode::ChecksumMismatch));
+    }
+
+    #[tokio::test]
+    async fn failed_owner_lookups_fail_unless_offline_ok() {
+        let dir = tempdir().unwrap();
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/crates/left-pad")
+            .with_status(404)
+            .create_async()
+            .await;
+        let mut config = Config::from_env();
+        config.owner_change = OwnerChangePolicy::Extend;
+        config.registry_api = format!("{}/", server.url());
+        config.cache_dir = Some(dir.path().join("cache"));
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        config.offline_ok = false;
+        let now = Utc::now();
+
+        let session = Session::new(&config).await.unwrap();
+        let err = session
+            .owner_check("left-pad", "1.0.0", 60, now)
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("failed to check the owners of left-pad"));
+
+        config.offline_ok = true;
+        let session = Session::new(&config).await.unwrap();
+        assert_eq!(
+            session
+                .owner_check("left-pad", "1.0.0", 60, now)
+                .await
+                .unwrap(),
+            60
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_yanked_lookups_fail_unless_offline_ok() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::from_env();
+        config.yanked = YankedPolicy::Warn;
+        // An empty cache answers nothing.
+        config.metadata_sources = vec![MetadataSourceKind::Cache];
+        config.cache_dir = Some(dir.path().join("cache"));
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        config.offline_ok = false;
+        let packages = [LockedPackage {
+            name: "left-pad".into(),
+            version: "1.0.0".into(),
+            source: Some("registry+https://github.com/rust-lang/crates.io-index".into()),
+            checksum: None,
+        }];
+        let lockfile = dir.path().join("Cargo.lock");
+
+        let session = Session::new(&config).await.unwrap();
+        let err = check_yanked(&session, &lockfile, &packages)
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("failed to check whether left-pad@1.0.0 was yanked"));
+
+        config.offline_ok = true;
+        let session = Session::new(&config).await.unwrap();
+        check_yanked(&session, &lockfile, &packages).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn the_soft_window_warns_between_the_cooldown_and_warn_minutes() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::from_env();
+        config.cooldown_minutes = 60;
+        config.warn_minutes = Some(600);
+        config.cache_dir = Some(dir.path().join("cache"));
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        let now = Utc::now();
+        let ago = |minutes: i64| now - chrono::Duration::minutes(minutes);
+
+        let session = Session::new(&config).await.unwrap();
+        // Inside the cooldown: held back, not warned about.
+        let (minimum, cutoff, exempt) = session.window("serde", "1.0.0", 60, now);
+        assert_eq!(minimum, 60);
+        assert!(!exempt);
+        assert!(ago(30) > cutoff);
+        // Inside only the soft window: warned about with the soft window.
+        assert!(ago(120) <= cutoff);
+        session.note_soft("serde", "1.0.1", ago(120), minimum, now);
+        // Past both windows: nothing to say.
+        session.note_soft("serde", "1.0.2", ago(900), minimum, now);
+        let soft = session.soft_violations();
+        assert_eq!(soft.len(), 1);
+        assert_eq!(soft[0].version, "1.0.1");
+        assert_eq!(soft[0].minimum_minutes, 600);
+
+        // A soft window no longer than the cooldown adds nothing.
+        let with_warn = |warn_minutes| {
+            let mut config = config.clone();
+            config.warn_minutes = warn_minutes;
+            config
+        };
+        for (warn_minutes, published) in [(60, 61), (30, 45)] {
+            let config = with_warn(Some(warn_minutes));
+            let session = Session::new(&config).await.unwrap();
+            session.note_soft("serde", "1.0.1", ago(published), 60, now);
+            assert!(session.soft_violations().is_empty());
         }
+        // Without a cooldown, only a soft window keeps releases checked.
+        let config = with_warn(Some(30));
+        let session = Session::new(&config).await.unwrap();
+        assert!(!session.window("serde", "1.0.1", 0, now).2);
+        let config = with_warn(None);
+        let session = Session::new(&config).await.unwrap();
+        let (_, _, exempt) = session.window("serde", "1.0.1", 0, now);
+        assert!(exempt);
     }
-    blockers
-}
 
-#[derive(Debug)]
-struct Blocker {
-    name: String,
-    version: Option<String>,
-}
+    #[tokio::test]
+    async fn patch_grace_covers_stable_patches_of_locked_series() {
+        let dir = tempdir().unwrap();
+        let lockfile = dir.path().join("Cargo.lock");
+        let source = "source = \"registry+https://github.com/rust-lang/crates.io-index\"";
+        fs::write(
+            &lockfile,
+            format!(
+                "version = 4\n\n\
+                 [[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n{source}\n\n\
+                 [[package]]\nname = \"tokio\"\nversion = \"0.3.1\"\n{source}\n\n\
+                 [[package]]\nname = \"zerocopy\"\nversion = \"0.0.3\"\n{source}\n"
+            ),
+        )
+        .unwrap();
+        let mut config = Config::from_env();
+        config.patch_grace = true;
+        config.cache_dir = Some(dir.path().join("cache"));
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        let mut session = Session::new(&config).await.unwrap();
+        load_previous(&mut session, &lockfile);
 
-fn satisfies_requirements(version: &str, requirements: &[VersionReq]) -> bool {
-    if requirements.is_empty() {
-        return true;
+        assert!(session.has_grace("serde", "1.0.201"));
+        assert!(!session.has_grace("serde", "1.1.0"));
+        assert!(session.has_grace("tokio", "0.3.2"));
+        assert!(!session.has_grace("tokio", "0.4.0"));
+        // Every 0.0.x release is a series of its own.
+        assert!(!session.has_grace("zerocopy", "0.0.4"));
+        assert!(!session.has_grace("serde", "1.0.201-rc.1"));
+        // Already locked: nothing moved, so there is nothing to exempt.
+        assert!(!session.has_grace("serde", "1.0.200"));
+        assert!(!session.has_grace("unlocked", "1.0.1"));
+
+        config.patch_grace = false;
+        let mut session = Session::new(&config).await.unwrap();
+        load_previous(&mut session, &lockfile);
+        assert!(!session.has_grace("serde", "1.0.201"));
     }
-    match Version::parse(version) {
-        Ok(parsed) => requirements.iter().all(|req| req.matches(&parsed)),
-        Err(_) => false,
+
+    #[test]
+    fn cargo_deny_bans_count_without_a_cooldown_window() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        let mut config = Config::from_env();
+        config.cooldown_minutes = 0;
+        config.allowlist_path = Some(dir.path().join("cooldown-allowlist.toml"));
+        let mut manifest = Manifest::default();
+        manifest.manifest_path = Some(dir.path().join("Cargo.toml"));
+        assert!(!has_bans(&config, &manifest));
+
+        fs::write(
+            dir.path().join("deny.toml"),
+            "[bans]\ndeny = [{ name = \"left-pad\" }]\n",
+        )
+        .unwrap();
+        assert!(has_bans(&config, &manifest));
+        config.cargo_deny = false;
+        assert!(!has_bans(&config, &manifest));
     }
 }
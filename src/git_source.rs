@@ -0,0 +1,120 @@
+//! Dating the commits git dependencies are locked to, so they can be held to
+//! a cooldown like registry releases are.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+
+use crate::metadata::cargo_home;
+
+/// The repository URL and commit of a `git+URL?query#COMMIT` lockfile source.
+pub fn locked_commit(source: &str) -> Option<(&str, &str)> {
+    let (repository, commit) = source.strip_prefix("git+")?.split_once('#')?;
+    let repository = repository
+        .split_once('?')
+        .map_or(repository, |(url, _)| url);
+    (!commit.is_empty()).then_some((repository, commit))
+}
+
+/// When `commit` was committed. Cargo's own clones of the repository are
+/// asked first; otherwise the commit alone is fetched from `repository`.
+pub fn commit_date(repository: &str, commit: &str) -> Result<DateTime<Utc>> {
+    for db in cargo_git_dbs() {
+        if let Ok(date) = committed_at(&db, commit) {
+            return Ok(date);
+        }
+    }
+
+    let scratch = env::temp_dir().join(format!(
+        "cargo-cooldown-git-{}-{commit}",
+        std::process::id()
+    ));
+    let fetched = fetch_commit(&scratch, repository, commit)
+        .and_then(|()| committed_at(&scratch, "FETCH_HEAD"));
+    let _ = fs::remove_dir_all(&scratch);
+    fetched.with_context(|| format!("failed to date commit {commit} of {repository}"))
+}
+
+/// Shallow-fetch just `commit`, without its files, into a bare repository
+/// at `dir`.
+fn fetch_commit(dir: &Path, repository: &str, commit: &str) -> Result<()> {
+    git(None, &["init", "--quiet", "--bare", &dir.to_string_lossy()])?;
+    git(
+        Some(dir),
+        &[
+            "fetch",
+            "--quiet",
+            "--depth=1",
+            "--filter=tree:0",
+            repository,
+            commit,
+        ],
+    )?;
+    Ok(())
+}
+
+fn committed_at(git_dir: &Path, commit: &str) -> Result<DateTime<Utc>> {
+    let output = git(
+        Some(git_dir),
+        &["show", "--no-patch", "--format=%cI", commit],
+    )?;
+    Ok(DateTime::parse_from_rfc3339(output.trim())
+        .context("git printed an invalid commit date")?
+        .with_timezone(&Utc))
+}
+
+fn git(git_dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = git_dir {
+        command.arg("--git-dir").arg(dir);
+    }
+    let output = command.args(args).output().context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The bare repositories Cargo keeps for the git dependencies it fetched.
+fn cargo_git_dbs() -> Vec<PathBuf> {
+    let Some(home) = cargo_home() else {
+        return Vec::new();
+    };
+    fs::read_dir(home.join("git").join("db"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_locked_git_sources() {
+        assert_eq!(
+            locked_commit("git+https://github.com/rust-lang/cargo?branch=master#0123abc"),
+            Some(("https://github.com/rust-lang/cargo", "0123abc"))
+        );
+        assert_eq!(
+            locked_commit("git+ssh://git@example.com/app.git#feedbeef"),
+            Some(("ssh://git@example.com/app.git", "feedbeef"))
+        );
+        assert_eq!(
+            locked_commit("registry+https://github.com/rust-lang/crates.io-index"),
+            None
+        );
+        assert_eq!(locked_commit("git+https://example.com/app?rev=main"), None);
+    }
+}
@@ -0,0 +1,441 @@
+//! Helpers for walking the resolved dependency graph.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use cargo_metadata::{DependencyKind, Metadata, NodeDep, PackageId, Resolve};
+use semver::{Version, VersionReq};
+
+/// A crate present in the graph under several semver-incompatible versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateMajor {
+    pub name: String,
+    /// The newest version of each incompatible series, lowest first.
+    pub versions: Vec<String>,
+    /// Crates compiled only because the older series are in the graph.
+    pub extra_crates: usize,
+}
+
+/// Outgoing edges of every node in the resolve graph.
+pub fn adjacency(resolve: &Resolve) -> HashMap<PackageId, Vec<PackageId>> {
+    resolve
+        .nodes
+        .iter()
+        .map(|node| {
+            let deps = node.deps.iter().map(|dep| dep.pkg.clone()).collect();
+            (node.id.clone(), deps)
+        })
+        .collect()
+}
+
+/// Like [`adjacency`], but without edges that exist only as dev-dependencies,
+/// so walking it from the workspace members yields what a build compiles.
+pub fn build_adjacency(resolve: &Resolve) -> HashMap<PackageId, Vec<PackageId>> {
+    resolve
+        .nodes
+        .iter()
+        .map(|node| {
+            let deps = node
+                .deps
+                .iter()
+                .filter(|dep| !is_dev_only(dep))
+                .map(|dep| dep.pkg.clone())
+                .collect();
+            (node.id.clone(), deps)
+        })
+        .collect()
+}
+
+fn is_dev_only(dep: &NodeDep) -> bool {
+    !dep.dep_kinds.is_empty()
+        && dep
+            .dep_kinds
+            .iter()
+            .all(|info| info.kind == DependencyKind::Development)
+}
+
+/// Shortest number of edges from any root to each reachable node. Roots have
+/// depth 0, their direct dependencies depth 1, and so on.
+pub fn shortest_depths<N: Clone + Eq + Hash>(
+    roots: &[N],
+    edges: &HashMap<N, Vec<N>>,
+) -> HashMap<N, usize> {
+    let mut depths: HashMap<N, usize> = HashMap::new();
+    let mut queue: VecDeque<N> = VecDeque::new();
+    for root in roots {
+        if depths.insert(root.clone(), 0).is_none() {
+            queue.push_back(root.clone());
+        }
+    }
+    while let Some(node) = queue.pop_front() {
+        let depth = depths[&node];
+        for next in edges.get(&node).into_iter().flatten() {
+            if !depths.contains_key(next) {
+                depths.insert(next.clone(), depth + 1);
+                queue.push_back(next.clone());
+            }
+        }
+    }
+    depths
+}
+
+/// For every node, the direct dependencies of the roots (depth 1) whose
+/// subtree contains it. Roots themselves are never listed.
+pub fn reached_through<N: Clone + Eq + Hash>(
+    roots: &[N],
+    edges: &HashMap<N, Vec<N>>,
+) -> HashMap<N, Vec<N>> {
+    let mut direct: Vec<N> = Vec::new();
+    for root in roots {
+        for dep in edges.get(root).into_iter().flatten() {
+            if !roots.contains(dep) && !direct.contains(dep) {
+                direct.push(dep.clone());
+            }
+        }
+    }
+
+    let mut through: HashMap<N, Vec<N>> = HashMap::new();
+    for dep in &direct {
+        for node in shortest_depths(std::slice::from_ref(dep), edges).into_keys() {
+            if !roots.contains(&node) {
+                through.entry(node).or_default().push(dep.clone());
+            }
+        }
+    }
+    through
+}
+
+/// Registry crates that appear under more than one semver-compatible series
+/// (`1.x` and `2.x`, or `0.3.x` and `0.4.x`) in the resolved graph.
+pub fn duplicate_majors(metadata: &Metadata) -> Vec<DuplicateMajor> {
+    let Some(resolve) = &metadata.resolve else {
+        return Vec::new();
+    };
+    let packages = metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.source.is_some() && resolve.nodes.iter().any(|node| node.id == pkg.id));
+    find_duplicates(
+        packages.map(|pkg| (pkg.id.clone(), pkg.name.to_string(), pkg.version.clone())),
+        &metadata.workspace_members,
+        &adjacency(resolve),
+    )
+}
+
+fn find_duplicates<N: Clone + Eq + Hash>(
+    packages: impl IntoIterator<Item = (N, String, Version)>,
+    roots: &[N],
+    edges: &HashMap<N, Vec<N>>,
+) -> Vec<DuplicateMajor> {
+    let mut series: BTreeMap<String, BTreeMap<Series, (Version, N)>> = BTreeMap::new();
+    for (id, name, version) in packages {
+        let entry = series.entry(name).or_default();
+        let key = compatibility_series(&version);
+        if entry.get(&key).is_none_or(|(newest, _)| *newest < version) {
+            entry.insert(key, (version, id));
+        }
+    }
+
+    let reachable = shortest_depths(roots, edges).len();
+    series
+        .into_iter()
+        .filter(|(_, by_series)| by_series.len() > 1)
+        .map(|(name, by_series)| {
+            let mut copies: Vec<(Version, N)> = by_series.into_values().collect();
+            copies.sort_by(|a, b| a.0.cmp(&b.0));
+            // Everything that would disappear if only the newest series remained.
+            let older: HashSet<&N> = copies[..copies.len() - 1]
+                .iter()
+                .map(|(_, id)| id)
+                .collect();
+            let pruned: HashMap<N, Vec<N>> = edges
+                .iter()
+                .filter(|(node, _)| !older.contains(node))
+                .map(|(node, deps)| {
+                    let deps = deps.iter().filter(|dep| !older.contains(dep)).cloned();
+                    (node.clone(), deps.collect())
+                })
+                .collect();
+            DuplicateMajor {
+                name,
+                versions: copies
+                    .iter()
+                    .map(|(version, _)| version.to_string())
+                    .collect(),
+                extra_crates: reachable.saturating_sub(shortest_depths(roots, &pruned).len()),
+            }
+        })
+        .collect()
+}
+
+/// Locked packages that keep a crate from moving to `candidate`, worked out
+/// from the graph rather than from Cargo's error text:
+///
+/// - `dependents` (package, requirement on the crate) whose requirement
+///   excludes the candidate;
+/// - `locked` packages (id, name, version) that a requirement in
+///   `candidate_deps` (crate name, requirement) excludes although they are
+///   in the series it asks for. Cargo locks one version per series, so
+///   those have to move down first.
+pub fn blockers<N: Clone + Eq + Hash>(
+    candidate: &Version,
+    dependents: &[(N, VersionReq)],
+    candidate_deps: &[(String, VersionReq)],
+    locked: &[(N, String, Version)],
+) -> Vec<N> {
+    let mut found: Vec<N> = dependents
+        .iter()
+        .filter(|(_, req)| !req.matches(candidate))
+        .map(|(id, _)| id.clone())
+        .collect();
+    for (dep, req) in candidate_deps {
+        for (id, name, version) in locked {
+            if name == dep && excluded_from_series(req, version) && !found.contains(id) {
+                found.push(id.clone());
+            }
+        }
+    }
+    found
+}
+
+/// Whether `req` asks for the compatible series `version` belongs to but
+/// does not accept `version` itself. Cargo locks one version per series, so
+/// the two cannot share a lockfile.
+pub fn excluded_from_series(req: &VersionReq, version: &Version) -> bool {
+    !req.matches(version)
+        && req.comparators.iter().any(|comparator| {
+            compatibility_series(&Version::new(
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0),
+            )) == compatibility_series(version)
+        })
+}
+
+/// Draw the graph below `roots` the way `cargo tree` does, one node per
+/// line. A node drawn before is marked `(*)` instead of repeated. Only nodes
+/// that match `keep`, or lead to one that does, are drawn.
+pub fn render_tree<N: Clone + Eq + Hash>(
+    roots: &[N],
+    edges: &HashMap<N, Vec<N>>,
+    label: impl Fn(&N) -> String,
+    keep: impl Fn(&N) -> bool,
+) -> String {
+    let mut out = String::new();
+    for line in tree_lines(roots, edges, keep) {
+        out.push_str(&line.prefix);
+        out.push_str(&label(&line.node));
+        if line.repeated {
+            out.push_str(" (*)");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// One line of a drawn tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeLine<N> {
+    /// Indentation and branch drawing, e.g. `│   └── `.
+    pub prefix: String,
+    pub node: N,
+    /// Drawn before, with dependencies that are not repeated here.
+    pub repeated: bool,
+}
+
+/// The lines [`render_tree`] draws, for callers that label them themselves.
+pub fn tree_lines<N: Clone + Eq + Hash>(
+    roots: &[N],
+    edges: &HashMap<N, Vec<N>>,
+    keep: impl Fn(&N) -> bool,
+) -> Vec<TreeLine<N>> {
+    let mut relevant = HashMap::new();
+    for root in roots {
+        leads_to(root, edges, &keep, &mut relevant);
+    }
+    let mut lines = Vec::new();
+    let mut drawn = HashSet::new();
+    for root in roots.iter().filter(|root| relevant[*root]) {
+        lines.push(TreeLine {
+            prefix: String::new(),
+            node: root.clone(),
+            repeated: false,
+        });
+        drawn.insert(root.clone());
+        draw_children(root, "", edges, &relevant, &mut drawn, &mut lines);
+    }
+    lines
+}
+
+/// Whether `node` matches `keep` or reaches a node that does, memoized in
+/// `relevant`. A node on the current path counts as not leading anywhere,
+/// which ends cycles through dev-dependencies.
+fn leads_to<N: Clone + Eq + Hash>(
+    node: &N,
+    edges: &HashMap<N, Vec<N>>,
+    keep: &impl Fn(&N) -> bool,
+    relevant: &mut HashMap<N, bool>,
+) -> bool {
+    if let Some(&known) = relevant.get(node) {
+        return known;
+    }
+    relevant.insert(node.clone(), false);
+    let mut found = keep(node);
+    for child in edges.get(node).into_iter().flatten() {
+        found |= leads_to(child, edges, keep, relevant);
+    }
+    relevant.insert(node.clone(), found);
+    found
+}
+
+fn draw_children<N: Clone + Eq + Hash>(
+    node: &N,
+    prefix: &str,
+    edges: &HashMap<N, Vec<N>>,
+    relevant: &HashMap<N, bool>,
+    drawn: &mut HashSet<N>,
+    lines: &mut Vec<TreeLine<N>>,
+) {
+    let children: Vec<&N> = edges
+        .get(node)
+        .into_iter()
+        .flatten()
+        .filter(|child| relevant.get(*child).copied().unwrap_or(false))
+        .collect();
+    for (index, &child) in children.iter().enumerate() {
+        let last = index + 1 == children.len();
+        let has_children = edges
+            .get(child)
+            .is_some_and(|deps| deps.iter().any(|dep| relevant.get(dep) == Some(&true)));
+        let first = drawn.insert(child.clone());
+        lines.push(TreeLine {
+            prefix: format!("{prefix}{}", if last { "└── " } else { "├── " }),
+            node: child.clone(),
+            repeated: !first && has_children,
+        });
+        if first {
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            draw_children(child, &prefix, edges, relevant, drawn, lines);
+        }
+    }
+}
+
+/// `(major, minor, patch)` with the components below the first non-zero one
+/// cleared.
+type Series = (u64, u64, u64);
+
+/// Cargo treats `1.x`, `0.3.x`, and `0.0.4` as separate compatible series.
+fn compatibility_series(version: &Version) -> Series {
+    match (version.major, version.minor) {
+        (0, 0) => (0, 0, version.patch),
+        (0, minor) => (0, minor, 0),
+        (major, _) => (major, 0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blockers_come_from_requirements_in_the_graph() {
+        let req = |raw: &str| VersionReq::parse(raw).unwrap();
+        let version = |raw: &str| Version::parse(raw).unwrap();
+        let dependents = vec![("app", req("^1.2")), ("tool", req("^1.8"))];
+        let candidate_deps = vec![
+            ("bytes".to_string(), req("=1.4.0")),
+            ("libc".to_string(), req("^0.2")),
+            ("mio".to_string(), req("^0.8")),
+        ];
+        let locked = vec![
+            ("bytes", "bytes".to_string(), version("1.6.0")),
+            ("libc", "libc".to_string(), version("0.2.150")),
+            ("mio", "mio".to_string(), version("1.0.0")),
+        ];
+        // tool needs ^1.8; bytes 1.6.0 shares the series of the exact 1.4.0;
+        // mio 1.0 can live next to a 0.8 copy.
+        assert_eq!(
+            blockers(&version("1.7.0"), &dependents, &candidate_deps, &locked),
+            vec!["tool", "bytes"]
+        );
+    }
+
+    #[test]
+    fn trees_keep_only_paths_to_matches() {
+        let edges: HashMap<&str, Vec<&str>> = HashMap::from([
+            ("app", vec!["serde", "tokio", "log"]),
+            ("serde", vec!["serde_derive"]),
+            ("tokio", vec!["mio", "serde"]),
+            ("mio", vec!["libc"]),
+        ]);
+        let label = |node: &&str| node.to_string();
+        let lines =
+            |rendered: String| -> Vec<String> { rendered.lines().map(str::to_string).collect() };
+        assert_eq!(
+            lines(render_tree(&["app"], &edges, label, |_| true)),
+            [
+                "app",
+                "├── serde",
+                "│   └── serde_derive",
+                "├── tokio",
+                "│   ├── mio",
+                "│   │   └── libc",
+                "│   └── serde (*)",
+                "└── log",
+            ]
+        );
+        assert_eq!(
+            lines(render_tree(&["app"], &edges, label, |node| *node == "libc")),
+            ["app", "└── tokio", "    └── mio", "        └── libc"]
+        );
+    }
+
+    #[test]
+    fn depths_follow_the_shortest_path() {
+        let edges: HashMap<&str, Vec<&str>> = HashMap::from([
+            ("app", vec!["serde", "tokio"]),
+            ("tools", vec!["mio"]),
+            ("tokio", vec!["mio", "bytes"]),
+            ("mio", vec!["libc"]),
+        ]);
+        let depths = shortest_depths(&["app", "tools"], &edges);
+        assert_eq!(depths["app"], 0);
+        assert_eq!(depths["tokio"], 1);
+        assert_eq!(depths["mio"], 1);
+        assert_eq!(depths["bytes"], 2);
+        assert_eq!(depths["libc"], 2);
+
+        let through = reached_through(&["app", "tools"], &edges);
+        assert_eq!(through["bytes"], vec!["tokio"]);
+        let mut libc = through["libc"].clone();
+        libc.sort();
+        assert_eq!(libc, vec!["mio", "tokio"]);
+        assert!(!through.contains_key("app"));
+    }
+
+    #[test]
+    fn duplicates_are_grouped_by_compatible_series() {
+        let edges: HashMap<&str, Vec<&str>> = HashMap::from([
+            ("app", vec!["rand08", "old-lib", "serde"]),
+            ("old-lib", vec!["rand07"]),
+            ("rand07", vec!["rand_core05"]),
+            ("rand08", vec!["rand_core06"]),
+        ]);
+        let v = |s: &str| Version::parse(s).unwrap();
+        let packages = vec![
+            ("rand07", "rand".to_string(), v("0.7.3")),
+            ("rand08", "rand".to_string(), v("0.8.5")),
+            ("rand_core05", "rand_core".to_string(), v("0.5.1")),
+            ("rand_core06", "rand_core".to_string(), v("0.6.4")),
+            ("serde", "serde".to_string(), v("1.0.200")),
+            ("old-lib", "old-lib".to_string(), v("1.0.0")),
+        ];
+        let duplicates = find_duplicates(packages, &["app"], &edges);
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].name, "rand");
+        assert_eq!(duplicates[0].versions, vec!["0.7.3", "0.8.5"]);
+        assert_eq!(duplicates[0].extra_crates, 2);
+        assert_eq!(duplicates[1].name, "rand_core");
+        assert_eq!(duplicates[1].extra_crates, 1);
+    }
+}
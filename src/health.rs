@@ -0,0 +1,167 @@
+//! Per-endpoint request health: success rates and latency percentiles for the
+//! current run, plus a rolling history kept in the cache across runs, so slow
+//! runs can be pinned on crates.io, a mirror, or a proxy.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+
+/// Crate names cannot start with `_`, so this never shadows a cached crate.
+const HISTORY_KEY: &str = "_health/endpoints";
+/// Latency samples kept per endpoint in the persisted history.
+const HISTORY_SAMPLES: usize = 500;
+
+static RUN: Mutex<BTreeMap<String, EndpointStats>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EndpointStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Latency of every attempt in milliseconds, oldest first.
+    pub latencies_ms: Vec<u64>,
+}
+
+impl EndpointStats {
+    fn requests(&self) -> u64 {
+        self.successes + self.failures
+    }
+
+    fn success_rate(&self) -> f64 {
+        match self.requests() {
+            0 => 0.0,
+            total => self.successes as f64 * 100.0 / total as f64,
+        }
+    }
+
+    /// Nearest-rank percentile of the recorded latencies.
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let mut sorted = self.latencies_ms.clone();
+        sorted.sort_unstable();
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+
+    fn merge(&mut self, other: &EndpointStats) {
+        self.successes += other.successes;
+        self.failures += other.failures;
+        self.latencies_ms.extend(&other.latencies_ms);
+        let excess = self.latencies_ms.len().saturating_sub(HISTORY_SAMPLES);
+        self.latencies_ms.drain(..excess);
+    }
+}
+
+/// Record one HTTP attempt against the endpoint serving `url`.
+pub fn record(url: &Url, elapsed: Duration, ok: bool) {
+    let mut run = RUN.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let stats = run.entry(endpoint(url)).or_default();
+    if ok {
+        stats.successes += 1;
+    } else {
+        stats.failures += 1;
+    }
+    stats.latencies_ms.push(elapsed.as_millis() as u64);
+}
+
+/// Statistics gathered so far in this process.
+pub fn current() -> BTreeMap<String, EndpointStats> {
+    RUN.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Fold this run into the persisted history and return the updated history.
+pub fn persist(cache: &Cache) -> Result<BTreeMap<String, EndpointStats>> {
+    let run = current();
+    let mut history: BTreeMap<String, EndpointStats> = cache
+        .get_stale(HISTORY_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if !run.is_empty() {
+        for (endpoint, stats) in &run {
+            history.entry(endpoint.clone()).or_default().merge(stats);
+        }
+        cache.put(HISTORY_KEY, &history)?;
+    }
+    Ok(history)
+}
+
+/// Render a table of `stats`, one endpoint per line.
+pub fn render(title: &str, stats: &BTreeMap<String, EndpointStats>) -> String {
+    let mut out = format!("{title}\n");
+    if stats.is_empty() {
+        out.push_str("  no registry requests recorded\n");
+        return out;
+    }
+    let _ = writeln!(
+        out,
+        "  {:<40} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "endpoint", "requests", "ok", "p50", "p90", "p99"
+    );
+    for (endpoint, stats) in stats {
+        let ms = |p| {
+            stats
+                .percentile(p)
+                .map(|ms| format!("{ms}ms"))
+                .unwrap_or_else(|| "-".into())
+        };
+        let _ = writeln!(
+            out,
+            "  {:<40} {:>8} {:>7.1}% {:>8} {:>8} {:>8}",
+            endpoint,
+            stats.requests(),
+            stats.success_rate(),
+            ms(50.0),
+            ms(90.0),
+            ms(99.0)
+        );
+    }
+    out
+}
+
+/// Scheme, host, and port: the unit a mirror or proxy problem shows up in.
+fn endpoint(url: &Url) -> String {
+    let host = url.host_str().unwrap_or("unknown");
+    match url.port() {
+        Some(port) => format!("{}://{host}:{port}", url.scheme()),
+        None => format!("{}://{host}", url.scheme()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_and_history_are_bounded() {
+        let mut stats = EndpointStats {
+            successes: 9,
+            failures: 1,
+            latencies_ms: (1..=10).map(|ms| ms * 10).collect(),
+        };
+        assert_eq!(stats.percentile(50.0), Some(50));
+        assert_eq!(stats.percentile(90.0), Some(90));
+        assert_eq!(stats.percentile(99.0), Some(100));
+        assert_eq!(stats.success_rate(), 90.0);
+
+        let burst = EndpointStats {
+            successes: HISTORY_SAMPLES as u64,
+            failures: 0,
+            latencies_ms: vec![1; HISTORY_SAMPLES],
+        };
+        stats.merge(&burst);
+        assert_eq!(stats.requests(), 10 + HISTORY_SAMPLES as u64);
+        assert_eq!(stats.latencies_ms.len(), HISTORY_SAMPLES);
+        assert!(stats.latencies_ms.iter().all(|&ms| ms == 1));
+
+        let url = Url::parse("http://127.0.0.1:8080/api/v1/crates/serde").unwrap();
+        assert_eq!(endpoint(&url), "http://127.0.0.1:8080");
+    }
+}
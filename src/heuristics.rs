@@ -0,0 +1,138 @@
+//! Typosquat detection: dependencies whose name is one or two edits away
+//! from a popular crate, and which are themselves young or rarely
+//! downloaded, are reported or refused.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::db_dump;
+
+/// Embedded fallback for when no database dump is configured.
+const EMBEDDED: &str = include_str!("popular-crates.txt");
+
+/// How many of the most downloaded crates of a database dump count as popular.
+const DUMP_TOP: usize = 500;
+
+/// Crates with fewer downloads across all releases count as rarely used.
+pub const LOW_DOWNLOADS: u64 = 10_000;
+
+pub struct PopularCrates {
+    names: Vec<String>,
+    normalized: HashSet<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateRow {
+    name: String,
+    downloads: u64,
+}
+
+impl PopularCrates {
+    /// The most downloaded crates of `db_dump`, or the embedded list.
+    pub fn load(db_dump: Option<&Path>) -> Result<Self> {
+        let names = match db_dump {
+            Some(path) => from_dump(path)?,
+            None => EMBEDDED
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+        };
+        Ok(Self::new(names))
+    }
+
+    fn new(names: Vec<String>) -> Self {
+        let normalized = names.iter().map(|name| normalize(name)).collect();
+        Self { names, normalized }
+    }
+
+    /// The popular crate `name` is a near miss of, if any. Popular crates
+    /// themselves never match, even when they resemble each other.
+    pub fn lookalike(&self, name: &str) -> Option<&str> {
+        let candidate = normalize(name);
+        if self.normalized.contains(&candidate) {
+            return None;
+        }
+        // One edit is enough to turn a short name into another real one.
+        let max = if candidate.chars().count() < 6 { 1 } else { 2 };
+        self.names
+            .iter()
+            .find(|popular| distance(&candidate, &normalize(popular)) <= max)
+            .map(String::as_str)
+    }
+}
+
+fn from_dump(path: &Path) -> Result<Vec<String>> {
+    let crates_path = db_dump::locate_data_dir(path)?.join("crates.csv");
+    let mut reader = csv::Reader::from_path(&crates_path)
+        .with_context(|| format!("failed to open {}", crates_path.display()))?;
+    let mut rows = Vec::new();
+    for row in reader.deserialize::<CrateRow>() {
+        rows.push(row.with_context(|| format!("failed to parse {}", crates_path.display()))?);
+    }
+    rows.sort_by_key(|row| std::cmp::Reverse(row.downloads));
+    Ok(rows
+        .into_iter()
+        .take(DUMP_TOP)
+        .map(|row| row.name)
+        .collect())
+}
+
+/// crates.io treats `-` and `_` as the same character in names.
+fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase().replace('_', "-")
+}
+
+/// Edit distance counting insertions, deletions, substitutions, and swaps
+/// of adjacent characters as one edit each.
+pub fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_near_misses_of_popular_crates() {
+        let popular = PopularCrates::new(vec![
+            "serde".into(),
+            "serde_json".into(),
+            "tokio".into(),
+            "log".into(),
+        ]);
+        assert_eq!(popular.lookalike("sedre"), Some("serde"));
+        assert_eq!(popular.lookalike("serde-jsno"), Some("serde_json"));
+        assert_eq!(popular.lookalike("serdejsn"), Some("serde_json"));
+        assert_eq!(popular.lookalike("tokioo"), Some("tokio"));
+        assert_eq!(popular.lookalike("serde-json"), None);
+        assert_eq!(popular.lookalike("tokio"), None);
+        // Short names only tolerate a single edit.
+        assert_eq!(popular.lookalike("lag"), Some("log"));
+        assert_eq!(popular.lookalike("leg2"), None);
+        assert!(PopularCrates::load(None).unwrap().names.len() > 100);
+    }
+}
@@ -0,0 +1,178 @@
+//! Publication times read from the git history of a registry index, for
+//! users who cannot reach or do not trust the crates.io API: a release counts
+//! as published when the commit that added its line to the index was made.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use tracing::debug;
+
+use crate::provider::{BoxFuture, VersionMetadataProvider};
+use crate::registry::{IndexEntry, VersionMeta, sparse_index_path};
+
+/// A local clone, bare or not, of a git registry index such as
+/// <https://github.com/rust-lang/crates.io-index>.
+pub struct IndexHistory {
+    repo: PathBuf,
+}
+
+impl IndexHistory {
+    pub fn open(path: &Path) -> Result<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(path)
+            .args(["rev-parse", "--git-dir"])
+            .output()
+            .context("failed to run git")?;
+        if !output.status.success() {
+            bail!("{} is not a git clone of a registry index", path.display());
+        }
+        Ok(Self {
+            repo: path.to_path_buf(),
+        })
+    }
+
+    fn versions_of(&self, name: &str) -> Result<Vec<VersionMeta>> {
+        let file = sparse_index_path(name);
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo)
+            .args([
+                "log",
+                "--reverse",
+                "--format=commit %cI",
+                "--unified=0",
+                "--no-color",
+                "--no-renames",
+                "HEAD",
+                "--",
+                &file,
+            ])
+            .output()
+            .context("failed to run git")?;
+        if !output.status.success() {
+            bail!(
+                "`git log` failed in {}: {}",
+                self.repo.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let versions = parse_history(&String::from_utf8_lossy(&output.stdout))?;
+        if versions.is_empty() {
+            bail!(
+                "crate {name} has no history in the index at {}",
+                self.repo.display()
+            );
+        }
+        debug!(crate = %name, versions = versions.len(), "read publication times from the index history");
+        Ok(versions)
+    }
+}
+
+/// Walk `git log --reverse --unified=0` output of one index file. The
+/// commit that first adds a version's line dates it; later changes to the
+/// line, such as a yank, only update its flags.
+fn parse_history(log: &str) -> Result<Vec<VersionMeta>> {
+    let mut versions: Vec<VersionMeta> = Vec::new();
+    let mut committed_at = None;
+    for line in log.lines() {
+        if let Some(date) = line.strip_prefix("commit ") {
+            committed_at = Some(
+                DateTime::parse_from_rfc3339(date.trim())
+                    .with_context(|| format!("git printed an invalid commit date `{date}`"))?
+                    .with_timezone(&Utc),
+            );
+            continue;
+        }
+        let (Some(added), Some(committed_at)) = (line.strip_prefix('+'), committed_at) else {
+            continue;
+        };
+        // Also skips the `+++ b/path` header.
+        let Ok(entry) = serde_json::from_str::<IndexEntry>(added) else {
+            continue;
+        };
+        match versions.iter_mut().find(|meta| meta.num == entry.vers) {
+            Some(meta) => meta.yanked = entry.yanked,
+            None => versions.push(VersionMeta {
+                created_at: committed_at,
+                yanked: entry.yanked,
+                num: entry.vers,
+                downloads: None,
+                license: None,
+            }),
+        }
+    }
+    Ok(versions)
+}
+
+impl VersionMetadataProvider for IndexHistory {
+    fn label(&self) -> &'static str {
+        "index history"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(async move {
+            self.versions_of(name)?
+                .into_iter()
+                .find(|meta| meta.num == version)
+                .ok_or_else(|| anyhow!("{name}@{version} is not in the index history"))
+        })
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(async move { self.versions_of(name) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_versions_by_the_commit_that_added_them() {
+        let log = r#"commit 2025-01-02T10:00:00+01:00
+
+diff --git a/3/f/foo b/3/f/foo
+new file mode 100644
+--- /dev/null
++++ b/3/f/foo
+@@ -0,0 +1 @@
++{"name":"foo","vers":"1.0.0","deps":[],"cksum":"aa","features":{},"yanked":false}
+commit 2025-02-03T12:30:00+00:00
+
+diff --git a/3/f/foo b/3/f/foo
+--- a/3/f/foo
++++ b/3/f/foo
+@@ -1,0 +2 @@
++{"name":"foo","vers":"1.1.0","deps":[],"cksum":"bb","features":{},"yanked":false}
+commit 2025-03-01T00:00:00+00:00
+
+diff --git a/3/f/foo b/3/f/foo
+--- a/3/f/foo
++++ b/3/f/foo
+@@ -2 +2 @@
+-{"name":"foo","vers":"1.1.0","deps":[],"cksum":"bb","features":{},"yanked":false}
++{"name":"foo","vers":"1.1.0","deps":[],"cksum":"bb","features":{},"yanked":true}
+"#;
+        let versions = parse_history(log).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].num, "1.0.0");
+        assert_eq!(
+            versions[0].created_at.to_rfc3339(),
+            "2025-01-02T09:00:00+00:00"
+        );
+        assert!(!versions[0].yanked);
+        assert_eq!(versions[1].num, "1.1.0");
+        assert_eq!(
+            versions[1].created_at.to_rfc3339(),
+            "2025-02-03T12:30:00+00:00"
+        );
+        assert!(versions[1].yanked);
+    }
+}
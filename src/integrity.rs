@@ -0,0 +1,97 @@
+//! Lockfile integrity: every `checksum` recorded in `Cargo.lock` must match
+//! the one the registry index publishes for that release.
+
+use std::fmt;
+
+use crate::lockfile::LockedPackage;
+use crate::registry::IndexEntry;
+
+/// A locked release whose checksum the index does not confirm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub name: String,
+    pub version: String,
+    pub locked: String,
+    /// `None` when the index has no such release at all.
+    pub published: Option<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.published {
+            Some(published) => write!(
+                f,
+                "{}@{}: locked checksum {} but the index publishes {}",
+                self.name, self.version, self.locked, published
+            ),
+            None => write!(
+                f,
+                "{}@{}: locked with checksum {} but the release is not in the index",
+                self.name, self.version, self.locked
+            ),
+        }
+    }
+}
+
+/// Compare the checksum locked for `pkg` with the index `entries` of its
+/// crate. Packages locked without a checksum, and index entries without
+/// one, are not judged.
+pub fn compare(pkg: &LockedPackage, entries: &[IndexEntry]) -> Option<Mismatch> {
+    let locked = pkg.checksum.as_deref()?;
+    let published = match entries.iter().find(|entry| entry.vers == pkg.version) {
+        Some(entry) => entry.cksum.as_deref()?,
+        None => {
+            return Some(Mismatch {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                locked: locked.to_string(),
+                published: None,
+            });
+        }
+    };
+    if published.eq_ignore_ascii_case(locked) {
+        return None;
+    }
+    Some(Mismatch {
+        name: pkg.name.clone(),
+        version: pkg.version.clone(),
+        locked: locked.to_string(),
+        published: Some(published.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_checksums_the_index_does_not_publish() {
+        let entry = |vers: &str, cksum: Option<&str>| IndexEntry {
+            vers: vers.into(),
+            yanked: false,
+            pubtime: None,
+            cksum: cksum.map(str::to_string),
+            rust_version: None,
+            deps: Vec::new(),
+        };
+        let entries = vec![entry("1.0.0", Some("aa11")), entry("1.1.0", None)];
+        let locked = |version: &str, checksum: Option<&str>| LockedPackage {
+            name: "foo".into(),
+            version: version.into(),
+            source: None,
+            checksum: checksum.map(str::to_string),
+        };
+
+        assert_eq!(compare(&locked("1.0.0", Some("AA11")), &entries), None);
+        assert_eq!(compare(&locked("1.0.0", None), &entries), None);
+        assert_eq!(compare(&locked("1.1.0", Some("bb22")), &entries), None);
+        let tampered = compare(&locked("1.0.0", Some("bb22")), &entries).unwrap();
+        assert_eq!(tampered.published.as_deref(), Some("aa11"));
+        assert!(
+            compare(&locked("2.0.0", Some("bb22")), &entries)
+                .unwrap()
+                .published
+                .is_none()
+        );
+    }
+}
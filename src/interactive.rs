@@ -0,0 +1,181 @@
+//! `--interactive`: instead of pinning on its own, the run asks what to do
+//! about each release it would move.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+
+use crate::report::format_age;
+use crate::resolver::Candidate;
+
+/// What the user chose for one release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Pin to the candidate at this index.
+    Downgrade(usize),
+    /// Keep the release for this run; with a reason, also record it in the
+    /// allowlist until it cools down.
+    Allow {
+        reason: Option<String>,
+    },
+    /// Sleep until the release cools down.
+    Wait,
+    Abort,
+}
+
+/// The release being decided on.
+pub struct Prompt<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    pub minimum_minutes: u64,
+    /// `None` for releases that only block a fresher one.
+    pub published_at: Option<DateTime<Utc>>,
+    pub cools_down_at: Option<DateTime<Utc>>,
+    pub candidates: &'a [Candidate],
+}
+
+/// Ask on the terminal until a valid answer is given.
+pub fn ask(prompt: &Prompt<'_>, now: DateTime<Utc>) -> Result<Decision> {
+    ask_with(
+        prompt,
+        now,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+    )
+}
+
+fn ask_with(
+    prompt: &Prompt<'_>,
+    now: DateTime<Utc>,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<Decision> {
+    match prompt.published_at {
+        Some(published_at) => writeln!(
+            output,
+            "\n{}@{} was published {} ago, inside its {}-minute cooldown window.",
+            prompt.name,
+            prompt.version,
+            format_age(now - published_at),
+            prompt.minimum_minutes
+        )?,
+        None => writeln!(
+            output,
+            "\n{}@{} has to move so that fresher releases can be pinned.",
+            prompt.name, prompt.version
+        )?,
+    }
+    for (index, candidate) in prompt.candidates.iter().enumerate() {
+        writeln!(
+            output,
+            "  {}) downgrade to {} (published {} ago)",
+            index + 1,
+            candidate.version,
+            format_age(now - candidate.created_at)
+        )?;
+    }
+    writeln!(output, "  a) allow it for this run")?;
+    if let Some(cools_down_at) = prompt.cools_down_at {
+        writeln!(
+            output,
+            "  w) wait until it cools down (in {})",
+            format_age(cools_down_at - now)
+        )?;
+    }
+    writeln!(output, "  q) abort")?;
+
+    loop {
+        let Some(answer) = read_answer(input, output, "> ")? else {
+            return Ok(Decision::Abort);
+        };
+        match answer.as_str() {
+            "a" => {
+                let reason = read_answer(
+                    input,
+                    output,
+                    "reason to record in the allowlist (empty to allow for this run only): ",
+                )?
+                .filter(|reason| !reason.is_empty());
+                return Ok(Decision::Allow { reason });
+            }
+            "w" if prompt.cools_down_at.is_some() => return Ok(Decision::Wait),
+            "q" => return Ok(Decision::Abort),
+            number => match number.parse::<usize>() {
+                Ok(choice) if (1..=prompt.candidates.len()).contains(&choice) => {
+                    return Ok(Decision::Downgrade(choice - 1));
+                }
+                _ => writeln!(output, "unknown choice `{number}`")?,
+            },
+        }
+    }
+}
+
+/// One trimmed line, or `None` at the end of the input.
+fn read_answer(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    label: &str,
+) -> Result<Option<String>> {
+    write!(output, "{label}")?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// `--interactive` needs someone to answer.
+pub fn ensure_terminal() -> Result<()> {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() {
+        bail!("--interactive needs a terminal to read answers from");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn asks_until_a_listed_choice_is_given() {
+        let now = Utc::now();
+        let candidates = [Candidate {
+            version: "1.0.1".into(),
+            created_at: now - Duration::days(30),
+        }];
+        let prompt = Prompt {
+            name: "serde",
+            version: "1.0.2",
+            minimum_minutes: 10080,
+            published_at: Some(now - Duration::hours(2)),
+            cools_down_at: None,
+            candidates: &candidates,
+        };
+        let mut output = Vec::new();
+
+        let mut input = "2\nw\n1\n".as_bytes();
+        let decision = ask_with(&prompt, now, &mut input, &mut output).unwrap();
+        assert_eq!(decision, Decision::Downgrade(0));
+        let shown = String::from_utf8(output).unwrap();
+        assert!(shown.contains("1) downgrade to 1.0.1"));
+        assert!(shown.contains("unknown choice `2`"));
+        assert!(shown.contains("unknown choice `w`"));
+
+        let mut input = "a\nreviewed the diff\n".as_bytes();
+        let decision = ask_with(&prompt, now, &mut input, &mut Vec::new()).unwrap();
+        assert_eq!(
+            decision,
+            Decision::Allow {
+                reason: Some("reviewed the diff".into())
+            }
+        );
+
+        let mut input = "".as_bytes();
+        let decision = ask_with(&prompt, now, &mut input, &mut Vec::new()).unwrap();
+        assert_eq!(decision, Decision::Abort);
+    }
+}
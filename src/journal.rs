@@ -0,0 +1,137 @@
+//! Append-only journal of the pins this tool applied, so later runs can tell
+//! which locked versions it chose and keep an eye on them.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PinRecord {
+    pub pinned_at: DateTime<Utc>,
+    pub workspace: PathBuf,
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// Why the release was moved, e.g. how recently it was published.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// When the replaced release was published, if known.
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn append(&self, record: &PinRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open pin journal {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)
+            .with_context(|| format!("failed to write pin journal {}", self.path.display()))
+    }
+
+    /// Every readable record, oldest first. Corrupt lines are skipped.
+    pub fn records(&self) -> Result<Vec<PinRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read pin journal {}", self.path.display()))?;
+        Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(err) => {
+                    warn!(error = %err, "skipping unreadable pin journal entry");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// The latest pin per crate in `workspace` whose target version is still
+    /// locked, i.e. versions that are in the lockfile because we chose them.
+    pub fn active_pins(
+        &self,
+        workspace: &Path,
+        locked: &HashSet<(String, String)>,
+    ) -> Result<Vec<PinRecord>> {
+        let mut latest: HashMap<String, PinRecord> = HashMap::new();
+        for record in self.records()? {
+            if record.workspace == workspace {
+                latest.insert(record.name.clone(), record);
+            }
+        }
+        let mut active: Vec<PinRecord> = latest
+            .into_values()
+            .filter(|record| locked.contains(&(record.name.clone(), record.to.clone())))
+            .collect();
+        active.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn active_pins_follow_the_latest_record_still_locked() {
+        let dir = tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("pins.jsonl"));
+        let workspace = PathBuf::from("/work/app");
+        let record = |name: &str, to: &str, workspace: &Path| PinRecord {
+            pinned_at: Utc::now(),
+            workspace: workspace.to_path_buf(),
+            name: name.into(),
+            from: "9.9.9".into(),
+            to: to.into(),
+            reason: None,
+            published_at: None,
+        };
+        journal
+            .append(&record("serde", "1.0.1", &workspace))
+            .unwrap();
+        journal
+            .append(&record("serde", "1.0.2", &workspace))
+            .unwrap();
+        journal
+            .append(&record("tokio", "1.40.0", &workspace))
+            .unwrap();
+        journal
+            .append(&record("rand", "0.8.5", Path::new("/work/other")))
+            .unwrap();
+
+        let locked: HashSet<(String, String)> = [
+            ("serde".to_string(), "1.0.2".to_string()),
+            ("rand".to_string(), "0.8.5".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let active = journal.active_pins(&workspace, &locked).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].name, "serde");
+        assert_eq!(active[0].to, "1.0.2");
+    }
+}
@@ -0,0 +1,20 @@
+//! Library half of `cargo-cooldown`.
+//!
+//! With `default-features = false, features = ["cooldown-verify"]` this crate
+//! only exposes certificate verification, which is small enough to call from a
+//! project's `build.rs` or a test. That turns the cooldown check into an
+//! in-tree hook: the build fails whenever `Cargo.lock` no longer matches the
+//! certificate an enforce run issued, however the build was invoked.
+//!
+//! ```no_run
+//! // In build.rs's `main`:
+//! println!("cargo:rerun-if-changed=cooldown.cert");
+//! println!("cargo:rerun-if-changed=Cargo.lock");
+//! println!("cargo:rerun-if-env-changed=COOLDOWN_CERT_PUBLIC_KEY");
+//! if let Err(err) = cargo_cooldown::cert::verify_workspace("cooldown.cert") {
+//!     panic!("Cargo.lock has not passed cooldown: {err}");
+//! }
+//! ```
+
+#[cfg(feature = "cooldown-verify")]
+pub mod cert;
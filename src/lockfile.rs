@@ -0,0 +1,403 @@
+//! Reading `Cargo.lock` and its git history.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use toml_edit::{DocumentMut, Value};
+
+/// How many commits touching `Cargo.lock` are inspected when attributing
+/// locked packages to the change that introduced them.
+const HISTORY_DEPTH: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawLockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+pub fn parse(contents: &str) -> Result<Vec<LockedPackage>> {
+    let raw: RawLockfile = toml::from_str(contents).context("failed to parse Cargo.lock")?;
+    Ok(raw.package)
+}
+
+pub fn read(path: &Path) -> Result<Vec<LockedPackage>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse(&contents)
+}
+
+/// The change that added a `name@version` entry to `Cargo.lock`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LockfileChange {
+    /// Present in the working tree but not in `HEAD`.
+    Uncommitted,
+    Commit {
+        short_id: String,
+        summary: String,
+    },
+}
+
+/// Attribute each wanted `(name, version)` to the commit that first added it
+/// to the lockfile, walking recent history newest first. Entries that predate
+/// the inspected history are left out.
+pub fn introductions(
+    lockfile: &Path,
+    wanted: &[(String, String)],
+) -> Result<HashMap<(String, String), LockfileChange>> {
+    let dir = lockfile
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file = lockfile
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Cargo.lock");
+
+    let mut pending: HashSet<(String, String)> = wanted.iter().cloned().collect();
+    let mut found = HashMap::new();
+
+    // The working tree is compared against `HEAD`, then every commit that
+    // touched the lockfile against its parent.
+    let mut comparisons = vec![(None, "HEAD".to_string(), LockfileChange::Uncommitted)];
+    let log = git(
+        dir,
+        &[
+            "log",
+            &format!("-n{HISTORY_DEPTH}"),
+            "--format=%H%x1f%h%x1f%s",
+            "--",
+            file,
+        ],
+    )?;
+    for line in log.lines() {
+        let mut fields = line.split('\x1f');
+        let (Some(id), Some(short_id), Some(summary)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        comparisons.push((
+            Some(id.to_string()),
+            format!("{id}^"),
+            LockfileChange::Commit {
+                short_id: short_id.to_string(),
+                summary: summary.to_string(),
+            },
+        ));
+    }
+
+    for (newer, older, change) in comparisons {
+        if pending.is_empty() {
+            break;
+        }
+        let newer = match newer {
+            Some(revision) => packages_at(dir, file, &revision)?,
+            None => package_set(&read(lockfile)?),
+        };
+        let older = packages_at(dir, file, &older)?;
+        pending.retain(|key| {
+            if newer.contains(key) && !older.contains(key) {
+                found.insert(key.clone(), change.clone());
+                false
+            } else {
+                true
+            }
+        });
+    }
+    Ok(found)
+}
+
+/// Whether `lockfile` is tracked by git and has changes, staged or not, that
+/// are not committed yet. Outside a git checkout there is nothing to lose,
+/// so the answer is `false`.
+pub fn has_uncommitted_changes(lockfile: &Path) -> bool {
+    let dir = lockfile
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file = lockfile
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Cargo.lock");
+    git(
+        dir,
+        &["status", "--porcelain", "--untracked-files=no", "--", file],
+    )
+    .is_ok_and(|status| !status.trim().is_empty())
+}
+
+/// Locked packages at a git revision; empty when the lockfile did not exist.
+fn packages_at(dir: &Path, file: &str, revision: &str) -> Result<HashSet<(String, String)>> {
+    match git(dir, &["show", &format!("{revision}:./{file}")]) {
+        Ok(contents) => Ok(package_set(&parse(&contents)?)),
+        Err(_) => Ok(HashSet::new()),
+    }
+}
+
+/// Whether `after` locks exactly the releases of `before` with
+/// `name@from` replaced by `name@to`: a pin that left the rest of the graph
+/// alone.
+pub fn only_replaced(
+    before: &[LockedPackage],
+    after: &[LockedPackage],
+    name: &str,
+    from: &str,
+    to: &str,
+) -> bool {
+    let mut expected = package_set(before);
+    if !expected.remove(&(name.to_string(), from.to_string())) {
+        return false;
+    }
+    expected.insert((name.to_string(), to.to_string()));
+    expected == package_set(after)
+}
+
+/// Rewrite lockfile `contents` so `name@from` is locked at `to` instead,
+/// including the references other packages hold to it. `checksum` replaces
+/// the entry's checksum, or drops it when the new release has none on
+/// record. Nothing else is re-resolved: whether the result still satisfies
+/// every requirement is for Cargo to confirm.
+pub fn replace_version(
+    contents: &str,
+    name: &str,
+    from: &str,
+    to: &str,
+    checksum: Option<&str>,
+) -> Result<String> {
+    let mut document: DocumentMut = contents.parse().context("failed to parse Cargo.lock")?;
+    let packages = document
+        .get_mut("package")
+        .and_then(|item| item.as_array_of_tables_mut())
+        .context("Cargo.lock has no [[package]] entries")?;
+    let field = |table: &toml_edit::Table, key: &str| {
+        table
+            .get(key)
+            .and_then(|item| item.as_str())
+            .map(str::to_string)
+    };
+    if packages.iter().any(|table| {
+        field(table, "name").as_deref() == Some(name)
+            && field(table, "version").as_deref() == Some(to)
+    }) {
+        bail!("{name}@{to} is already locked");
+    }
+    let mut replaced = false;
+    let old_reference = format!("{name} {from}");
+    let new_reference = format!("{name} {to}");
+    for table in packages.iter_mut() {
+        if field(table, "name").as_deref() == Some(name)
+            && field(table, "version").as_deref() == Some(from)
+        {
+            table["version"] = toml_edit::value(to);
+            match checksum {
+                Some(checksum) => table["checksum"] = toml_edit::value(checksum),
+                None => {
+                    table.remove("checksum");
+                }
+            }
+            replaced = true;
+        }
+        let Some(dependencies) = table
+            .get_mut("dependencies")
+            .and_then(|item| item.as_array_mut())
+        else {
+            continue;
+        };
+        for dependency in dependencies.iter_mut() {
+            let Some(reference) = dependency.as_str() else {
+                continue;
+            };
+            if let Some(rest) = reference.strip_prefix(&old_reference)
+                && (rest.is_empty() || rest.starts_with(' '))
+            {
+                let decor = dependency.decor().clone();
+                *dependency = Value::from(format!("{new_reference}{rest}"));
+                *dependency.decor_mut() = decor;
+            }
+        }
+    }
+    if !replaced {
+        bail!("{name}@{from} is not locked");
+    }
+    Ok(document.to_string())
+}
+
+/// Releases `after` locks at another version than `before`, as
+/// `(name, from, to)`. Only crates locked at a single version on both sides
+/// are paired up.
+pub fn moved_releases(
+    before: &[LockedPackage],
+    after: &[LockedPackage],
+) -> Vec<(String, String, String)> {
+    let single = |packages: &[LockedPackage]| {
+        let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+        for pkg in packages {
+            versions
+                .entry(pkg.name.clone())
+                .or_default()
+                .push(pkg.version.clone());
+        }
+        versions
+    };
+    let before = single(before);
+    let after = single(after);
+    let mut moved: Vec<(String, String, String)> = before
+        .iter()
+        .filter_map(
+            |(name, from)| match (from.as_slice(), after.get(name)?.as_slice()) {
+                ([from], [to]) if from != to => Some((name.clone(), from.clone(), to.clone())),
+                _ => None,
+            },
+        )
+        .collect();
+    moved.sort();
+    moved
+}
+
+fn package_set(packages: &[LockedPackage]) -> HashSet<(String, String)> {
+    packages
+        .iter()
+        .map(|pkg| (pkg.name.clone(), pkg.version.clone()))
+        .collect()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_locked_packages() {
+        let packages = parse(
+            r#"
+version = 4
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "c8e3592472072e6e22e0a54d5904d9febf8508f65fb8552499a1abc7d1078c3a"
+"#,
+        )
+        .unwrap();
+        assert_eq!(packages.len(), 2);
+        assert!(packages[0].source.is_none());
+        assert_eq!(packages[1].name, "serde");
+        assert!(packages[1].checksum.as_deref().unwrap().starts_with("c8e3"));
+    }
+
+    #[test]
+    fn recognizes_pins_that_left_the_graph_alone() {
+        let locked = |entries: &[(&str, &str)]| -> Vec<LockedPackage> {
+            entries
+                .iter()
+                .map(|(name, version)| LockedPackage {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    source: None,
+                    checksum: None,
+                })
+                .collect()
+        };
+        let before = locked(&[("serde", "1.0.210"), ("itoa", "1.0.11")]);
+        let pinned = locked(&[("serde", "1.0.200"), ("itoa", "1.0.11")]);
+        let reshaped = locked(&[("serde", "1.0.200"), ("itoa", "1.0.9")]);
+        assert!(only_replaced(
+            &before, &pinned, "serde", "1.0.210", "1.0.200"
+        ));
+        assert!(!only_replaced(
+            &before, &reshaped, "serde", "1.0.210", "1.0.200"
+        ));
+        assert!(!only_replaced(
+            &before, &pinned, "serde", "1.0.199", "1.0.200"
+        ));
+        assert_eq!(
+            moved_releases(&before, &reshaped),
+            vec![
+                (
+                    "itoa".to_string(),
+                    "1.0.11".to_string(),
+                    "1.0.9".to_string()
+                ),
+                (
+                    "serde".to_string(),
+                    "1.0.210".to_string(),
+                    "1.0.200".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrites_a_locked_release_and_its_references() {
+        let contents = r#"version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "itoa 1.0.11",
+ "serde",
+]
+
+[[package]]
+name = "itoa"
+version = "1.0.11"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "aaaa"
+
+[[package]]
+name = "itoa"
+version = "0.4.8"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "bbbb"
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+dependencies = [
+ "itoa 0.4.8",
+]
+"#;
+        let edited = replace_version(contents, "itoa", "1.0.11", "1.0.9", Some("cccc")).unwrap();
+        assert!(edited.contains(" \"itoa 1.0.9\",\n \"serde\","));
+        assert!(edited.contains("version = \"1.0.9\"\nsource"));
+        assert!(edited.contains("checksum = \"cccc\""));
+        assert!(edited.contains(" \"itoa 0.4.8\","));
+        assert!(replace_version(contents, "itoa", "1.0.11", "0.4.8", None).is_err());
+    }
+}
@@ -1,21 +1,66 @@
+mod advisories;
 mod allowlist;
+mod audit;
+mod autocommit;
+mod baseline;
+mod blocking;
+mod bundle;
 mod cache;
+mod calendar;
+mod certificate;
+mod ci;
+mod codes;
+mod commands;
 mod config;
+mod db_dump;
+mod deny;
+mod duration;
+mod events;
 mod executor;
+mod git_source;
+mod graph;
+mod health;
+mod heuristics;
+mod index_history;
+mod integrity;
+mod interactive;
+mod journal;
+mod lockfile;
 mod metadata;
+mod metrics;
+mod notify;
+mod owners;
+mod plan;
+mod policy;
+mod provider;
 mod registry;
+mod report;
 mod resolver;
+mod resume;
+mod rollback;
+mod rollout;
+mod runlock;
+mod stamp;
+mod vendor;
+mod vet;
+mod wait;
 
 use std::ffi::OsString;
+use std::path::PathBuf;
 use std::process::Command;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use clap::Parser;
 use clap_cargo::{Features, Manifest, Workspace};
-use tracing::warn;
+use tracing::{debug, info, warn};
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
 
-use crate::config::{Config, Mode};
+use crate::codes::{ErrorCode, Outcome, bail_with};
+use crate::config::{
+    CiProvider, Config, ExitCodes, MetadataSourceKind, Mode, Origin, PinStrategy, SETTINGS,
+};
 
 #[derive(Debug, Parser)]
 #[command(bin_name = "cargo")]
@@ -38,6 +83,100 @@ struct Cli {
     workspace: Workspace,
     #[command(flatten)]
     features: Features,
+    #[arg(
+        long = "profile",
+        value_name = "NAME",
+        help = "Apply the [profile.NAME] table of cooldown.toml over its top-level settings (overrides COOLDOWN_PROFILE)."
+    )]
+    profile: Option<String>,
+    #[arg(
+        long = "trace",
+        value_name = "TARGET",
+        help = "Enable trace output and span timings for a module (registry, resolver, executor, cache, metadata, or all); repeatable."
+    )]
+    trace: Vec<String>,
+    #[arg(
+        long = "if-changed",
+        value_name = "GLOB",
+        num_args = 0..=1,
+        require_equals = true,
+        help = "Skip the cooldown checks when no file matching GLOB (default **/Cargo.toml and **/Cargo.lock) changed since the last successful run; repeatable."
+    )]
+    if_changed: Option<Vec<String>>,
+    #[arg(
+        long = "config",
+        value_name = "KEY=VALUE",
+        help = "Cargo configuration override, passed to every Cargo invocation including the forwarded command; repeatable."
+    )]
+    cargo_config: Vec<String>,
+    #[arg(
+        long = "timings",
+        help = "Print per-endpoint request counts, success rates, and latency percentiles for this run and across runs."
+    )]
+    timings: bool,
+    #[arg(
+        long = "pin-strategy",
+        value_enum,
+        value_name = "STRATEGY",
+        help = "Override COOLDOWN_PIN_STRATEGY for this run (newest_cooled, closest_below_current, same_minor_only, oldest_compatible, or fail)."
+    )]
+    pin_strategy: Option<PinStrategy>,
+    #[arg(
+        long = "keep-partial",
+        help = "Keep the pins already written to Cargo.lock when the run fails instead of restoring the lockfile."
+    )]
+    keep_partial: bool,
+    #[arg(
+        long = "wait",
+        help = "When releases are still inside their cooldown window, sleep until they cool down (rechecking every 15 minutes, up to COOLDOWN_MAX_WAIT_MINUTES) and then run the Cargo command instead of failing."
+    )]
+    wait: bool,
+    #[arg(
+        long = "interactive",
+        help = "Ask what to do about each release the run would pin: downgrade to a listed candidate, allow it (optionally recording it in the allowlist), wait for it to cool down, or abort."
+    )]
+    interactive: bool,
+    #[arg(
+        long = "explain",
+        value_name = "CODE",
+        help = "Explain an error code such as COOLDOWN002 and how to get past it, then exit."
+    )]
+    explain: Option<String>,
+    #[arg(
+        long = "as-of",
+        value_name = "TIMESTAMP",
+        help = "Evaluate the cooldown as of this RFC 3339 instant instead of now, seeing only releases published by then (overrides COOLDOWN_AS_OF)."
+    )]
+    as_of: Option<DateTime<Utc>>,
+    #[arg(
+        long = "allow-dirty",
+        help = "Pin even when Cargo.lock has uncommitted changes."
+    )]
+    allow_dirty: bool,
+    #[arg(
+        long = "commit",
+        help = "Commit the pinned lockfiles to git after a successful run, listing every pin in the message."
+    )]
+    commit: bool,
+    #[arg(
+        long = "ci",
+        value_enum,
+        value_name = "PROVIDER",
+        help = "Also report through the CI system's own channels (github: workflow annotations, job summary, and step outputs)."
+    )]
+    ci: Option<CiProvider>,
+    #[arg(
+        long = "events",
+        value_name = "PATH",
+        help = "Write one JSON object per decision (crate_inspected, crate_fresh, pin_attempted, pin_applied, pin_rejected, blocked) to PATH as it happens, or to stdout for `-`."
+    )]
+    events: Option<String>,
+    #[arg(
+        long = "blocking-graph",
+        value_name = "PATH",
+        help = "If pinning fails, draw the fresh releases and the requirements on them to PATH, as Mermaid for `.mmd` and Graphviz DOT otherwise."
+    )]
+    blocking_graph: Option<PathBuf>,
     #[arg(
         value_name = "CARGO_ARG",
         trailing_var_arg = true,
@@ -48,22 +187,45 @@ struct Cli {
     cargo_args: Vec<OsString>,
 }
 
-fn init_logging(verbose: bool) {
+fn init_logging(verbose: bool, trace_targets: &[String]) {
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        if verbose {
-            EnvFilter::new("cargo_cooldown=debug,cargo_cooldown::executor=debug,info")
+        let mut directives = vec![if verbose {
+            "cargo_cooldown=debug,info".to_string()
         } else {
-            EnvFilter::new("info")
-        }
+            "info".to_string()
+        }];
+        directives.extend(trace_targets.iter().map(|target| trace_directive(target)));
+        EnvFilter::new(directives.join(","))
     });
+    let span_events = if trace_targets.is_empty() {
+        FmtSpan::NONE
+    } else {
+        FmtSpan::CLOSE
+    };
     let _ = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_target(false)
+        .with_target(!trace_targets.is_empty())
+        .with_span_events(span_events)
         .without_time()
         .with_writer(std::io::stderr)
         .try_init();
 }
 
+/// Map user-facing `--trace` values (`registry`, `cooldown::registry`, `all`)
+/// onto the crate's module targets.
+fn trace_directive(target: &str) -> String {
+    let module = target
+        .trim()
+        .trim_start_matches("cargo_cooldown")
+        .trim_start_matches("cooldown")
+        .trim_start_matches("::");
+    if module.is_empty() || module == "all" {
+        "cargo_cooldown=trace".to_string()
+    } else {
+        format!("cargo_cooldown::{module}=trace")
+    }
+}
+
 fn parse_cli(raw_args: &[OsString]) -> Cli {
     match CargoCli::try_parse_from(raw_args.iter().cloned()) {
         Ok(CargoCli::Cooldown(cli)) => cli,
@@ -101,6 +263,11 @@ fn assemble_cargo_args(cli: &Cli) -> Vec<OsString> {
         args.push(path.into());
     }
 
+    for value in &cli.cargo_config {
+        args.push(OsString::from("--config"));
+        args.push(OsString::from(value));
+    }
+
     for package in &cli.workspace.package {
         args.push(OsString::from("--package"));
         args.push(OsString::from(package));
@@ -151,35 +318,294 @@ fn split_features(raw: &str) -> Vec<String> {
         .collect()
 }
 
+/// Platforms used to trim target-specific dependencies from the graph. Falls
+/// back to the unfiltered graph when the target cannot be determined.
+fn target_platforms(config: &Config, cargo_args: &[OsString]) -> Vec<String> {
+    if !config.filter_platform {
+        return Vec::new();
+    }
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    match metadata::target_platforms(cargo_args, &config.cargo_config, &cwd) {
+        Ok(platforms) => platforms,
+        Err(err) => {
+            warn!(error = %err, "could not determine the target platform; checking dependencies for every platform");
+            Vec::new()
+        }
+    }
+}
+
+/// Fingerprint for `--if-changed`, or for `skip_unchanged` before a Cargo
+/// command, taken over the workspace directory. Any failure disables the
+/// guard rather than skipping checks on a guess.
+fn if_changed_stamp(cli: &Cli, config: &Config) -> Option<stamp::Stamp> {
+    let patterns = match &cli.if_changed {
+        Some(patterns) => patterns.clone(),
+        // Built-in commands report rather than guard a build, so only the
+        // explicit flag skips them.
+        None if config.skip_unchanged && commands::parse(&cli.cargo_args).is_none() => Vec::new(),
+        None => return None,
+    };
+    // Recording a pass at another instant would skip the real checks later.
+    if config.as_of.is_some() {
+        return None;
+    }
+    let scope = format!(
+        "features={:?}\nplatforms={:?}\ncargo_config={:?}",
+        cli.features,
+        target_platforms(config, &cli.cargo_args),
+        config.cargo_config
+    );
+    let root = cli
+        .manifest
+        .manifest_path
+        .as_ref()
+        .and_then(|path| path.parent())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    match stamp::Stamp::new(config, &root, &patterns, &scope) {
+        Ok(stamp) => Some(stamp),
+        Err(err) => {
+            warn!(error = %err, "could not fingerprint watched files; running the full checks");
+            None
+        }
+    }
+}
+
+fn record_stamp(stamp: Option<&stamp::Stamp>) {
+    if let Some(stamp) = stamp
+        && let Err(err) = stamp.record()
+    {
+        warn!(error = %err, "failed to record the unchanged-files stamp");
+    }
+}
+
+/// Fold this run's registry health into the cache and print it on `--timings`.
+fn report_health(config: &Config, timings: bool) {
+    let history = match cache::Cache::for_config(config).and_then(|cache| health::persist(&cache)) {
+        Ok(history) => history,
+        Err(err) => {
+            warn!(error = %err, "failed to persist registry health");
+            Default::default()
+        }
+    };
+    if timings {
+        eprint!(
+            "{}",
+            health::render("registry requests (this run):", &health::current())
+        );
+        eprint!(
+            "{}",
+            health::render("registry requests (all runs):", &history)
+        );
+    }
+}
+
+async fn export_metrics(config: &Config) {
+    if let Err(err) = metrics::export(config).await {
+        warn!(error = %err, "failed to export metrics");
+    }
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
+    let code = match run().await {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("{}", codes::render_error(&err));
+            1
+        }
+    };
+    std::process::exit(code);
+}
+
+/// Configure the run, then execute it and map how it ended to an exit code.
+async fn run() -> Result<i32> {
     let raw_args: Vec<OsString> = std::env::args_os().collect();
     let cli = parse_cli(&raw_args);
-    let config = Config::from_env();
-    init_logging(config.verbose);
+    if let Some(code) = &cli.explain {
+        print!("{}", codes::explain(code)?);
+        return Ok(0);
+    }
+    let diagnoses_config =
+        commands::parse(&cli.cargo_args).is_some_and(|builtin| builtin.diagnoses_config());
+    let loaded = if diagnoses_config {
+        Config::skipping_broken_files(cli.profile.as_deref())
+    } else {
+        Config::with_profile(cli.profile.as_deref())
+    };
+    let mut config = match loaded {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", codes::render_error(&err));
+            // The file that failed may be the one mapping exit codes.
+            let exit_codes = std::env::var("COOLDOWN_EXIT_CODES")
+                .ok()
+                .and_then(|codes| ExitCodes::parse(&codes).ok())
+                .unwrap_or_default();
+            return Ok(exit_codes.code(Outcome::of(&err)));
+        }
+    };
+    config.cargo_config = cli.cargo_config.clone();
+    config
+        .cargo_config
+        .extend(metadata::config_overrides(&cli.cargo_args));
+    if let Some(strategy) = cli.pin_strategy {
+        config.pin_strategy = strategy;
+        config.set_flag("pin_strategy", "--pin-strategy");
+    }
+    let (offline, locked) = metadata::offline_and_locked(&cli.cargo_args);
+    if offline {
+        // Cargo cannot reach the registry either, so the cache is all there is.
+        config.offline = true;
+        config.metadata_sources = vec![MetadataSourceKind::Cache];
+        config.sparse_version_lists = false;
+        config.cargo_config.push("net.offline=true".to_string());
+        config.set_flag("metadata_sources", "--offline");
+    }
+    if locked {
+        // Verify only: releases inside their window fail the run.
+        config.locked = true;
+        config.pin_strategy = PinStrategy::Fail;
+        config.set_flag("pin_strategy", "--locked");
+    }
+    for (enabled, setting, flag) in [
+        (cli.keep_partial, "keep_partial", "--keep-partial"),
+        (cli.wait, "wait", "--wait"),
+        (cli.allow_dirty, "allow_dirty", "--allow-dirty"),
+        (cli.commit, "commit", "--commit"),
+    ] {
+        if enabled {
+            config.set_flag(setting, flag);
+        }
+    }
+    config.keep_partial |= cli.keep_partial;
+    config.wait |= cli.wait;
+    config.allow_dirty |= cli.allow_dirty;
+    config.commit |= cli.commit;
+    if cli.interactive {
+        interactive::ensure_terminal()?;
+        config.interactive = true;
+    }
+    if cli.as_of.is_some() {
+        config.as_of = cli.as_of;
+        config.set_flag("as_of", "--as-of");
+    }
+    if cli.ci.is_some() {
+        config.ci = cli.ci;
+        config.set_flag("ci", "--ci");
+    }
+    if cli.events.is_some() {
+        config.events = cli.events.clone();
+        config.set_flag("events", "--events");
+    }
+    if cli.blocking_graph.is_some() {
+        config.blocking_graph_path = cli.blocking_graph.clone();
+        config.set_flag("blocking_graph_path", "--blocking-graph");
+    }
+    init_logging(config.verbose, &cli.trace);
+    for (setting, _) in SETTINGS {
+        let origin = config.origin(setting);
+        if origin != Origin::Default {
+            debug!(setting, source = %origin, "configured");
+        }
+    }
+    if let Some(rollout) = rollout::apply(&mut config) {
+        let outcome = if rollout.enforced {
+            "enforces"
+        } else {
+            "only warns"
+        };
+        info!(
+            "enforcement rollout at {}%: this run (bucket {}) {outcome}",
+            rollout.percent, rollout.bucket
+        );
+    }
+    match execute(&cli, &config).await {
+        Ok(outcome) => Ok(config.exit_codes.code(outcome)),
+        Err(err) => {
+            eprintln!("{}", codes::render_error(&err));
+            Ok(config.exit_codes.code(Outcome::of(&err)))
+        }
+    }
+}
+
+/// Everything after configuration: built-in commands, or the checks and
+/// then the forwarded Cargo command.
+async fn execute(cli: &Cli, config: &Config) -> Result<Outcome> {
+    // Windows are measured at the fixed instant, so sleeping would never
+    // let anything cool down.
+    if config.wait && config.as_of.is_some() {
+        bail_with!(
+            ErrorCode::InvalidConfig,
+            "--wait cannot be combined with --as-of, which evaluates the graph at a fixed time"
+        );
+    }
+    // A pass at another instant says nothing about the lockfile today.
+    if config.certificate_path.is_some() && config.as_of.is_some() {
+        bail_with!(
+            ErrorCode::InvalidConfig,
+            "certificate_path cannot be combined with --as-of; a certificate must vouch for the policy as of now"
+        );
+    }
+    let stamp = if_changed_stamp(cli, config);
+    let unchanged = stamp
+        .as_ref()
+        .is_some_and(|stamp| stamp.is_current().unwrap_or(false));
+    if unchanged {
+        info!("no watched file changed since the last successful run; skipping cooldown checks");
+    }
 
-    let forwarded_args = assemble_cargo_args(&cli);
+    if let Some(builtin) = commands::parse(&cli.cargo_args) {
+        if unchanged {
+            return Ok(Outcome::Success);
+        }
+        let ctx = commands::Context {
+            config,
+            manifest: &cli.manifest,
+            features: &cli.features,
+            platforms: target_platforms(config, &cli.cargo_args),
+        };
+        let outcome = commands::run(builtin, &ctx).await;
+        report_health(config, cli.timings);
+        export_metrics(config).await;
+        let outcome = outcome?;
+        if outcome == Outcome::Success {
+            record_stamp(stamp.as_ref());
+        }
+        return Ok(outcome);
+    }
+
+    let forwarded_args = assemble_cargo_args(cli);
 
     if forwarded_args.is_empty() {
         eprintln!("Usage: cargo cooldown <cargo-command> [args...]");
         std::process::exit(2);
     }
 
-    if matches!(
-        cli.cargo_args.first().and_then(|value| value.to_str()),
-        Some("update")
-    ) {
-        eprintln!(
-            "cargo-cooldown is designed for commands like build, check, test, or run.\n\
-             Running it with `cargo update` would replace the lockfile you just cooled down.\n\
-             Invoke `cargo update` directly instead if you truly intend to refresh dependency versions."
-        );
-        std::process::exit(2);
-    }
-
-    if config.mode != Mode::Off && config.cooldown_minutes > 0 {
-        match executor::run_pinning_flow(&config, &cli.manifest, &cli.features).await {
-            Ok(_) => {}
+    if !unchanged
+        && config.mode != Mode::Off
+        && (config.has_policy() || executor::has_bans(config, &cli.manifest))
+    {
+        let platforms = target_platforms(config, &cli.cargo_args);
+        rollback::handle_interrupts();
+        let outcome = if config.wait {
+            wait::until_cooled(config, &cli.manifest, &cli.features, &platforms).await
+        } else {
+            executor::run_pinning_flow(config, &cli.manifest, &cli.features, &platforms).await
+        };
+        report_health(config, cli.timings);
+        export_metrics(config).await;
+        match outcome {
+            Ok(_) => {
+                record_stamp(stamp.as_ref());
+                if config.mode == Mode::Enforce
+                    && let Some(path) = &config.certificate_path
+                {
+                    let issued = certificate::issue(config, &cli.manifest)?;
+                    certificate::write(&issued, path)?;
+                }
+            }
             Err(err) => match config.mode {
                 Mode::Warn => {
                     warn!(error = %err, "cooldown guard failed; continuing due to warn mode");
@@ -193,12 +619,15 @@ async fn main() -> Result<()> {
     }
 
     let status = Command::new("cargo").args(&forwarded_args).status()?;
-    std::process::exit(status.code().unwrap_or(1));
+    Ok(match status.code() {
+        Some(0) => Outcome::Success,
+        code => Outcome::Cargo(code.unwrap_or(1)),
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{assemble_cargo_args, parse_cli};
+    use super::{assemble_cargo_args, parse_cli, trace_directive};
     use std::ffi::OsString;
     use std::path::PathBuf;
 
@@ -251,6 +680,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn trace_flag_is_not_forwarded() {
+        let raw = to_os_vec(&[
+            "cargo-cooldown",
+            "cooldown",
+            "--trace",
+            "cooldown::registry",
+            "--trace",
+            "resolver",
+            "build",
+        ]);
+        let cli = parse_cli(&raw);
+        assert_eq!(cli.trace, vec!["cooldown::registry", "resolver"]);
+        assert_eq!(to_string_vec(&assemble_cargo_args(&cli)), vec!["build"]);
+        assert_eq!(
+            trace_directive("cooldown::registry"),
+            "cargo_cooldown::registry=trace"
+        );
+        assert_eq!(
+            trace_directive("resolver"),
+            "cargo_cooldown::resolver=trace"
+        );
+        assert_eq!(trace_directive("all"), "cargo_cooldown=trace");
+    }
+
+    #[test]
+    fn if_changed_takes_patterns_only_with_equals() {
+        let raw = to_os_vec(&["cargo-cooldown", "cooldown", "--if-changed", "check"]);
+        let cli = parse_cli(&raw);
+        assert_eq!(cli.if_changed, Some(Vec::new()));
+        assert_eq!(to_string_vec(&assemble_cargo_args(&cli)), vec!["check"]);
+
+        let raw = to_os_vec(&[
+            "cargo-cooldown",
+            "cooldown",
+            "--if-changed=**/Cargo.lock",
+            "--if-changed=rust-toolchain.toml",
+            "check",
+        ]);
+        let cli = parse_cli(&raw);
+        assert_eq!(
+            cli.if_changed,
+            Some(vec![
+                "**/Cargo.lock".to_string(),
+                "rust-toolchain.toml".to_string()
+            ])
+        );
+        assert!(
+            parse_cli(&to_os_vec(&["cargo-cooldown", "check"]))
+                .if_changed
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn config_overrides_are_forwarded() {
+        let raw = to_os_vec(&[
+            "cargo-cooldown",
+            "cooldown",
+            "--config",
+            "net.git-fetch-with-cli=true",
+            "build",
+            "--config=net.retry=5",
+        ]);
+        let cli = parse_cli(&raw);
+        assert_eq!(cli.cargo_config, vec!["net.git-fetch-with-cli=true"]);
+        assert_eq!(
+            to_string_vec(&assemble_cargo_args(&cli)),
+            vec![
+                "build",
+                "--config",
+                "net.git-fetch-with-cli=true",
+                "--config=net.retry=5"
+            ]
+        );
+    }
+
     #[test]
     fn parse_detects_update_command() {
         let raw = to_os_vec(&[
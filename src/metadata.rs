@@ -1,11 +1,447 @@
-use anyhow::Result;
-use cargo_metadata::Metadata;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use cargo_metadata::{Metadata, MetadataCommand};
 
 use clap_cargo::{Features, Manifest};
+use tracing::instrument;
+
+use crate::config::Config;
+
+#[instrument(level = "debug", skip_all, fields(platforms = ?platforms))]
+pub fn read_metadata(
+    manifest: &Manifest,
+    features: &Features,
+    platforms: &[String],
+    config: &Config,
+) -> Result<Metadata> {
+    let metadata = metadata_command(
+        manifest,
+        features,
+        platforms,
+        &config.cargo_config,
+        config.locked,
+    )
+    .exec()?;
+    Ok(metadata)
+}
 
-pub fn read_metadata(manifest: &Manifest, features: &Features) -> Result<Metadata> {
+/// `cargo metadata` invocation for the graph cargo will actually build:
+/// `--manifest-path`, the feature selectors, and `--config` overrides are
+/// forwarded as given, and the resolve is trimmed to dependencies compiled
+/// for `platforms`. With `locked`, Cargo may not rewrite the lockfile.
+fn metadata_command(
+    manifest: &Manifest,
+    features: &Features,
+    platforms: &[String],
+    cargo_config: &[String],
+    locked: bool,
+) -> MetadataCommand {
     let mut command = manifest.metadata();
     features.forward_metadata(&mut command);
-    let metadata = command.exec()?;
-    Ok(metadata)
+    let mut options: Vec<String> = platforms
+        .iter()
+        .flat_map(|triple| ["--filter-platform".to_string(), triple.clone()])
+        .collect();
+    options.extend(config_args(cargo_config));
+    if locked {
+        options.push("--locked".to_string());
+    }
+    command.other_options(options);
+    command
+}
+
+/// `--config` arguments for every Cargo invocation, so resolution during the
+/// cooldown phase sees the same registries, net settings, and patches as the
+/// real build.
+pub fn config_args(cargo_config: &[String]) -> Vec<String> {
+    cargo_config
+        .iter()
+        .flat_map(|value| ["--config".to_string(), value.clone()])
+        .collect()
+}
+
+/// Whether the forwarded Cargo arguments pass `flag` to Cargo itself, as
+/// opposed to the program run after `--`.
+fn forwards_flag(cargo_args: &[OsString], flag: &str) -> bool {
+    cargo_args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == flag)
+}
+
+/// Whether the forwarded Cargo arguments keep Cargo off the network
+/// (`--offline` or `--frozen`), and the lockfile as it is (`--locked` or
+/// `--frozen`).
+pub fn offline_and_locked(cargo_args: &[OsString]) -> (bool, bool) {
+    let frozen = forwards_flag(cargo_args, "--frozen");
+    (
+        frozen || forwards_flag(cargo_args, "--offline"),
+        frozen || forwards_flag(cargo_args, "--locked"),
+    )
+}
+
+/// Every `--config` value in the forwarded Cargo arguments.
+pub fn config_overrides(cargo_args: &[OsString]) -> Vec<String> {
+    let mut overrides = Vec::new();
+    let mut iter = cargo_args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            if let Some(value) = iter.next() {
+                overrides.push(value.into_owned());
+            }
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            overrides.push(value.to_string());
+        }
+    }
+    overrides
+}
+
+/// Target triples the build compiles for, resolved the way Cargo does:
+/// every `--target` in the forwarded Cargo arguments, else `build.target`
+/// from `--config` values, `CARGO_BUILD_TARGET`, and the `.cargo/config.toml`
+/// files from `cwd` up to `CARGO_HOME`, else the host triple. A config file
+/// that cannot be read is an error, so callers check every platform rather
+/// than guess.
+pub fn target_platforms(
+    cargo_args: &[OsString],
+    cargo_config: &[String],
+    cwd: &Path,
+) -> Result<Vec<String>> {
+    let targets = targets_from_args(cargo_args);
+    if !targets.is_empty() {
+        return Ok(targets);
+    }
+    // Later values win, as with Cargo's own `--config`.
+    for value in cargo_config.iter().rev() {
+        let table = match value.parse::<toml::Table>() {
+            Ok(table) => table,
+            // Not `KEY=VALUE`, so a path to an extra config file.
+            Err(_) => read_cargo_config(Path::new(value))?,
+        };
+        if let Some(targets) = build_target(&table)? {
+            return Ok(targets);
+        }
+    }
+    if let Ok(target) = env::var("CARGO_BUILD_TARGET")
+        && !target.is_empty()
+    {
+        return Ok(vec![target]);
+    }
+    for path in cargo_config_files(cwd) {
+        if let Some(targets) = build_target(&read_cargo_config(&path)?)? {
+            return Ok(targets);
+        }
+    }
+    Ok(vec![host_triple()?])
+}
+
+/// Cargo's config files, most specific first: `.cargo/config.toml` (or the
+/// older `.cargo/config`) in `cwd` and each of its ancestors, then the one
+/// in `CARGO_HOME`.
+fn cargo_config_files(cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = cwd.ancestors().map(|dir| dir.join(".cargo")).collect();
+    if let Some(home) = cargo_home()
+        && !dirs.contains(&home)
+    {
+        dirs.push(home);
+    }
+    dirs.into_iter()
+        .filter_map(|dir| {
+            [dir.join("config.toml"), dir.join("config")]
+                .into_iter()
+                .find(|path| path.is_file())
+        })
+        .collect()
+}
+
+fn read_cargo_config(path: &Path) -> Result<toml::Table> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    contents
+        .parse()
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// `build.target` of a Cargo config table, a triple or a list of them.
+fn build_target(table: &toml::Table) -> Result<Option<Vec<String>>> {
+    let Some(target) = table.get("build").and_then(|build| build.get("target")) else {
+        return Ok(None);
+    };
+    let targets = match target {
+        toml::Value::String(target) => vec![target.clone()],
+        toml::Value::Array(targets) => targets
+            .iter()
+            .map(|target| {
+                target
+                    .as_str()
+                    .map(str::to_string)
+                    .context("`build.target` entries must be strings")
+            })
+            .collect::<Result<_>>()?,
+        _ => bail!("`build.target` must be a string or an array of strings"),
+    };
+    Ok(Some(targets).filter(|targets: &Vec<String>| !targets.is_empty()))
+}
+
+fn targets_from_args(cargo_args: &[OsString]) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut iter = cargo_args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--target" {
+            if let Some(value) = iter.next() {
+                targets.extend(value.split(',').map(str::to_string));
+            }
+        } else if let Some(value) = arg.strip_prefix("--target=") {
+            targets.extend(value.split(',').map(str::to_string));
+        }
+    }
+    targets.retain(|target| !target.is_empty());
+    targets
+}
+
+fn host_triple() -> Result<String> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
+    let output = Command::new(&rustc)
+        .arg("-vV")
+        .output()
+        .context("failed to run `rustc -vV`")?;
+    if !output.status.success() {
+        bail!(
+            "`rustc -vV` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_string())
+        .context("`rustc -vV` did not report a host triple")
+}
+
+/// Location of the `Cargo.lock` that belongs to the workspace selected by
+/// `manifest`, resolved through `cargo locate-project --workspace`.
+pub fn lockfile_path(manifest: &Manifest) -> Result<PathBuf> {
+    let mut command = Command::new("cargo");
+    command.args(["locate-project", "--workspace", "--message-format", "plain"]);
+    if let Some(path) = &manifest.manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+    let output = command
+        .output()
+        .context("failed to run `cargo locate-project`")?;
+    if !output.status.success() {
+        bail!(
+            "`cargo locate-project` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let root_manifest = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    let root = root_manifest
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    Ok(root.join("Cargo.lock"))
+}
+
+/// Cargo's home directory, where it keeps downloaded sources and git clones.
+pub fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Find standalone Cargo projects (their own `Cargo.toml` and `Cargo.lock`)
+/// in the given directories under `workspace_root`, or one level below them.
+pub fn discover_subprojects(workspace_root: &Path, dirs: &[String]) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for dir in dirs {
+        let base = workspace_root.join(dir);
+        if !base.is_dir() {
+            continue;
+        }
+        let mut candidates = vec![base.clone()];
+        if let Ok(entries) = std::fs::read_dir(&base) {
+            let mut children: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir())
+                .collect();
+            children.sort();
+            candidates.extend(children);
+        }
+        for candidate in candidates {
+            let manifest = candidate.join("Cargo.toml");
+            if manifest.is_file() && candidate.join("Cargo.lock").is_file() {
+                found.push(manifest);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn metadata_command_forwards_selectors() {
+        let mut manifest = Manifest::default();
+        manifest.manifest_path = Some(PathBuf::from("examples/demo/Cargo.toml"));
+        let mut features = Features::default();
+        features.no_default_features = true;
+        features.features = vec!["serde,rt".to_string()];
+
+        let platforms = vec!["x86_64-unknown-linux-gnu".to_string()];
+        let cargo_config = vec!["net.offline=true".to_string()];
+        let command =
+            metadata_command(&manifest, &features, &platforms, &cargo_config, true).cargo_command();
+        let args: Vec<String> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let position = |flag: &str| args.iter().position(|arg| arg == flag).unwrap();
+        assert_eq!(
+            args[position("--manifest-path") + 1],
+            "examples/demo/Cargo.toml"
+        );
+        assert!(args.contains(&"--no-default-features".to_string()));
+        assert!(args.contains(&"--locked".to_string()));
+        assert!(args[position("--features") + 1].contains("serde"));
+        assert!(!args.contains(&"--all-features".to_string()));
+        assert_eq!(
+            args[position("--filter-platform") + 1],
+            "x86_64-unknown-linux-gnu"
+        );
+        assert_eq!(args[position("--config") + 1], "net.offline=true");
+    }
+
+    #[test]
+    fn forwards_flags_before_the_program_arguments() {
+        let args: Vec<OsString> = ["test", "--frozen", "--", "--offline"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert!(forwards_flag(&args, "--frozen"));
+        // After `--` the flag belongs to the test binary, not Cargo.
+        assert!(!forwards_flag(&args, "--offline"));
+        assert!(!forwards_flag(&args, "--locked"));
+        assert!(!forwards_flag(&[OsString::from("--lockedx")], "--locked"));
+
+        assert_eq!(offline_and_locked(&args), (true, true));
+        let offline: Vec<OsString> = ["build", "--offline", "--", "--locked"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(offline_and_locked(&offline), (true, false));
+        assert_eq!(
+            offline_and_locked(&[OsString::from("--locked")]),
+            (false, true)
+        );
+    }
+
+    #[test]
+    fn resolves_the_target_from_cargo_config() {
+        let workspace = TempDir::new().unwrap();
+        let member = workspace.child("crates/app");
+        member.create_dir_all().unwrap();
+        workspace
+            .child(".cargo/config.toml")
+            .write_str("[build]\ntarget = \"aarch64-unknown-linux-gnu\"\n")
+            .unwrap();
+
+        assert_eq!(
+            target_platforms(&[], &[], member.path()).unwrap(),
+            ["aarch64-unknown-linux-gnu"]
+        );
+        let overrides = [
+            "net.offline=true".to_string(),
+            "build.target=[\"wasm32-unknown-unknown\", \"x86_64-pc-windows-msvc\"]".to_string(),
+        ];
+        assert_eq!(
+            target_platforms(&[], &overrides, member.path()).unwrap(),
+            ["wasm32-unknown-unknown", "x86_64-pc-windows-msvc"]
+        );
+        let args = [OsString::from("--target=riscv64gc-unknown-none-elf")];
+        assert_eq!(
+            target_platforms(&args, &overrides, member.path()).unwrap(),
+            ["riscv64gc-unknown-none-elf"]
+        );
+
+        // An unreadable config means the target is unknown, not the host.
+        workspace
+            .child("crates/.cargo/config.toml")
+            .write_str("[build\n")
+            .unwrap();
+        assert!(target_platforms(&[], &[], member.path()).is_err());
+    }
+
+    #[test]
+    fn collects_target_flags() {
+        let args: Vec<OsString> = [
+            "build",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--target=aarch64-apple-darwin",
+            "--config",
+            "net.retry=5",
+            "--config=patch.crates-io.foo.path='../foo'",
+            "--",
+            "--target",
+            "ignored",
+            "--config=ignored",
+        ]
+        .iter()
+        .map(OsString::from)
+        .collect();
+        assert_eq!(
+            targets_from_args(&args),
+            vec!["wasm32-unknown-unknown", "aarch64-apple-darwin"]
+        );
+        assert_eq!(
+            config_overrides(&args),
+            vec!["net.retry=5", "patch.crates-io.foo.path='../foo'"]
+        );
+    }
+
+    #[test]
+    fn discovers_subprojects_with_lockfiles() {
+        let root = TempDir::new().unwrap();
+        root.child("fuzz/Cargo.toml").write_str("").unwrap();
+        root.child("fuzz/Cargo.lock").write_str("").unwrap();
+        root.child("examples/demo/Cargo.toml")
+            .write_str("")
+            .unwrap();
+        root.child("examples/demo/Cargo.lock")
+            .write_str("")
+            .unwrap();
+        root.child("examples/nolock/Cargo.toml")
+            .write_str("")
+            .unwrap();
+        root.child("xtask/Cargo.toml").write_str("").unwrap();
+
+        let dirs = vec![
+            "examples".to_string(),
+            "fuzz".to_string(),
+            "xtask".to_string(),
+        ];
+        let found = discover_subprojects(root.path(), &dirs);
+        assert_eq!(
+            found,
+            vec![
+                root.path().join("examples/demo/Cargo.toml"),
+                root.path().join("fuzz/Cargo.toml"),
+            ]
+        );
+    }
 }
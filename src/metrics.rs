@@ -0,0 +1,205 @@
+//! Run counters in the Prometheus text format, written for the node
+//! exporter's textfile collector or pushed to a Pushgateway, so cooldown
+//! enforcement shows up on dashboards.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+use crate::events::Event;
+use crate::health::{self, EndpointStats};
+use crate::registry;
+use crate::rollout::RolloutDecision;
+
+static CRATES_CHECKED: AtomicU64 = AtomicU64::new(0);
+static FRESH_CRATES: AtomicU64 = AtomicU64::new(0);
+static PINS_APPLIED: AtomicU64 = AtomicU64::new(0);
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Count the decisions that have a metric.
+pub fn observe(event: &Event<'_>) {
+    let counter = match event {
+        Event::CrateInspected { .. } => &CRATES_CHECKED,
+        Event::CrateFresh { .. } => &FRESH_CRATES,
+        Event::PinApplied { .. } => &PINS_APPLIED,
+        _ => return,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn cache_lookup(hit: bool) {
+    let counter = if hit { &CACHE_HITS } else { &CACHE_MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Write or push this run's metrics, wherever the configuration asks for.
+pub async fn export(config: &Config) -> Result<()> {
+    if config.metrics_path.is_none() && config.metrics_pushgateway.is_none() {
+        return Ok(());
+    }
+    let text = render(&health::current(), config.rollout, Utc::now());
+    if let Some(path) = &config.metrics_path {
+        write_textfile(path, &text)?;
+    }
+    if let Some(gateway) = &config.metrics_pushgateway {
+        let url = format!(
+            "{}/metrics/job/cargo-cooldown",
+            gateway.trim_end_matches('/')
+        );
+        registry::http_client(config)?
+            .put(&url)
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(text)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to push metrics to {url}"))?;
+    }
+    Ok(())
+}
+
+/// The collector may read at any time, so the file is replaced in one step.
+fn write_textfile(path: &Path, text: &str) -> Result<()> {
+    let partial = path.with_extension("prom.tmp");
+    fs::write(&partial, text)
+        .and_then(|()| fs::rename(&partial, path))
+        .with_context(|| format!("failed to write metrics to {}", path.display()))
+}
+
+fn render(
+    endpoints: &BTreeMap<String, EndpointStats>,
+    rollout: Option<RolloutDecision>,
+    now: DateTime<Utc>,
+) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, value: u64| {
+        let _ = writeln!(
+            out,
+            "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}"
+        );
+    };
+    gauge(
+        "cargo_cooldown_crates_checked",
+        "Registry releases whose age was looked up.",
+        CRATES_CHECKED.load(Ordering::Relaxed),
+    );
+    gauge(
+        "cargo_cooldown_fresh_crates",
+        "Releases found inside their cooldown window.",
+        FRESH_CRATES.load(Ordering::Relaxed),
+    );
+    gauge(
+        "cargo_cooldown_pins_applied",
+        "Pins written to Cargo.lock.",
+        PINS_APPLIED.load(Ordering::Relaxed),
+    );
+    gauge(
+        "cargo_cooldown_cache_hits",
+        "Metadata lookups answered by the cache.",
+        CACHE_HITS.load(Ordering::Relaxed),
+    );
+    gauge(
+        "cargo_cooldown_cache_misses",
+        "Metadata lookups the cache could not answer.",
+        CACHE_MISSES.load(Ordering::Relaxed),
+    );
+    if let Some(rollout) = rollout {
+        gauge(
+            "cargo_cooldown_enforced",
+            "Whether the enforcement rollout let this run enforce (1) or only warn (0).",
+            u64::from(rollout.enforced),
+        );
+        gauge(
+            "cargo_cooldown_rollout_bucket",
+            "The run's rollout bucket; buckets below the sample percent enforce.",
+            u64::from(rollout.bucket),
+        );
+    }
+    gauge(
+        "cargo_cooldown_last_run_timestamp_seconds",
+        "When the run finished.",
+        now.timestamp().max(0) as u64,
+    );
+
+    if endpoints.is_empty() {
+        return out;
+    }
+    out.push_str("# HELP cargo_cooldown_api_latency_seconds Registry request latency.\n");
+    out.push_str("# TYPE cargo_cooldown_api_latency_seconds summary\n");
+    for (endpoint, stats) in endpoints {
+        let label = endpoint.replace('\\', "\\\\").replace('"', "\\\"");
+        for (quantile, percentile) in [("0.5", 50.0), ("0.9", 90.0), ("0.99", 99.0)] {
+            if let Some(ms) = stats.percentile(percentile) {
+                let _ = writeln!(
+                    out,
+                    "cargo_cooldown_api_latency_seconds{{endpoint=\"{label}\",quantile=\"{quantile}\"}} {}",
+                    ms as f64 / 1000.0
+                );
+            }
+        }
+        let sum: u64 = stats.latencies_ms.iter().sum();
+        let _ = writeln!(
+            out,
+            "cargo_cooldown_api_latency_seconds_sum{{endpoint=\"{label}\"}} {}\n\
+             cargo_cooldown_api_latency_seconds_count{{endpoint=\"{label}\"}} {}",
+            sum as f64 / 1000.0,
+            stats.latencies_ms.len()
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn renders_latency_summaries_per_endpoint() {
+        let endpoints = [(
+            "index.crates.io".to_string(),
+            EndpointStats {
+                successes: 2,
+                failures: 0,
+                latencies_ms: vec![100, 300],
+            },
+        )]
+        .into();
+        let text = render(
+            &endpoints,
+            None,
+            Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap(),
+        );
+        assert!(!text.contains("cargo_cooldown_enforced"));
+        assert!(text.contains("# TYPE cargo_cooldown_pins_applied gauge\n"));
+        assert!(text.contains("cargo_cooldown_last_run_timestamp_seconds 1735776000\n"));
+        assert!(text.contains(
+            "cargo_cooldown_api_latency_seconds{endpoint=\"index.crates.io\",quantile=\"0.5\"} 0.1\n"
+        ));
+        assert!(text.contains(
+            "cargo_cooldown_api_latency_seconds_sum{endpoint=\"index.crates.io\"} 0.4\n"
+        ));
+        assert!(text.contains(
+            "cargo_cooldown_api_latency_seconds_count{endpoint=\"index.crates.io\"} 2\n"
+        ));
+    }
+
+    #[test]
+    fn renders_the_rollout_decision() {
+        let rollout = RolloutDecision {
+            percent: 25,
+            bucket: 62,
+            enforced: false,
+        };
+        let text = render(&BTreeMap::new(), Some(rollout), Utc::now());
+        assert!(text.contains("# TYPE cargo_cooldown_enforced gauge\ncargo_cooldown_enforced 0\n"));
+        assert!(text.contains("cargo_cooldown_rollout_bucket 62\n"));
+    }
+}
@@ -0,0 +1,139 @@
+//! Webhook notifications when enforcement blocks a build.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::registry;
+use crate::report::{Violation, format_age};
+
+/// Used when `webhook_template` is not set. `{project}`, `{message}`, and
+/// `{crates}` (one line per release) are filled in.
+pub const DEFAULT_TEMPLATE: &str = "cargo-cooldown blocked {project}: {message}\n{crates}";
+
+/// POST the releases that blocked `project` to the configured webhook. The
+/// payload carries the rendered `text` that Slack and Teams display, next to
+/// the same details as fields for generic receivers. Delivery failures are
+/// logged; they never change the outcome of the run.
+pub async fn blocked(
+    config: &Config,
+    project: &str,
+    message: &str,
+    violations: &[Violation],
+    now: DateTime<Utc>,
+) {
+    let Some(url) = &config.webhook_url else {
+        return;
+    };
+    let template = config
+        .webhook_template
+        .as_deref()
+        .unwrap_or(DEFAULT_TEMPLATE);
+    let payload = payload(template, project, message, violations, now);
+    match post(config, url, &payload).await {
+        Ok(()) => info!("sent the blocked build to the webhook"),
+        Err(err) => warn!(error = %err, "failed to notify the webhook"),
+    }
+}
+
+/// What notifications call the workspace owning `lockfile`.
+pub fn project_name(lockfile: &Path) -> String {
+    lockfile
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+async fn post(config: &Config, url: &str, payload: &serde_json::Value) -> Result<()> {
+    registry::http_client(config)?
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("POST {url} failed"))?;
+    Ok(())
+}
+
+fn payload(
+    template: &str,
+    project: &str,
+    message: &str,
+    violations: &[Violation],
+    now: DateTime<Utc>,
+) -> serde_json::Value {
+    let lines: Vec<String> = violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "- {} {}: published {} ago, needs {} minutes",
+                violation.name,
+                violation.version,
+                format_age(now - violation.published_at),
+                violation.minimum_minutes
+            )
+        })
+        .collect();
+    let text = template
+        .replace("{project}", project)
+        .replace("{message}", message)
+        .replace("{crates}", &lines.join("\n"));
+    let crates: Vec<_> = violations
+        .iter()
+        .map(|violation| {
+            json!({
+                "crate": violation.name,
+                "version": violation.version,
+                "published_at": violation.published_at,
+                "age_minutes": (now - violation.published_at).num_minutes().max(0),
+                "required_minutes": violation.minimum_minutes,
+            })
+        })
+        .collect();
+    json!({
+        "text": text.trim_end(),
+        "project": project,
+        "message": message,
+        "crates": crates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn payload_has_text_and_fields() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let violation = Violation {
+            name: "serde".into(),
+            version: "1.0.210".into(),
+            published_at: Utc.with_ymd_and_hms(2025, 1, 2, 9, 0, 0).unwrap(),
+            minimum_minutes: 1440,
+            cools_down_at: Utc.with_ymd_and_hms(2025, 1, 2, 9, 0, 0).unwrap()
+                + Duration::minutes(1440),
+            via: Vec::new(),
+        };
+        let payload = payload(
+            DEFAULT_TEMPLATE,
+            "app",
+            "1 release too new",
+            &[violation],
+            now,
+        );
+        assert_eq!(
+            payload["text"],
+            "cargo-cooldown blocked app: 1 release too new\n\
+             - serde 1.0.210: published 3h 0m ago, needs 1440 minutes"
+        );
+        assert_eq!(payload["crates"][0]["crate"], "serde");
+        assert_eq!(payload["crates"][0]["age_minutes"], 180);
+        assert_eq!(payload["crates"][0]["required_minutes"], 1440);
+    }
+}
@@ -0,0 +1,138 @@
+//! Owner-change detection: a recent release published by an account that
+//! published none of the earlier ones, or a crate that gained owners since
+//! the last run, gets a longer cooldown or blocks the run.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use tracing::debug;
+
+use crate::cache::Cache;
+use crate::config::{Config, OwnerChangePolicy};
+use crate::registry::{PublishedRelease, RegistryClient};
+
+/// Crate names cannot start with `_`, so this never shadows a cached crate.
+const CACHE_PREFIX: &str = "_owners";
+
+pub struct OwnerWatch {
+    client: RegistryClient,
+    minutes: u64,
+}
+
+impl OwnerWatch {
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        if config.owner_change == OwnerChangePolicy::Off {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            client: RegistryClient::new(config)?,
+            minutes: config.owner_change_minutes,
+        }))
+    }
+
+    pub fn minutes(&self) -> u64 {
+        self.minutes
+    }
+
+    /// Describe the ownership change behind `name@version`, if the release is
+    /// younger than the owner-change window and one happened.
+    pub async fn check(
+        &self,
+        cache: &Cache,
+        name: &str,
+        version: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<String>> {
+        let releases = self.releases(cache, name).await?;
+        let Some(release) = releases.iter().find(|release| release.num == version) else {
+            return Ok(None);
+        };
+        if release.created_at <= now - Duration::minutes(self.minutes as i64) {
+            return Ok(None);
+        }
+        if let Some(change) = new_publisher(&releases, release) {
+            return Ok(Some(change));
+        }
+
+        let owners: BTreeSet<String> = self.client.owners(name).await?.into_iter().collect();
+        let known_key = format!("{CACHE_PREFIX}/{name}/known");
+        let known: Option<BTreeSet<String>> = cache.get_stale(&known_key).ok().flatten();
+        cache.put(&known_key, &owners)?;
+        let Some(known) = known else {
+            debug!(crate = %name, ?owners, "recorded owners for the first time");
+            return Ok(None);
+        };
+        let added: Vec<&String> = owners.difference(&known).collect();
+        if added.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "{} added as owner since the last run",
+            added
+                .iter()
+                .map(|login| login.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    }
+
+    async fn releases(&self, cache: &Cache, name: &str) -> Result<Vec<PublishedRelease>> {
+        let key = format!("{CACHE_PREFIX}/{name}/releases");
+        if let Ok(Some(releases)) = cache.get(&key) {
+            return Ok(releases);
+        }
+        let releases = self.client.published_releases(name).await?;
+        cache.put(&key, &releases)?;
+        Ok(releases)
+    }
+}
+
+/// `release` was published by an account that published none of the earlier
+/// releases. Releases without publisher information are not judged.
+fn new_publisher(releases: &[PublishedRelease], release: &PublishedRelease) -> Option<String> {
+    let publisher = &release.published_by.as_ref()?.login;
+    let earlier: BTreeSet<&str> = releases
+        .iter()
+        .filter(|other| other.created_at < release.created_at)
+        .filter_map(|other| other.published_by.as_ref())
+        .map(|user| user.login.as_str())
+        .collect();
+    if earlier.is_empty() || earlier.contains(publisher.as_str()) {
+        return None;
+    }
+    Some(format!(
+        "published by {publisher}, who published none of the earlier releases"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::User;
+    use chrono::TimeZone;
+
+    #[test]
+    fn flags_releases_from_unseen_publishers() {
+        let release = |num: &str, day, login: Option<&str>| PublishedRelease {
+            num: num.into(),
+            created_at: Utc.with_ymd_and_hms(2025, 3, day, 12, 0, 0).unwrap(),
+            published_by: login.map(|login| User {
+                login: login.into(),
+            }),
+        };
+        let releases = vec![
+            release("1.0.0", 1, None),
+            release("1.1.0", 2, Some("alice")),
+            release("1.2.0", 3, Some("bob")),
+            release("1.2.1", 4, Some("alice")),
+        ];
+        assert_eq!(
+            new_publisher(&releases, &releases[2]).as_deref(),
+            Some("published by bob, who published none of the earlier releases")
+        );
+        assert!(new_publisher(&releases, &releases[3]).is_none());
+        // The first tracked publisher has nobody to be compared with.
+        assert!(new_publisher(&releases, &releases[1]).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! Choosing cooled-down releases for every fresh crate at once.
+//!
+//! The pinning loop tries one crate at a time and reacts to what Cargo
+//! rejects, which can go back and forth for a long time on tangled graphs.
+//! A plan instead picks a release for each fresh crate up front from the
+//! index metadata, with a bounded backtracking search, so the lockfile is
+//! edited once. It is a best-effort pre-plan, not a full resolver: it only
+//! moves the fresh crates, ignores features, and leaves the final say to
+//! Cargo, which rejects a plan that does not resolve; the pinning loop then
+//! takes over.
+
+use semver::{Version, VersionReq};
+
+use crate::graph::excluded_from_series;
+
+/// How many candidate choices the search may try before giving up on a plan.
+const MAX_STEPS: usize = 20_000;
+
+/// A fresh crate the plan has to choose a release for.
+#[derive(Debug, Clone)]
+pub struct PlanCrate<N> {
+    pub id: N,
+    pub name: String,
+    /// Packages depending on the crate, with their requirement on it. Those
+    /// that are planned themselves are checked against the dependencies of
+    /// the release chosen for them instead.
+    pub dependents: Vec<(N, VersionReq)>,
+    /// Releases to choose from, preferred first.
+    pub candidates: Vec<PlanCandidate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlanCandidate {
+    pub version: Version,
+    /// Required non-dev dependencies (crate name, requirement) of the
+    /// release.
+    pub deps: Vec<(String, VersionReq)>,
+}
+
+/// Pick one candidate per crate so that every requirement in the graph
+/// holds: the dependents' requirements, and each chosen release's
+/// dependencies against what stays locked (`locked`: id, name, version) or
+/// is chosen for another crate. A lockfile edit cannot add packages, so a
+/// release depending on a crate that is neither locked nor planned is never
+/// chosen. Returns the index of the chosen candidate of each crate, or
+/// `None` when no such choice exists or the search ran out of steps.
+pub fn solve<N: PartialEq>(
+    crates: &[PlanCrate<N>],
+    locked: &[(N, String, Version)],
+) -> Option<Vec<usize>> {
+    let mut chosen: Vec<usize> = Vec::with_capacity(crates.len());
+    let mut next = 0;
+    let mut steps = 0;
+    while chosen.len() < crates.len() {
+        let position = chosen.len();
+        let found = (next..crates[position].candidates.len())
+            .find(|&candidate| consistent(crates, locked, &chosen, position, candidate));
+        steps += 1;
+        if steps > MAX_STEPS {
+            return None;
+        }
+        match found {
+            Some(candidate) => {
+                chosen.push(candidate);
+                next = 0;
+            }
+            None => next = chosen.pop()? + 1,
+        }
+    }
+    Some(chosen)
+}
+
+/// Whether choosing `candidate` for `crates[position]` agrees with the
+/// choices already made for the crates before it.
+fn consistent<N: PartialEq>(
+    crates: &[PlanCrate<N>],
+    locked: &[(N, String, Version)],
+    chosen: &[usize],
+    position: usize,
+    candidate: usize,
+) -> bool {
+    let planned = &crates[position];
+    let release = &planned.candidates[candidate];
+    let is_planned = |id: &N| crates.iter().any(|other| &other.id == id);
+
+    let dependents_agree = planned
+        .dependents
+        .iter()
+        .filter(|(id, _)| !is_planned(id))
+        .all(|(_, req)| req.matches(&release.version));
+
+    let deps_are_locked = release.deps.iter().all(|(dep, req)| {
+        crates.iter().any(|other| other.name == *dep)
+            || locked
+                .iter()
+                .any(|(id, name, version)| name == dep && !is_planned(id) && req.matches(version))
+    });
+
+    let deps_agree_with_locked = release.deps.iter().all(|(dep, req)| {
+        locked
+            .iter()
+            .filter(|(id, name, _)| name == dep && !is_planned(id))
+            .all(|(_, _, version)| !excluded_from_series(req, version))
+    });
+
+    let agrees_with_chosen = chosen.iter().enumerate().all(|(earlier, &picked)| {
+        let other = &crates[earlier];
+        let other_release = &other.candidates[picked];
+        release
+            .deps
+            .iter()
+            .filter(|(dep, _)| *dep == other.name)
+            .all(|(_, req)| !excluded_from_series(req, &other_release.version))
+            && other_release
+                .deps
+                .iter()
+                .filter(|(dep, _)| *dep == planned.name)
+                .all(|(_, req)| !excluded_from_series(req, &release.version))
+    });
+
+    dependents_agree && deps_are_locked && deps_agree_with_locked && agrees_with_chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(raw: &str) -> VersionReq {
+        VersionReq::parse(raw).unwrap()
+    }
+
+    fn version(raw: &str) -> Version {
+        Version::parse(raw).unwrap()
+    }
+
+    fn release(raw: &str, deps: &[(&str, &str)]) -> PlanCandidate {
+        PlanCandidate {
+            version: version(raw),
+            deps: deps
+                .iter()
+                .map(|(name, raw)| (name.to_string(), req(raw)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn backtracks_to_a_consistent_plan() {
+        // The preferred tower release needs a bytes newer than any cooled
+        // one, so the plan has to settle for the older tower.
+        let crates = vec![
+            PlanCrate {
+                id: "tower",
+                name: "tower".to_string(),
+                dependents: vec![("app", req("^0.5"))],
+                candidates: vec![
+                    release("0.5.2", &[("bytes", "^1.6")]),
+                    release("0.5.1", &[("bytes", "^1.4")]),
+                ],
+            },
+            PlanCrate {
+                id: "bytes",
+                name: "bytes".to_string(),
+                dependents: vec![("tower", req("^1.6")), ("app", req("^1"))],
+                candidates: vec![release("1.5.0", &[]), release("1.4.0", &[])],
+            },
+        ];
+        let locked = vec![
+            ("app", "app".to_string(), version("0.1.0")),
+            ("tower", "tower".to_string(), version("0.5.3")),
+            ("bytes", "bytes".to_string(), version("1.7.0")),
+        ];
+        assert_eq!(solve(&crates, &locked), Some(vec![1, 0]));
+
+        let unsatisfiable = vec![PlanCrate {
+            id: "bytes",
+            name: "bytes".to_string(),
+            dependents: vec![("app", req("^1.6"))],
+            candidates: vec![release("1.5.0", &[])],
+        }];
+        assert_eq!(solve(&unsatisfiable, &locked), None);
+    }
+
+    #[test]
+    fn skips_releases_needing_a_crate_that_is_not_locked() {
+        let crates = vec![PlanCrate {
+            id: "hyper",
+            name: "hyper".to_string(),
+            dependents: vec![("app", req("^1"))],
+            candidates: vec![
+                release("1.4.0", &[("bytes", "^1"), ("h2", "^0.4")]),
+                release("1.3.0", &[("bytes", "^1")]),
+            ],
+        }];
+        let locked = vec![
+            ("app", "app".to_string(), version("0.1.0")),
+            ("hyper", "hyper".to_string(), version("1.5.0")),
+            ("bytes", "bytes".to_string(), version("1.7.0")),
+        ];
+        assert_eq!(solve(&crates, &locked), Some(vec![1]));
+
+        // Nor one whose locked release of the dependency is too old.
+        let locked = vec![
+            ("app", "app".to_string(), version("0.1.0")),
+            ("hyper", "hyper".to_string(), version("1.5.0")),
+            ("bytes", "bytes".to_string(), version("0.5.6")),
+        ];
+        assert_eq!(solve(&crates, &locked), None);
+    }
+
+    #[test]
+    fn gives_up_after_the_step_limit() {
+        // Only the least preferred release of `first` suits `last`, and
+        // every crate in between multiplies the choices to go through.
+        let plan = |between: usize| {
+            let any = |id: &'static str| PlanCrate {
+                id,
+                name: id.to_string(),
+                dependents: Vec::new(),
+                candidates: (0..10)
+                    .rev()
+                    .map(|patch| release(&format!("1.0.{patch}"), &[]))
+                    .collect(),
+            };
+            let mut crates = vec![any("first")];
+            crates.extend(["b", "c", "d", "e"].into_iter().take(between).map(any));
+            crates.push(PlanCrate {
+                id: "last",
+                name: "last".to_string(),
+                dependents: Vec::new(),
+                candidates: vec![release("1.0.0", &[("first", "=1.0.0")])],
+            });
+            crates
+        };
+        let solved = solve(&plan(2), &[]).unwrap();
+        assert_eq!(solved[0], 9);
+        assert_eq!(solve(&plan(4), &[]), None);
+    }
+}
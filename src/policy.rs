@@ -0,0 +1,134 @@
+//! Organization policy served over HTTPS: an allowlist file maintained
+//! centrally and verified against a minisign public key before it is used.
+
+use anyhow::{Context, Result, bail};
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::cache::Cache;
+use crate::config::Config;
+use crate::registry::RegistryClient;
+
+/// A policy document together with the detached signature it was served with.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct SignedPolicy {
+    contents: String,
+    signature: String,
+}
+
+/// Contents of the remote allowlist, or `None` when `allowlist_url` is unset.
+///
+/// Copies younger than the cache TTL are reused; otherwise the document and
+/// `<url>.minisig` are fetched again. When the fetch fails the last cached copy
+/// is used. Every copy is verified before use and a bad signature is an error,
+/// never a reason to continue without the policy.
+pub async fn fetch(config: &Config, cache: &Cache) -> Result<Option<String>> {
+    let Some(url) = &config.allowlist_url else {
+        return Ok(None);
+    };
+    let key = public_key(config)?;
+    let cache_key = cache_key(url);
+
+    if let Some(policy) = cache.get::<SignedPolicy>(&cache_key)? {
+        verify(&key, &policy, url)?;
+        return Ok(Some(policy.contents));
+    }
+
+    let client = RegistryClient::new(config)?;
+    let fetched = async {
+        Ok::<_, anyhow::Error>(SignedPolicy {
+            contents: client.fetch_text(url).await?,
+            signature: client.fetch_text(&format!("{url}.minisig")).await?,
+        })
+    }
+    .await;
+    let policy = match fetched {
+        Ok(policy) => {
+            verify(&key, &policy, url)?;
+            cache.put(&cache_key, &policy)?;
+            policy
+        }
+        Err(err) => match cache.get_stale::<SignedPolicy>(&cache_key)? {
+            Some(policy) => {
+                warn!(%url, error = %err, "could not refresh the remote allowlist; using the cached copy");
+                verify(&key, &policy, url)?;
+                policy
+            }
+            None => {
+                return Err(err.context(format!("failed to fetch the remote allowlist {url}")));
+            }
+        },
+    };
+    Ok(Some(policy.contents))
+}
+
+/// The last verified copy of the remote allowlist, regardless of age.
+pub fn cached(config: &Config) -> Result<Option<String>> {
+    let Some(url) = &config.allowlist_url else {
+        return Ok(None);
+    };
+    let cache = Cache::for_config(config)?;
+    Ok(cache
+        .get_stale::<SignedPolicy>(&cache_key(url))?
+        .map(|policy| policy.contents))
+}
+
+fn cache_key(url: &str) -> String {
+    format!("policy/{}", hex::encode(Sha256::digest(url.as_bytes())))
+}
+
+/// Accepts either the bare base64 key or the full `minisign.pub` file.
+fn public_key(config: &Config) -> Result<PublicKey> {
+    let Some(key) = config.allowlist_public_key.as_deref() else {
+        bail!("COOLDOWN_ALLOWLIST_PUBLIC_KEY must be set to use a remote allowlist");
+    };
+    let key = key.trim();
+    let parsed = if key.contains('\n') {
+        PublicKey::decode(key)
+    } else {
+        PublicKey::from_base64(key)
+    };
+    parsed.map_err(|err| anyhow::anyhow!("invalid allowlist public key: {err}"))
+}
+
+fn verify(key: &PublicKey, policy: &SignedPolicy, url: &str) -> Result<()> {
+    let signature = Signature::decode(&policy.signature)
+        .map_err(|err| anyhow::anyhow!("{err}"))
+        .with_context(|| format!("malformed signature for the remote allowlist {url}"))?;
+    if key
+        .verify(policy.contents.as_bytes(), &signature, false)
+        .is_err()
+    {
+        bail!("signature of the remote allowlist {url} does not match the configured public key");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const POLICY: &str = "[[allow.package]]\ncrate = \"tokio\"\nminutes = 60\n";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBAgMEBQYHCDz+Ed9SmOph7kKtQaGeYDzKO6xQybwa+3rjXfp8gUnPUG5NeNw2CFB4wa4zd//tJ4OU4dOyfneDtV6g6F9gSAM=
+trusted comment: timestamp:1700000000
+ATf7paFtFVUcea37aPSr+gXVq7UaC+hyPOE+CZDNkPLIBwT4IiGKESEaZCMeCyxcPe6doSydMtsdz+YvXD+hDQ==
+";
+
+    #[test]
+    fn rejects_policies_that_do_not_match_the_signature() {
+        let key = PublicKey::from_base64(PUBLIC_KEY).unwrap();
+        let mut policy = SignedPolicy {
+            contents: POLICY.to_string(),
+            signature: SIGNATURE.to_string(),
+        };
+        verify(&key, &policy, "https://policies.example/cooldown.toml").unwrap();
+
+        policy.contents = policy.contents.replace("60", "0");
+        let err = verify(&key, &policy, "https://policies.example/cooldown.toml").unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+}
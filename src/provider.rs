@@ -0,0 +1,208 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use anyhow::{Result, anyhow, bail};
+use tracing::{debug, warn};
+
+use crate::cache::Cache;
+use crate::codes::ErrorCode;
+use crate::config::{Config, MetadataSourceKind};
+use crate::db_dump::DbDump;
+use crate::index_history::IndexHistory;
+use crate::registry::{RegistryClient, SparseIndexClient, VersionMeta};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Where publication metadata comes from: the sparse index, the crates.io
+/// API, a database dump, the index's git history, or previously cached
+/// responses.
+pub trait VersionMetadataProvider: Send + Sync {
+    /// Short human readable name used in logs and errors.
+    fn label(&self) -> &'static str;
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>>;
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>>;
+}
+
+/// Tries each provider in order and returns the first successful answer.
+pub struct ProviderChain {
+    providers: Vec<Box<dyn VersionMetadataProvider>>,
+}
+
+impl ProviderChain {
+    pub fn new(providers: Vec<Box<dyn VersionMetadataProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// Build the chain configured through `metadata_sources`.
+    pub fn from_config(config: &Config, cache: &Cache) -> Result<Self> {
+        let mut providers: Vec<Box<dyn VersionMetadataProvider>> = Vec::new();
+        for kind in &config.metadata_sources {
+            match kind {
+                MetadataSourceKind::Sparse => {
+                    providers.push(Box::new(SparseIndexClient::new(config)?));
+                }
+                MetadataSourceKind::Api => providers.push(Box::new(RegistryClient::new(config)?)),
+                MetadataSourceKind::DbDump => match &config.db_dump_path {
+                    Some(path) => providers.push(Box::new(DbDump::open(path)?)),
+                    None => warn!(
+                        "db_dump metadata source requested without COOLDOWN_DB_DUMP_PATH; skipping"
+                    ),
+                },
+                MetadataSourceKind::IndexHistory => match &config.index_repo_path {
+                    Some(path) => providers.push(Box::new(IndexHistory::open(path)?)),
+                    None => warn!(
+                        "index_history metadata source requested without COOLDOWN_INDEX_REPO; skipping"
+                    ),
+                },
+                MetadataSourceKind::Cache => {
+                    providers.push(Box::new(CacheOnly::new(cache.clone())))
+                }
+            }
+        }
+        if providers.is_empty() {
+            bail!("no usable metadata source configured");
+        }
+        Ok(Self::new(providers))
+    }
+
+    async fn first_success<'a, T, F>(&'a self, what: &str, mut call: F) -> Result<T>
+    where
+        F: FnMut(&'a dyn VersionMetadataProvider) -> BoxFuture<'a, Result<T>>,
+    {
+        let mut failures = Vec::new();
+        for provider in &self.providers {
+            match call(provider.as_ref()).await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    debug!(source = provider.label(), error = %err, "metadata source failed; trying next");
+                    failures.push(format!("{}: {err:#}", provider.label()));
+                }
+            }
+        }
+        Err(ErrorCode::RegistryUnreachable.fail(format!(
+            "no metadata source could provide {what}: {}",
+            failures.join("; ")
+        )))
+    }
+}
+
+impl VersionMetadataProvider for ProviderChain {
+    fn label(&self) -> &'static str {
+        "fallback chain"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(async move {
+            self.first_success(&format!("{name}@{version}"), |provider| {
+                provider.fetch_version(name, version)
+            })
+            .await
+        })
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(async move {
+            self.first_success(&format!("the version list of {name}"), |provider| {
+                provider.list_versions(name)
+            })
+            .await
+        })
+    }
+}
+
+/// Serves whatever is in the on-disk cache, ignoring the TTL. Meant as the
+/// last resort of a chain when every live source is unreachable.
+pub struct CacheOnly {
+    cache: Cache,
+}
+
+impl CacheOnly {
+    pub fn new(cache: Cache) -> Self {
+        Self { cache }
+    }
+}
+
+impl VersionMetadataProvider for CacheOnly {
+    fn label(&self) -> &'static str {
+        "cache"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(async move {
+            self.cache
+                .get_stale::<VersionMeta>(&format!("{name}/{version}"))?
+                .ok_or_else(|| anyhow!("{name}@{version} is not cached"))
+        })
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(async move {
+            self.cache
+                .get_stale::<Vec<VersionMeta>>(&format!("{name}/_list"))?
+                .ok_or_else(|| anyhow!("the version list of {name} is not cached"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    struct Failing;
+
+    impl VersionMetadataProvider for Failing {
+        fn label(&self) -> &'static str {
+            "failing"
+        }
+
+        fn fetch_version<'a>(
+            &'a self,
+            _name: &'a str,
+            _version: &'a str,
+        ) -> BoxFuture<'a, Result<VersionMeta>> {
+            Box::pin(async { bail!("offline") })
+        }
+
+        fn list_versions<'a>(&'a self, _name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+            Box::pin(async { bail!("offline") })
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_falls_back_to_stale_cache() {
+        let dir = tempdir().unwrap();
+        let cache = Cache::with_root(dir.path().to_path_buf(), Duration::from_secs(0)).unwrap();
+        let meta = VersionMeta {
+            created_at: Utc::now(),
+            yanked: false,
+            num: "1.0.0".into(),
+            downloads: None,
+            license: None,
+        };
+        cache.put("foo/1.0.0", &meta).unwrap();
+
+        let chain = ProviderChain::new(vec![Box::new(Failing), Box::new(CacheOnly::new(cache))]);
+        let fetched = chain.fetch_version("foo", "1.0.0").await.unwrap();
+        assert_eq!(fetched.num, "1.0.0");
+
+        let err = chain.list_versions("foo").await.unwrap_err();
+        assert!(err.to_string().contains("failing: offline"));
+    }
+}
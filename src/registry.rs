@@ -1,12 +1,15 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use chrono::{DateTime, Utc};
-use reqwest::{Client, Url};
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, Response, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
+use tracing::{instrument, trace, warn};
 
 use crate::config::Config;
+use crate::health;
+use crate::provider::{BoxFuture, VersionMetadataProvider};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct VersionMeta {
@@ -14,6 +17,12 @@ pub struct VersionMeta {
     pub yanked: bool,
     #[serde(default)]
     pub num: String,
+    /// Not reported by the sparse index or older cache entries.
+    #[serde(default)]
+    pub downloads: Option<u64>,
+    /// SPDX license expression; not reported by the sparse index.
+    #[serde(default)]
+    pub license: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,36 +35,156 @@ struct CrateResponse {
     versions: Vec<VersionMeta>,
 }
 
+/// A crates.io account, as it appears in owner lists and `published_by`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct User {
+    pub login: String,
+}
+
+/// A release together with the account that published it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PublishedRelease {
+    pub num: String,
+    pub created_at: DateTime<Utc>,
+    /// Missing for releases that predate publisher tracking.
+    #[serde(default)]
+    pub published_by: Option<User>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PublishedResponse {
+    versions: Vec<PublishedRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwnersResponse {
+    users: Vec<User>,
+}
+
+/// One line of a sparse index file (`index.crates.io/{prefix}/{name}`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexEntry {
+    pub vers: String,
+    #[serde(default)]
+    pub yanked: bool,
+    /// Publication time; only present for releases published after the
+    /// index started recording it.
+    #[serde(default)]
+    pub pubtime: Option<DateTime<Utc>>,
+    /// SHA-256 of the `.crate` file; missing from older cache entries.
+    #[serde(default)]
+    pub cksum: Option<String>,
+    /// The release's `package.rust-version`, e.g. `1.70`.
+    #[serde(default)]
+    pub rust_version: Option<String>,
+    #[serde(default)]
+    pub deps: Vec<IndexDependency>,
+}
+
+/// A dependency as declared by one release in the sparse index.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexDependency {
+    /// Name the dependency is imported under.
+    pub name: String,
+    pub req: String,
+    /// `normal`, `build`, or `dev`; absent means `normal`.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Only pulled in when a feature enables it.
+    #[serde(default)]
+    pub optional: bool,
+    /// The crate's real name when it was renamed in the manifest.
+    #[serde(default)]
+    pub package: Option<String>,
+}
+
+impl IndexDependency {
+    pub fn crate_name(&self) -> &str {
+        self.package.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl IndexEntry {
+    pub fn version_meta(&self) -> Option<VersionMeta> {
+        self.pubtime.map(|created_at| VersionMeta {
+            created_at,
+            yanked: self.yanked,
+            num: self.vers.clone(),
+            downloads: None,
+            license: None,
+        })
+    }
+}
+
+/// An HTTP client with the proxy and TLS settings of the configuration, for
+/// registry requests and anything else the tool sends out.
+pub fn http_client(config: &Config) -> Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent("cargo-cooldown/0.1");
+    if let Some(proxy) = &config.http_proxy {
+        // `socks5://` and `socks5h://` URLs are accepted alongside HTTP(S) proxies.
+        let proxy = Proxy::all(proxy).with_context(|| format!("invalid HTTP proxy {proxy}"))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &config.ca_bundle_path {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("failed to read CA bundle {}", path.display()))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("failed to parse CA bundle {}", path.display()))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if config.danger_accept_invalid_certs {
+        warn!("TLS certificate verification is disabled for registry requests");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+/// HTTP client shared by every registry endpoint, with retries on top of
+/// [`http_client`].
 #[derive(Clone)]
-pub struct RegistryClient {
-    http: Client,
-    base: Url,
+struct HttpFetcher {
+    client: Client,
     retries: u32,
 }
 
-impl RegistryClient {
-    pub fn new(config: &Config) -> Result<Self> {
-        let http = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .user_agent("cargo-cooldown/0.1")
-            .build()?;
-        let base = Url::parse(&config.registry_api).context("invalid registry API URL")?;
+impl HttpFetcher {
+    fn new(config: &Config) -> Result<Self> {
         Ok(Self {
-            http,
-            base,
+            client: http_client(config)?,
             retries: config.http_retries,
         })
     }
 
-    async fn get_json<T: for<'de> Deserialize<'de>>(&self, url: Url) -> Result<T> {
+    async fn get(&self, url: Url) -> Result<Response> {
+        self.send(url, |client, url| client.get(url)).await
+    }
+
+    #[instrument(level = "debug", skip(self, request), fields(url = %url))]
+    async fn send(
+        &self,
+        url: Url,
+        request: impl Fn(&Client, Url) -> RequestBuilder,
+    ) -> Result<Response> {
         let mut attempt = 0;
         loop {
-            let response = self.http.get(url.clone()).send().await;
+            trace!(attempt, "sending registry request");
+            let started = Instant::now();
+            let response = request(&self.client, url.clone()).send().await;
+            // A 404 is a healthy answer from the endpoint; server errors are not.
+            let healthy = response
+                .as_ref()
+                .is_ok_and(|resp| !resp.status().is_server_error());
+            health::record(&url, started.elapsed(), healthy);
             match response {
                 Ok(resp) => {
-                    let status_resp = resp.error_for_status()?;
-                    let value = status_resp.json::<T>().await?;
-                    return Ok(value);
+                    if resp.status() == StatusCode::NOT_FOUND {
+                        bail!("{url} was not found");
+                    }
+                    return Ok(resp.error_for_status()?);
                 }
                 Err(err) => {
                     attempt += 1;
@@ -63,18 +192,39 @@ impl RegistryClient {
                         return Err(err.into());
                     }
                     let backoff = Duration::from_millis(200 * attempt as u64);
+                    trace!(attempt, error = %err, ?backoff, "registry request failed; retrying");
                     sleep(backoff).await;
                 }
             }
         }
     }
 
+    async fn get_json<T: for<'de> Deserialize<'de>>(&self, url: Url) -> Result<T> {
+        Ok(self.get(url).await?.json::<T>().await?)
+    }
+}
+
+#[derive(Clone)]
+pub struct RegistryClient {
+    http: HttpFetcher,
+    base: Url,
+}
+
+impl RegistryClient {
+    pub fn new(config: &Config) -> Result<Self> {
+        let base = Url::parse(&config.registry_api).context("invalid registry API URL")?;
+        Ok(Self {
+            http: HttpFetcher::new(config)?,
+            base,
+        })
+    }
+
     pub async fn fetch_version(&self, name: &str, version: &str) -> Result<VersionMeta> {
         let url = self
             .base
             .join(&format!("crates/{}/{}", name, version))
             .with_context(|| format!("failed to build version URL for {name}:{version}"))?;
-        let resp: VersionResponse = self.get_json(url).await?;
+        let resp: VersionResponse = self.http.get_json(url).await?;
         Ok(resp.version)
     }
 
@@ -83,7 +233,184 @@ impl RegistryClient {
             .base
             .join(&format!("crates/{}", name))
             .with_context(|| format!("failed to build crate URL for {name}"))?;
-        let resp: CrateResponse = self.get_json(url).await?;
+        let resp: CrateResponse = self.http.get_json(url).await?;
         Ok(resp.versions)
     }
+
+    /// Every release of `name` with its publisher.
+    pub async fn published_releases(&self, name: &str) -> Result<Vec<PublishedRelease>> {
+        let url = self
+            .base
+            .join(&format!("crates/{}", name))
+            .with_context(|| format!("failed to build crate URL for {name}"))?;
+        let resp: PublishedResponse = self.http.get_json(url).await?;
+        Ok(resp.versions)
+    }
+
+    /// Logins of the users and teams that currently own `name`.
+    pub async fn owners(&self, name: &str) -> Result<Vec<String>> {
+        let url = self
+            .base
+            .join(&format!("crates/{}/owners", name))
+            .with_context(|| format!("failed to build owners URL for {name}"))?;
+        let resp: OwnersResponse = self.http.get_json(url).await?;
+        Ok(resp.users.into_iter().map(|user| user.login).collect())
+    }
+
+    /// Fetch an arbitrary document, such as a policy file, with the same
+    /// proxy, TLS, and retry settings as registry requests.
+    pub async fn fetch_text(&self, url: &str) -> Result<String> {
+        let url = Url::parse(url).with_context(|| format!("invalid URL {url}"))?;
+        Ok(self.http.get(url).await?.text().await?)
+    }
+
+    /// POST `body` as JSON to an arbitrary endpoint, such as an advisory
+    /// database, and decode the JSON answer.
+    pub async fn post_json<B: Serialize, T: for<'de> Deserialize<'de>>(
+        &self,
+        url: &str,
+        body: &B,
+    ) -> Result<T> {
+        let url = Url::parse(url).with_context(|| format!("invalid URL {url}"))?;
+        let response = self
+            .http
+            .send(url, |client, url| client.post(url).json(body))
+            .await?;
+        Ok(response.json::<T>().await?)
+    }
+}
+
+/// Client for a sparse registry index such as `https://index.crates.io/`.
+#[derive(Clone)]
+pub struct SparseIndexClient {
+    http: HttpFetcher,
+    base: Url,
+}
+
+impl SparseIndexClient {
+    pub fn new(config: &Config) -> Result<Self> {
+        let base = Url::parse(&config.sparse_index_url).context("invalid sparse index URL")?;
+        Ok(Self {
+            http: HttpFetcher::new(config)?,
+            base,
+        })
+    }
+
+    pub async fn entries(&self, name: &str) -> Result<Vec<IndexEntry>> {
+        let url = self
+            .base
+            .join(&sparse_index_path(name))
+            .with_context(|| format!("failed to build index URL for {name}"))?;
+        let body = self.http.get(url).await?.text().await?;
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<IndexEntry>(line)
+                    .with_context(|| format!("failed to parse index entry for {name}"))
+            })
+            .collect()
+    }
+
+    /// Versions with publication times from the index. Fails when any
+    /// release predates `pubtime` so a fallback provider can answer instead.
+    pub async fn list_versions(&self, name: &str) -> Result<Vec<VersionMeta>> {
+        let entries = self.entries(name).await?;
+        let metas: Vec<VersionMeta> = entries
+            .iter()
+            .filter_map(IndexEntry::version_meta)
+            .collect();
+        if metas.len() != entries.len() {
+            bail!("sparse index lacks publish times for some versions of {name}");
+        }
+        Ok(metas)
+    }
+
+    pub async fn fetch_version(&self, name: &str, version: &str) -> Result<VersionMeta> {
+        let entries = self.entries(name).await?;
+        let Some(entry) = entries.iter().find(|entry| entry.vers == version) else {
+            bail!("{name}@{version} is not present in the sparse index");
+        };
+        match entry.version_meta() {
+            Some(meta) => Ok(meta),
+            None => bail!("sparse index lacks a publish time for {name}@{version}"),
+        }
+    }
+}
+
+/// Relative path of a crate's file in a sparse index, following Cargo's
+/// prefix layout (`1/a`, `2/ab`, `3/a/abc`, `ab/cd/abcd`).
+pub fn sparse_index_path(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    }
+}
+
+impl VersionMetadataProvider for SparseIndexClient {
+    fn label(&self) -> &'static str {
+        "sparse index"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(SparseIndexClient::fetch_version(self, name, version))
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(SparseIndexClient::list_versions(self, name))
+    }
+}
+
+impl VersionMetadataProvider for RegistryClient {
+    fn label(&self) -> &'static str {
+        "crates.io API"
+    }
+
+    fn fetch_version<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a str,
+    ) -> BoxFuture<'a, Result<VersionMeta>> {
+        Box::pin(RegistryClient::fetch_version(self, name, version))
+    }
+
+    fn list_versions<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Vec<VersionMeta>>> {
+        Box::pin(RegistryClient::list_versions(self, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_index_paths_follow_cargo_layout() {
+        assert_eq!(sparse_index_path("a"), "1/a");
+        assert_eq!(sparse_index_path("ab"), "2/ab");
+        assert_eq!(sparse_index_path("abc"), "3/a/abc");
+        assert_eq!(sparse_index_path("Serde_JSON"), "se/rd/serde_json");
+    }
+
+    #[test]
+    fn index_entries_without_pubtime_have_no_meta() {
+        let entry: IndexEntry = serde_json::from_str(
+            r#"{"name":"foo","vers":"1.0.0","deps":[],"cksum":"abc","features":{},"yanked":false}"#,
+        )
+        .unwrap();
+        assert!(entry.version_meta().is_none());
+
+        let entry: IndexEntry = serde_json::from_str(
+            r#"{"name":"foo","vers":"1.1.0","cksum":"def","yanked":true,"pubtime":"2025-03-01T10:00:00Z"}"#,
+        )
+        .unwrap();
+        let meta = entry.version_meta().unwrap();
+        assert!(meta.yanked);
+        assert_eq!(meta.num, "1.1.0");
+    }
 }
@@ -0,0 +1,645 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::codes::ErrorCode;
+use crate::journal::PinRecord;
+use crate::lockfile::LockfileChange;
+
+/// Releases published within this many minutes of each other are treated as
+/// one release train.
+const RELEASE_TRAIN_GAP_MINUTES: i64 = 60;
+
+/// A locked release that is still inside its cooldown window.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub name: String,
+    pub version: String,
+    pub published_at: DateTime<Utc>,
+    pub minimum_minutes: u64,
+    /// When the release leaves its cooldown window, which depends on the
+    /// calendar the window is counted on.
+    pub cools_down_at: DateTime<Utc>,
+    /// Direct dependencies of the workspace that pull this release in.
+    pub via: Vec<String>,
+}
+
+/// `2025-01-03 11:18 UTC (in 23h 18m)`.
+pub fn format_eta(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    format!(
+        "{} (in {})",
+        at.format("%Y-%m-%d %H:%M UTC"),
+        format_age(at - now)
+    )
+}
+
+/// When the last release in `violations` leaves its window, if that is
+/// still ahead.
+pub fn graph_cools_down_at(violations: &[Violation], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    violations
+        .iter()
+        .map(|violation| violation.cools_down_at)
+        .max()
+        .filter(|at| *at > now)
+}
+
+/// The sentence saying when every release in `violations` will have cooled
+/// down, or `None` when none of them is waiting on time alone.
+pub fn graph_eta(violations: &[Violation], now: DateTime<Utc>) -> Option<String> {
+    let latest = graph_cools_down_at(violations, now)?;
+    Some(format!(
+        "the whole graph cools down at {}",
+        format_eta(latest, now)
+    ))
+}
+
+/// Where a locked release stands against its cooldown window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeStatus {
+    Cooled,
+    Fresh,
+    /// Allowlisted, part of the baseline, or under a window that has
+    /// already passed for every release.
+    Exempt,
+    /// The publication time could not be looked up.
+    Unknown,
+}
+
+impl AgeStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            AgeStatus::Cooled => "cooled",
+            AgeStatus::Fresh => "fresh",
+            AgeStatus::Exempt => "exempt",
+            AgeStatus::Unknown => "unknown",
+        }
+    }
+}
+
+/// A locked release with its publication time, for the read-only listings.
+#[derive(Debug, Clone)]
+pub struct ReleaseAge {
+    pub name: String,
+    pub version: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub minimum_minutes: u64,
+    pub status: AgeStatus,
+}
+
+/// Table of `ages`, youngest first. Releases older than `stale` are marked
+/// so dependencies nobody has updated in a long time stand out too.
+pub fn render_ages(ages: &[ReleaseAge], now: DateTime<Utc>, stale: Option<Duration>) -> String {
+    let mut sorted: Vec<&ReleaseAge> = ages.iter().collect();
+    sorted.sort_by_key(|age| (std::cmp::Reverse(age.published_at), age.name.as_str()));
+
+    let width = sorted
+        .iter()
+        .map(|age| age.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let mut out = format!(
+        "{:<width$} {:<12} {:<20} {:>9} {:>9}  status\n",
+        "crate", "version", "published", "age", "cooldown"
+    );
+    for age in sorted {
+        let (published, elapsed) = match age.published_at {
+            Some(at) => (
+                at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                format_age(now - at),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let mut status = age.status.label().to_string();
+        if let (Some(at), Some(stale)) = (age.published_at, stale)
+            && now - at > stale
+        {
+            status.push_str(" (stale)");
+        }
+        out.push_str(&format!(
+            "{:<width$} {:<12} {:<20} {:>9} {:>9}  {status}\n",
+            age.name,
+            age.version,
+            published,
+            elapsed,
+            format_age(Duration::minutes(age.minimum_minutes as i64))
+        ));
+    }
+    out
+}
+
+/// How built-in commands print violations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// One line per release, for people.
+    #[default]
+    Text,
+    /// JSON lines shaped like `cargo deny --format json` diagnostics, for
+    /// tooling that already consumes cargo-deny output.
+    DenyJson,
+}
+
+/// Violations as cargo-deny JSON diagnostics, one per line, followed by a
+/// summary line. `severity` is `error` when the run enforces and `warning`
+/// otherwise; releases in `soft`, which are only warned about, are always
+/// warnings.
+pub fn render_deny_json(
+    violations: &[Violation],
+    soft: &[Violation],
+    now: DateTime<Utc>,
+    severity: &str,
+) -> String {
+    let mut out = String::new();
+    let entries = violations
+        .iter()
+        .map(|violation| (violation, severity, "cooldown"))
+        .chain(
+            soft.iter()
+                .map(|violation| (violation, "warning", "window; warn only")),
+        );
+    for (violation, severity, window) in entries {
+        let age = format_age(now - violation.published_at);
+        let mut notes = vec![format!(
+            "published {}",
+            violation.published_at.format("%Y-%m-%d %H:%M UTC")
+        )];
+        if !violation.via.is_empty() {
+            notes.push(format!("pulled in by {}", violation.via.join(", ")));
+        }
+        let diagnostic = json!({
+            "type": "diagnostic",
+            "fields": {
+                "severity": severity,
+                "code": ErrorCode::FreshCrateBlocked.id(),
+                "message": format!(
+                    "{}@{} was published {age} ago, inside its {} minute {window}",
+                    violation.name, violation.version, violation.minimum_minutes
+                ),
+                "labels": [],
+                "notes": notes,
+                "graphs": [{
+                    "Krate": { "name": violation.name, "version": violation.version }
+                }],
+            }
+        });
+        out.push_str(&format!("{diagnostic}\n"));
+    }
+    let (errors, warnings) = if severity == "error" {
+        (violations.len(), soft.len())
+    } else {
+        (0, violations.len() + soft.len())
+    };
+    let summary = json!({
+        "type": "summary",
+        "fields": {
+            "advisories": { "errors": errors, "warnings": warnings, "notes": 0, "helps": 0 }
+        }
+    });
+    out.push_str(&format!("{summary}\n"));
+    out
+}
+
+/// Plain text listing of violations, one line per crate, youngest first.
+pub fn render_violations(violations: &[Violation], now: DateTime<Utc>) -> String {
+    let mut sorted: Vec<&Violation> = violations.iter().collect();
+    sorted.sort_by_key(|violation| std::cmp::Reverse(violation.published_at));
+
+    let mut out = String::new();
+    for violation in &sorted {
+        let age = now - violation.published_at;
+        out.push_str(&format!(
+            "{}@{} published {} ({} old), cooldown {} minutes",
+            violation.name,
+            violation.version,
+            violation.published_at.format("%Y-%m-%d %H:%M UTC"),
+            format_age(age),
+            violation.minimum_minutes,
+        ));
+        let cools_down_at = violation.cools_down_at;
+        if cools_down_at > now {
+            out.push_str(&format!(", cools down {}", format_eta(cools_down_at, now)));
+        }
+        out.push('\n');
+    }
+    if let Some(eta) = graph_eta(violations, now) {
+        out.push_str(&eta);
+        out.push('\n');
+    }
+    out
+}
+
+/// Plain text listing of the releases that are only warned about.
+pub fn render_soft(soft: &[Violation], now: DateTime<Utc>) -> String {
+    let mut sorted: Vec<&Violation> = soft.iter().collect();
+    sorted.sort_by_key(|violation| std::cmp::Reverse(violation.published_at));
+
+    let mut out = format!("warn only, not blocking ({}):\n", soft.len());
+    for violation in sorted {
+        out.push_str(&format!(
+            "  {}@{} published {} ({} old), window {} minutes\n",
+            violation.name,
+            violation.version,
+            violation.published_at.format("%Y-%m-%d %H:%M UTC"),
+            format_age(now - violation.published_at),
+            violation.minimum_minutes,
+        ));
+    }
+    out
+}
+
+/// Why a locked package was left out of enforcement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    Path,
+    Git,
+    /// A registry that is not in `allowed_registries`.
+    Registry(String),
+}
+
+/// A locked package the cooldown policy could not vet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub name: String,
+    pub version: String,
+    pub reason: SkipReason,
+}
+
+/// Plain text listing of the packages enforcement skipped, and why.
+pub fn render_skipped(skipped: &[Skipped]) -> String {
+    let mut out = format!("not checked against the cooldown ({}):\n", skipped.len());
+    for package in skipped {
+        let reason = match &package.reason {
+            SkipReason::Path => "path dependency".to_string(),
+            SkipReason::Git => "git dependency".to_string(),
+            SkipReason::Registry(source) => format!("registry {source} is not allowed"),
+        };
+        out.push_str(&format!(
+            "  {}@{}: {reason}\n",
+            package.name, package.version
+        ));
+    }
+    out
+}
+
+/// Markdown summary of a pinning run for CI to post on the pull request:
+/// the pins applied, with links to the releases on crates.io, and what
+/// blocked the run if it failed.
+pub fn render_pr_comment(pins: &[PinRecord], blocked: Option<&str>, rolled_back: bool) -> String {
+    let release = |name: &str, version: &str| {
+        format!("[{version}](https://crates.io/crates/{name}/{version})")
+    };
+    let mut out = String::from("## cargo-cooldown\n\n");
+    if pins.is_empty() && blocked.is_none() {
+        out.push_str("Every locked release is outside its cooldown window; nothing was pinned.\n");
+        return out;
+    }
+    if !pins.is_empty() {
+        out.push_str(if rolled_back {
+            "### Downgrades (rolled back)\n\n"
+        } else {
+            "### Downgraded\n\n"
+        });
+        out.push_str("| Crate | Locked | Pinned to | Published | Why |\n");
+        out.push_str("| --- | --- | --- | --- | --- |\n");
+        for pin in pins {
+            out.push_str(&format!(
+                "| `{}` | {} | {} | {} | {} |\n",
+                pin.name,
+                release(&pin.name, &pin.from),
+                release(&pin.name, &pin.to),
+                pin.published_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_default(),
+                pin.reason.as_deref().unwrap_or_default(),
+            ));
+        }
+        out.push('\n');
+    }
+    if let Some(blocked) = blocked {
+        out.push_str("### Blocked\n\n```text\n");
+        out.push_str(blocked.trim_end());
+        out.push_str("\n```\n");
+    }
+    out
+}
+
+/// Look for a single root cause shared by most violations: one lockfile
+/// change, one direct dependency, or one burst of releases. Each finding is
+/// a sentence meant to lead the report.
+pub fn triage(
+    violations: &[Violation],
+    introductions: &HashMap<(String, String), LockfileChange>,
+) -> Vec<String> {
+    let total = violations.len();
+    if total < 2 {
+        return Vec::new();
+    }
+    let significant = |count: usize| count >= 2 && count * 2 >= total;
+    let mut findings = Vec::new();
+
+    let mut by_change: HashMap<&LockfileChange, usize> = HashMap::new();
+    for violation in violations {
+        let key = (violation.name.clone(), violation.version.clone());
+        if let Some(change) = introductions.get(&key) {
+            *by_change.entry(change).or_default() += 1;
+        }
+    }
+    if let Some((change, count)) = by_change
+        .into_iter()
+        .max_by_key(|(change, count)| (*count, std::cmp::Reverse(*change)))
+        && significant(count)
+    {
+        let origin = match change {
+            LockfileChange::Uncommitted => "uncommitted changes to Cargo.lock".to_string(),
+            LockfileChange::Commit { short_id, summary } => {
+                format!("commit {short_id} ({summary})")
+            }
+        };
+        findings.push(format!(
+            "{count} of {total} fresh releases entered Cargo.lock together in {origin}"
+        ));
+    }
+
+    let mut by_direct: HashMap<&str, usize> = HashMap::new();
+    for violation in violations {
+        for direct in &violation.via {
+            *by_direct.entry(direct.as_str()).or_default() += 1;
+        }
+    }
+    if let Some((direct, count)) = by_direct
+        .into_iter()
+        .max_by_key(|(name, count)| (*count, std::cmp::Reverse(*name)))
+        && significant(count)
+    {
+        findings.push(format!(
+            "{count} of {total} fresh releases are pulled in through the direct dependency {direct}"
+        ));
+    }
+
+    let mut sorted: Vec<&Violation> = violations.iter().collect();
+    sorted.sort_by_key(|violation| violation.published_at);
+    let mut trains: Vec<Vec<&Violation>> = Vec::new();
+    for violation in sorted {
+        match trains.last_mut() {
+            Some(train)
+                if (violation.published_at - train[train.len() - 1].published_at).num_minutes()
+                    <= RELEASE_TRAIN_GAP_MINUTES =>
+            {
+                train.push(violation)
+            }
+            _ => trains.push(vec![violation]),
+        }
+    }
+    if let Some(train) = trains.into_iter().max_by_key(Vec::len)
+        && significant(train.len())
+    {
+        let span = train[train.len() - 1].published_at - train[0].published_at;
+        let names: Vec<String> = train
+            .iter()
+            .take(5)
+            .map(|violation| format!("{}@{}", violation.name, violation.version))
+            .collect();
+        let more = if train.len() > 5 { ", ..." } else { "" };
+        findings.push(format!(
+            "{} of {total} fresh releases were published within {} of each other: {}{more}",
+            train.len(),
+            format_age(span),
+            names.join(", ")
+        ));
+    }
+
+    findings
+}
+
+/// Compact human readable duration such as `2d 3h`, `5h 12m`, or `42m`.
+pub fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes().max(0);
+    let (days, hours, mins) = (minutes / 1440, (minutes % 1440) / 60, minutes % 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else {
+        format!("{mins}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn renders_youngest_first() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let violations = vec![
+            Violation {
+                name: "old".into(),
+                version: "1.0.0".into(),
+                published_at: Utc.with_ymd_and_hms(2024, 12, 31, 9, 0, 0).unwrap(),
+                minimum_minutes: 4320,
+                cools_down_at: Utc.with_ymd_and_hms(2024, 12, 31, 9, 0, 0).unwrap()
+                    + Duration::minutes(4320),
+                via: Vec::new(),
+            },
+            Violation {
+                name: "young".into(),
+                version: "0.2.0".into(),
+                published_at: Utc.with_ymd_and_hms(2025, 1, 2, 11, 18, 0).unwrap(),
+                minimum_minutes: 60,
+                cools_down_at: Utc.with_ymd_and_hms(2025, 1, 2, 11, 18, 0).unwrap()
+                    + Duration::minutes(60),
+                via: Vec::new(),
+            },
+        ];
+        let rendered = render_violations(&violations, now);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "young@0.2.0 published 2025-01-02 11:18 UTC (42m old), cooldown 60 minutes, cools down 2025-01-02 12:18 UTC (in 18m)",
+                "old@1.0.0 published 2024-12-31 09:00 UTC (2d 3h old), cooldown 4320 minutes, cools down 2025-01-03 09:00 UTC (in 21h 0m)",
+                "the whole graph cools down at 2025-01-03 09:00 UTC (in 21h 0m)",
+            ]
+        );
+    }
+
+    #[test]
+    fn ages_mark_stale_releases() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let age = |name: &str, published_at, status| ReleaseAge {
+            name: name.into(),
+            version: "1.0.0".into(),
+            published_at,
+            minimum_minutes: 1440,
+            status,
+        };
+        let ages = vec![
+            age(
+                "ancient",
+                Some(Utc.with_ymd_and_hms(2022, 1, 2, 12, 0, 0).unwrap()),
+                AgeStatus::Cooled,
+            ),
+            age("lost", None, AgeStatus::Unknown),
+            age(
+                "young",
+                Some(Utc.with_ymd_and_hms(2025, 1, 2, 11, 18, 0).unwrap()),
+                AgeStatus::Fresh,
+            ),
+        ];
+        let rendered = render_ages(&ages, now, Some(Duration::days(365)));
+        let lines: Vec<&str> = rendered.lines().map(str::trim_end).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "crate   version      published                  age  cooldown  status",
+                "young   1.0.0        2025-01-02 11:18 UTC       42m     1d 0h  fresh",
+                "ancient 1.0.0        2022-01-02 12:00 UTC  1096d 0h     1d 0h  cooled (stale)",
+                "lost    1.0.0        -                            -     1d 0h  unknown",
+            ]
+        );
+    }
+
+    #[test]
+    fn deny_json_has_one_diagnostic_per_violation() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let violations = vec![Violation {
+            name: "young".into(),
+            version: "0.2.0".into(),
+            published_at: Utc.with_ymd_and_hms(2025, 1, 2, 11, 18, 0).unwrap(),
+            minimum_minutes: 60,
+            cools_down_at: Utc.with_ymd_and_hms(2025, 1, 2, 11, 18, 0).unwrap()
+                + Duration::minutes(60),
+            via: vec!["app-dep".into()],
+        }];
+        let soft = vec![Violation {
+            name: "settling".into(),
+            version: "1.4.0".into(),
+            published_at: Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap(),
+            minimum_minutes: 10080,
+            cools_down_at: Utc.with_ymd_and_hms(2025, 1, 8, 12, 0, 0).unwrap(),
+            via: Vec::new(),
+        }];
+        let rendered = render_deny_json(&violations, &soft, now, "error");
+        let lines: Vec<serde_json::Value> = rendered
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["fields"]["severity"], "error");
+        assert_eq!(lines[0]["fields"]["graphs"][0]["Krate"]["name"], "young");
+        assert_eq!(
+            lines[0]["fields"]["message"],
+            "young@0.2.0 was published 42m ago, inside its 60 minute cooldown"
+        );
+        assert_eq!(lines[1]["fields"]["severity"], "warning");
+        assert_eq!(
+            lines[1]["fields"]["message"],
+            "settling@1.4.0 was published 1d 0h ago, inside its 10080 minute window; warn only"
+        );
+        assert_eq!(lines[2]["fields"]["advisories"]["errors"], 1);
+        assert_eq!(lines[2]["fields"]["advisories"]["warnings"], 1);
+    }
+
+    #[test]
+    fn triage_finds_a_shared_root_cause() {
+        let at = |minute| Utc.with_ymd_and_hms(2025, 1, 2, 10, minute, 0).unwrap();
+        let violation = |name: &str, minute, via: &str| Violation {
+            name: name.into(),
+            version: "1.0.0".into(),
+            published_at: at(minute),
+            minimum_minutes: 1440,
+            cools_down_at: at(minute) + Duration::minutes(1440),
+            via: vec![via.into()],
+        };
+        let violations = vec![
+            violation("tokio", 0, "tokio"),
+            violation("tokio-macros", 5, "tokio"),
+            violation("mio", 20, "tokio"),
+            violation("serde", 55, "serde"),
+        ];
+        let commit = LockfileChange::Commit {
+            short_id: "abc1234".into(),
+            summary: "cargo update".into(),
+        };
+        let introductions = HashMap::from([
+            (("tokio".to_string(), "1.0.0".to_string()), commit.clone()),
+            (("mio".to_string(), "1.0.0".to_string()), commit),
+        ]);
+
+        let findings = triage(&violations, &introductions);
+        assert_eq!(
+            findings,
+            vec![
+                "2 of 4 fresh releases entered Cargo.lock together in commit abc1234 (cargo update)",
+                "3 of 4 fresh releases are pulled in through the direct dependency tokio",
+                "4 of 4 fresh releases were published within 55m of each other: tokio@1.0.0, tokio-macros@1.0.0, mio@1.0.0, serde@1.0.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn triage_breaks_ties_between_lockfile_changes_deterministically() {
+        let at = Utc.with_ymd_and_hms(2025, 1, 2, 10, 0, 0).unwrap();
+        let violation = |name: &str, hours| Violation {
+            name: name.into(),
+            version: "1.0.0".into(),
+            published_at: at + Duration::hours(hours),
+            minimum_minutes: 1440,
+            cools_down_at: at + Duration::hours(hours + 24),
+            via: vec![name.into()],
+        };
+        let violations = vec![
+            violation("tokio", 0),
+            violation("mio", 2),
+            violation("serde", 4),
+            violation("syn", 6),
+        ];
+        let commit = |short_id: &str| LockfileChange::Commit {
+            short_id: short_id.into(),
+            summary: "cargo update".into(),
+        };
+        let key = |name: &str| (name.to_string(), "1.0.0".to_string());
+        let introductions = HashMap::from([
+            (key("tokio"), commit("def5678")),
+            (key("mio"), commit("def5678")),
+            (key("serde"), commit("abc1234")),
+            (key("syn"), commit("abc1234")),
+        ]);
+
+        for _ in 0..20 {
+            assert_eq!(
+                triage(&violations, &introductions),
+                vec![
+                    "2 of 4 fresh releases entered Cargo.lock together in commit abc1234 (cargo update)"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn lists_skipped_packages_with_reasons() {
+        let skipped = |name: &str, reason| Skipped {
+            name: name.into(),
+            version: "0.1.0".into(),
+            reason,
+        };
+        let rendered = render_skipped(&[
+            skipped("local", SkipReason::Path),
+            skipped("forked", SkipReason::Git),
+            skipped(
+                "internal",
+                SkipReason::Registry("registry+https://example.com/index".into()),
+            ),
+        ]);
+        assert_eq!(
+            rendered,
+            "not checked against the cooldown (3):\n\
+             \x20 local@0.1.0: path dependency\n\
+             \x20 forked@0.1.0: git dependency\n\
+             \x20 internal@0.1.0: registry registry+https://example.com/index is not allowed\n"
+        );
+    }
+}
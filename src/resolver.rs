@@ -1,9 +1,17 @@
+use std::fs;
+use std::path::Path;
 use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
+use semver::{Version, VersionReq};
+use tracing::{instrument, trace};
 
-use crate::registry::VersionMeta;
+use crate::calendar::Calendar;
+use crate::config::{Bump, MsrvPolicy, PinStrategy};
+use crate::lockfile;
+use crate::metadata::config_args;
+use crate::registry::{IndexEntry, VersionMeta};
 
 #[derive(Debug, Clone)]
 pub struct Candidate {
@@ -11,12 +19,24 @@ pub struct Candidate {
     pub created_at: DateTime<Utc>,
 }
 
-pub fn filter_candidates(
-    versions: Vec<VersionMeta>,
-    minimum_minutes: u64,
+/// Latest publication instant a release may have to count as cooled down.
+///
+/// The relative cooldown window, counted on `calendar`, and the optional
+/// absolute freeze date both apply; whichever is earlier wins.
+pub fn cooldown_cutoff(
     now: DateTime<Utc>,
-) -> Vec<Candidate> {
-    let cutoff = now - Duration::minutes(minimum_minutes as i64);
+    minimum_minutes: u64,
+    freeze_after: Option<DateTime<Utc>>,
+    calendar: &Calendar,
+) -> DateTime<Utc> {
+    let cutoff = calendar.window_start(now, minimum_minutes);
+    match freeze_after {
+        Some(freeze) => cutoff.min(freeze),
+        None => cutoff,
+    }
+}
+
+pub fn filter_candidates(versions: Vec<VersionMeta>, cutoff: DateTime<Utc>) -> Vec<Candidate> {
     let mut filtered: Vec<Candidate> = versions
         .into_iter()
         .filter(|meta| !meta.yanked)
@@ -26,36 +46,375 @@ pub fn filter_candidates(
             created_at: meta.created_at,
         })
         .collect();
-    filtered.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    filtered.sort_by_key(|candidate| std::cmp::Reverse(candidate.created_at));
     filtered
 }
 
+/// Order `candidates` (cooled down, below `current`, satisfying every
+/// requirement) by preference under `strategy`, dropping the ones it rules
+/// out. Candidates that are not valid semver keep their relative order at
+/// the end.
+pub fn order_candidates(
+    strategy: PinStrategy,
+    current: &str,
+    mut candidates: Vec<Candidate>,
+) -> Vec<Candidate> {
+    let current = Version::parse(current).ok();
+    let parsed = |candidate: &Candidate| Version::parse(&candidate.version).ok();
+    match strategy {
+        PinStrategy::NewestCooled | PinStrategy::Fail => {}
+        PinStrategy::ClosestBelowCurrent => {
+            candidates.sort_by_key(|candidate| std::cmp::Reverse(parsed(candidate)));
+        }
+        PinStrategy::SameMinorOnly => {
+            candidates.retain(|candidate| match (parsed(candidate), &current) {
+                (Some(version), Some(current)) => {
+                    version.major == current.major && version.minor == current.minor
+                }
+                _ => false,
+            });
+            candidates.sort_by_key(|candidate| std::cmp::Reverse(parsed(candidate)));
+        }
+        PinStrategy::OldestCompatible => {
+            candidates.sort_by_key(|candidate| {
+                let version = parsed(candidate);
+                (version.is_none(), version)
+            });
+        }
+    }
+    candidates
+}
+
+/// Whether pinning `current` down to `candidate` stays within `max_steps`
+/// minor series of the same major version. Versions that are not valid
+/// semver are not judged.
+pub fn within_downgrade(current: &str, candidate: &str, max_steps: u64) -> bool {
+    let (Ok(current), Ok(candidate)) = (Version::parse(current), Version::parse(candidate)) else {
+        return true;
+    };
+    current.major == candidate.major && current.minor.saturating_sub(candidate.minor) <= max_steps
+}
+
+/// Parse a `rust-version` such as `1.70` or `1.70.1`.
+pub fn parse_rust_version(raw: &str) -> Option<Version> {
+    let raw = raw.trim();
+    let padded = match raw.matches('.').count() {
+        0 => format!("{raw}.0.0"),
+        1 => format!("{raw}.0"),
+        _ => raw.to_string(),
+    };
+    Version::parse(&padded).ok()
+}
+
+/// Why a candidate declaring `candidate` as its MSRV may not replace a
+/// release declaring `current`, for a workspace that promises to build with
+/// `workspace`. Undeclared versions are not judged.
+pub fn msrv_rejection(
+    policy: MsrvPolicy,
+    candidate: Option<&Version>,
+    workspace: Option<&Version>,
+    current: Option<&Version>,
+) -> Option<String> {
+    let candidate = candidate?;
+    if policy == MsrvPolicy::Off {
+        return None;
+    }
+    if let Some(workspace) = workspace.filter(|workspace| candidate > *workspace) {
+        return Some(format!(
+            "requires Rust {candidate}, newer than the workspace's rust-version {workspace}"
+        ));
+    }
+    if policy == MsrvPolicy::Exact
+        && let Some(current) = current.filter(|current| candidate < *current)
+    {
+        return Some(format!(
+            "declares rust-version {candidate}, lower than the {current} of the current release"
+        ));
+    }
+    None
+}
+
+/// Whether two SPDX license expressions differ, ignoring case, spacing,
+/// and the legacy `/` separator for `OR`.
+pub fn license_changed(current: &str, candidate: &str) -> bool {
+    let normalize = |license: &str| {
+        license
+            .replace('/', " OR ")
+            .split_whitespace()
+            .map(str::to_ascii_uppercase)
+            .collect::<Vec<_>>()
+    };
+    normalize(current) != normalize(candidate)
+}
+
+/// Releases older than this are no longer held back for lack of downloads,
+/// so rarely used crates do not stay blocked forever.
+pub const MIN_DOWNLOADS_HORIZON_DAYS: i64 = 30;
+
+/// Whether a recent release has fewer than `min_downloads` downloads.
+/// Releases whose source does not report downloads are not judged.
+pub fn lacks_downloads(meta: &VersionMeta, min_downloads: Option<u64>, now: DateTime<Utc>) -> bool {
+    let (Some(required), Some(downloads)) = (min_downloads, meta.downloads) else {
+        return false;
+    };
+    downloads < required && meta.created_at > now - Duration::days(MIN_DOWNLOADS_HORIZON_DAYS)
+}
+
+/// Whether a crate is too new to be trusted: fewer than `min_versions`
+/// releases, or a first release younger than `min_age_days`.
+pub fn is_new_crate(
+    versions: &[VersionMeta],
+    now: DateTime<Utc>,
+    min_versions: usize,
+    min_age_days: u64,
+) -> bool {
+    let Some(first_release) = versions.iter().map(|meta| meta.created_at).min() else {
+        return true;
+    };
+    versions.len() < min_versions
+        || now - first_release < Duration::days(min_age_days.min(i64::MAX as u64) as i64)
+}
+
+/// Whether `version` is still live and at least `required` higher, non-yanked
+/// releases were published after it, i.e. the ecosystem moved past it without
+/// pulling it.
+pub fn survived_releases(versions: &[VersionMeta], version: &str, required: usize) -> bool {
+    let Some(release) = versions.iter().find(|meta| meta.num == version) else {
+        return false;
+    };
+    if release.yanked {
+        return false;
+    }
+    let parsed = Version::parse(version).ok();
+    let newer = versions
+        .iter()
+        .filter(|meta| !meta.yanked && meta.created_at > release.created_at)
+        .filter(|meta| match (&parsed, Version::parse(&meta.num)) {
+            (Some(release), Ok(other)) => other > *release,
+            _ => true,
+        })
+        .count();
+    newer >= required
+}
+
+/// How far `version` moves a crate from the closest lower version in
+/// `previous`, the versions locked before the run. `None` when the release
+/// was already locked, nothing lower was, or a version does not parse.
+pub fn bump_from<'a>(previous: impl IntoIterator<Item = &'a str>, version: &str) -> Option<Bump> {
+    let version = Version::parse(version).ok()?;
+    let mut closest: Option<Version> = None;
+    for previous in previous {
+        let Ok(previous) = Version::parse(previous) else {
+            continue;
+        };
+        if previous == version {
+            return None;
+        }
+        if previous < version && closest.as_ref().is_none_or(|closest| previous > *closest) {
+            closest = Some(previous);
+        }
+    }
+    let previous = closest?;
+    let compatible = match (previous.major, previous.minor) {
+        (0, 0) => version.major == 0 && version.minor == 0 && version.patch == previous.patch,
+        (0, minor) => version.major == 0 && version.minor == minor,
+        (major, _) => version.major == major,
+    };
+    Some(if !compatible {
+        Bump::Major
+    } else if version.minor != previous.minor {
+        Bump::Minor
+    } else {
+        Bump::Patch
+    })
+}
+
+pub fn satisfies_requirements(version: &str, requirements: &[VersionReq]) -> bool {
+    if requirements.is_empty() {
+        return true;
+    }
+    match Version::parse(version) {
+        Ok(parsed) => requirements.iter().all(|req| req.matches(&parsed)),
+        Err(_) => false,
+    }
+}
+
+/// Non-yanked index entries below `current` that satisfy every requirement,
+/// highest version first.
+pub fn index_candidates(
+    entries: Vec<IndexEntry>,
+    current: &str,
+    requirements: &[VersionReq],
+) -> Vec<IndexEntry> {
+    let current = Version::parse(current).ok();
+    let mut filtered: Vec<(Version, IndexEntry)> = entries
+        .into_iter()
+        .filter(|entry| !entry.yanked)
+        .filter_map(|entry| {
+            Version::parse(&entry.vers)
+                .ok()
+                .map(|version| (version, entry))
+        })
+        .filter(|(version, _)| current.as_ref().is_none_or(|current| version < current))
+        .filter(|(version, _)| {
+            current
+                .as_ref()
+                .is_none_or(|current| prerelease_allowed(current, version))
+        })
+        .filter(|(_, entry)| satisfies_requirements(&entry.vers, requirements))
+        .collect();
+    filtered.sort_by(|a, b| b.0.cmp(&a.0));
+    filtered.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Cargo only selects a pre-release when the requirement opts into it, which
+/// in practice means staying on the same `major.minor.patch` pre-release
+/// train. Stable releases never fall back to a pre-release.
+pub fn prerelease_allowed(current: &Version, candidate: &Version) -> bool {
+    candidate.pre.is_empty()
+        || (!current.pre.is_empty()
+            && (current.major, current.minor, current.patch)
+                == (candidate.major, candidate.minor, candidate.patch))
+}
+
+/// `version` without its `+build` suffix. Build metadata never distinguishes
+/// two releases on crates.io, and Cargo's package id specs and `--precise`
+/// reject or mis-match it.
+pub fn without_build_metadata(version: &str) -> &str {
+    version.split_once('+').map_or(version, |(base, _)| base)
+}
+
+/// `name@version` spec for `cargo update -p`.
+pub fn pin_spec(name: &str, version: &str) -> String {
+    format!("{name}@{}", without_build_metadata(version))
+}
+
+/// Explain a rejected pin when Cargo refused the candidate because of the
+/// pre-release matching rules rather than a conflicting dependent.
+pub fn prerelease_rejection(name: &str, candidate: &str, stderr: &str) -> Option<String> {
+    let version = Version::parse(candidate).ok()?;
+    if version.pre.is_empty() {
+        return None;
+    }
+    let stderr = stderr.to_ascii_lowercase();
+    if !(stderr.contains("pre-release") || stderr.contains("prerelease")) {
+        return None;
+    }
+    Some(format!(
+        "cargo refused to pin {name} to pre-release {candidate}: a pre-release is only selected when the dependency requirement names the same {}.{}.{} pre-release (for example `={candidate}`)",
+        version.major, version.minor, version.patch
+    ))
+}
+
 #[derive(Debug)]
 pub enum PinOutcome {
     Applied,
-    Rejected { stdout: String, stderr: String },
+    Rejected { stderr: String },
 }
 
-pub fn try_pin_precise(name: &str, current: &str, version: &str) -> Result<PinOutcome> {
-    let spec = format!("{name}@{current}");
-    let output = Command::new("cargo")
-        .args(["update", "-p", &spec, "--precise", version])
-        .output()?;
+#[instrument(level = "debug", skip(manifest_path, cargo_config))]
+pub fn try_pin_precise(
+    manifest_path: Option<&Path>,
+    cargo_config: &[String],
+    name: &str,
+    current: &str,
+    version: &str,
+) -> Result<PinOutcome> {
+    let spec = pin_spec(name, current);
+    let mut command = Command::new("cargo");
+    command.args([
+        "update",
+        "-p",
+        &spec,
+        "--precise",
+        without_build_metadata(version),
+    ]);
+    if let Some(path) = manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+    command.args(config_args(cargo_config));
+    let output = command.output()?;
+    trace!(status = %output.status, "cargo update finished");
     if output.status.success() {
         Ok(PinOutcome::Applied)
     } else {
         Ok(PinOutcome::Rejected {
-            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
             stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
         })
     }
 }
 
+/// One locked release to move in a direct lockfile edit.
+#[derive(Debug, Clone)]
+pub struct LockEdit {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    pub checksum: Option<String>,
+}
+
+/// Pin by rewriting `Cargo.lock` in place and letting
+/// `cargo metadata --locked` confirm the edited graph still resolves. A
+/// rejected edit is rolled back.
+#[instrument(level = "debug", skip(manifest_path, cargo_config, lockfile_path))]
+pub fn try_pin_in_lockfile(
+    manifest_path: Option<&Path>,
+    cargo_config: &[String],
+    lockfile_path: &Path,
+    edits: &[LockEdit],
+) -> Result<PinOutcome> {
+    let original = fs::read_to_string(lockfile_path)
+        .with_context(|| format!("failed to read {}", lockfile_path.display()))?;
+    let mut edited = original.clone();
+    for edit in edits {
+        edited = lockfile::replace_version(
+            &edited,
+            &edit.name,
+            &edit.from,
+            &edit.to,
+            edit.checksum.as_deref(),
+        )?;
+    }
+    fs::write(lockfile_path, edited)
+        .with_context(|| format!("failed to write {}", lockfile_path.display()))?;
+    let mut command = Command::new("cargo");
+    command.args(["metadata", "--format-version", "1", "--locked"]);
+    if let Some(path) = manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+    command.args(config_args(cargo_config));
+    let output = command.output()?;
+    trace!(status = %output.status, "cargo metadata finished");
+    if output.status.success() {
+        return Ok(PinOutcome::Applied);
+    }
+    fs::write(lockfile_path, original)
+        .with_context(|| format!("failed to restore {}", lockfile_path.display()))?;
+    Ok(PinOutcome::Rejected {
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::TimeZone;
 
+    #[test]
+    fn bumps_follow_cargo_compatibility() {
+        let previous = ["1.2.3", "0.4.1", "0.0.3", "2.0.0"];
+        assert_eq!(bump_from(previous, "1.2.4"), Some(Bump::Patch));
+        assert_eq!(bump_from(previous, "1.3.0"), Some(Bump::Minor));
+        assert_eq!(bump_from(previous, "0.4.7"), Some(Bump::Patch));
+        assert_eq!(bump_from(previous, "0.5.0"), Some(Bump::Major));
+        assert_eq!(bump_from(previous, "0.0.4"), Some(Bump::Major));
+        // Measured from 2.0.0, the closest lower version.
+        assert_eq!(bump_from(previous, "2.1.0"), Some(Bump::Minor));
+        assert_eq!(bump_from(previous, "1.2.3"), None);
+        assert_eq!(bump_from(["1.0.0"], "0.9.0"), None);
+        assert_eq!(bump_from([], "1.0.0"), None);
+    }
+
     #[test]
     fn filters_fresh_versions() {
         let now = Utc.with_ymd_and_hms(2024, 10, 1, 0, 0, 0).unwrap();
@@ -64,20 +423,250 @@ mod tests {
                 created_at: Utc.with_ymd_and_hms(2024, 9, 30, 23, 50, 0).unwrap(),
                 yanked: false,
                 num: "1.2.3".into(),
+                downloads: None,
+                license: None,
             },
             VersionMeta {
                 created_at: Utc.with_ymd_and_hms(2024, 9, 30, 22, 0, 0).unwrap(),
                 yanked: false,
                 num: "1.2.2".into(),
+                downloads: None,
+                license: None,
             },
             VersionMeta {
                 created_at: Utc.with_ymd_and_hms(2024, 9, 30, 20, 0, 0).unwrap(),
                 yanked: true,
                 num: "1.2.1".into(),
+                downloads: None,
+                license: None,
             },
         ];
-        let candidates = filter_candidates(versions, 30, now);
+        let candidates = filter_candidates(
+            versions,
+            cooldown_cutoff(now, 30, None, &Calendar::default()),
+        );
         assert_eq!(candidates.len(), 1);
         assert_eq!(candidates[0].version, "1.2.2");
     }
+
+    #[test]
+    fn index_candidates_walk_down_from_current() {
+        let entry = |vers: &str, yanked: bool| IndexEntry {
+            vers: vers.into(),
+            yanked,
+            pubtime: None,
+            cksum: None,
+            rust_version: None,
+            deps: Vec::new(),
+        };
+        let entries = vec![
+            entry("1.0.0", false),
+            entry("1.2.0", false),
+            entry("1.1.0", true),
+            entry("1.3.0", false),
+            entry("2.0.0", false),
+        ];
+        let requirements = vec![VersionReq::parse("^1.0.5").unwrap()];
+        let versions: Vec<String> = index_candidates(entries, "1.3.0", &requirements)
+            .into_iter()
+            .map(|entry| entry.vers)
+            .collect();
+        assert_eq!(versions, vec!["1.2.0"]);
+    }
+
+    #[test]
+    fn pin_specs_handle_prerelease_and_build_metadata() {
+        assert_eq!(pin_spec("foo", "1.2.3"), "foo@1.2.3");
+        assert_eq!(pin_spec("foo", "1.2.3-beta.1"), "foo@1.2.3-beta.1");
+        assert_eq!(pin_spec("foo", "1.2.3+build.5"), "foo@1.2.3");
+        assert_eq!(pin_spec("foo", "1.2.3-rc.1+git.abc"), "foo@1.2.3-rc.1");
+        for version in ["1.2.3", "1.2.3-beta.1", "1.2.3-rc.1+git.abc"] {
+            let spec = pin_spec("foo", version);
+            let parsed = Version::parse(spec.strip_prefix("foo@").unwrap()).unwrap();
+            let original = Version::parse(version).unwrap();
+            assert_eq!(parsed.pre, original.pre);
+            assert_eq!(
+                (parsed.major, parsed.minor, parsed.patch),
+                (original.major, original.minor, original.patch)
+            );
+            assert!(parsed.build.is_empty());
+        }
+
+        let v = |s: &str| Version::parse(s).unwrap();
+        assert!(prerelease_allowed(&v("1.2.3"), &v("1.2.2")));
+        assert!(!prerelease_allowed(&v("1.2.3"), &v("1.2.3-rc.1")));
+        assert!(prerelease_allowed(&v("1.2.3-rc.2"), &v("1.2.3-rc.1")));
+        assert!(!prerelease_allowed(&v("1.3.0-rc.2"), &v("1.2.3-rc.1")));
+
+        let stderr = "error: failed to select a version for the requirement `foo = \"^1.2\"`\ncandidate versions found which didn't match: 1.2.3-rc.1\nif you are looking for the prerelease package it needs to be specified explicitly";
+        assert!(prerelease_rejection("foo", "1.2.3-rc.1", stderr).is_some());
+        assert!(prerelease_rejection("foo", "1.2.2", stderr).is_none());
+        assert!(prerelease_rejection("foo", "1.2.3-rc.1", "some other conflict").is_none());
+    }
+
+    #[test]
+    fn new_crates_have_few_releases_or_a_recent_debut() {
+        let now = Utc::now();
+        let release = |days_ago| VersionMeta {
+            created_at: now - Duration::days(days_ago),
+            yanked: false,
+            num: String::new(),
+            downloads: None,
+            license: None,
+        };
+        let established: Vec<VersionMeta> = (0..6).map(|i| release(400 - i * 30)).collect();
+        assert!(!is_new_crate(&established, now, 5, 30));
+        assert!(is_new_crate(&established[..3], now, 5, 30));
+        let debut: Vec<VersionMeta> = (0..6).map(release).collect();
+        assert!(is_new_crate(&debut, now, 5, 30));
+        assert!(is_new_crate(&[], now, 5, 30));
+    }
+
+    #[test]
+    fn survived_releases_count_later_live_releases() {
+        let now = Utc::now();
+        let release = |num: &str, hours_ago, yanked| VersionMeta {
+            created_at: now - Duration::hours(hours_ago),
+            yanked,
+            num: num.into(),
+            downloads: None,
+            license: None,
+        };
+        let versions = vec![
+            release("1.0.0", 10, false),
+            release("1.0.1", 8, true),
+            release("1.0.2", 6, false),
+            release("0.9.9", 4, false),
+            release("1.0.3", 2, false),
+        ];
+        assert!(survived_releases(&versions, "1.0.0", 2));
+        assert!(!survived_releases(&versions, "1.0.0", 3));
+        assert!(!survived_releases(&versions, "1.0.1", 1));
+        assert!(!survived_releases(&versions, "1.0.3", 1));
+        assert!(!survived_releases(&versions, "2.0.0", 1));
+    }
+
+    #[test]
+    fn recent_releases_need_downloads() {
+        let now = Utc::now();
+        let release = |days_ago, downloads| VersionMeta {
+            created_at: now - Duration::days(days_ago),
+            yanked: false,
+            num: String::new(),
+            downloads,
+            license: None,
+        };
+        assert!(lacks_downloads(&release(2, Some(40)), Some(500), now));
+        assert!(!lacks_downloads(&release(2, Some(900)), Some(500), now));
+        assert!(!lacks_downloads(&release(2, None), Some(500), now));
+        assert!(!lacks_downloads(&release(2, Some(40)), None, now));
+        assert!(!lacks_downloads(&release(90, Some(40)), Some(500), now));
+    }
+
+    #[test]
+    fn strategies_order_candidates() {
+        let now = Utc::now();
+        // Newest first by publication, as `filter_candidates` returns them:
+        // 1.7.9 is a backport published after 1.8.1.
+        let candidates: Vec<Candidate> = [("1.7.9", 1), ("1.8.1", 2), ("1.8.0", 3), ("1.6.0", 4)]
+            .into_iter()
+            .map(|(version, days_ago)| Candidate {
+                version: version.into(),
+                created_at: now - Duration::days(days_ago),
+            })
+            .collect();
+        let order = |strategy| -> Vec<String> {
+            order_candidates(strategy, "1.8.3", candidates.clone())
+                .into_iter()
+                .map(|candidate| candidate.version)
+                .collect()
+        };
+        assert_eq!(
+            order(PinStrategy::NewestCooled),
+            ["1.7.9", "1.8.1", "1.8.0", "1.6.0"]
+        );
+        assert_eq!(
+            order(PinStrategy::ClosestBelowCurrent),
+            ["1.8.1", "1.8.0", "1.7.9", "1.6.0"]
+        );
+        assert_eq!(order(PinStrategy::SameMinorOnly), ["1.8.1", "1.8.0"]);
+        assert_eq!(
+            order(PinStrategy::OldestCompatible),
+            ["1.6.0", "1.7.9", "1.8.0", "1.8.1"]
+        );
+    }
+
+    #[test]
+    fn downgrade_distance_counts_minor_series() {
+        assert!(within_downgrade("1.8.3", "1.8.0", 0));
+        assert!(!within_downgrade("1.8.3", "1.7.9", 0));
+        assert!(within_downgrade("1.8.3", "1.7.9", 1));
+        assert!(!within_downgrade("1.8.3", "1.2.0", 1));
+        assert!(within_downgrade("1.8.3", "1.2.0", 6));
+        assert!(!within_downgrade("2.0.1", "1.9.0", 5));
+    }
+
+    #[test]
+    fn msrv_screening_follows_the_policy() {
+        let v = |raw: &str| parse_rust_version(raw);
+        assert_eq!(v("1.70"), Version::parse("1.70.0").ok());
+        let workspace = v("1.70");
+        let current = v("1.65");
+        let check = |policy, candidate: &str| {
+            msrv_rejection(
+                policy,
+                v(candidate).as_ref(),
+                workspace.as_ref(),
+                current.as_ref(),
+            )
+        };
+        assert!(check(MsrvPolicy::Workspace, "1.74").is_some());
+        assert!(check(MsrvPolicy::Workspace, "1.60").is_none());
+        assert!(check(MsrvPolicy::Exact, "1.60").is_some());
+        assert!(check(MsrvPolicy::Exact, "1.68").is_none());
+        assert!(check(MsrvPolicy::Off, "1.74").is_none());
+        assert!(msrv_rejection(MsrvPolicy::Exact, None, workspace.as_ref(), None).is_none());
+    }
+
+    #[test]
+    fn license_comparison_ignores_notation() {
+        assert!(!license_changed("MIT OR Apache-2.0", "MIT/Apache-2.0"));
+        assert!(!license_changed("MIT or Apache-2.0", "MIT  OR Apache-2.0"));
+        assert!(license_changed("MIT OR Apache-2.0", "MIT"));
+        assert!(license_changed("MIT", "GPL-3.0-only"));
+    }
+
+    #[test]
+    fn freeze_date_clamps_cutoff() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let freeze = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        assert_eq!(
+            cooldown_cutoff(now, 60, Some(freeze), &Calendar::default()),
+            freeze
+        );
+        assert_eq!(cooldown_cutoff(now, 0, None, &Calendar::default()), now);
+
+        let versions = vec![
+            VersionMeta {
+                created_at: Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap(),
+                yanked: false,
+                num: "2.0.0".into(),
+                downloads: None,
+                license: None,
+            },
+            VersionMeta {
+                created_at: Utc.with_ymd_and_hms(2025, 1, 10, 0, 0, 0).unwrap(),
+                yanked: false,
+                num: "1.9.0".into(),
+                downloads: None,
+                license: None,
+            },
+        ];
+        let candidates = filter_candidates(
+            versions,
+            cooldown_cutoff(now, 0, Some(freeze), &Calendar::default()),
+        );
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].version, "1.9.0");
+    }
 }
@@ -0,0 +1,193 @@
+//! Resumable pinning runs. Every pin is saved to `.cooldown/plan.json` next to
+//! the lockfile as it is applied, so a run cut short by a network error or an
+//! interrupt, whose lockfile was rolled back, can re-apply them in one edit
+//! and continue from there instead of starting the fixed-point loop over.
+//!
+//! Only the pins are kept: the queue follows from analyzing the graph they
+//! leave, and crates that could not be pinned get a fresh attempt.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::journal::PinRecord;
+use crate::lockfile;
+use crate::resolver::LockEdit;
+
+const PLAN_FILE: &str = ".cooldown/plan.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct SavedPlan {
+    /// sha256 of the lockfile the interrupted run started from.
+    lockfile: String,
+    /// The policy the pins were chosen under.
+    policy: String,
+    pins: Vec<SavedPin>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPin {
+    #[serde(flatten)]
+    pub record: PinRecord,
+    /// Checksum of the release pinned to, for editing the lockfile directly.
+    pub checksum: Option<String>,
+}
+
+pub struct PlanFile {
+    path: PathBuf,
+    plan: SavedPlan,
+}
+
+impl PlanFile {
+    /// The plan saved for `lockfile`, when it was saved by a run that started
+    /// from the same lockfile under the same policy; otherwise an empty one.
+    pub fn open(lockfile: &Path, policy: &str) -> Result<Self> {
+        let contents =
+            fs::read(lockfile).with_context(|| format!("failed to read {}", lockfile.display()))?;
+        let path = lockfile.with_file_name(PLAN_FILE);
+        let fresh = SavedPlan {
+            lockfile: hex::encode(Sha256::digest(&contents)),
+            policy: policy.to_string(),
+            pins: Vec::new(),
+        };
+        let plan = match fs::read_to_string(&path) {
+            Ok(saved) => match serde_json::from_str::<SavedPlan>(&saved) {
+                Ok(saved) if saved.lockfile == fresh.lockfile && saved.policy == fresh.policy => {
+                    saved
+                }
+                Ok(_) => {
+                    debug!(path = %path.display(), "saved plan is for another lockfile or policy; starting over");
+                    fresh
+                }
+                Err(err) => {
+                    debug!(path = %path.display(), error = %err, "saved plan is unreadable; starting over");
+                    fresh
+                }
+            },
+            Err(_) => fresh,
+        };
+        Ok(Self { path, plan })
+    }
+
+    pub fn pins(&self) -> &[SavedPin] {
+        &self.plan.pins
+    }
+
+    /// The saved pins as one lockfile edit.
+    pub fn edits(&self) -> Vec<LockEdit> {
+        self.plan
+            .pins
+            .iter()
+            .map(|pin| LockEdit {
+                name: pin.record.name.clone(),
+                from: pin.record.from.clone(),
+                to: pin.record.to.clone(),
+                checksum: pin.checksum.clone(),
+            })
+            .collect()
+    }
+
+    /// Forget the saved pins, e.g. when Cargo no longer accepts them.
+    pub fn clear(&mut self) -> Result<()> {
+        self.plan.pins.clear();
+        self.save()
+    }
+
+    /// Save a pin that was just applied to `lockfile`.
+    pub fn record(&mut self, record: PinRecord, lockfile: &Path) -> Result<()> {
+        let checksum = lockfile::read(lockfile)?
+            .into_iter()
+            .find(|pkg| pkg.name == record.name && pkg.version == record.to)
+            .and_then(|pkg| pkg.checksum);
+        self.plan.pins.push(SavedPin { record, checksum });
+        self.save()
+    }
+
+    /// The run finished: nothing is left to resume.
+    pub fn finish(self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to remove {}", self.path.display()));
+            }
+        }
+        if let Some(dir) = self.path.parent() {
+            // Left alone when something else lives there.
+            let _ = fs::remove_dir(dir);
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        // Written aside and renamed, so an interrupt never leaves half a plan.
+        let partial = self.path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec_pretty(&self.plan)?)
+            .with_context(|| format!("failed to write {}", partial.display()))?;
+        fs::rename(&partial, &self.path)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn plans_resume_only_from_the_same_lockfile_and_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile = dir.path().join("Cargo.lock");
+        let original = "version = 4\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.210\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"aa\"\n";
+        fs::write(&lockfile, original).unwrap();
+
+        let mut plan = PlanFile::open(&lockfile, "policy").unwrap();
+        assert!(plan.pins().is_empty());
+        fs::write(&lockfile, original.replace("1.0.210", "1.0.200")).unwrap();
+        plan.record(
+            PinRecord {
+                pinned_at: Utc::now(),
+                workspace: dir.path().to_path_buf(),
+                name: "serde".into(),
+                from: "1.0.210".into(),
+                to: "1.0.200".into(),
+                reason: None,
+                published_at: None,
+            },
+            &lockfile,
+        )
+        .unwrap();
+
+        // The interrupted run's lockfile was rolled back.
+        fs::write(&lockfile, original).unwrap();
+        let resumed = PlanFile::open(&lockfile, "policy").unwrap();
+        let edits = resumed.edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].to, "1.0.200");
+        assert_eq!(edits[0].checksum.as_deref(), Some("aa"));
+        assert!(
+            PlanFile::open(&lockfile, "stricter")
+                .unwrap()
+                .pins()
+                .is_empty()
+        );
+
+        resumed.finish().unwrap();
+        assert!(!dir.path().join(".cooldown").exists());
+        assert!(
+            PlanFile::open(&lockfile, "policy")
+                .unwrap()
+                .pins()
+                .is_empty()
+        );
+    }
+}
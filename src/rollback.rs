@@ -0,0 +1,215 @@
+//! Putting `Cargo.lock` back when a pinning run does not finish, whether
+//! it errors out or is interrupted.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use crate::lockfile;
+
+/// Lockfiles of guards still alive, for the signal handler to deal with
+/// while the flow is blocked on a Cargo subprocess.
+static ACTIVE: Mutex<Vec<Backup>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+struct Backup {
+    path: PathBuf,
+    /// `None` when the lockfile did not exist yet.
+    original: Option<Vec<u8>>,
+    /// Restore on failure; otherwise the backup only feeds the report.
+    restore: bool,
+}
+
+/// Holds the contents a lockfile had before the pinning flow touched it and
+/// restores them when dropped, so a run that errors out leaves no half-pinned
+/// lockfile behind. [`LockfileGuard::commit`] keeps the changes, and so does
+/// every guard created with `keep_partial`.
+#[derive(Debug)]
+pub struct LockfileGuard {
+    backup: Backup,
+}
+
+impl LockfileGuard {
+    pub fn new(path: &Path, keep_partial: bool) -> Result<Self> {
+        let original = match fs::read(path) {
+            Ok(contents) => Some(contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to back up {}", path.display()));
+            }
+        };
+        let backup = Backup {
+            path: path.to_path_buf(),
+            original,
+            restore: !keep_partial,
+        };
+        active().push(backup.clone());
+        Ok(Self { backup })
+    }
+
+    /// The run finished; keep whatever it wrote.
+    pub fn commit(mut self) {
+        self.backup.restore = false;
+    }
+}
+
+impl Drop for LockfileGuard {
+    fn drop(&mut self) {
+        // Only this guard's entry; an outer guard on the same lockfile
+        // stays registered.
+        let mut active = active();
+        if let Some(index) = active
+            .iter()
+            .rposition(|backup| backup.path == self.backup.path)
+        {
+            active.remove(index);
+        }
+        drop(active);
+        if !self.backup.restore || !self.backup.changed() {
+            return;
+        }
+        match self.backup.restore() {
+            Ok(()) => {
+                info!(lockfile = %self.backup.path.display(), "run did not finish; restored the lockfile")
+            }
+            Err(err) => {
+                warn!(error = %err, "run did not finish and the lockfile could not be restored")
+            }
+        }
+    }
+}
+
+impl Backup {
+    /// Whether the lockfile differs from the backup, i.e. there is anything
+    /// to restore.
+    fn changed(&self) -> bool {
+        fs::read(&self.path).ok() != self.original
+    }
+
+    fn restore(&self) -> Result<()> {
+        match &self.original {
+            Some(contents) => fs::write(&self.path, contents),
+            None => fs::remove_file(&self.path),
+        }
+        .with_context(|| format!("failed to restore {}", self.path.display()))
+    }
+
+    /// What the run did to this lockfile so far, and what happens to it now.
+    fn report(&self) -> String {
+        let read = |contents: &[u8]| {
+            lockfile::parse(&String::from_utf8_lossy(contents)).unwrap_or_default()
+        };
+        let before = self.original.as_deref().map(read).unwrap_or_default();
+        let after = fs::read(&self.path)
+            .map(|contents| read(&contents))
+            .unwrap_or_default();
+        let pins = lockfile::moved_releases(&before, &after);
+        let mut report = format!(
+            "{}: {} pin(s) {}\n",
+            self.path.display(),
+            pins.len(),
+            if self.restore && !pins.is_empty() {
+                "rolled back"
+            } else {
+                "kept"
+            }
+        );
+        for (name, from, to) in pins {
+            report.push_str(&format!("  {name} {from} -> {to}\n"));
+        }
+        report
+    }
+}
+
+fn active() -> std::sync::MutexGuard<'static, Vec<Backup>> {
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Watch for Ctrl-C and termination requests for the rest of the process.
+/// An interrupted run restores every lockfile the flow still guards, prints
+/// which pins were applied so far, and exits with the conventional status.
+pub fn handle_interrupts() {
+    tokio::spawn(async {
+        let status = interrupted().await;
+        warn!("interrupted; cleaning up lockfiles");
+        for backup in active().iter() {
+            eprint!("{}", backup.report());
+            if backup.restore
+                && backup.changed()
+                && let Err(err) = backup.restore()
+            {
+                warn!(error = %err, "failed to restore the lockfile");
+            }
+        }
+        std::process::exit(status);
+    });
+}
+
+/// Resolves with the exit status for the first interrupt received.
+#[cfg(unix)]
+async fn interrupted() -> i32 {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            warn!(error = %err, "cannot listen for SIGTERM");
+            let _ = tokio::signal::ctrl_c().await;
+            return 130;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => 130,
+        _ = terminate.recv() => 143,
+    }
+}
+
+#[cfg(windows)]
+async fn interrupted() -> i32 {
+    use tokio::signal::windows::{ctrl_break, ctrl_close};
+
+    match (ctrl_break(), ctrl_close()) {
+        (Ok(mut brk), Ok(mut close)) => tokio::select! {
+            _ = tokio::signal::ctrl_c() => 130,
+            _ = brk.recv() => 130,
+            _ = close.recv() => 130,
+        },
+        _ => {
+            let _ = tokio::signal::ctrl_c().await;
+            130
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_unless_committed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.lock");
+        fs::write(&path, "before").unwrap();
+
+        let guard = LockfileGuard::new(&path, false).unwrap();
+        fs::write(&path, "half pinned").unwrap();
+        drop(guard);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "before");
+
+        let guard = LockfileGuard::new(&path, false).unwrap();
+        fs::write(&path, "pinned").unwrap();
+        guard.commit();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "pinned");
+
+        let generated = dir.path().join("generated.lock");
+        let guard = LockfileGuard::new(&generated, false).unwrap();
+        fs::write(&generated, "half pinned").unwrap();
+        drop(guard);
+        assert!(!generated.exists());
+    }
+}
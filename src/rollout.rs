@@ -0,0 +1,96 @@
+//! Gradual enforcement rollout: only a deterministic fraction of runs
+//! enforce, the rest fall back to warn mode.
+
+use std::env;
+use std::process::Command;
+
+use chrono::{NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::config::{Config, Mode};
+
+/// Outcome of sampling one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RolloutDecision {
+    pub percent: u8,
+    /// Stable bucket in `0..100` for this repository and day.
+    pub bucket: u8,
+    pub enforced: bool,
+}
+
+/// Downgrade `config.mode` from enforce to warn when this run falls outside
+/// `enforce_sample_percent`, and record the decision in `config.rollout`.
+/// Returns `None` when no rollout is configured.
+pub fn apply(config: &mut Config) -> Option<RolloutDecision> {
+    let percent = config.enforce_sample_percent.min(100);
+    if config.mode != Mode::Enforce || percent >= 100 {
+        return None;
+    }
+    let bucket = sample_bucket(&repository_identity(), Utc::now().date_naive());
+    let enforced = bucket < percent;
+    if !enforced {
+        config.mode = Mode::Warn;
+    }
+    config.rollout = Some(RolloutDecision {
+        percent,
+        bucket,
+        enforced,
+    });
+    config.rollout
+}
+
+/// Every run of the same repository on the same UTC day lands in the same
+/// bucket, so a retry does not flip between enforce and warn.
+pub fn sample_bucket(repository: &str, day: NaiveDate) -> u8 {
+    let digest = Sha256::digest(format!("{repository}\n{day}").as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % 100) as u8
+}
+
+/// Repository name from common CI variables, falling back to the `origin`
+/// remote and finally the working directory.
+fn repository_identity() -> String {
+    for var in [
+        "GITHUB_REPOSITORY",
+        "CI_PROJECT_PATH",
+        "BUILD_REPOSITORY_NAME",
+    ] {
+        if let Ok(value) = env::var(var)
+            && !value.is_empty()
+        {
+            return value;
+        }
+    }
+    if let Ok(output) = Command::new("git")
+        .args(["config", "--get", "remote.origin.url"])
+        .output()
+        && output.status.success()
+    {
+        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !url.is_empty() {
+            return url;
+        }
+    }
+    env::current_dir()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_are_stable_per_repository_and_day() {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let bucket = sample_bucket("org/service", day);
+        assert!(bucket < 100);
+        assert_eq!(bucket, sample_bucket("org/service", day));
+
+        let buckets: std::collections::HashSet<u8> = (1..=28)
+            .map(|d| sample_bucket("org/service", NaiveDate::from_ymd_opt(2025, 2, d).unwrap()))
+            .collect();
+        assert!(buckets.len() > 1, "sampling should vary from day to day");
+    }
+}
@@ -0,0 +1,83 @@
+//! One pinning run per workspace at a time.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::info;
+
+/// File next to `Cargo.lock` that concurrent runs take an advisory lock on.
+pub const RUN_LOCK_FILE: &str = ".cooldown.lock";
+
+/// Exclusive lock on a workspace for the duration of a pinning run, so two
+/// invocations cannot interleave their `cargo update` calls. Released when
+/// dropped, including when the process dies.
+#[derive(Debug)]
+pub struct RunLock {
+    _file: File,
+}
+
+impl RunLock {
+    /// Lock the workspace owning `lockfile`, waiting for a run that holds it.
+    pub fn acquire(lockfile: &Path) -> Result<Self> {
+        if let Some(lock) = Self::try_acquire(lockfile)? {
+            return Ok(lock);
+        }
+        let path = lock_path(lockfile);
+        info!(lock = %path.display(), "another cargo-cooldown run is pinning this workspace; waiting for it to finish");
+        let file = open(&path)?;
+        file.lock()
+            .with_context(|| format!("failed to lock {}", path.display()))?;
+        Ok(Self::holding(file))
+    }
+
+    /// Lock the workspace owning `lockfile` unless another run holds it.
+    pub fn try_acquire(lockfile: &Path) -> Result<Option<Self>> {
+        let path = lock_path(lockfile);
+        let file = open(&path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self::holding(file))),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(err)) => {
+                Err(err).with_context(|| format!("failed to lock {}", path.display()))
+            }
+        }
+    }
+
+    /// Record who holds the lock, for whoever finds the file.
+    fn holding(mut file: File) -> Self {
+        let _ = file.set_len(0);
+        let _ = writeln!(file, "{}", std::process::id());
+        Self { _file: file }
+    }
+}
+
+fn lock_path(lockfile: &Path) -> PathBuf {
+    lockfile.with_file_name(RUN_LOCK_FILE)
+}
+
+fn open(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_run_waits_for_the_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile = dir.path().join("Cargo.lock");
+        let held = RunLock::acquire(&lockfile).unwrap();
+        assert!(RunLock::try_acquire(&lockfile).unwrap().is_none());
+        drop(held);
+        assert!(RunLock::try_acquire(&lockfile).unwrap().is_some());
+        assert!(dir.path().join(RUN_LOCK_FILE).exists());
+    }
+}
@@ -0,0 +1,180 @@
+//! `--if-changed` and `skip_unchanged` guard: a fingerprint of the files that
+//! shape the dependency graph, stamped after each successful run so task
+//! runners in multi-language monorepos, and plain repeated builds, only pay
+//! for the analysis when something Rust-related changed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::allowlist::name_matches;
+use crate::cache::Cache;
+use crate::certificate::policy_digest;
+use crate::config::Config;
+
+/// Watched when `--if-changed` is given without patterns.
+pub const DEFAULT_PATTERNS: [&str; 2] = ["**/Cargo.toml", "**/Cargo.lock"];
+
+/// Directories never searched for watched files.
+const SKIPPED_DIRS: [&str; 3] = ["target", "node_modules", ".git"];
+
+pub struct Stamp {
+    cache: Cache,
+    key: String,
+    fingerprint: String,
+}
+
+impl Stamp {
+    /// Fingerprint the files under `root` that match `patterns`, together with
+    /// the mode and cooldown policy, so loosening either never reuses a stamp,
+    /// and `scope`, the other inputs that select which graph is checked.
+    pub fn new(config: &Config, root: &Path, patterns: &[String], scope: &str) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("failed to resolve {}", root.display()))?;
+        let patterns: Vec<String> = if patterns.is_empty() {
+            DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect()
+        } else {
+            patterns.to_vec()
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(format!(
+            "mode={:?}\npolicy={}\nscope={scope}\n",
+            config.mode,
+            policy_digest(config, &root)?
+        ));
+        for path in watched_files(&root, &patterns)? {
+            let contents =
+                fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            let relative = path.strip_prefix(&root)?;
+            hasher.update(format!("{}\n", relative.display()));
+            hasher.update(Sha256::digest(&contents));
+        }
+
+        let location = format!("{}\n{}", root.display(), patterns.join("\n"));
+        Ok(Self {
+            cache: Cache::for_config(config)?,
+            key: format!(
+                "stamps/{}",
+                hex::encode(Sha256::digest(location.as_bytes()))
+            ),
+            fingerprint: hex::encode(hasher.finalize()),
+        })
+    }
+
+    /// Whether the last successful run saw exactly the same files and policy.
+    pub fn is_current(&self) -> Result<bool> {
+        Ok(self.cache.get_stale::<String>(&self.key)?.as_ref() == Some(&self.fingerprint))
+    }
+
+    pub fn record(&self) -> Result<()> {
+        self.cache.put(&self.key, &self.fingerprint)
+    }
+}
+
+/// Files under `root` whose relative path matches one of `patterns`, sorted.
+pub fn watched_files(root: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("failed to list {}", dir.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if !SKIPPED_DIRS
+                    .iter()
+                    .any(|skipped| entry.file_name() == *skipped)
+                {
+                    pending.push(path);
+                }
+                continue;
+            }
+            let relative = path
+                .strip_prefix(root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            if patterns
+                .iter()
+                .any(|pattern| path_matches(pattern, &relative))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Match a `/`-separated relative path against a pattern where `**` spans any
+/// number of directories and `*`/`?` stay within one path segment.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches("./").split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, remaining)) => {
+                name_matches(segment, name) && segments_match(rest, remaining)
+            }
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn stamps_track_watched_files_only() {
+        assert!(path_matches("**/Cargo.toml", "Cargo.toml"));
+        assert!(path_matches("**/Cargo.toml", "crates/core/Cargo.toml"));
+        assert!(path_matches("crates/*/src/**", "crates/core/src/lib.rs"));
+        assert!(!path_matches("crates/*.toml", "crates/core/Cargo.toml"));
+
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        fs::write(dir.path().join("package.json"), "{}").unwrap();
+        let mut config = Config::from_env();
+        config.cache_dir = Some(dir.path().join("cache"));
+
+        let stamp = Stamp::new(&config, dir.path(), &[], "").unwrap();
+        assert!(!stamp.is_current().unwrap());
+        stamp.record().unwrap();
+
+        fs::write(dir.path().join("package.json"), "{\"a\": 1}").unwrap();
+        assert!(
+            Stamp::new(&config, dir.path(), &[], "")
+                .unwrap()
+                .is_current()
+                .unwrap()
+        );
+
+        assert!(
+            !Stamp::new(&config, dir.path(), &[], "features=[\"extra\"]")
+                .unwrap()
+                .is_current()
+                .unwrap()
+        );
+
+        fs::write(dir.path().join("Cargo.toml"), "[workspace]").unwrap();
+        assert!(
+            !Stamp::new(&config, dir.path(), &[], "")
+                .unwrap()
+                .is_current()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Reading a `cargo vendor` directory back into the releases it holds.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::lockfile::LockedPackage;
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: String,
+}
+
+/// The `.cargo-checksum.json` Cargo writes next to every vendored crate.
+#[derive(Deserialize)]
+struct Checksums {
+    /// SHA-256 of the `.crate` file; `null` for git dependencies.
+    package: Option<String>,
+}
+
+/// Every crate vendored under `dir`, as the lockfile entry it came from.
+/// Registry crates get `source` and the `.crate` checksum Cargo recorded;
+/// git dependencies have no checksum and are returned without a source.
+pub fn read(dir: &Path, source: &str) -> Result<Vec<LockedPackage>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    let mut packages = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let manifest_path = path.join("Cargo.toml");
+        let checksum_path = path.join(".cargo-checksum.json");
+        if !manifest_path.is_file() || !checksum_path.is_file() {
+            continue;
+        }
+        let manifest: Manifest = toml::from_str(&fs::read_to_string(&manifest_path)?)
+            .with_context(|| format!("failed to parse {}", manifest_path.display()))?;
+        let checksums: Checksums = serde_json::from_str(&fs::read_to_string(&checksum_path)?)
+            .with_context(|| format!("failed to parse {}", checksum_path.display()))?;
+        packages.push(LockedPackage {
+            name: manifest.package.name,
+            version: manifest.package.version,
+            source: checksums.package.is_some().then(|| source.to_string()),
+            checksum: checksums.package,
+        });
+    }
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_registry_and_git_crates() {
+        let dir = tempfile::tempdir().unwrap();
+        let vendor = |dir_name: &str, name: &str, version: &str, checksum: &str| {
+            let path = dir.path().join(dir_name);
+            fs::create_dir(&path).unwrap();
+            fs::write(
+                path.join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n"),
+            )
+            .unwrap();
+            fs::write(
+                path.join(".cargo-checksum.json"),
+                format!(r#"{{"files":{{}},"package":{checksum}}}"#),
+            )
+            .unwrap();
+        };
+        vendor("serde", "serde", "1.0.210", r#""abc123""#);
+        vendor("itoa-1.0.1", "itoa", "1.0.1", r#""def456""#);
+        vendor("patched", "patched", "0.1.0", "null");
+        fs::create_dir(dir.path().join("not-a-crate")).unwrap();
+
+        let source = "registry+https://github.com/rust-lang/crates.io-index";
+        let packages = read(dir.path(), source).unwrap();
+        assert_eq!(
+            packages,
+            [
+                LockedPackage {
+                    name: "itoa".into(),
+                    version: "1.0.1".into(),
+                    source: Some(source.into()),
+                    checksum: Some("def456".into()),
+                },
+                LockedPackage {
+                    name: "patched".into(),
+                    version: "0.1.0".into(),
+                    source: None,
+                    checksum: None,
+                },
+                LockedPackage {
+                    name: "serde".into(),
+                    version: "1.0.210".into(),
+                    source: Some(source.into()),
+                    checksum: Some("abc123".into()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! cargo-vet interop: releases audited in the workspace's `supply-chain/`
+//! store count as reviewed and skip the cooldown, the same way an
+//! `[[allow.exact]]` entry does.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::allowlist::{AllowExact, Allowlist};
+
+#[derive(Debug, Default, Deserialize)]
+struct AuditsFile {
+    #[serde(default)]
+    audits: BTreeMap<String, Vec<AuditEntry>>,
+}
+
+/// `imports.lock` nests the audits fetched from each peer one level deeper.
+#[derive(Debug, Default, Deserialize)]
+struct ImportsLock {
+    #[serde(default)]
+    audits: BTreeMap<String, AuditsFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditEntry {
+    /// A full audit of one release.
+    #[serde(default)]
+    version: Option<String>,
+    /// An audit of the diff `"<from> -> <to>"`.
+    #[serde(default)]
+    delta: Option<String>,
+}
+
+/// Exemptions for every release audited in `workspace_root/supply-chain`,
+/// own audits and imported ones alike, or `None` without a vet store.
+pub fn load(workspace_root: &Path) -> Result<Option<Allowlist>> {
+    let store = workspace_root.join("supply-chain");
+    let audits_path = store.join("audits.toml");
+    if !audits_path.exists() {
+        return Ok(None);
+    }
+    let mut audits: BTreeMap<String, Vec<AuditEntry>> =
+        read_toml::<AuditsFile>(&audits_path)?.audits;
+    let imports_path = store.join("imports.lock");
+    if imports_path.exists() {
+        for (_, imported) in read_toml::<ImportsLock>(&imports_path)?.audits {
+            for (name, entries) in imported.audits {
+                audits.entry(name).or_default().extend(entries);
+            }
+        }
+    }
+
+    let origin = audits_path.display().to_string();
+    let mut allowlist = Allowlist::default();
+    for (name, entries) in &audits {
+        for version in audited_versions(entries) {
+            allowlist.allow.exact.push(AllowExact {
+                crate_name: name.clone(),
+                version,
+                reason: Some(format!("audited in {origin}")),
+                expires: None,
+            });
+        }
+    }
+    Ok(Some(allowlist))
+}
+
+fn read_toml<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Fully audited releases plus every release reachable from one through a
+/// chain of delta audits.
+fn audited_versions(entries: &[AuditEntry]) -> BTreeSet<String> {
+    let mut audited: BTreeSet<String> = entries
+        .iter()
+        .filter_map(|entry| entry.version.clone())
+        .collect();
+    let deltas: Vec<(&str, &str)> = entries
+        .iter()
+        .filter_map(|entry| entry.delta.as_deref()?.split_once("->"))
+        .map(|(from, to)| (from.trim(), to.trim()))
+        .collect();
+    loop {
+        let before = audited.len();
+        for (from, to) in &deltas {
+            if audited.contains(*from) {
+                audited.insert(to.to_string());
+            }
+        }
+        if audited.len() == before {
+            return audited;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_chains_extend_full_audits() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("supply-chain");
+        fs::create_dir(&store).unwrap();
+        fs::write(
+            store.join("audits.toml"),
+            r#"
+[[audits.serde]]
+who = "Jane <jane@example.com>"
+criteria = "safe-to-deploy"
+version = "1.0.200"
+
+[[audits.serde]]
+who = "Jane <jane@example.com>"
+criteria = "safe-to-deploy"
+delta = "1.0.200 -> 1.0.210"
+
+[[audits.serde]]
+who = "Jane <jane@example.com>"
+criteria = "safe-to-deploy"
+delta = "1.0.210 -> 1.0.215"
+
+[[audits.log]]
+who = ["Jane <jane@example.com>"]
+criteria = ["safe-to-deploy"]
+delta = "0.4.20 -> 0.4.22"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            store.join("imports.lock"),
+            r#"
+[[audits.example-org.audits.itoa]]
+who = "Org"
+criteria = "safe-to-run"
+version = "1.0.11"
+"#,
+        )
+        .unwrap();
+
+        let allowlist = load(dir.path()).unwrap().unwrap();
+        assert!(allowlist.is_exact_allowed("serde", "1.0.215"));
+        assert!(allowlist.is_exact_allowed("itoa", "1.0.11"));
+        assert!(!allowlist.is_exact_allowed("log", "0.4.22"));
+        assert!(load(&dir.path().join("missing")).unwrap().is_none());
+    }
+}
@@ -0,0 +1,80 @@
+//! `--wait`: sleep through the cooldown instead of failing, for unattended
+//! pipelines that would rather build late than not at all.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use clap_cargo::{Features, Manifest};
+use tracing::info;
+
+use crate::codes::{self, ErrorCode, bail_with};
+use crate::config::Config;
+use crate::executor::{self, CoolingDown};
+use crate::report::format_eta;
+
+/// How long to sleep at most between two attempts, so yanks and newly
+/// published releases are picked up while waiting.
+const RECHECK_MINUTES: i64 = 15;
+
+/// Run the pinning flow until it succeeds, sleeping whenever it fails only
+/// because releases are still cooling down. Gives up right away when the
+/// graph would cool down after `max_wait_minutes`.
+pub async fn until_cooled(
+    config: &Config,
+    manifest: &Manifest,
+    features: &Features,
+    platforms: &[String],
+) -> Result<()> {
+    let deadline = Utc::now() + Duration::minutes(config.max_wait_minutes as i64);
+    loop {
+        let err = match executor::run_pinning_flow(config, manifest, features, platforms).await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        let Some(until) = err
+            .downcast_ref::<CoolingDown>()
+            .map(|cooling| cooling.until)
+        else {
+            return Err(err);
+        };
+        let now = Utc::now();
+        if until > deadline {
+            let code = codes::classify(&err).unwrap_or(ErrorCode::FreshCrateBlocked);
+            bail_with!(
+                code,
+                "{err:#}\nthat is past the {}-minute --wait limit; not waiting",
+                config.max_wait_minutes
+            );
+        }
+        info!(
+            "waiting for the graph to cool down at {}",
+            format_eta(until, now)
+        );
+        sleep_until(next_check(until, now)).await;
+    }
+}
+
+/// A little past `until`, so the last release is out of its window when
+/// checked, but never more than [`RECHECK_MINUTES`] away.
+fn next_check(until: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+    (until + Duration::seconds(30)).min(now + Duration::minutes(RECHECK_MINUTES))
+}
+
+async fn sleep_until(at: DateTime<Utc>) {
+    let delay = (at - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(delay).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn checks_again_at_least_every_recheck_interval() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let soon = now + Duration::minutes(5);
+        assert_eq!(next_check(soon, now), soon + Duration::seconds(30));
+        let later = now + Duration::hours(3);
+        assert_eq!(next_check(later, now), now + Duration::minutes(15));
+    }
+}